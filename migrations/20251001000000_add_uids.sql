@@ -0,0 +1,11 @@
+-- Add identifiers that are stable across databases, used to sync lists and items
+-- between machines. Existing rows get a random 128-bit hex identifier
+
+ALTER TABLE todo_lists ADD COLUMN uid TEXT;
+UPDATE todo_lists SET uid = lower(hex(randomblob(16)));
+
+ALTER TABLE todo_items ADD COLUMN uid TEXT;
+UPDATE todo_items SET uid = lower(hex(randomblob(16)));
+
+CREATE UNIQUE INDEX idx_todo_lists_uid ON todo_lists(uid);
+CREATE UNIQUE INDEX idx_todo_items_uid ON todo_items(uid);
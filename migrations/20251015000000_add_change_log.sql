@@ -0,0 +1,28 @@
+-- Change log used to merge concurrent edits made on different machines.
+-- Every change to a field of a list or item is recorded with a hybrid logical
+-- clock; deletions are recorded as a 'deleted' field (tombstone)
+
+CREATE TABLE sync_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    node_id TEXT NOT NULL,
+    last_hlc TEXT
+);
+
+-- Each database is a node with its own random identifier
+INSERT INTO sync_state (id, node_id) VALUES (1, lower(hex(randomblob(8))));
+
+CREATE TABLE sync_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    hlc TEXT NOT NULL,
+    entity TEXT NOT NULL CHECK (entity IN ('list', 'item')),
+    uid TEXT NOT NULL,
+    field TEXT NOT NULL,
+    value TEXT
+);
+
+CREATE UNIQUE INDEX idx_sync_changes_unique ON sync_changes(hlc, entity, uid, field);
+CREATE INDEX idx_sync_changes_row ON sync_changes(entity, uid);
+
+-- Clock of the last change applied to each row
+ALTER TABLE todo_lists ADD COLUMN hlc TEXT;
+ALTER TABLE todo_items ADD COLUMN hlc TEXT;
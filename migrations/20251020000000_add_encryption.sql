@@ -0,0 +1,8 @@
+-- Optional at-rest encryption of list and item names. When the database is
+-- encrypted, this table holds the salt used to derive the key from the
+-- passphrase and a known value encrypted with it to check passphrases
+CREATE TABLE encryption (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    salt TEXT NOT NULL,
+    verifier TEXT NOT NULL
+);
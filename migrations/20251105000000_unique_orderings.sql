@@ -0,0 +1,29 @@
+-- Space out the orderings of lists and items, so that moving a row only has to
+-- change that row, and make them unique among siblings. Rows sharing an
+-- ordering keep the order in which they were created
+
+CREATE TEMP TABLE list_orderings AS
+SELECT id, ROW_NUMBER() OVER (ORDER BY ordering, id) * 1024 AS ordering
+FROM todo_lists;
+
+UPDATE todo_lists SET ordering = (
+    SELECT ordering FROM list_orderings WHERE list_orderings.id = todo_lists.id
+);
+
+DROP TABLE list_orderings;
+
+CREATE TEMP TABLE item_orderings AS
+SELECT id, ROW_NUMBER() OVER (PARTITION BY list_id ORDER BY ordering, id) * 1024 AS ordering
+FROM todo_items;
+
+UPDATE todo_items SET ordering = (
+    SELECT ordering FROM item_orderings WHERE item_orderings.id = todo_items.id
+);
+
+DROP TABLE item_orderings;
+
+-- The unique indexes replace the plain ones on orderings
+DROP INDEX idx_todo_lists_ordering;
+DROP INDEX idx_todo_items_list_ordering;
+CREATE UNIQUE INDEX idx_todo_lists_ordering ON todo_lists(ordering);
+CREATE UNIQUE INDEX idx_todo_items_list_ordering ON todo_items(list_id, ordering);
@@ -0,0 +1,5 @@
+-- Optional workflow of the lists shown as a board. `statuses` holds the
+-- comma-separated statuses of a list (the last one meaning done), and `status`
+-- the status of an item. Both are NULL until a board is used
+ALTER TABLE todo_lists ADD COLUMN statuses TEXT;
+ALTER TABLE todo_items ADD COLUMN status TEXT;
@@ -0,0 +1,42 @@
+-- Keep only the winning change of each field in the change log, and nothing
+-- but the tombstone of deleted rows
+DELETE FROM sync_changes WHERE EXISTS (
+    SELECT 1 FROM sync_changes later
+    WHERE later.entity = sync_changes.entity AND later.uid = sync_changes.uid
+        AND later.field = sync_changes.field AND later.hlc > sync_changes.hlc
+);
+DELETE FROM sync_changes WHERE field != 'deleted' AND EXISTS (
+    SELECT 1 FROM sync_changes tombstone
+    WHERE tombstone.entity = sync_changes.entity AND tombstone.uid = sync_changes.uid
+        AND tombstone.field = 'deleted'
+);
+
+-- Count the writes to lists and items, so that the database is only diffed
+-- against the change log when something was written since the last time
+ALTER TABLE sync_state ADD COLUMN data_version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE sync_state ADD COLUMN recorded_version INTEGER;
+
+CREATE TRIGGER todo_lists_insert_version AFTER INSERT ON todo_lists
+BEGIN
+    UPDATE sync_state SET data_version = data_version + 1;
+END;
+CREATE TRIGGER todo_lists_update_version AFTER UPDATE ON todo_lists
+BEGIN
+    UPDATE sync_state SET data_version = data_version + 1;
+END;
+CREATE TRIGGER todo_lists_delete_version AFTER DELETE ON todo_lists
+BEGIN
+    UPDATE sync_state SET data_version = data_version + 1;
+END;
+CREATE TRIGGER todo_items_insert_version AFTER INSERT ON todo_items
+BEGIN
+    UPDATE sync_state SET data_version = data_version + 1;
+END;
+CREATE TRIGGER todo_items_update_version AFTER UPDATE ON todo_items
+BEGIN
+    UPDATE sync_state SET data_version = data_version + 1;
+END;
+CREATE TRIGGER todo_items_delete_version AFTER DELETE ON todo_items
+BEGIN
+    UPDATE sync_state SET data_version = data_version + 1;
+END;
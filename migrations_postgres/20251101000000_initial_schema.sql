@@ -0,0 +1,33 @@
+-- Schema of PostgreSQL databases, matching the SQLite schema in migrations/
+-- (without the change log and encryption tables, which are SQLite only)
+
+-- Create TodoLists table
+CREATE TABLE todo_lists (
+    id BIGSERIAL PRIMARY KEY,
+    uid TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    ordering BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Create TodoItems table
+CREATE TABLE todo_items (
+    id BIGSERIAL PRIMARY KEY,
+    uid TEXT NOT NULL UNIQUE,
+    list_id BIGINT NOT NULL REFERENCES todo_lists (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    is_done BOOLEAN NOT NULL DEFAULT FALSE,
+    priority TEXT CHECK (priority IN ('high', 'medium', 'low') OR priority IS NULL),
+    due_date TIMESTAMPTZ,
+    ordering BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Create indexes for better performance
+CREATE INDEX idx_todo_items_list_id ON todo_items(list_id);
+CREATE INDEX idx_todo_items_priority ON todo_items(priority);
+CREATE INDEX idx_todo_items_is_done ON todo_items(is_done);
+CREATE INDEX idx_todo_lists_ordering ON todo_lists(ordering);
+CREATE INDEX idx_todo_items_list_ordering ON todo_items(list_id, ordering);
@@ -0,0 +1,22 @@
+-- Space out the orderings of lists and items, so that moving a row only has to
+-- change that row, and make them unique among siblings (as in migrations/)
+
+UPDATE todo_lists SET ordering = numbered.ordering
+FROM (
+    SELECT id, ROW_NUMBER() OVER (ORDER BY ordering, id) * 1024 AS ordering
+    FROM todo_lists
+) AS numbered
+WHERE todo_lists.id = numbered.id;
+
+UPDATE todo_items SET ordering = numbered.ordering
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY list_id ORDER BY ordering, id) * 1024 AS ordering
+    FROM todo_items
+) AS numbered
+WHERE todo_items.id = numbered.id;
+
+-- The unique indexes replace the plain ones on orderings
+DROP INDEX idx_todo_lists_ordering;
+DROP INDEX idx_todo_items_list_ordering;
+CREATE UNIQUE INDEX idx_todo_lists_ordering ON todo_lists(ordering);
+CREATE UNIQUE INDEX idx_todo_items_list_ordering ON todo_items(list_id, ordering);
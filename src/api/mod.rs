@@ -0,0 +1,513 @@
+//! Local HTTP/JSON API (`judo serve`), so that other programs (chat bots,
+//! browser extensions...) can read and edit the lists of a database.
+//!
+//! Every request needs an `Authorization: Bearer <token>` header. Lists and
+//! items are returned as JSON with an `ETag` header derived from their
+//! `updated_at`; a request carrying `If-Match` only changes a list or item
+//! that wasn't modified since, and fails with 412 otherwise (the check and the
+//! write are atomic: of concurrent requests with the same ETag, only one goes
+//! through). Moves honour `If-Match` as well. The JSON schemas
+//! of the requests and responses are served at `/schema` (see [`schema`]).
+//!
+//! | Method | Path                  | Body          | Response          |
+//! |--------|-----------------------|---------------|-------------------|
+//! | GET    | `/lists`              |               | `[List]`          |
+//! | POST   | `/lists`              | `NewList`     | `List` (201)      |
+//! | GET    | `/lists/{id}`         |               | `List`            |
+//! | PATCH  | `/lists/{id}`         | `ListPatch`   | `List`            |
+//! | DELETE | `/lists/{id}`         |               | 204               |
+//! | POST   | `/lists/{id}/move`    | `Move`        | `List`            |
+//! | GET    | `/lists/{id}/items`   |               | `[Item]`          |
+//! | POST   | `/lists/{id}/items`   | `NewItem`     | `Item` (201)      |
+//! | GET    | `/items/{id}`         |               | `Item`            |
+//! | PATCH  | `/items/{id}`         | `ItemPatch`   | `Item`            |
+//! | DELETE | `/items/{id}`         |               | 204               |
+//! | POST   | `/items/{id}/toggle`  |               | `Item`            |
+//! | POST   | `/items/{id}/move`    | `Move`        | `Item`            |
+
+pub mod schema;
+
+use crate::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use crate::db::store::TodoStore;
+use anyhow::Context;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::Arc;
+
+/// Address the server listens on by default
+pub const DEFAULT_BIND: &str = "127.0.0.1:7878";
+
+/// Environment variable holding the token expected from clients
+pub const TOKEN_ENV: &str = "JUDO_API_TOKEN";
+
+/// Store served and token expected from clients
+struct ApiState {
+    store: Arc<dyn TodoStore>,
+    token: String,
+}
+
+/// Error of a request, returned as `{"error": "..."}`
+#[derive(Debug)]
+pub enum ApiError {
+    Unauthorized,
+    NotFound(String),
+    BadRequest(String),
+    /// The list or item changed since the ETag given in `If-Match`
+    PreconditionFailed,
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        ApiError::Internal(error)
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::BadRequest(rejection.body_text())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "Invalid token".to_string()),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::PreconditionFailed => (
+                StatusCode::PRECONDITION_FAILED,
+                "Modified since the given ETag".to_string(),
+            ),
+            ApiError::Internal(error) => {
+                // The details (paths, queries) are for the server log only
+                eprintln!("Internal error: {:#}", error);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
+        };
+        (status, Json(ErrorBody { error: message })).into_response()
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+type ApiResult<T = Response> = std::result::Result<T, ApiError>;
+
+/// Body of `POST /lists`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewList {
+    pub name: String,
+    /// Position among the lists (defaults to the end)
+    #[serde(default)]
+    pub position: Option<usize>,
+}
+
+/// Body of `PATCH /lists/{id}`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListPatch {
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Body of `POST /lists/{id}/items`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewItem {
+    pub name: String,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub due_date: Option<DateTime<Utc>>,
+    /// Position in the list (defaults to the end)
+    #[serde(default)]
+    pub position: Option<usize>,
+}
+
+/// Body of `PATCH /items/{id}`. Missing fields are left as they are, and
+/// `null` clears the priority or due date
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ItemPatch {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub is_done: Option<bool>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub priority: Option<Option<Priority>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub due_date: Option<Option<DateTime<Utc>>>,
+}
+
+/// Body of the `move` endpoints: positions to move by (negative is up)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Move {
+    pub by: i64,
+}
+
+/// Tell a `null` field (`Some(None)`) from a missing one (`None`)
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Router of the API over a store, accepting requests with the given token
+pub fn router(store: Arc<dyn TodoStore>, token: String) -> Router {
+    let state = Arc::new(ApiState { store, token });
+    Router::new()
+        .route("/schema", get(get_schema))
+        .route("/lists", get(get_lists).post(create_list))
+        .route(
+            "/lists/{id}",
+            get(get_list).patch(update_list).delete(delete_list),
+        )
+        .route("/lists/{id}/move", post(move_list))
+        .route("/lists/{id}/items", get(get_items).post(create_item))
+        .route(
+            "/items/{id}",
+            get(get_item).patch(update_item).delete(delete_item),
+        )
+        .route("/items/{id}/toggle", post(toggle_item))
+        .route("/items/{id}/move", post(move_item))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+/// Serve the API over a store until the process is stopped
+pub async fn serve(bind: &str, store: Arc<dyn TodoStore>, token: String) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(bind)
+        .await
+        .with_context(|| format!("Failed to listen on {}", bind))?;
+    axum::serve(listener, router(store, token))
+        .await
+        .with_context(|| "Server failed")
+}
+
+/// Token from the environment, or a random one
+pub fn token_from_env_or_random() -> anyhow::Result<(String, bool)> {
+    if let Ok(token) = std::env::var(TOKEN_ENV)
+        && !token.is_empty()
+    {
+        return Ok((token, false));
+    }
+    let mut bytes = [0u8; 24];
+    getrandom::fill(&mut bytes).map_err(|e| anyhow::anyhow!("No randomness: {}", e))?;
+    Ok((URL_SAFE_NO_PAD.encode(bytes), true))
+}
+
+async fn authorize(State(state): State<Arc<ApiState>>, request: Request, next: Next) -> Response {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if constant_time_eq(token.as_bytes(), state.token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => ApiError::Unauthorized.into_response(),
+    }
+}
+
+/// Compare secrets in a time that doesn't depend on where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// ETag of a list or item, changing whenever it is updated
+fn etag(updated_at: &DateTime<Utc>) -> String {
+    format!("\"{}\"", updated_at.timestamp_micros())
+}
+
+/// Fail unless the `If-Match` header (if any) matches the current ETag.
+/// Returns whether the request expects this very version (not for `*`)
+fn check_if_match(headers: &HeaderMap, updated_at: &DateTime<Utc>) -> ApiResult<bool> {
+    let Some(if_match) = headers.get(header::IF_MATCH) else {
+        return Ok(false);
+    };
+    let current = etag(updated_at);
+    let tags: Vec<&str> = if_match
+        .to_str()
+        .map(|value| value.split(',').map(str::trim).collect())
+        .unwrap_or_default();
+    if tags.contains(&"*") {
+        Ok(false)
+    } else if tags.contains(&current.as_str()) {
+        Ok(true)
+    } else {
+        Err(ApiError::PreconditionFailed)
+    }
+}
+
+/// Find a list to write to, checking the `If-Match` header. A list expected
+/// at its current version is claimed (its `updated_at` set to now, atomically),
+/// so that concurrent writes expecting the same version fail
+async fn claim_list(store: &dyn TodoStore, id: i64, headers: &HeaderMap) -> ApiResult<TodoList> {
+    let mut list = find_list(store, id).await?;
+    if check_if_match(headers, &list.updated_at)? {
+        let now = Utc::now();
+        if !store.claim_list(list.id, list.updated_at, now).await? {
+            return Err(ApiError::PreconditionFailed);
+        }
+        list.updated_at = now;
+    }
+    Ok(list)
+}
+
+/// Same as [`claim_list`] for an item
+async fn claim_item(store: &dyn TodoStore, id: i64, headers: &HeaderMap) -> ApiResult<TodoItem> {
+    let mut item = find_item(store, id).await?;
+    if check_if_match(headers, &item.updated_at)? {
+        let now = Utc::now();
+        if !store.claim_item(item.id, item.updated_at, now).await? {
+            return Err(ApiError::PreconditionFailed);
+        }
+        item.updated_at = now;
+    }
+    Ok(item)
+}
+
+/// JSON response with the ETag of the list or item
+fn with_etag<T: Serialize>(status: StatusCode, value: &T, updated_at: &DateTime<Utc>) -> Response {
+    let mut response = (status, Json(value)).into_response();
+    if let Ok(tag) = HeaderValue::from_str(&etag(updated_at)) {
+        response.headers_mut().insert(header::ETAG, tag);
+    }
+    response
+}
+
+fn valid_name(name: &str) -> ApiResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest("The name can't be empty".to_string()));
+    }
+    Ok(name.to_string())
+}
+
+async fn find_list(store: &dyn TodoStore, id: i64) -> ApiResult<TodoList> {
+    TodoList::get_by_id(store, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("No list with id {}", id)))
+}
+
+async fn find_item(store: &dyn TodoStore, id: i64) -> ApiResult<TodoItem> {
+    TodoItem::get_by_id(store, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("No item with id {}", id)))
+}
+
+async fn get_schema() -> Json<serde_json::Value> {
+    Json(schema::schemas())
+}
+
+async fn get_lists(State(state): State<Arc<ApiState>>) -> ApiResult<Json<Vec<TodoList>>> {
+    Ok(Json(TodoList::get_all(&*state.store).await?))
+}
+
+async fn create_list(
+    State(state): State<Arc<ApiState>>,
+    body: Result<Json<NewList>, JsonRejection>,
+) -> ApiResult {
+    let Json(body) = body?;
+    let new_list = NewTodoList {
+        name: valid_name(&body.name)?,
+    };
+    let list = match body.position {
+        Some(position) => TodoList::create_at(&*state.store, new_list, position).await?,
+        None => TodoList::create(&*state.store, new_list).await?,
+    };
+    Ok(with_etag(StatusCode::CREATED, &list, &list.updated_at))
+}
+
+async fn get_list(State(state): State<Arc<ApiState>>, Path(id): Path<i64>) -> ApiResult {
+    let list = find_list(&*state.store, id).await?;
+    Ok(with_etag(StatusCode::OK, &list, &list.updated_at))
+}
+
+async fn update_list(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    body: Result<Json<ListPatch>, JsonRejection>,
+) -> ApiResult {
+    let Json(body) = body?;
+    let mut list = claim_list(&*state.store, id, &headers).await?;
+
+    if let Some(name) = body.name {
+        list.update_name(&*state.store, valid_name(&name)?).await?;
+    }
+    Ok(with_etag(StatusCode::OK, &list, &list.updated_at))
+}
+
+async fn delete_list(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> ApiResult<StatusCode> {
+    let list = claim_list(&*state.store, id, &headers).await?;
+    list.delete(&*state.store).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn move_list(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    body: Result<Json<Move>, JsonRejection>,
+) -> ApiResult {
+    let Json(body) = body?;
+    let mut list = claim_list(&*state.store, id, &headers).await?;
+    let amount = body.by.unsigned_abs() as usize;
+    if body.by < 0 {
+        list.move_up_by(&*state.store, amount).await?;
+    } else {
+        list.move_down_by(&*state.store, amount).await?;
+    }
+    Ok(with_etag(StatusCode::OK, &list, &list.updated_at))
+}
+
+async fn get_items(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<i64>,
+) -> ApiResult<Json<Vec<TodoItem>>> {
+    let list = find_list(&*state.store, id).await?;
+    Ok(Json(
+        TodoItem::get_by_list_id(&*state.store, list.id).await?,
+    ))
+}
+
+async fn create_item(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<i64>,
+    body: Result<Json<NewItem>, JsonRejection>,
+) -> ApiResult {
+    let Json(body) = body?;
+    let list = find_list(&*state.store, id).await?;
+    let new_item = NewTodoItem {
+        list_id: list.id,
+        name: valid_name(&body.name)?,
+        priority: body.priority,
+        due_date: body.due_date,
+    };
+    let item = match body.position {
+        Some(position) => TodoItem::create_at(&*state.store, new_item, position).await?,
+        None => TodoItem::create(&*state.store, new_item).await?,
+    };
+    Ok(with_etag(StatusCode::CREATED, &item, &item.updated_at))
+}
+
+async fn get_item(State(state): State<Arc<ApiState>>, Path(id): Path<i64>) -> ApiResult {
+    let item = find_item(&*state.store, id).await?;
+    Ok(with_etag(StatusCode::OK, &item, &item.updated_at))
+}
+
+async fn update_item(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    body: Result<Json<ItemPatch>, JsonRejection>,
+) -> ApiResult {
+    let Json(body) = body?;
+    let mut item = claim_item(&*state.store, id, &headers).await?;
+
+    let name = match body.name {
+        Some(name) => valid_name(&name)?,
+        None => item.name.clone(),
+    };
+    let is_done = body.is_done.unwrap_or(item.is_done);
+    let priority = body.priority.unwrap_or_else(|| item.priority.clone());
+    let due_date = body.due_date.unwrap_or(item.due_date);
+    item.update_details(&*state.store, name, is_done, priority, due_date)
+        .await?;
+    Ok(with_etag(StatusCode::OK, &item, &item.updated_at))
+}
+
+async fn delete_item(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> ApiResult<StatusCode> {
+    let item = claim_item(&*state.store, id, &headers).await?;
+    item.delete(&*state.store).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn toggle_item(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> ApiResult {
+    let mut item = claim_item(&*state.store, id, &headers).await?;
+    item.toggle_done(&*state.store).await?;
+    Ok(with_etag(StatusCode::OK, &item, &item.updated_at))
+}
+
+async fn move_item(
+    State(state): State<Arc<ApiState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    body: Result<Json<Move>, JsonRejection>,
+) -> ApiResult {
+    let Json(body) = body?;
+    let mut item = claim_item(&*state.store, id, &headers).await?;
+    let amount = body.by.unsigned_abs() as usize;
+    if body.by < 0 {
+        item.move_up_by(&*state.store, amount).await?;
+    } else {
+        item.move_down_by(&*state.store, amount).await?;
+    }
+    Ok(with_etag(StatusCode::OK, &item, &item.updated_at))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_check_if_match() {
+        let updated_at = Utc.with_ymd_and_hms(2025, 10, 1, 8, 0, 0).unwrap();
+        let mut headers = HeaderMap::new();
+        assert!(!check_if_match(&headers, &updated_at).unwrap());
+
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("\"1\", \"2\""));
+        assert!(matches!(
+            check_if_match(&headers, &updated_at),
+            Err(ApiError::PreconditionFailed)
+        ));
+
+        let current = HeaderValue::from_str(&etag(&updated_at)).unwrap();
+        headers.insert(header::IF_MATCH, current);
+        assert!(check_if_match(&headers, &updated_at).unwrap());
+
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("\"1\", *"));
+        assert!(!check_if_match(&headers, &updated_at).unwrap());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+    }
+}
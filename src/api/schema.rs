@@ -0,0 +1,122 @@
+use serde_json::{Value, json};
+
+/// JSON Schema dialect of the schemas
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+fn priority() -> Value {
+    json!({ "type": ["string", "null"], "enum": ["high", "medium", "low", null] })
+}
+
+fn date_time() -> Value {
+    json!({ "type": "string", "format": "date-time" })
+}
+
+fn nullable_date_time() -> Value {
+    json!({ "type": ["string", "null"], "format": "date-time" })
+}
+
+/// JSON schemas of the bodies of the requests and responses of the API, by
+/// name (see the table of endpoints in [`crate::api`])
+pub fn schemas() -> Value {
+    json!({
+        "List": {
+            "$schema": DIALECT,
+            "type": "object",
+            "required": ["id", "uid", "name", "ordering", "created_at", "updated_at"],
+            "properties": {
+                "id": { "type": "integer" },
+                "uid": { "type": "string", "description": "Identifier stable across databases" },
+                "name": { "type": "string" },
+                "ordering": { "type": "integer" },
+                "statuses": {
+                    "type": ["string", "null"],
+                    "description": "Comma-separated statuses of the board, null for the default ones"
+                },
+                "created_at": date_time(),
+                "updated_at": date_time()
+            }
+        },
+        "Item": {
+            "$schema": DIALECT,
+            "type": "object",
+            "required": [
+                "id", "uid", "list_id", "name", "is_done", "priority", "due_date",
+                "ordering", "created_at", "updated_at"
+            ],
+            "properties": {
+                "id": { "type": "integer" },
+                "uid": { "type": "string", "description": "Identifier stable across databases" },
+                "list_id": { "type": "integer" },
+                "name": { "type": "string" },
+                "is_done": { "type": "boolean" },
+                "priority": priority(),
+                "due_date": nullable_date_time(),
+                "ordering": { "type": "integer" },
+                "status": {
+                    "type": ["string", "null"],
+                    "description": "Status of the item on the board of its list"
+                },
+                "created_at": date_time(),
+                "updated_at": date_time()
+            }
+        },
+        "NewList": {
+            "$schema": DIALECT,
+            "type": "object",
+            "required": ["name"],
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "position": { "type": "integer", "minimum": 0 }
+            }
+        },
+        "ListPatch": {
+            "$schema": DIALECT,
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string", "minLength": 1 }
+            }
+        },
+        "NewItem": {
+            "$schema": DIALECT,
+            "type": "object",
+            "required": ["name"],
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "priority": priority(),
+                "due_date": nullable_date_time(),
+                "position": { "type": "integer", "minimum": 0 }
+            }
+        },
+        "ItemPatch": {
+            "$schema": DIALECT,
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "is_done": { "type": "boolean" },
+                "priority": priority(),
+                "due_date": nullable_date_time()
+            }
+        },
+        "Move": {
+            "$schema": DIALECT,
+            "type": "object",
+            "required": ["by"],
+            "additionalProperties": false,
+            "properties": {
+                "by": { "type": "integer", "description": "Positions to move by, negative is up" }
+            }
+        },
+        "Error": {
+            "$schema": DIALECT,
+            "type": "object",
+            "required": ["error"],
+            "properties": {
+                "error": { "type": "string" }
+            }
+        }
+    })
+}
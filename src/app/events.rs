@@ -1,7 +1,13 @@
 use crate::app::state::{App, CurrentScreen};
-use crate::ui::components::{ItemsComponent, ListsComponent};
+use crate::db::models::parse_statuses;
+use crate::ui::components::calendar::CalendarFocus;
+use crate::ui::components::{
+    BoardComponent, CalendarComponent, DatabaseComponent, ItemsComponent, ListsComponent,
+};
 use crate::ui::cursor::CursorState;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::ui::layout::AppLayout;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
 
 pub struct EventHandler;
 
@@ -36,7 +42,7 @@ impl EventHandler {
 
     /// Handle key events that are screen-agnostic
     pub fn matches_global_keys(app: &mut App, key: KeyEvent) -> bool {
-        let main_screens = vec![
+        let main_screens = [
             CurrentScreen::ListSelection,
             CurrentScreen::ItemSelection,
             CurrentScreen::DBSelection,
@@ -82,6 +88,9 @@ impl EventHandler {
 
         match key.code {
             KeyCode::Char('q') => app.exit = true, // Quit application
+            KeyCode::Char(':') if main_screens.contains(&app.current_screen) => {
+                app.enter_ex_line();
+            }
             KeyCode::Char('h') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 app.current_screen = CurrentScreen::Help;
             }
@@ -118,6 +127,14 @@ impl EventHandler {
             KeyCode::Char('1') => app.current_screen = CurrentScreen::ListSelection,
             KeyCode::Char('2') => app.current_screen = CurrentScreen::ItemSelection,
             KeyCode::Char('3') => app.current_screen = CurrentScreen::DBSelection,
+            KeyCode::Char('t') => app.enter_theme_selection_screen(),
+            KeyCode::Char('c') => app.enter_calendar_screen(),
+            KeyCode::Char('h') => app.enter_hook_log_screen(),
+            KeyCode::Char('x') => app.enter_script_picker(),
+            KeyCode::Char('s') => {
+                app.go_back();
+                app.sync_now().await;
+            }
             KeyCode::Esc => {
                 app.go_back();
                 app.reset_key_buffer();
@@ -139,6 +156,350 @@ impl EventHandler {
         }
     }
 
+    /// Handle key press in the hook log: `c` clears it
+    pub async fn handle_hook_log_screen_key(app: &mut App, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('c') => {
+                if let Some(hooks) = &app.hooks {
+                    hooks.clear_failures();
+                }
+            }
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => app.go_back(),
+            _ => {}
+        }
+    }
+
+    /// Handle key press while the sync report is shown
+    pub async fn handle_sync_report_key(app: &mut App, key: KeyEvent) {
+        if let KeyCode::Esc | KeyCode::Enter = key.code {
+            app.sync_messages.clear();
+        }
+    }
+
+    /// Handle key press while the template report is shown
+    pub async fn handle_template_report_key(app: &mut App, key: KeyEvent) {
+        if let KeyCode::Esc | KeyCode::Enter = key.code {
+            app.template_messages.clear();
+        }
+    }
+
+    /// Handle key press while the output of a script is shown
+    pub async fn handle_script_output_key(app: &mut App, key: KeyEvent) {
+        if let KeyCode::Esc | KeyCode::Enter = key.code {
+            app.script_output.clear();
+        }
+    }
+
+    /// Handle key press from user in the pop-up asking a question of a script
+    pub async fn handle_script_prompt_screen_key(app: &mut App, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => app.answer_script_question(None),
+            KeyCode::Enter => {
+                let answer = app.input_state.get_text().to_string();
+                app.answer_script_question(Some(answer));
+            }
+            KeyCode::Backspace => app.input_state.remove_char_before_cursor(),
+            KeyCode::Delete => app.input_state.delete_char_after_cursor(),
+            KeyCode::Char(value) => app.input_state.add_char(value),
+            KeyCode::Left => app.input_state.move_cursor_left(),
+            KeyCode::Right => app.input_state.move_cursor_right(),
+            _ => {}
+        }
+    }
+
+    /// Handle key press from user in the pop-up with the options of a script
+    /// (or the scripts to run)
+    pub async fn handle_script_select_screen_key(app: &mut App, key: KeyEvent) {
+        let Some(question) = app.script_question.as_mut() else {
+            app.go_back();
+            return;
+        };
+        let count = question.options.len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if count > 0 => {
+                question.selected = (question.selected + 1) % count;
+            }
+            KeyCode::Up | KeyCode::Char('k') if count > 0 => {
+                question.selected = (question.selected + count - 1) % count;
+            }
+            KeyCode::Enter => {
+                let answer = question.options.get(question.selected).cloned();
+                app.answer_script_question(answer);
+            }
+            KeyCode::Esc | KeyCode::Char('q') => app.answer_script_question(None),
+            _ => {}
+        }
+    }
+
+    /// Handle key press from user in the pop-up listing the templates
+    pub async fn handle_template_select_screen_key(app: &mut App, key: KeyEvent) {
+        let count = app.templates.len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if count > 0 => {
+                app.selected_template = (app.selected_template + 1) % count;
+            }
+            KeyCode::Up | KeyCode::Char('k') if count > 0 => {
+                app.selected_template = (app.selected_template + count - 1) % count;
+            }
+            KeyCode::Enter => app.choose_template().await,
+            KeyCode::Esc | KeyCode::Char('q') => app.current_screen = CurrentScreen::ListSelection,
+            _ => {}
+        }
+    }
+
+    /// Handle key press from user in the pop-up asking for a variable of a template
+    pub async fn handle_template_variable_screen_key(app: &mut App, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => app.answer_template_variable(None).await,
+            KeyCode::Enter => {
+                let value = app.input_state.get_text().to_string();
+                app.answer_template_variable(Some(value)).await;
+            }
+            KeyCode::Backspace => app.input_state.remove_char_before_cursor(),
+            KeyCode::Delete => app.input_state.delete_char_after_cursor(),
+            KeyCode::Char(value) => app.input_state.add_char(value),
+            KeyCode::Left => app.input_state.move_cursor_left(),
+            KeyCode::Right => app.input_state.move_cursor_right(),
+            _ => {}
+        }
+    }
+
+    /// Handle key press in the command palette: typing filters the commands,
+    /// Tab completes the selected one and Enter runs it (or, for commands
+    /// taking an argument, completes it first)
+    pub async fn handle_command_palette_screen_key(app: &mut App, key: KeyEvent) {
+        let Some(command_palette) = app.palette.as_mut() else {
+            app.go_back();
+            return;
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => {
+                app.current_screen = command_palette.screen.clone();
+                app.palette = None;
+            }
+            KeyCode::Enter => match command_palette.selected_entry() {
+                Some(entry) if entry.is_complete() => app.run_palette_entry(entry).await,
+                Some(_) => command_palette.complete(),
+                None => {}
+            },
+            KeyCode::Tab => command_palette.complete(),
+            KeyCode::Down => command_palette.select(1),
+            KeyCode::Up => command_palette.select(-1),
+            KeyCode::Char('n') if ctrl => command_palette.select(1),
+            KeyCode::Char('p') if ctrl => command_palette.select(-1),
+            KeyCode::Left => command_palette.input.move_cursor_left(),
+            KeyCode::Right => command_palette.input.move_cursor_right(),
+            // Editing the input starts over from the best match
+            KeyCode::Backspace => {
+                command_palette.input.remove_char_before_cursor();
+                command_palette.selected = 0;
+            }
+            KeyCode::Delete => {
+                command_palette.input.delete_char_after_cursor();
+                command_palette.selected = 0;
+            }
+            KeyCode::Char(value) => {
+                command_palette.input.add_char(value);
+                command_palette.selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle key press on the ex command line: Enter runs the command, Tab
+    /// completes it and Up/Down go through the history of the commands
+    /// starting with what was typed
+    pub async fn handle_ex_line_screen_key(app: &mut App, key: KeyEvent) {
+        let Some(ex_line) = app.ex_line.as_mut() else {
+            app.go_back();
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => {
+                app.current_screen = ex_line.screen.clone();
+                app.ex_line = None;
+            }
+            KeyCode::Enter => app.run_ex_line().await,
+            KeyCode::Tab => ex_line.complete(),
+            KeyCode::Up => ex_line.history_previous(&app.ex_history),
+            KeyCode::Down => ex_line.history_next(&app.ex_history),
+            KeyCode::Left => ex_line.input.move_cursor_left(),
+            KeyCode::Right => ex_line.input.move_cursor_right(),
+            // Deleting the whole command closes the command line, as in vim
+            KeyCode::Backspace if ex_line.input.get_text().is_empty() => {
+                app.current_screen = ex_line.screen.clone();
+                app.ex_line = None;
+            }
+            KeyCode::Backspace => {
+                ex_line.input.remove_char_before_cursor();
+                ex_line.edited();
+            }
+            KeyCode::Delete => {
+                ex_line.input.delete_char_after_cursor();
+                ex_line.edited();
+            }
+            KeyCode::Char(value) => {
+                ex_line.input.add_char(value);
+                ex_line.edited();
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle key press in the calendar: hjkl move by day and week, H/L by
+    /// month. Enter focuses the items of the selected day, where `m` picks up
+    /// an item to drop it on another day with Enter
+    pub async fn handle_calendar_screen_key(app: &mut App, key: KeyEvent) {
+        let calendar = &mut app.calendar_component;
+        let lists = &app.lists_component.lists;
+
+        if calendar.focus == CalendarFocus::Items {
+            match key.code {
+                KeyCode::Down | KeyCode::Char('j') => calendar.select_next_item(lists),
+                KeyCode::Up | KeyCode::Char('k') => calendar.select_previous_item(),
+                KeyCode::Char('m') => calendar.pick_selected_item(lists),
+                KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') => calendar.focus_days(),
+                _ => {}
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Left | KeyCode::Char('h') => calendar.move_days(-1),
+            KeyCode::Right | KeyCode::Char('l') => calendar.move_days(1),
+            KeyCode::Up | KeyCode::Char('k') => calendar.move_days(-7),
+            KeyCode::Down | KeyCode::Char('j') => calendar.move_days(7),
+            KeyCode::Char('H') => calendar.move_months(-1),
+            KeyCode::Char('L') => calendar.move_months(1),
+            KeyCode::Char('t') => calendar.select_date(calendar.today),
+            KeyCode::Enter if calendar.moving.is_some() => {
+                if let Err(e) = CalendarComponent::drop_moving_item(
+                    calendar,
+                    &mut app.lists_component,
+                    &*app.store,
+                )
+                .await
+                {
+                    eprintln!("Failed to reschedule item: {}", e);
+                }
+            }
+            KeyCode::Enter => calendar.focus_items(lists),
+            KeyCode::Esc if calendar.moving.is_some() => calendar.moving = None,
+            KeyCode::Esc | KeyCode::Char('q') => app.go_back(),
+            _ => {}
+        }
+    }
+
+    /// Handle key press in the board of a list: hjkl select cards, H/L move
+    /// the selected card to the previous or next status and J/K move it within
+    /// its column
+    pub async fn handle_board_screen_key(app: &mut App, key: KeyEvent) {
+        let board = &mut app.board_component;
+        let Some(ui_list) = app.lists_component.get_selected_list() else {
+            app.go_back();
+            return;
+        };
+
+        match key.code {
+            KeyCode::Left | KeyCode::Char('h') => board.select_column(ui_list, -1),
+            KeyCode::Right | KeyCode::Char('l') => board.select_column(ui_list, 1),
+            KeyCode::Up | KeyCode::Char('k') => board.select_card(ui_list, -1),
+            KeyCode::Down | KeyCode::Char('j') => board.select_card(ui_list, 1),
+            KeyCode::Char('H') | KeyCode::Char('L') => {
+                let offset = if key.code == KeyCode::Char('H') {
+                    -1
+                } else {
+                    1
+                };
+                if let Err(e) = BoardComponent::move_card_to_column(
+                    board,
+                    &mut app.lists_component,
+                    offset,
+                    &*app.store,
+                )
+                .await
+                {
+                    eprintln!("Failed to change item status: {}", e);
+                }
+            }
+            KeyCode::Char('K') | KeyCode::Char('J') => {
+                let offset = if key.code == KeyCode::Char('K') {
+                    -1
+                } else {
+                    1
+                };
+                if let Err(e) = BoardComponent::move_card_in_column(
+                    board,
+                    &mut app.lists_component,
+                    offset,
+                    &*app.store,
+                )
+                .await
+                {
+                    eprintln!("Failed to move item: {}", e);
+                }
+            }
+            KeyCode::Char('e') => app.enter_edit_statuses_screen(),
+            KeyCode::Esc | KeyCode::Char('q') => app.go_back(),
+            _ => {}
+        }
+    }
+
+    /// Handle key press from user in the pop-up editing the statuses of a board
+    pub async fn handle_edit_statuses_screen_key(app: &mut App, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                app.input_state.clear();
+                app.current_screen = CurrentScreen::Board;
+            }
+            KeyCode::Backspace => app.input_state.remove_char_before_cursor(),
+            KeyCode::Delete => app.input_state.delete_char_after_cursor(),
+            KeyCode::Char(value) => app.input_state.add_char(value),
+            KeyCode::Left => app.input_state.move_cursor_left(),
+            KeyCode::Right => app.input_state.move_cursor_right(),
+            KeyCode::Enter => {
+                let statuses = parse_statuses(app.input_state.get_text());
+                // A board needs at least two columns
+                if statuses.len() < 2 {
+                    return;
+                }
+                if let Err(e) = ListsComponent::update_selected_list_statuses(
+                    &mut app.lists_component,
+                    statuses,
+                    &*app.store,
+                )
+                .await
+                {
+                    eprintln!("Failed to update statuses: {}", e);
+                } else {
+                    app.board_component = BoardComponent::new();
+                    app.input_state.clear();
+                    app.current_screen = CurrentScreen::Board;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle key press from user in theme selection screen
+    pub async fn handle_theme_selection_screen_key(app: &mut App, key: KeyEvent) {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => app.select_previous_theme(),
+            KeyCode::Down | KeyCode::Char('j') => app.select_next_theme(),
+            KeyCode::Enter => {
+                if let Err(e) = app.save_selected_theme() {
+                    eprintln!("Failed to save theme: {}", e);
+                }
+                app.go_back();
+            }
+            KeyCode::Esc => app.exit_theme_selection_without_saving(),
+            _ => {}
+        }
+    }
+
     /// Handle key press from user in list selection screen
     pub async fn handle_list_selection_screen_key(app: &mut App, key: KeyEvent) {
         if EventHandler::matches_global_keys(app, key) {
@@ -152,7 +513,7 @@ impl EventHandler {
                 KeyCode::Up | KeyCode::Char('K') => {
                     // Ctrl+Up: Move selected item up
                     if let Err(e) =
-                        ListsComponent::move_selected_list_up(&mut app.lists_component, &app.pool)
+                        ListsComponent::move_selected_list_up(&mut app.lists_component, &*app.store)
                             .await
                     {
                         eprintln!("Failed to move list up: {}", e);
@@ -160,13 +521,18 @@ impl EventHandler {
                 }
                 KeyCode::Down | KeyCode::Char('J') => {
                     // Ctrl+Down: Move selected item down
-                    if let Err(e) =
-                        ListsComponent::move_selected_list_down(&mut app.lists_component, &app.pool)
-                            .await
+                    if let Err(e) = ListsComponent::move_selected_list_down(
+                        &mut app.lists_component,
+                        &*app.store,
+                    )
+                    .await
                     {
                         eprintln!("Failed to move list down: {}", e);
                     }
                 }
+                KeyCode::Char('O') => app.enter_insert_list_screen(false), // Insert list above
+                KeyCode::Char('P') => EventHandler::paste_list(app, false).await, // Paste list above
+                KeyCode::Char('T') => app.save_selected_list_as_template(),
                 _ => {}
             }
             return;
@@ -182,6 +548,15 @@ impl EventHandler {
                 }
             }
             KeyCode::Char('a') => app.enter_add_list_screen(), // Add new list
+            KeyCode::Char('o') => app.enter_insert_list_screen(true), // Insert list below
+            KeyCode::Char('b') => app.enter_board_screen(),
+            KeyCode::Char('t') => app.enter_template_picker(), // New list from template
+            KeyCode::Char('y') => {
+                if let Some(text) = app.lists_component.yank_selected_list() {
+                    app.yank(text);
+                }
+            }
+            KeyCode::Char('p') => EventHandler::paste_list(app, true).await, // Paste list below
             KeyCode::Char('m') => {
                 if let Some(selected_list) = app.lists_component.get_selected_list() {
                     app.enter_modify_list_screen(&selected_list.list.clone())
@@ -201,9 +576,11 @@ impl EventHandler {
         match key.code {
             KeyCode::Char('y') | KeyCode::Char('Y') => {
                 // Actually delete the list
-                if let Err(e) =
-                    ListsComponent::delete_selected_list_static(&mut app.lists_component, &app.pool)
-                        .await
+                if let Err(e) = ListsComponent::delete_selected_list_static(
+                    &mut app.lists_component,
+                    &*app.store,
+                )
+                .await
                 {
                     eprintln!("Failed to delete list: {}", e);
                 }
@@ -231,7 +608,7 @@ impl EventHandler {
             let amount = if app.number_modifier == 0 {
                 1
             } else {
-                app.number_modifier as usize
+                app.number_modifier
             };
 
             match key.code {
@@ -240,7 +617,7 @@ impl EventHandler {
                     if let Some(selected_list) = app.lists_component.get_selected_list_mut()
                         && let Err(e) = ItemsComponent::move_selected_item_up_by(
                             selected_list,
-                            &app.pool,
+                            &*app.store,
                             amount,
                         )
                         .await
@@ -254,7 +631,7 @@ impl EventHandler {
                     if let Some(selected_list) = app.lists_component.get_selected_list_mut()
                         && let Err(e) = ItemsComponent::move_selected_item_down_by(
                             selected_list,
-                            &app.pool,
+                            &*app.store,
                             amount,
                         )
                         .await
@@ -273,6 +650,14 @@ impl EventHandler {
                         );
                     }
                 }
+                KeyCode::Char('O') => app.enter_insert_item_screen(false),
+                KeyCode::Char('Y') => {
+                    // Yank the whole list
+                    if let Some(text) = app.lists_component.yank_selected_list() {
+                        app.yank(text);
+                    }
+                }
+                KeyCode::Char('P') => EventHandler::paste_items(app, false).await,
                 _ => {}
             }
             return;
@@ -289,7 +674,7 @@ impl EventHandler {
             }
             KeyCode::Down | KeyCode::Char('j') => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
-                    match app.number_modifier.clone() {
+                    match app.number_modifier {
                         0 => ItemsComponent::select_next_item(selected_list),
                         _ => {
                             let total_items = selected_list.clone().items.len();
@@ -309,7 +694,7 @@ impl EventHandler {
             }
             KeyCode::Up | KeyCode::Char('k') => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
-                    match app.number_modifier.clone() {
+                    match app.number_modifier {
                         0 => ItemsComponent::select_previous_item(selected_list),
                         _ => {
                             let amount = if app.number_modifier > selected_list.items.len() {
@@ -330,6 +715,18 @@ impl EventHandler {
                 }
             }
             KeyCode::Char('a') => app.enter_add_item_screen(),
+            KeyCode::Char('o') => app.enter_insert_item_screen(true),
+            KeyCode::Char('b') => app.enter_board_screen(),
+            KeyCode::Char('y') => {
+                if let Some(selected_list) = app.lists_component.get_selected_list()
+                    && let Some(text) =
+                        ItemsComponent::yank_selected_items(selected_list, app.number_modifier)
+                {
+                    app.yank(text);
+                }
+                app.reset_number_modifier();
+            }
+            KeyCode::Char('p') => EventHandler::paste_items(app, true).await,
             KeyCode::Char('m') => {
                 if let Some(selected_list) = app.lists_component.get_selected_list() {
                     app.enter_modify_item_screen(&selected_list.clone())
@@ -338,14 +735,15 @@ impl EventHandler {
             KeyCode::Char('d') => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut()
                     && let Err(e) =
-                        ItemsComponent::delete_selected_item(selected_list, &app.pool).await
+                        ItemsComponent::delete_selected_item(selected_list, &*app.store).await
                 {
                     eprintln!("Failed to delete item: {}", e);
                 }
             }
             KeyCode::Enter => {
                 if let Some(selected_list) = app.lists_component.get_selected_list_mut()
-                    && let Err(e) = ItemsComponent::toggle_item_done(selected_list, &app.pool).await
+                    && let Err(e) =
+                        ItemsComponent::toggle_item_done(selected_list, &*app.store).await
                 {
                     eprintln!("Failed to toggle item: {}", e);
                 }
@@ -357,6 +755,69 @@ impl EventHandler {
         }
     }
 
+    /// Paste the register as a new list below (or above) the selected one
+    async fn paste_list(app: &mut App, below: bool) {
+        let Some(text) = app.register.clone() else {
+            return;
+        };
+        let position = match app.lists_component.selected() {
+            Some(i) if below => i + 1,
+            Some(i) => i,
+            None => app.lists_component.lists.len(),
+        };
+
+        if let Err(e) =
+            ListsComponent::paste_list(&mut app.lists_component, &text, position, &*app.store).await
+        {
+            eprintln!("Failed to paste list: {}", e);
+        }
+    }
+
+    /// Paste the register as items below (or above) the selected one
+    async fn paste_items(app: &mut App, below: bool) {
+        if let Some(text) = app.register.clone() {
+            EventHandler::paste_items_text(app, &text, below).await;
+        }
+    }
+
+    async fn paste_items_text(app: &mut App, text: &str, below: bool) {
+        if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
+            let position = match selected_list.item_state.selected() {
+                Some(j) if below => j + 1,
+                Some(j) => j,
+                None => selected_list.items.len(),
+            };
+
+            if let Err(e) =
+                ItemsComponent::paste_items(selected_list, text, position, &*app.store).await
+            {
+                eprintln!("Failed to paste items: {}", e);
+            }
+        }
+    }
+
+    /// Handle text pasted in the terminal: lines become items below the
+    /// selected one, or are typed in the text being edited
+    pub async fn handle_paste(app: &mut App, text: &str) {
+        match app.current_screen {
+            CurrentScreen::ItemSelection => {
+                EventHandler::paste_items_text(app, text, true).await;
+            }
+            CurrentScreen::AddList
+            | CurrentScreen::ModifyList
+            | CurrentScreen::AddItem
+            | CurrentScreen::ModifyItem
+            | CurrentScreen::AddDB
+            | CurrentScreen::ModifyDB
+            | CurrentScreen::EditStatuses => {
+                for c in text.chars().map(|c| if c.is_control() { ' ' } else { c }) {
+                    app.input_state.add_char(c);
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// Handle key press from user in add list screen
     pub async fn handle_add_or_modify_list_screen_key(app: &mut App, key: KeyEvent) {
         match key.code {
@@ -374,7 +835,7 @@ impl EventHandler {
                         if let Err(e) = ListsComponent::update_list(
                             &mut app.lists_component,
                             list_name,
-                            &app.pool,
+                            &*app.store,
                         )
                         .await
                         {
@@ -383,9 +844,13 @@ impl EventHandler {
                             app.go_back();
                             app.input_state.clear();
                         }
-                    } else if let Err(e) =
-                        ListsComponent::create_list(&mut app.lists_component, list_name, &app.pool)
-                            .await
+                    } else if let Err(e) = ListsComponent::create_list(
+                        &mut app.lists_component,
+                        list_name,
+                        app.input_state.insert_position,
+                        &*app.store,
+                    )
+                    .await
                     {
                         eprintln!("Failed to create list: {}", e);
                     } else {
@@ -414,15 +879,20 @@ impl EventHandler {
                 {
                     if app.input_state.is_modifying {
                         if let Err(e) =
-                            ItemsComponent::update_item(selected_list, item_name, &app.pool).await
+                            ItemsComponent::update_item(selected_list, item_name, &*app.store).await
                         {
                             eprintln!("Failed to update item: {}", e);
                         } else {
                             app.current_screen = CurrentScreen::ItemSelection;
                             app.input_state.clear();
                         }
-                    } else if let Err(e) =
-                        ItemsComponent::create_item(selected_list, item_name, &app.pool).await
+                    } else if let Err(e) = ItemsComponent::create_item(
+                        selected_list,
+                        item_name,
+                        app.input_state.insert_position,
+                        &*app.store,
+                    )
+                    .await
                     {
                         eprintln!("Failed to create item: {}", e);
                     } else {
@@ -451,7 +921,10 @@ impl EventHandler {
                 if let Err(e) = app.switch_to_selected_db().await {
                     eprintln!("Failed to switch database: {}", e);
                 }
-                app.go_back();
+                // Encrypted databases ask for their passphrase first
+                if app.current_screen != CurrentScreen::EnterPassphrase {
+                    app.go_back();
+                }
             }
             KeyCode::Char('a') => app.enter_add_db_screen(),
             KeyCode::Char('s') => {
@@ -464,6 +937,9 @@ impl EventHandler {
                 }
             }
             KeyCode::Char('m') => app.enter_modify_db_screen(),
+            KeyCode::Char('c') => app.enter_command_palette_with("duplicate-db "),
+            KeyCode::Char('M') => app.enter_command_palette_with("merge-into "),
+            KeyCode::Char('r') => app.enter_command_palette_with("move-db-file "),
             KeyCode::Char('d') => {
                 let db_name = app
                     .config
@@ -481,9 +957,10 @@ impl EventHandler {
 
     pub async fn handle_delete_database_confirmation_key(app: &mut App, key: KeyEvent) {
         match key.code {
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                // Perform the deletion
-                if let Err(e) = app.delete_selected_db().await {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Char('f') | KeyCode::Char('F') => {
+                // Perform the deletion, of the file too with F
+                let delete_file = matches!(key.code, KeyCode::Char('f') | KeyCode::Char('F'));
+                if let Err(e) = app.delete_selected_db(delete_file).await {
                     eprintln!("Failed to delete database: {}", e);
                 }
                 app.pending_delete_db_name = None;
@@ -533,10 +1010,10 @@ impl EventHandler {
         match key.code {
             KeyCode::Enter => {
                 let new_name = app.input_state.get_text().to_string();
-                if !new_name.trim().is_empty() {
-                    if let Err(e) = app.modify_selected_db(Some(new_name), None).await {
-                        eprintln!("Failed to modify database: {}", e);
-                    }
+                if !new_name.trim().is_empty()
+                    && let Err(e) = app.modify_selected_db(Some(new_name), None).await
+                {
+                    eprintln!("Failed to modify database: {}", e);
                 }
                 app.exit_modify_db_without_saving();
             }
@@ -551,4 +1028,222 @@ impl EventHandler {
             _ => {}
         }
     }
+
+    /// Handle key press from user in the passphrase screen of an encrypted database
+    pub async fn handle_passphrase_screen_key(app: &mut App, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => app.cancel_passphrase(),
+            KeyCode::Enter => {
+                if let Err(e) = app.submit_passphrase().await {
+                    eprintln!("Failed to open database: {}", e);
+                }
+            }
+            KeyCode::Backspace => app.input_state.remove_char_before_cursor(),
+            KeyCode::Char(value) => app.input_state.add_char(value),
+            _ => {}
+        }
+    }
+
+    /// Handle mouse events: clicks focus panels and select rows, clicking an item
+    /// number toggles the item, the wheel scrolls and dragging reorders lists/items
+    pub async fn handle_mouse_event(app: &mut App, mouse: MouseEvent) {
+        let main_screens = [
+            CurrentScreen::ListSelection,
+            CurrentScreen::ItemSelection,
+            CurrentScreen::DBSelection,
+        ];
+
+        // Pop-ups are keyboard only
+        if !main_screens.contains(&app.current_screen) {
+            return;
+        }
+
+        // Releasing the button always ends a drag, wherever it happens
+        if let MouseEventKind::Up(_) = mouse.kind {
+            app.dragging = None;
+            return;
+        }
+
+        let position = Position::new(mouse.column, mouse.row);
+        let Some((panel, area)) = EventHandler::panel_at(app, position) else {
+            return;
+        };
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                EventHandler::handle_mouse_click(app, panel, area, position).await
+            }
+            MouseEventKind::Drag(MouseButton::Left) if app.dragging.as_ref() == Some(&panel) => {
+                EventHandler::handle_mouse_drag(app, panel, area, position).await
+            }
+            MouseEventKind::ScrollDown => match panel {
+                CurrentScreen::ListSelection => app.lists_component.select_next(),
+                CurrentScreen::ItemSelection => {
+                    if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
+                        ItemsComponent::select_next_item(selected_list);
+                    }
+                }
+                _ => app.select_next_db(),
+            },
+            MouseEventKind::ScrollUp => match panel {
+                CurrentScreen::ListSelection => app.lists_component.select_previous(),
+                CurrentScreen::ItemSelection => {
+                    if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
+                        ItemsComponent::select_previous_item(selected_list);
+                    }
+                }
+                _ => app.select_previous_db(),
+            },
+            _ => {}
+        }
+    }
+
+    /// Find the panel (and its area) under the given position
+    fn panel_at(app: &App, position: Position) -> Option<(CurrentScreen, Rect)> {
+        let (lists_area, items_area, _logo_area, db_selector_area) =
+            AppLayout::calculate_main_layout(app.last_frame_area);
+
+        if lists_area.contains(position) {
+            Some((CurrentScreen::ListSelection, lists_area))
+        } else if items_area.contains(position) {
+            Some((CurrentScreen::ItemSelection, items_area))
+        } else if db_selector_area.contains(position) {
+            Some((CurrentScreen::DBSelection, db_selector_area))
+        } else {
+            None
+        }
+    }
+
+    /// Find the index of the list/item/database displayed at the given position
+    fn entry_at(app: &App, panel: &CurrentScreen, area: Rect, position: Position) -> Option<usize> {
+        let inner = AppLayout::panel_inner_area(area);
+        if !inner.contains(position) {
+            return None;
+        }
+
+        let row = position.y - inner.y;
+        let width = inner.width as usize;
+
+        match panel {
+            CurrentScreen::ListSelection => AppLayout::index_at_row(
+                &app.lists_component.row_heights(width),
+                app.lists_component.list_state.offset(),
+                row,
+            ),
+            CurrentScreen::ItemSelection => {
+                let selected_list = app.lists_component.get_selected_list()?;
+                AppLayout::index_at_row(
+                    &ItemsComponent::row_heights(selected_list, width),
+                    selected_list.item_state.offset(),
+                    row,
+                )
+            }
+            _ => AppLayout::index_at_row(
+                &DatabaseComponent::row_heights(&app.config, width),
+                app.database_component.list_state.offset(),
+                row,
+            ),
+        }
+    }
+
+    /// Focus the clicked panel and select the clicked row
+    async fn handle_mouse_click(
+        app: &mut App,
+        panel: CurrentScreen,
+        area: Rect,
+        position: Position,
+    ) {
+        app.current_screen = panel.clone();
+        app.last_active_screen = panel.clone();
+
+        let Some(index) = EventHandler::entry_at(app, &panel, area, position) else {
+            return;
+        };
+
+        match panel {
+            CurrentScreen::ListSelection => {
+                app.lists_component.list_state.select(Some(index));
+                app.dragging = Some(panel);
+            }
+            CurrentScreen::ItemSelection => {
+                let column = (position.x - AppLayout::panel_inner_area(area).x) as usize;
+                if let Some(selected_list) = app.lists_component.get_selected_list_mut() {
+                    selected_list.item_state.select(Some(index));
+
+                    // The item number works as a checkbox
+                    if column < ItemsComponent::gutter_width(selected_list) {
+                        if let Err(e) =
+                            ItemsComponent::toggle_item_done(selected_list, &*app.store).await
+                        {
+                            eprintln!("Failed to toggle item: {}", e);
+                        }
+                    } else {
+                        app.dragging = Some(panel);
+                    }
+                }
+            }
+            _ => app.selected_db_index = index,
+        }
+    }
+
+    /// Move the dragged list/item to the row under the pointer
+    async fn handle_mouse_drag(
+        app: &mut App,
+        panel: CurrentScreen,
+        area: Rect,
+        position: Position,
+    ) {
+        let Some(target) = EventHandler::entry_at(app, &panel, area, position) else {
+            return;
+        };
+
+        match panel {
+            CurrentScreen::ListSelection => {
+                let Some(i) = app.lists_component.selected() else {
+                    return;
+                };
+                for _ in 0..i.abs_diff(target) {
+                    let result = if target < i {
+                        ListsComponent::move_selected_list_up(&mut app.lists_component, &*app.store)
+                            .await
+                    } else {
+                        ListsComponent::move_selected_list_down(
+                            &mut app.lists_component,
+                            &*app.store,
+                        )
+                        .await
+                    };
+                    if let Err(e) = result {
+                        eprintln!("Failed to move list: {}", e);
+                        break;
+                    }
+                }
+            }
+            CurrentScreen::ItemSelection => {
+                if let Some(selected_list) = app.lists_component.get_selected_list_mut()
+                    && let Some(j) = selected_list.item_state.selected()
+                {
+                    let result = if target < j {
+                        ItemsComponent::move_selected_item_up_by(
+                            selected_list,
+                            &*app.store,
+                            j - target,
+                        )
+                        .await
+                    } else {
+                        ItemsComponent::move_selected_item_down_by(
+                            selected_list,
+                            &*app.store,
+                            target - j,
+                        )
+                        .await
+                    };
+                    if let Err(e) = result {
+                        eprintln!("Failed to move item: {}", e);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
 }
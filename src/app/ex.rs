@@ -0,0 +1,651 @@
+//! Ex command line (`:`), as in vim. Commands can take a range of items of the
+//! selected list and an argument:
+//!
+//! - `:5` selects the fifth item
+//! - `:[range]d[elete]` deletes the items (e.g. `:3,7d`, `:%d`)
+//! - `:[range]m[ove] {address}` moves the items below another (`:m 0` to the top)
+//! - `:sor[t][!] [name|priority|due|done]` sorts the items (`!` in reverse)
+//! - `:w[rite][!] {file}` exports the selected list as Markdown
+//! - `:e[dit] {db}` switches to another database
+//! - `:b[uffer] {list}` selects another list
+//! - `:q[uit]` quits
+//!
+//! Addresses are item numbers, `.` (the selected item) or `$` (the last one),
+//! optionally followed by offsets (`.+2`, `$-1`). `%` is the whole list.
+
+use crate::app::state::CurrentScreen;
+use crate::db::models::{Priority, TodoItem};
+use crate::ui::components::InputState;
+use crate::ui::cursor::CursorState;
+use color_eyre::Result;
+use color_eyre::eyre::{bail, eyre};
+use std::path::PathBuf;
+
+/// Names of the commands, with their shortest abbreviation
+pub const COMMAND_NAMES: &[(&str, &str)] = &[
+    ("buffer", "b"),
+    ("delete", "d"),
+    ("edit", "e"),
+    ("move", "m"),
+    ("quit", "q"),
+    ("sort", "sor"),
+    ("write", "w"),
+];
+
+/// Keys the items can be sorted by
+pub const SORT_KEYS: &[&str] = &["name", "priority", "due", "done"];
+
+/// Most commands kept in the history
+const MAX_HISTORY: usize = 100;
+
+/// Item an address starts from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Base {
+    /// Item number, from 1 (0 is above the first item)
+    Line(usize),
+    /// Selected item (`.`)
+    Current,
+    /// Last item (`$`)
+    Last,
+}
+
+/// Item of the selected list, e.g. `5`, `.+2` or `$`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Address {
+    pub base: Base,
+    pub offset: i64,
+}
+
+impl Address {
+    /// Item number of the address (from 1), given the index of the selected
+    /// item and the number of items
+    pub fn resolve(&self, selected: Option<usize>, len: usize) -> Result<i64> {
+        let base = match self.base {
+            Base::Line(line) => line as i64,
+            Base::Current => selected.ok_or_else(|| eyre!("No item selected"))? as i64 + 1,
+            Base::Last => len as i64,
+        };
+        Ok(base + self.offset)
+    }
+}
+
+/// Items from `start` to `end`, both included
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub start: Address,
+    pub end: Address,
+}
+
+impl Range {
+    /// Indices of the first and last items of the range, which must exist
+    pub fn resolve(&self, selected: Option<usize>, len: usize) -> Result<(usize, usize)> {
+        let start = self.start.resolve(selected, len)?;
+        let end = self.end.resolve(selected, len)?;
+        let (start, end) = (start.min(end), start.max(end));
+        if start < 1 || end > len as i64 {
+            bail!("Invalid range");
+        }
+        Ok((start as usize - 1, end as usize - 1))
+    }
+}
+
+/// Key the items are sorted by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Name,
+    Priority,
+    Due,
+    Done,
+}
+
+/// Command of the ex command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExCommand {
+    /// Select an item
+    Go(Address),
+    Delete(Option<Range>),
+    /// Move items below the item at an address
+    Move(Option<Range>, Address),
+    Sort {
+        key: SortKey,
+        reverse: bool,
+    },
+    /// Export the selected list to a file, overwriting it if `force`
+    Write {
+        path: PathBuf,
+        force: bool,
+    },
+    /// Switch to a database
+    Edit(String),
+    /// Select a list
+    Buffer(String),
+    Quit,
+}
+
+/// Full name of a command from its name or an abbreviation
+fn command_name(name: &str) -> Option<&'static str> {
+    COMMAND_NAMES
+        .iter()
+        .find(|(full, short)| full.starts_with(name) && name.starts_with(short))
+        .map(|(full, _)| *full)
+}
+
+/// Parse an address at the start of `text`, returning the rest of the text
+fn parse_address(text: &str) -> Result<(Option<Address>, &str)> {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (base, mut rest) = if digits > 0 {
+        let line = text[..digits]
+            .parse()
+            .map_err(|_| eyre!("Invalid address"))?;
+        (Some(Base::Line(line)), &text[digits..])
+    } else if let Some(rest) = text.strip_prefix('.') {
+        (Some(Base::Current), rest)
+    } else if let Some(rest) = text.strip_prefix('$') {
+        (Some(Base::Last), rest)
+    } else {
+        (None, text)
+    };
+
+    let mut offset = 0;
+    let mut has_offset = false;
+    while let Some(sign) = rest.chars().next().filter(|c| *c == '+' || *c == '-') {
+        let after = &rest[1..];
+        let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let amount: i64 = if digits == 0 {
+            1
+        } else {
+            after[..digits]
+                .parse()
+                .map_err(|_| eyre!("Invalid address"))?
+        };
+        offset += if sign == '+' { amount } else { -amount };
+        has_offset = true;
+        rest = &after[digits..];
+    }
+
+    let address = match (base, has_offset) {
+        (Some(base), _) => Some(Address { base, offset }),
+        // An offset alone is relative to the selected item (`:+2`)
+        (None, true) => Some(Address {
+            base: Base::Current,
+            offset,
+        }),
+        (None, false) => None,
+    };
+    Ok((address, rest))
+}
+
+/// Parse the range at the start of `text`, returning the rest of the text
+fn parse_range(text: &str) -> Result<(Option<Range>, &str)> {
+    if let Some(rest) = text.strip_prefix('%') {
+        let range = Range {
+            start: Address {
+                base: Base::Line(1),
+                offset: 0,
+            },
+            end: Address {
+                base: Base::Last,
+                offset: 0,
+            },
+        };
+        return Ok((Some(range), rest));
+    }
+
+    let (Some(start), rest) = parse_address(text)? else {
+        return Ok((None, text));
+    };
+    let Some(rest) = rest.strip_prefix(',') else {
+        return Ok((Some(Range { start, end: start }), rest));
+    };
+    match parse_address(rest)? {
+        (Some(end), rest) => Ok((Some(Range { start, end }), rest)),
+        (None, _) => bail!("Invalid range"),
+    }
+}
+
+/// Split a command line into its range, command name, `!` and argument
+fn split(text: &str) -> Result<(Option<Range>, &str, bool, &str)> {
+    let text = text.trim().trim_start_matches(':').trim_start();
+    let (range, rest) = parse_range(text)?;
+    let rest = rest.trim_start();
+    let name_len = rest.len() - rest.trim_start_matches(|c: char| c.is_alphabetic()).len();
+    let (name, rest) = rest.split_at(name_len);
+    let (force, rest) = match rest.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    Ok((range, name, force, rest.trim()))
+}
+
+/// Parse a command line (without its `:`)
+pub fn parse(text: &str) -> Result<ExCommand> {
+    let (range, name, force, argument) = split(text)?;
+
+    if name.is_empty() {
+        return match range {
+            Some(range) if !force && argument.is_empty() => Ok(ExCommand::Go(range.end)),
+            Some(_) => bail!("Trailing characters: {}", text.trim()),
+            None => bail!("Not a command: {}", text.trim()),
+        };
+    }
+    let name = command_name(name).ok_or_else(|| eyre!("Not a command: {}", name))?;
+
+    if range.is_some() && !matches!(name, "delete" | "move") {
+        bail!("No range allowed for {}", name);
+    }
+    if force && !matches!(name, "sort" | "write") {
+        bail!("No ! allowed for {}", name);
+    }
+    let needs_argument = matches!(name, "move" | "write" | "edit" | "buffer");
+    if needs_argument && argument.is_empty() {
+        bail!("Argument required for {}", name);
+    }
+    if matches!(name, "delete" | "quit") && !argument.is_empty() {
+        bail!("Trailing characters: {}", argument);
+    }
+
+    Ok(match name {
+        "delete" => ExCommand::Delete(range),
+        "move" => match parse_address(argument)? {
+            (Some(address), "") => ExCommand::Move(range, address),
+            _ => bail!("Invalid address: {}", argument),
+        },
+        "sort" => {
+            let key = match argument {
+                "" | "name" => SortKey::Name,
+                "priority" => SortKey::Priority,
+                "due" => SortKey::Due,
+                "done" => SortKey::Done,
+                _ => bail!("Can't sort by {} (use {})", argument, SORT_KEYS.join(", ")),
+            };
+            ExCommand::Sort {
+                key,
+                reverse: force,
+            }
+        }
+        "write" => ExCommand::Write {
+            path: expand_home(argument)?,
+            force,
+        },
+        "edit" => ExCommand::Edit(argument.to_string()),
+        "buffer" => ExCommand::Buffer(argument.to_string()),
+        _ => ExCommand::Quit,
+    })
+}
+
+/// Path of a file, with `~/` standing for the home directory
+fn expand_home(path: &str) -> Result<PathBuf> {
+    match path.strip_prefix("~/") {
+        Some(rest) => Ok(dirs::home_dir()
+            .ok_or_else(|| eyre!("Could not find home directory"))?
+            .join(rest)),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+/// Order of `len` items once the items from `start` to `end` (indices) are
+/// moved below item number `target` (0 for the top). Returns the indices of
+/// the items in their new order
+pub fn moved_order(len: usize, start: usize, end: usize, target: usize) -> Result<Vec<usize>> {
+    if target > len {
+        bail!("Invalid address");
+    }
+    if target > start && target <= end {
+        bail!("Can't move items into themselves");
+    }
+    let moved = start..=end;
+    let mut order: Vec<usize> = (0..len).filter(|i| !moved.contains(i)).collect();
+    // Items above the target stay above it
+    let position = order.iter().take_while(|&&i| i < target).count();
+    order.splice(position..position, moved);
+    Ok(order)
+}
+
+/// Order of items sorted by a key (keeping the order of equal items). Returns
+/// the indices of the items in their new order
+pub fn sorted_order(items: &[&TodoItem], key: SortKey, reverse: bool) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (items[a], items[b]);
+        let ordering = match key {
+            SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortKey::Priority => priority_rank(&a.priority).cmp(&priority_rank(&b.priority)),
+            // Items without a due date come last
+            SortKey::Due => {
+                (a.due_date.is_none(), a.due_date).cmp(&(b.due_date.is_none(), b.due_date))
+            }
+            SortKey::Done => a.is_done.cmp(&b.is_done),
+        };
+        if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    order
+}
+
+/// Rank of a priority, the highest first
+fn priority_rank(priority: &Option<Priority>) -> u8 {
+    match priority {
+        Some(Priority::High) => 0,
+        Some(Priority::Medium) => 1,
+        Some(Priority::Low) => 2,
+        None => 3,
+    }
+}
+
+/// Commands run on the ex command line, kept across sessions in a file
+#[derive(Debug, Default)]
+pub struct ExHistory {
+    /// Commands, the oldest first
+    pub entries: Vec<String>,
+    /// File the history is saved to (not saved if `None`)
+    path: Option<PathBuf>,
+}
+
+impl ExHistory {
+    /// Load the history from a file, empty if it doesn't exist yet
+    pub fn load(path: PathBuf) -> Self {
+        let entries = std::fs::read_to_string(&path)
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        ExHistory {
+            entries,
+            path: Some(path),
+        }
+    }
+
+    /// Add a command to the history (moving it to the end if already there),
+    /// and save the history
+    pub fn add(&mut self, command: &str) -> Result<()> {
+        let command = command.trim();
+        if command.is_empty() {
+            return Ok(());
+        }
+        self.entries.retain(|entry| entry != command);
+        self.entries.push(command.to_string());
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.drain(..self.entries.len() - MAX_HISTORY);
+        }
+
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, self.entries.join("\n") + "\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Candidates completing the word at the end of the input
+#[derive(Debug, Clone)]
+struct Completion {
+    /// Input before the completed word
+    head: String,
+    candidates: Vec<String>,
+    /// Candidate in the input
+    selected: usize,
+}
+
+/// State of the ex command line
+#[derive(Debug, Clone)]
+pub struct ExLine {
+    /// Command typed, without its `:`
+    pub input: InputState,
+    /// Error of the last command run, shown until the input is edited
+    pub error: Option<String>,
+    /// Screen the command line was opened from
+    pub screen: CurrentScreen,
+    /// Names the arguments are completed from
+    pub lists: Vec<String>,
+    pub databases: Vec<String>,
+    completion: Option<Completion>,
+    /// Entry of the history in the input, while browsing it
+    history_index: Option<usize>,
+    /// Input typed before browsing the history, which filters the entries
+    draft: String,
+}
+
+impl ExLine {
+    /// Open the command line. A count (as in `3:`) starts a range from the
+    /// selected item, like vim does
+    pub fn new(screen: CurrentScreen, count: usize) -> Self {
+        let mut input = InputState::default();
+        input.current_input = match count {
+            0 => String::new(),
+            1 => ".".to_string(),
+            count => format!(".,.+{}", count - 1),
+        };
+        input.cursor_pos = input.current_input.chars().count();
+        ExLine {
+            input,
+            error: None,
+            screen,
+            lists: Vec::new(),
+            databases: Vec::new(),
+            completion: None,
+            history_index: None,
+            draft: String::new(),
+        }
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.input.cursor_pos = text.chars().count();
+        self.input.current_input = text;
+    }
+
+    /// Forget the completion and the position in the history once the input
+    /// is edited
+    pub fn edited(&mut self) {
+        self.completion = None;
+        self.history_index = None;
+        self.error = None;
+    }
+
+    /// Candidates of the current completion, and the one in the input
+    pub fn completions(&self) -> Option<(&[String], usize)> {
+        self.completion
+            .as_ref()
+            .map(|completion| (completion.candidates.as_slice(), completion.selected))
+    }
+
+    /// Complete the command name or argument at the end of the input, or put
+    /// the next candidate in the input if already completing
+    pub fn complete(&mut self) {
+        if let Some(completion) = &mut self.completion {
+            completion.selected = (completion.selected + 1) % completion.candidates.len();
+            let text = format!(
+                "{}{}",
+                completion.head, completion.candidates[completion.selected]
+            );
+            self.set_text(text);
+            return;
+        }
+
+        let text = self.input.get_text().to_string();
+        let Ok((_, name, _, argument)) = split(&text) else {
+            return;
+        };
+        let after_name = text.trim_end().ends_with(name) && !text.ends_with(' ');
+        let (word, candidates): (&str, Vec<String>) = if after_name && argument.is_empty() {
+            let names = COMMAND_NAMES.iter().map(|(full, _)| full.to_string());
+            (name, names.collect())
+        } else {
+            let values = match command_name(name) {
+                Some("buffer") => self.lists.clone(),
+                Some("edit") => self.databases.clone(),
+                Some("sort") => SORT_KEYS.iter().map(|key| key.to_string()).collect(),
+                _ => return,
+            };
+            (argument, values)
+        };
+
+        let word_lower = word.to_lowercase();
+        let candidates: Vec<String> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.to_lowercase().starts_with(&word_lower))
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+        let head = text.trim_end()[..text.trim_end().len() - word.len()].to_string();
+        let head = if word.is_empty() && !after_name {
+            format!("{} ", text.trim_end())
+        } else {
+            head
+        };
+        self.set_text(format!("{}{}", head, candidates[0]));
+        self.completion = Some(Completion {
+            head,
+            candidates,
+            selected: 0,
+        });
+    }
+
+    /// Put the previous command of the history starting with what was typed
+    /// in the input
+    pub fn history_previous(&mut self, history: &ExHistory) {
+        if self.history_index.is_none() {
+            self.draft = self.input.get_text().to_string();
+        }
+        let end = self.history_index.unwrap_or(history.entries.len());
+        let found = history.entries[..end]
+            .iter()
+            .rposition(|entry| entry.starts_with(&self.draft));
+        if let Some(index) = found {
+            self.history_index = Some(index);
+            self.set_text(history.entries[index].clone());
+            self.completion = None;
+        }
+    }
+
+    /// Put the next command of the history starting with what was typed in
+    /// the input, or what was typed after the last one
+    pub fn history_next(&mut self, history: &ExHistory) {
+        let Some(current) = self.history_index else {
+            return;
+        };
+        let found = history
+            .entries
+            .iter()
+            .enumerate()
+            .skip(current + 1)
+            .find(|(_, entry)| entry.starts_with(&self.draft))
+            .map(|(index, _)| index);
+        match found {
+            Some(index) => {
+                self.history_index = Some(index);
+                self.set_text(history.entries[index].clone());
+            }
+            None => {
+                self.history_index = None;
+                self.set_text(self.draft.clone());
+            }
+        }
+        self.completion = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn line(line: usize) -> Address {
+        Address {
+            base: Base::Line(line),
+            offset: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let range = Range {
+            start: line(3),
+            end: line(7),
+        };
+        assert_eq!(parse("3,7d").unwrap(), ExCommand::Delete(Some(range)));
+        assert_eq!(parse(":m 5").unwrap(), ExCommand::Move(None, line(5)));
+        assert_eq!(parse("5").unwrap(), ExCommand::Go(line(5)));
+        assert_eq!(
+            parse("sort! priority").unwrap(),
+            ExCommand::Sort {
+                key: SortKey::Priority,
+                reverse: true
+            }
+        );
+        assert_eq!(
+            parse("w export.md").unwrap(),
+            ExCommand::Write {
+                path: PathBuf::from("export.md"),
+                force: false
+            }
+        );
+        assert_eq!(
+            parse("e otherdb").unwrap(),
+            ExCommand::Edit("otherdb".to_string())
+        );
+        assert_eq!(parse("q").unwrap(), ExCommand::Quit);
+
+        let ExCommand::Move(Some(range), to) = parse(".,.+2move $-1").unwrap() else {
+            panic!("Not a move");
+        };
+        assert_eq!(range.resolve(Some(1), 10).unwrap(), (1, 3));
+        assert_eq!(to.resolve(Some(1), 10).unwrap(), 9);
+        assert_eq!(parse("%d").unwrap(), parse("1,$delete").unwrap());
+
+        assert!(parse("so").is_err());
+        assert!(parse("2,4sort").is_err());
+        assert!(parse("frobnicate").is_err());
+        assert!(parse("e").is_err());
+        assert!(parse("3,").is_err());
+        let ExCommand::Delete(Some(range)) = parse("8d").unwrap() else {
+            panic!("Not a delete");
+        };
+        assert!(range.resolve(None, 5).is_err());
+    }
+
+    #[test]
+    fn test_moved_order() {
+        assert_eq!(moved_order(5, 0, 1, 5).unwrap(), vec![2, 3, 4, 0, 1]);
+        assert_eq!(moved_order(5, 3, 3, 0).unwrap(), vec![3, 0, 1, 2, 4]);
+        assert_eq!(moved_order(5, 1, 2, 3).unwrap(), vec![0, 1, 2, 3, 4]);
+        assert!(moved_order(5, 0, 2, 2).is_err());
+        assert!(moved_order(5, 0, 0, 6).is_err());
+    }
+
+    #[test]
+    fn test_complete_and_history() {
+        let ex_line = ExLine::new(CurrentScreen::ListSelection, 3);
+        assert_eq!(ex_line.input.get_text(), ".,.+2");
+
+        let mut ex_line = ExLine::new(CurrentScreen::ListSelection, 0);
+        ex_line.lists = vec!["Groceries".to_string(), "Garden".to_string()];
+        ex_line.set_text("so".to_string());
+        ex_line.complete();
+        assert_eq!(ex_line.input.get_text(), "sort");
+
+        ex_line.set_text("b g".to_string());
+        ex_line.edited();
+        ex_line.complete();
+        assert_eq!(ex_line.input.get_text(), "b Groceries");
+        ex_line.complete();
+        assert_eq!(ex_line.input.get_text(), "b Garden");
+        assert_eq!(ex_line.completions().unwrap().1, 1);
+
+        let mut history = ExHistory::default();
+        for command in ["w list.md", "3d", "w other.md", "q"] {
+            history.add(command).unwrap();
+        }
+        ex_line.set_text("w".to_string());
+        ex_line.edited();
+        ex_line.history_previous(&history);
+        assert_eq!(ex_line.input.get_text(), "w other.md");
+        ex_line.history_previous(&history);
+        assert_eq!(ex_line.input.get_text(), "w list.md");
+        ex_line.history_next(&history);
+        ex_line.history_next(&history);
+        assert_eq!(ex_line.input.get_text(), "w");
+    }
+}
@@ -1,4 +1,6 @@
 pub mod events;
+pub mod ex;
+pub mod palette;
 pub mod state;
 
 pub use events::EventHandler;
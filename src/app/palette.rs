@@ -0,0 +1,731 @@
+//! Command palette (`Ctrl + p`): the actions of the current screen, found by
+//! typing part of their name. Actions bound to keys are run by pressing their
+//! keys on the screen the palette was opened from, so that the palette always
+//! does what the keys do. Actions taking an argument (`rename <name>`,
+//! `move-to <list>`, `db <name>`...) complete it from the lists, databases,
+//! scripts and themes.
+
+use crate::app::state::CurrentScreen;
+use crate::ui::components::InputState;
+use crate::ui::cursor::CursorState;
+use crate::ui::theme::THEME_PRESETS;
+
+/// Argument of a command, after its name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Argument {
+    /// Any text (e.g. a new name)
+    Text,
+    /// Path of a file
+    Path,
+    /// Name of a list of the current database
+    List,
+    /// Name of a database of the config
+    Database,
+    /// Name of a script of the scripts directory
+    Script,
+    /// Name of a built-in theme
+    Theme,
+}
+
+impl Argument {
+    /// Placeholder of the argument, shown until it is typed
+    fn placeholder(&self) -> &'static str {
+        match self {
+            Argument::Text => "<name>",
+            Argument::Path => "<path>",
+            Argument::List => "<list>",
+            Argument::Database => "<db>",
+            Argument::Script => "<script>",
+            Argument::Theme => "<theme>",
+        }
+    }
+}
+
+/// What a command does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// Press keys (in vim notation) on the screen the palette was opened from
+    Keys(&'static [&'static str]),
+    /// Rename the selected list or item
+    Rename,
+    /// Move the selected item to another list
+    MoveTo,
+    /// Switch to another database
+    SwitchDb,
+    /// Run a script
+    RunScript,
+    /// Use a built-in theme
+    UseTheme,
+    /// Copy the selected database to a new one
+    DuplicateDb,
+    /// Copy the lists of the selected database into another
+    MergeDbInto,
+    /// Move the file of the selected database
+    MoveDbFile,
+}
+
+impl Action {
+    /// Argument the action needs, if any
+    pub fn argument(&self) -> Option<Argument> {
+        match self {
+            Action::Keys(_) => None,
+            Action::Rename => Some(Argument::Text),
+            Action::MoveTo => Some(Argument::List),
+            Action::SwitchDb => Some(Argument::Database),
+            Action::RunScript => Some(Argument::Script),
+            Action::UseTheme => Some(Argument::Theme),
+            Action::DuplicateDb => Some(Argument::Text),
+            Action::MergeDbInto => Some(Argument::Database),
+            Action::MoveDbFile => Some(Argument::Path),
+        }
+    }
+}
+
+/// Action of the command palette
+#[derive(Debug)]
+pub struct Command {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Screens the command is available on
+    pub screens: &'static [CurrentScreen],
+    pub action: Action,
+}
+
+const MAIN: &[CurrentScreen] = &[
+    CurrentScreen::ListSelection,
+    CurrentScreen::ItemSelection,
+    CurrentScreen::DBSelection,
+];
+const LISTS: &[CurrentScreen] = &[CurrentScreen::ListSelection];
+const ITEMS: &[CurrentScreen] = &[CurrentScreen::ItemSelection];
+const LISTS_AND_ITEMS: &[CurrentScreen] =
+    &[CurrentScreen::ListSelection, CurrentScreen::ItemSelection];
+const DATABASES: &[CurrentScreen] = &[CurrentScreen::DBSelection];
+const BOARD: &[CurrentScreen] = &[CurrentScreen::Board];
+const CALENDAR: &[CurrentScreen] = &[CurrentScreen::Calendar];
+
+const fn command(
+    name: &'static str,
+    description: &'static str,
+    screens: &'static [CurrentScreen],
+    action: Action,
+) -> Command {
+    Command {
+        name,
+        description,
+        screens,
+        action,
+    }
+}
+
+/// Commands of the palette, in the order they are listed
+pub const COMMANDS: &[Command] = &[
+    // Lists
+    command(
+        "add-list",
+        "Add a list at the end",
+        LISTS,
+        Action::Keys(&["a"]),
+    ),
+    command(
+        "insert-list-below",
+        "Add a list below the selected one",
+        LISTS,
+        Action::Keys(&["o"]),
+    ),
+    command(
+        "insert-list-above",
+        "Add a list above the selected one",
+        LISTS,
+        Action::Keys(&["O"]),
+    ),
+    command(
+        "modify-list",
+        "Edit the name of the selected list",
+        LISTS,
+        Action::Keys(&["m"]),
+    ),
+    command(
+        "delete-list",
+        "Delete the selected list",
+        LISTS,
+        Action::Keys(&["d"]),
+    ),
+    command(
+        "move-list-up",
+        "Move the selected list up",
+        LISTS,
+        Action::Keys(&["K"]),
+    ),
+    command(
+        "move-list-down",
+        "Move the selected list down",
+        LISTS,
+        Action::Keys(&["J"]),
+    ),
+    command(
+        "yank-list",
+        "Copy the selected list",
+        LISTS,
+        Action::Keys(&["y"]),
+    ),
+    command(
+        "paste-list-below",
+        "Paste a list below the selected one",
+        LISTS,
+        Action::Keys(&["p"]),
+    ),
+    command(
+        "paste-list-above",
+        "Paste a list above the selected one",
+        LISTS,
+        Action::Keys(&["P"]),
+    ),
+    command(
+        "new-list-from-template",
+        "Create a list from a template",
+        LISTS,
+        Action::Keys(&["t"]),
+    ),
+    command(
+        "save-as-template",
+        "Save the selected list as a template",
+        LISTS,
+        Action::Keys(&["T"]),
+    ),
+    command(
+        "open-list",
+        "Go to the items of the selected list",
+        LISTS,
+        Action::Keys(&["l"]),
+    ),
+    // Items
+    command(
+        "add-item",
+        "Add an item at the end",
+        ITEMS,
+        Action::Keys(&["a"]),
+    ),
+    command(
+        "insert-item-below",
+        "Add an item below the selected one",
+        ITEMS,
+        Action::Keys(&["o"]),
+    ),
+    command(
+        "insert-item-above",
+        "Add an item above the selected one",
+        ITEMS,
+        Action::Keys(&["O"]),
+    ),
+    command(
+        "modify-item",
+        "Edit the name of the selected item",
+        ITEMS,
+        Action::Keys(&["m"]),
+    ),
+    command(
+        "toggle-done",
+        "Mark the selected item done or not done",
+        ITEMS,
+        Action::Keys(&["<Enter>"]),
+    ),
+    command(
+        "delete-item",
+        "Delete the selected item",
+        ITEMS,
+        Action::Keys(&["d"]),
+    ),
+    command(
+        "move-item-up",
+        "Move the selected item up",
+        ITEMS,
+        Action::Keys(&["K"]),
+    ),
+    command(
+        "move-item-down",
+        "Move the selected item down",
+        ITEMS,
+        Action::Keys(&["J"]),
+    ),
+    command(
+        "yank-items",
+        "Copy the selected item",
+        ITEMS,
+        Action::Keys(&["y"]),
+    ),
+    command(
+        "yank-all-items",
+        "Copy all the items of the list",
+        ITEMS,
+        Action::Keys(&["Y"]),
+    ),
+    command(
+        "paste-items-below",
+        "Paste items below the selected one",
+        ITEMS,
+        Action::Keys(&["p"]),
+    ),
+    command(
+        "paste-items-above",
+        "Paste items above the selected one",
+        ITEMS,
+        Action::Keys(&["P"]),
+    ),
+    command(
+        "back-to-lists",
+        "Go back to the lists",
+        ITEMS,
+        Action::Keys(&["h"]),
+    ),
+    command(
+        "move-to",
+        "Move the selected item to another list",
+        ITEMS,
+        Action::MoveTo,
+    ),
+    // Lists and items
+    command(
+        "rename",
+        "Rename the selected list or item",
+        LISTS_AND_ITEMS,
+        Action::Rename,
+    ),
+    command(
+        "board",
+        "Show the selected list as a board",
+        LISTS_AND_ITEMS,
+        Action::Keys(&["b"]),
+    ),
+    // Databases
+    command(
+        "open-db",
+        "Open the selected database",
+        DATABASES,
+        Action::Keys(&["<Enter>"]),
+    ),
+    command("add-db", "Add a database", DATABASES, Action::Keys(&["a"])),
+    command(
+        "set-default-db",
+        "Open the selected database by default",
+        DATABASES,
+        Action::Keys(&["s"]),
+    ),
+    command(
+        "modify-db",
+        "Rename the selected database and its file",
+        DATABASES,
+        Action::Keys(&["m"]),
+    ),
+    command(
+        "delete-db",
+        "Remove the selected database",
+        DATABASES,
+        Action::Keys(&["d"]),
+    ),
+    command(
+        "duplicate-db",
+        "Copy the selected database to a new one",
+        DATABASES,
+        Action::DuplicateDb,
+    ),
+    command(
+        "merge-into",
+        "Copy the lists of the selected database into another",
+        DATABASES,
+        Action::MergeDbInto,
+    ),
+    command(
+        "move-db-file",
+        "Move the file of the selected database",
+        DATABASES,
+        Action::MoveDbFile,
+    ),
+    // Everywhere on the main screens
+    command("db", "Switch to a database", MAIN, Action::SwitchDb),
+    command("script", "Run a script", MAIN, Action::RunScript),
+    command("theme", "Use a theme", MAIN, Action::UseTheme),
+    command(
+        "go-to-lists",
+        "Go to the lists",
+        MAIN,
+        Action::Keys(&["<Space>", "1"]),
+    ),
+    command(
+        "go-to-items",
+        "Go to the items",
+        MAIN,
+        Action::Keys(&["<Space>", "2"]),
+    ),
+    command(
+        "go-to-databases",
+        "Go to the databases",
+        MAIN,
+        Action::Keys(&["<Space>", "3"]),
+    ),
+    command(
+        "choose-theme",
+        "Preview and choose a theme",
+        MAIN,
+        Action::Keys(&["<Space>", "t"]),
+    ),
+    command(
+        "calendar",
+        "Show the items by due date",
+        MAIN,
+        Action::Keys(&["<Space>", "c"]),
+    ),
+    command(
+        "sync",
+        "Sync with git and CalDAV",
+        MAIN,
+        Action::Keys(&["<Space>", "s"]),
+    ),
+    command(
+        "hook-log",
+        "Show the hooks that failed",
+        MAIN,
+        Action::Keys(&["<Space>", "h"]),
+    ),
+    command(
+        "choose-script",
+        "Choose a script to run",
+        MAIN,
+        Action::Keys(&["<Space>", "x"]),
+    ),
+    command(
+        "help",
+        "Show the key bindings",
+        MAIN,
+        Action::Keys(&["<C-h>"]),
+    ),
+    command("quit", "Quit Judo", MAIN, Action::Keys(&["q"])),
+    // Board
+    command(
+        "move-card-left",
+        "Move the card to the previous status",
+        BOARD,
+        Action::Keys(&["H"]),
+    ),
+    command(
+        "move-card-right",
+        "Move the card to the next status",
+        BOARD,
+        Action::Keys(&["L"]),
+    ),
+    command(
+        "move-card-up",
+        "Move the card up its column",
+        BOARD,
+        Action::Keys(&["K"]),
+    ),
+    command(
+        "move-card-down",
+        "Move the card down its column",
+        BOARD,
+        Action::Keys(&["J"]),
+    ),
+    command(
+        "edit-statuses",
+        "Edit the statuses of the board",
+        BOARD,
+        Action::Keys(&["e"]),
+    ),
+    command(
+        "close-board",
+        "Go back to the lists",
+        BOARD,
+        Action::Keys(&["<Esc>"]),
+    ),
+    // Calendar
+    command("today", "Select today", CALENDAR, Action::Keys(&["t"])),
+    command(
+        "next-month",
+        "Go to the next month",
+        CALENDAR,
+        Action::Keys(&["L"]),
+    ),
+    command(
+        "previous-month",
+        "Go to the previous month",
+        CALENDAR,
+        Action::Keys(&["H"]),
+    ),
+    command(
+        "close-calendar",
+        "Go back to the lists",
+        CALENDAR,
+        Action::Keys(&["<Esc>"]),
+    ),
+];
+
+/// Whether the palette can be opened on a screen
+pub fn has_commands(screen: &CurrentScreen) -> bool {
+    COMMANDS
+        .iter()
+        .any(|command| command.screens.contains(screen))
+}
+
+/// Score of a text for a fuzzy pattern, if the characters of the pattern are
+/// found in the text in order (ignoring case). Characters following each
+/// other or starting words score higher
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+
+    for p in pattern
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+    {
+        let found = (next..text.len()).find(|&i| text[i] == p)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+/// Items matching a fuzzy pattern, the best matches first (and in their order
+/// for equal scores)
+fn fuzzy_filter<T>(
+    pattern: &str,
+    items: impl IntoIterator<Item = T>,
+    text: impl Fn(&T) -> &str,
+) -> Vec<T> {
+    let mut scored: Vec<(i64, T)> = items
+        .into_iter()
+        .filter_map(|item| fuzzy_score(pattern, text(&item)).map(|score| (score, item)))
+        .collect();
+    scored.sort_by_key(|(score, _)| -score);
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Command listed in the palette, with its argument if typed or completed
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub command: &'static Command,
+    pub argument: Option<String>,
+}
+
+impl PaletteEntry {
+    /// Command as typed, with a placeholder for its missing argument
+    pub fn label(&self) -> String {
+        match (&self.argument, self.command.action.argument()) {
+            (Some(argument), _) => format!("{} {}", self.command.name, argument),
+            (None, Some(argument)) => format!("{} {}", self.command.name, argument.placeholder()),
+            (None, None) => self.command.name.to_string(),
+        }
+    }
+
+    /// Keys running the command outside of the palette, if any (e.g. `SPACE + c`)
+    pub fn keys(&self) -> String {
+        let Action::Keys(keys) = self.command.action else {
+            return String::new();
+        };
+        keys.iter()
+            .map(|key| match *key {
+                "<Space>" => "SPACE".to_string(),
+                "<Enter>" => "Enter".to_string(),
+                "<Esc>" => "Esc".to_string(),
+                key => match key.strip_prefix("<C-").and_then(|k| k.strip_suffix('>')) {
+                    Some(key) => format!("Ctrl + {}", key),
+                    None => key.to_string(),
+                },
+            })
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+
+    /// Whether the command can run: commands taking an argument need it
+    pub fn is_complete(&self) -> bool {
+        self.command.action.argument().is_none() || self.argument.is_some()
+    }
+
+    /// Input completed with the entry, ready for the argument if missing
+    pub fn completion(&self) -> String {
+        match (&self.argument, self.command.action.argument()) {
+            (Some(argument), _) => format!("{} {}", self.command.name, argument),
+            (None, Some(_)) => format!("{} ", self.command.name),
+            (None, None) => self.command.name.to_string(),
+        }
+    }
+}
+
+/// State of the command palette
+#[derive(Debug, Clone)]
+pub struct CommandPalette {
+    /// Text typed: part of a command name, then its argument after a space
+    pub input: InputState,
+    /// Selected entry
+    pub selected: usize,
+    /// Screen the palette was opened from, whose commands are listed
+    pub screen: CurrentScreen,
+    /// Names the arguments are completed from
+    pub lists: Vec<String>,
+    pub databases: Vec<String>,
+    pub scripts: Vec<String>,
+}
+
+impl CommandPalette {
+    pub fn new(screen: CurrentScreen) -> Self {
+        CommandPalette {
+            input: InputState::default(),
+            selected: 0,
+            screen,
+            lists: Vec::new(),
+            databases: Vec::new(),
+            scripts: Vec::new(),
+        }
+    }
+
+    /// Entries matching the input: the commands of the screen matching the
+    /// name typed, or once a command name and a space are typed, the command
+    /// with the arguments matching the rest
+    pub fn entries(&self) -> Vec<PaletteEntry> {
+        let commands = COMMANDS
+            .iter()
+            .filter(|command| command.screens.contains(&self.screen));
+        let text = self.input.get_text().trim_start();
+
+        let Some((name, rest)) = text.split_once(' ') else {
+            return fuzzy_filter(text, commands, |command| command.name)
+                .into_iter()
+                .map(|command| PaletteEntry {
+                    command,
+                    argument: None,
+                })
+                .collect();
+        };
+
+        let Some(command) = commands.into_iter().find(|command| command.name == name) else {
+            return Vec::new();
+        };
+        let rest = rest.trim();
+        let values: &[String] = match command.action.argument() {
+            None => {
+                return vec![PaletteEntry {
+                    command,
+                    argument: None,
+                }];
+            }
+            Some(Argument::Text | Argument::Path) => {
+                let argument = (!rest.is_empty()).then(|| rest.to_string());
+                return vec![PaletteEntry { command, argument }];
+            }
+            Some(Argument::List) => &self.lists,
+            Some(Argument::Database) => &self.databases,
+            Some(Argument::Script) => &self.scripts,
+            Some(Argument::Theme) => {
+                let themes: Vec<String> = THEME_PRESETS.iter().map(|t| t.to_string()).collect();
+                return self.argument_entries(command, rest, &themes);
+            }
+        };
+        self.argument_entries(command, rest, values)
+    }
+
+    fn argument_entries(
+        &self,
+        command: &'static Command,
+        pattern: &str,
+        values: &[String],
+    ) -> Vec<PaletteEntry> {
+        fuzzy_filter(pattern, values, |value| value.as_str())
+            .into_iter()
+            .map(|value| PaletteEntry {
+                command,
+                argument: Some(value.clone()),
+            })
+            .collect()
+    }
+
+    /// Selected entry, if any matches the input
+    pub fn selected_entry(&self) -> Option<PaletteEntry> {
+        self.entries().into_iter().nth(self.selected)
+    }
+
+    /// Select the next (or previous, if `offset` is negative) entry
+    pub fn select(&mut self, offset: isize) {
+        let count = self.entries().len();
+        if count > 0 {
+            self.selected = (self.selected as isize + offset).rem_euclid(count as isize) as usize;
+        }
+    }
+
+    /// Replace the input with the completion of the selected entry
+    pub fn complete(&mut self) {
+        if let Some(entry) = self.selected_entry() {
+            let completion = entry.completion();
+            self.input.cursor_pos = completion.chars().count();
+            self.input.current_input = completion;
+            self.selected = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn palette(screen: CurrentScreen, input: &str) -> CommandPalette {
+        let mut palette = CommandPalette::new(screen);
+        palette.lists = vec!["Groceries".to_string(), "Chores".to_string()];
+        palette.input.current_input = input.to_string();
+        palette
+    }
+
+    fn labels(palette: &CommandPalette) -> Vec<String> {
+        palette
+            .entries()
+            .iter()
+            .map(|entry| entry.label())
+            .collect()
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("tgd", "toggle-done").is_some());
+        assert!(fuzzy_score("dgt", "toggle-done").is_none());
+        assert!(fuzzy_score("add", "add-item") > fuzzy_score("add", "paste-items-down"));
+        assert!(fuzzy_score("mt", "move-to") > fuzzy_score("mt", "delete-item"));
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn test_palette_entries() {
+        let items = palette(CurrentScreen::ItemSelection, "tgl");
+        assert_eq!(labels(&items)[0], "toggle-done");
+
+        // Commands of other screens aren't listed
+        let lists = palette(CurrentScreen::ListSelection, "toggle");
+        assert!(!labels(&lists).contains(&"toggle-done".to_string()));
+
+        let mut move_to = palette(CurrentScreen::ItemSelection, "move-t");
+        assert_eq!(labels(&move_to)[0], "move-to <list>");
+        assert!(!move_to.selected_entry().unwrap().is_complete());
+        move_to.complete();
+        assert_eq!(move_to.input.get_text(), "move-to ");
+        assert_eq!(
+            labels(&move_to),
+            vec!["move-to Groceries", "move-to Chores"]
+        );
+
+        move_to.input.current_input = "move-to cho".to_string();
+        let entry = move_to.selected_entry().unwrap();
+        assert!(entry.is_complete());
+        assert_eq!(entry.argument.as_deref(), Some("Chores"));
+
+        let rename = palette(CurrentScreen::ListSelection, "rename Shopping list");
+        assert_eq!(labels(&rename), vec!["rename Shopping list"]);
+        let entry = palette(CurrentScreen::DBSelection, "go-to-lists").selected_entry();
+        assert_eq!(entry.unwrap().keys(), "SPACE + 1");
+    }
+}
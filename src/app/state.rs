@@ -1,22 +1,51 @@
 use crate::app::events::EventHandler;
+use crate::app::ex::{self, ExCommand, ExHistory, ExLine};
+use crate::app::palette::{self, Action, CommandPalette, PaletteEntry};
+#[cfg(unix)]
+use crate::ctl::{ControlServer, Message, Request, Snapshot};
 use crate::db::config::{Config, DBConfig};
-use crate::db::connections::init_db;
-use crate::db::models::{TodoList, UIList};
+use crate::db::crypto;
+use crate::db::manage;
+use crate::db::models::{NewTodoItem, TodoItem, TodoList, UIList};
+use crate::db::store::{TodoStore, open_store};
+use crate::hooks::{HookEvent, HookKind, HookedStore, Hooks};
+use crate::remind::{ChannelNotifier, Reminder, Reminders, SystemClock};
+use crate::script::{self, RunningScript, Script, ScriptContext, ScriptRequest};
+use crate::sync::caldav::{CaldavSync, RunningCaldavSync};
+use crate::sync::merge::SyncConflict;
+use crate::sync::{GitSync, RunningGitSync, change_log};
+use crate::ui::clipboard;
 use crate::ui::components::{
-    AddDBPopUp, AddItemPopUp, AddListPopUp, DatabaseComponent, HelpPopUp, InputState,
+    AddDBPopUp, AddItemPopUp, AddListPopUp, BoardComponent, CalendarComponent, CommandPalettePopUp,
+    DatabaseComponent, EditStatusesPopUp, ExLinePopUp, HelpPopUp, HookLogPopUp, InputState,
     ItemsComponent, LeaderHelpPopUp, ListsComponent, Logo, ModifyDBPopUp, ModifyItemPopUp,
-    ModifyListPopUp,
+    ModifyListPopUp, PassphrasePopUp, ScriptOutputPopUp, ScriptPromptPopUp, ScriptSelectPopUp,
+    SyncReportPopUp, TemplateReportPopUp, TemplateSelectPopUp, TemplateVariablePopUp,
+    ThemeSelectionPopUp, ToastPopUp,
 };
 use crate::ui::cursor::CursorState;
+use crate::ui::keys::KeyBinding;
 use crate::ui::layout::AppLayout;
-use crate::ui::theme::Theme;
+use crate::ui::templates::{self, Template, TemplateForm};
+use crate::ui::theme::{ColorSupport, THEME_PRESETS, Theme};
 use color_eyre::Result;
-use crossterm::event::{self, KeyEvent};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::DefaultTerminal;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::widgets::Widget;
 use sqlx::SqlitePool;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// How long the main loop waits for an event before checking for reminders
+const TICK: Duration = Duration::from_millis(250);
+
+/// How long a reminder stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(8);
 
 /// Enum representing the different screens in the application
 #[derive(Debug, Clone, PartialEq)]
@@ -47,22 +76,113 @@ pub enum CurrentScreen {
     DeleteListConfirmation,
     /// Pop-up screen for deleting a database
     DeleteDatabaseConfirmation,
+    /// Pop-up screen for previewing and choosing a theme
+    ThemeSelection,
+    /// Pop-up screen for entering the passphrase of an encrypted database
+    EnterPassphrase,
+    /// Screen showing the items with a due date on a month grid
+    Calendar,
+    /// Screen showing the items of the selected list as a kanban board
+    Board,
+    /// Pop-up screen for editing the statuses of the board of a list
+    EditStatuses,
+    /// Pop-up screen listing the hooks that failed
+    HookLog,
+    /// Pop-up screen asking a question of a script
+    ScriptPrompt,
+    /// Pop-up screen for choosing among the options of a script, or the script to run
+    ScriptSelect,
+    /// Pop-up screen listing the actions of the screen it was opened from
+    CommandPalette,
+    /// Ex command line (`:`) at the bottom of the screen it was opened from
+    ExLine,
+    /// Pop-up screen listing the templates to create a list from
+    TemplateSelect,
+    /// Pop-up screen asking for the value of a variable of a template
+    TemplateVariable,
+}
+
+/// Question of a script (or choice of the script to run) waiting for an answer
+#[derive(Debug)]
+pub struct ScriptQuestion {
+    /// Question, or title of the options
+    pub title: String,
+    /// Options of a selection. Empty for a prompt, answered in the input field
+    pub options: Vec<String>,
+    /// Selected option
+    pub selected: usize,
+    /// Where the answer goes (`None` for the script picker)
+    reply: Option<Sender<Option<String>>>,
+    /// Screen to go back to once answered
+    return_screen: CurrentScreen,
+}
+
+/// What the git sync of the current database is asked to do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GitJob {
+    /// Commit the local changes
+    Commit,
+    /// Commit the local changes, then merge the remote and push
+    Sync,
+}
+
+/// Database waiting for its passphrase before being opened
+pub struct PendingUnlock {
+    /// Config of the database
+    pub db_config: DBConfig,
+    /// Store of the database
+    pub store: Arc<dyn TodoStore>,
+    /// Connection to the database (only SQLite databases can be encrypted)
+    pub pool: SqlitePool,
+    /// Whether the database isn't encrypted yet and the passphrase will be used to encrypt it
+    pub is_new_passphrase: bool,
+    /// Error of the last attempt (e.g. wrong passphrase)
+    pub error: Option<String>,
+}
+
+impl PendingUnlock {
+    /// Check whether a database needs a passphrase before it can be read: it is
+    /// encrypted and its key isn't cached yet, or it is configured as encrypted
+    /// but hasn't been encrypted yet
+    pub async fn for_db(db_config: &DBConfig, store: &Arc<dyn TodoStore>) -> Option<PendingUnlock> {
+        let pool = store.sqlite_pool()?;
+        if crypto::is_unlocked(pool) {
+            return None;
+        }
+
+        let is_encrypted = crypto::is_encrypted(pool).await.unwrap_or(false);
+        (is_encrypted || db_config.encrypted).then(|| PendingUnlock {
+            db_config: db_config.clone(),
+            store: store.clone(),
+            pool: pool.clone(),
+            is_new_passphrase: !is_encrypted,
+            error: None,
+        })
+    }
 }
 
 /// Main application state
 pub struct App {
     /// Configuration of available databases
     pub config: Config,
+    /// Path of the config file, written when the config changes
+    pub config_path: PathBuf,
+    /// Directory where new databases are created
+    pub data_dir: PathBuf,
     /// Config of currently selected database
     pub current_db_config: DBConfig,
     /// Current active screen (Main, AddList, ModifyList, or AddItem)
     pub current_screen: CurrentScreen,
-    /// Database connection pool
-    pub pool: SqlitePool,
+    /// Store of the current database
+    pub store: Arc<dyn TodoStore>,
     /// Database component for managing databases
     pub database_component: DatabaseComponent,
     /// Lists component for managing todo lists
     pub lists_component: ListsComponent,
+    /// Calendar of the items by due date
+    pub calendar_component: CalendarComponent,
+    /// Board of the selected list
+    pub board_component: BoardComponent,
     /// State of user-provided input
     pub input_state: InputState,
     /// Selected database index for DB selector
@@ -71,6 +191,10 @@ pub struct App {
     pub exit: bool,
     /// Theme configuration for the application
     pub theme: Theme,
+    /// Colors supported by the terminal, used to adapt the theme
+    pub color_support: ColorSupport,
+    /// Selected theme index for the theme selector
+    pub selected_theme_index: usize,
     /// Pending delete list name
     pub pending_delete_list_name: Option<String>,
     /// Pending delete database name
@@ -85,36 +209,147 @@ pub struct App {
     pub awaiting_second_g: bool,
     /// The last active screen
     pub last_active_screen: CurrentScreen,
+    /// Area of the last rendered frame, used to map mouse clicks to components
+    pub last_frame_area: Rect,
+    /// Panel in which a mouse drag (used for reordering) is in progress
+    pub dragging: Option<CurrentScreen>,
+    /// Git sync of the current database, if configured (and not running)
+    pub git_sync: Option<GitSync>,
+    /// Git sync running in the background, with the name of its database
+    pub running_git_sync: Option<(String, GitJob, RunningGitSync)>,
+    /// Git job asked for while another one was running
+    pub pending_git_job: Option<GitJob>,
+    /// CalDAV sync of the current database, if configured (and not running)
+    pub caldav_sync: Option<CaldavSync>,
+    /// CalDAV sync running in the background, with the name of its database
+    pub running_caldav_sync: Option<(String, RunningCaldavSync)>,
+    /// Conflicts and errors of the last sync, shown until dismissed
+    pub sync_messages: Vec<String>,
+    /// Encrypted database waiting for its passphrase
+    pub pending_unlock: Option<PendingUnlock>,
+    /// Markdown of the last yanked list or items, pasted with p/P
+    pub register: Option<String>,
+    /// Last reminder of an item due soon, shown for a few seconds
+    pub toast: Option<(Reminder, Instant)>,
+    /// Hooks of the config, fired by the store of the current database
+    pub hooks: Option<Arc<Hooks>>,
+    /// Scripts bound to keys in the config
+    pub script_keys: Vec<(KeyBinding, String)>,
+    /// Scripts listed in the script picker
+    pub scripts: Vec<Script>,
+    /// Script running in the background, if any
+    pub running_script: Option<RunningScript>,
+    /// Question of the running script (or choice of a script) waiting for an answer
+    pub script_question: Option<ScriptQuestion>,
+    /// Notifications and error of the last script, shown once it is over
+    pub script_output: Vec<String>,
+    /// Command palette, while open
+    pub palette: Option<CommandPalette>,
+    /// Ex command line, while open
+    pub ex_line: Option<ExLine>,
+    /// Commands run on the ex command line, kept across sessions
+    pub ex_history: ExHistory,
+    /// Templates listed in the template picker
+    pub templates: Vec<Template>,
+    /// Selected template in the template picker
+    pub selected_template: usize,
+    /// Template being filled in before its list is created
+    pub template_form: Option<TemplateForm>,
+    /// Where the last template was saved, or why a template couldn't be used or
+    /// saved, shown until dismissed
+    pub template_messages: Vec<String>,
+    /// Socket scripts drive the app through (started by [`App::run`])
+    #[cfg(unix)]
+    pub control: Option<ControlServer>,
+    /// What the clients of the socket saw last, to tell them what changed
+    #[cfg(unix)]
+    pub control_snapshot: Option<Snapshot>,
 }
 
 impl App {
     /// Create new app instance
     ///
-    /// Initializes the database connection, loads existing lists from the database,
-    /// and sets up the initial UI state.
+    /// Reads the config (creating a default one if missing), connects to the
+    /// default database and sets up the initial UI state.
     pub async fn new() -> Self {
         // Read the config (creates default if missing)
         let config = Config::read().expect("Failed to read config file");
-        let theme = config.theme.clone().unwrap_or(Theme::default());
 
         // Extract the default db and its connection string
         let default_db_config = config
             .get_default()
             .expect("Couldn't fetch default database");
-        let pool = init_db(&default_db_config.connection_str)
+        let store = open_store(&default_db_config.connection_str)
             .await
             .expect("Failed to connect to database");
 
+        let config_path = Config::default_path().expect("Could not find config directory");
+        let data_dir = Config::default_data_dir().expect("Could not find data directory");
+
+        Self::with_config(config, store, config_path, data_dir)
+            .await
+            .expect("Failed to start")
+    }
+
+    /// Create an app instance from a config and the store of its default
+    /// database. The config is saved to `config_path` when modified and new
+    /// databases are created in `data_dir`
+    pub async fn with_config(
+        config: Config,
+        store: Arc<dyn TodoStore>,
+        config_path: PathBuf,
+        data_dir: PathBuf,
+    ) -> Result<Self> {
+        let color_support = ColorSupport::detect();
+        let theme = config
+            .theme
+            .clone()
+            .unwrap_or_default()
+            .resolve()
+            .map_err(|e| color_eyre::eyre::eyre!("Invalid theme in judo.toml: {}", e))?
+            .adapted_to(color_support);
+
+        let default_db_config = config
+            .get_default()
+            .map_err(|e| color_eyre::eyre::eyre!("Couldn't fetch default database: {}", e))?;
+
         // Start from main screen
         let current_screen = CurrentScreen::ListSelection;
         let last_active_screen = CurrentScreen::ListSelection;
 
+        // Encrypted databases are only read once the passphrase has been entered
+        let pending_unlock = PendingUnlock::for_db(&default_db_config, &store).await;
+
+        // Changes made through the store run the hooks of the config (the store
+        // waiting for the passphrase gets them once opened)
+        let hooks = config
+            .hooks
+            .clone()
+            .map(|hooks| Arc::new(Hooks::new(hooks)));
+        let store = with_hooks(store, hooks.as_ref(), &default_db_config.name);
+
+        let script_keys = config
+            .scripts
+            .iter()
+            .flat_map(|scripts| &scripts.keys)
+            .map(|(key, name)| Ok((key.parse()?, name.clone())))
+            .collect::<anyhow::Result<Vec<(KeyBinding, String)>>>()
+            .map_err(|e| color_eyre::eyre::eyre!("Invalid [scripts] in judo.toml: {}", e))?;
+
+        let current_screen = if pending_unlock.is_some() {
+            CurrentScreen::EnterPassphrase
+        } else {
+            current_screen
+        };
+
         // Create lists component and load data
         let mut lists_component = ListsComponent::new();
-        lists_component
-            .load_lists(&pool)
-            .await
-            .expect("Failed to read lists");
+        if pending_unlock.is_none() {
+            lists_component
+                .load_lists(&*store)
+                .await
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to read lists: {}", e))?;
+        }
 
         let selected_db_index = config
             .dbs
@@ -122,17 +357,25 @@ impl App {
             .position(|db| db.name == default_db_config.name)
             .unwrap_or(0);
 
-        Self {
+        let ex_history = ExHistory::load(data_dir.join("ex_history"));
+
+        let mut app = Self {
             config,
+            config_path,
+            data_dir,
             current_db_config: default_db_config,
             current_screen,
-            pool,
+            store,
             database_component: DatabaseComponent::new(),
             lists_component,
+            calendar_component: CalendarComponent::new(),
+            board_component: BoardComponent::new(),
             input_state: InputState::new(),
             selected_db_index,
             exit: false,
             theme,
+            color_support,
+            selected_theme_index: 0,
             pending_delete_list_name: None,
             pending_delete_db_name: None,
             leader_awaiting: false,
@@ -140,7 +383,42 @@ impl App {
             keys_buffer: Vec::new(),
             awaiting_second_g: false,
             last_active_screen,
+            last_frame_area: Rect::default(),
+            dragging: None,
+            git_sync: None,
+            running_git_sync: None,
+            pending_git_job: None,
+            caldav_sync: None,
+            running_caldav_sync: None,
+            sync_messages: Vec::new(),
+            pending_unlock,
+            register: None,
+            toast: None,
+            hooks,
+            script_keys,
+            scripts: Vec::new(),
+            running_script: None,
+            script_question: None,
+            script_output: Vec::new(),
+            palette: None,
+            ex_line: None,
+            ex_history,
+            templates: Vec::new(),
+            selected_template: 0,
+            template_form: None,
+            template_messages: Vec::new(),
+            #[cfg(unix)]
+            control: None,
+            #[cfg(unix)]
+            control_snapshot: None,
+        };
+
+        // Pull changes from other machines before showing anything
+        if app.pending_unlock.is_none() {
+            app.start_sync().await;
         }
+
+        Ok(app)
     }
 
     /// Go back to the last active screen
@@ -149,13 +427,12 @@ impl App {
     }
 
     /// Add a key to the buffer and clean up old keys
-    pub fn add_key_to_buffer(&mut self, key: &String, is_digit: bool) {
-        if self.keys_buffer.len() > 0 {
-            if let Some((_, last_is_digit)) = self.keys_buffer.last() {
-                if !*last_is_digit || (!["k", "j", "K", "J"].contains(&key.as_str()) && !is_digit) {
-                    self.reset_key_buffer();
-                }
-            }
+    pub fn add_key_to_buffer(&mut self, key: &str, is_digit: bool) {
+        if !self.keys_buffer.is_empty()
+            && let Some((_, last_is_digit)) = self.keys_buffer.last()
+            && (!*last_is_digit || (!["k", "j", "K", "J"].contains(&key) && !is_digit))
+        {
+            self.reset_key_buffer();
         }
 
         let recent = self.recent_keys();
@@ -164,7 +441,7 @@ impl App {
             self.reset_number_modifier();
         }
 
-        self.keys_buffer.push((key.clone(), is_digit));
+        self.keys_buffer.push((key.to_string(), is_digit));
     }
 
     /// Reset the key buffer
@@ -180,6 +457,14 @@ impl App {
             .collect()
     }
 
+    /// Keep yanked text in the register and copy it to the system clipboard
+    pub fn yank(&mut self, text: String) {
+        if let Err(e) = clipboard::copy_to_system_clipboard(&text) {
+            eprintln!("Failed to copy to clipboard: {}", e);
+        }
+        self.register = Some(text);
+    }
+
     pub fn reset_number_modifier(&mut self) {
         self.number_modifier = 0;
     }
@@ -189,7 +474,7 @@ impl App {
             self.number_modifier = self.lists_component.lists.len();
         }
         self.number_modifier *= 10;
-        self.number_modifier += modifier.clone();
+        self.number_modifier += modifier;
     }
 
     /// Run the application
@@ -197,49 +482,265 @@ impl App {
     /// Main event loop that handles terminal drawing and user input.
     /// Continues until the user exits the application.
     pub async fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        let reminders = self.start_reminders().await;
+        #[cfg(unix)]
+        match ControlServer::bind_in(&ControlServer::socket_dir()) {
+            Ok(control) => self.control = Some(control),
+            Err(e) => eprintln!("Failed to open the control socket: {:#}", e),
+        }
+
         while !self.exit {
             // Draw the current state of the application
             terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
 
-            // Handle keyboard input based on current screen
-            if let Some(key) = event::read()?.as_key_press_event() {
-                self.handle_key_event(key).await;
+            if let Some(reminders) = &reminders {
+                while let Ok(reminder) = reminders.try_recv() {
+                    self.show_reminder(reminder);
+                }
+            }
+            if self
+                .toast
+                .as_ref()
+                .is_some_and(|(_, shown_at)| shown_at.elapsed() > TOAST_DURATION)
+            {
+                self.toast = None;
+            }
+
+            // Handle keyboard and mouse input based on current screen. Wake up
+            // regularly to show reminders and hide them
+            if event::poll(TICK)? {
+                self.handle_event(event::read()?).await;
             }
+            #[cfg(unix)]
+            self.handle_ctl_requests().await;
+            self.handle_script_requests().await;
+            self.handle_caldav_sync().await;
+            self.handle_git_sync().await;
+        }
+
+        // Don't leave a commit or a push half done
+        while self.running_git_sync.is_some() {
+            tokio::time::sleep(TICK).await;
+            self.handle_git_sync().await;
         }
 
         Ok(())
     }
 
+    /// Handle the requests received on the control socket, then tell its
+    /// clients what changed since they last heard from the app
+    #[cfg(unix)]
+    pub async fn handle_ctl_requests(&mut self) {
+        while let Some(pending) = self.control.as_mut().and_then(|c| c.try_recv()) {
+            let reply = match self.apply_ctl_request(pending.request.clone()).await {
+                Ok(reply) => reply,
+                Err(e) => Message::Error {
+                    message: e.to_string(),
+                },
+            };
+            pending.reply(reply);
+            self.record_changes().await;
+            self.commit_sync_changes().await;
+        }
+
+        let Some(control) = &self.control else {
+            return;
+        };
+        if !control.has_clients() {
+            self.control_snapshot = None;
+            return;
+        }
+        let snapshot = self.ctl_snapshot();
+        if let Some(previous) = &self.control_snapshot {
+            for event in snapshot.events_since(previous) {
+                control.emit(event);
+            }
+        }
+        self.control_snapshot = Some(snapshot);
+    }
+
+    /// Current database, screen and selection, as seen by the control socket
+    #[cfg(unix)]
+    pub fn ctl_snapshot(&self) -> Snapshot {
+        Snapshot::new(
+            &self.current_db_config.name,
+            &format!("{:?}", self.current_screen),
+            &self.lists_component.lists,
+            self.lists_component.selected(),
+        )
+    }
+
+    /// Apply a request of the control socket and return its reply
+    #[cfg(unix)]
+    async fn apply_ctl_request(&mut self, request: Request) -> Result<Message> {
+        if self.is_locked() && !matches!(request, Request::State | Request::SwitchDb { .. }) {
+            return Err(color_eyre::eyre::eyre!(
+                "Database '{}' is locked",
+                self.current_db_config.name
+            ));
+        }
+
+        match request {
+            Request::AddItem {
+                name,
+                list,
+                priority,
+                due_date,
+            } => {
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    return Err(color_eyre::eyre::eyre!("The name can't be empty"));
+                }
+                let index = self.ctl_list_index(list.as_deref())?;
+                let ui_list = &mut self.lists_component.lists[index];
+                let new_item = NewTodoItem {
+                    list_id: ui_list.list.id,
+                    name,
+                    priority,
+                    due_date,
+                };
+                TodoItem::create(&*self.store, new_item)
+                    .await
+                    .map_err(|e| color_eyre::eyre::eyre!("Failed to add item: {}", e))?;
+                ui_list
+                    .update_items(&*self.store)
+                    .await
+                    .map_err(|e| color_eyre::eyre::eyre!("Failed to reload items: {}", e))?;
+            }
+            Request::SelectList { name } => {
+                let index = self.ctl_list_index(Some(&name))?;
+                self.lists_component.list_state.select(Some(index));
+            }
+            Request::Toggle { item, list } => {
+                let index = self.ctl_list_index(list.as_deref())?;
+                let ui_list = &mut self.lists_component.lists[index];
+                let position = match &item {
+                    Some(name) => ui_list.items.iter().position(|i| &i.item.name == name),
+                    None => ui_list.item_state.selected(),
+                };
+                let ui_item = position
+                    .and_then(|j| ui_list.items.get_mut(j))
+                    .ok_or_else(|| match &item {
+                        Some(name) => color_eyre::eyre::eyre!(
+                            "No item named '{}' in '{}'",
+                            name,
+                            ui_list.list.name
+                        ),
+                        None => color_eyre::eyre::eyre!("No item selected"),
+                    })?;
+                ui_item
+                    .item
+                    .toggle_done(&*self.store)
+                    .await
+                    .map_err(|e| color_eyre::eyre::eyre!("Failed to toggle item: {}", e))?;
+                ui_list
+                    .update_items(&*self.store)
+                    .await
+                    .map_err(|e| color_eyre::eyre::eyre!("Failed to reload items: {}", e))?;
+            }
+            Request::Refresh => {
+                self.lists_component
+                    .refresh_lists(&*self.store)
+                    .await
+                    .map_err(|e| color_eyre::eyre::eyre!("Failed to reload lists: {}", e))?;
+            }
+            Request::SwitchDb { name } => {
+                self.selected_db_index = self
+                    .config
+                    .dbs
+                    .iter()
+                    .position(|db| db.name == name)
+                    .ok_or_else(|| color_eyre::eyre::eyre!("No database named '{}'", name))?;
+                self.switch_to_selected_db().await?;
+            }
+            Request::State => return Ok(Message::State(self.ctl_snapshot())),
+        }
+        Ok(Message::Ok)
+    }
+
+    /// Index of a list by name, or of the selected list
+    #[cfg(unix)]
+    fn ctl_list_index(&self, name: Option<&str>) -> Result<usize> {
+        match name {
+            Some(name) => self
+                .lists_component
+                .lists
+                .iter()
+                .position(|ui_list| ui_list.list.name == name)
+                .ok_or_else(|| color_eyre::eyre::eyre!("No list named '{}'", name)),
+            None => self
+                .lists_component
+                .selected()
+                .filter(|&i| i < self.lists_component.lists.len())
+                .ok_or_else(|| color_eyre::eyre::eyre!("No list selected")),
+        }
+    }
+
+    /// Scan all databases for items due soon in the background, if enabled in
+    /// the [remind] table of the config. Returns the reminders to show
+    async fn start_reminders(&self) -> Option<Receiver<Reminder>> {
+        let remind_config = self.config.remind.clone().filter(|remind| remind.tui)?;
+        let (sender, receiver) = mpsc::channel();
+
+        let reminders = Reminders::new(
+            Reminders::open_databases(&self.config).await,
+            Arc::new(ChannelNotifier::new(sender)),
+            Arc::new(SystemClock),
+            remind_config.lead_minutes,
+        );
+        tokio::spawn(reminders.run(remind_config.interval_secs));
+        Some(receiver)
+    }
+
+    /// Show a reminder as a toast, ringing the terminal bell
+    pub fn show_reminder(&mut self, reminder: Reminder) {
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+        self.toast = Some((reminder, Instant::now()));
+    }
+
+    /// Connection string of a new database in the data directory. New databases
+    /// use the same backend as the current one (SQLite for PostgreSQL, since
+    /// databases can't be created on the server from here)
+    fn new_db_connection_str(&self, db_name: &str) -> Result<String> {
+        manage::check_file_name(db_name).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+        // Create directory if it doesn't exist
+        std::fs::create_dir_all(&self.data_dir)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to create data directory: {}", e))?;
+
+        let connection_str = match self.current_db_config.connection_str.split_once(':') {
+            Some(("json", _)) => {
+                let path = self.data_dir.join(format!("{}.json", db_name));
+                format!("json:{}", path.display())
+            }
+            Some(("memory", _)) => format!("memory:{}", db_name),
+            _ => {
+                let path = self.data_dir.join(format!("{}.db", db_name));
+                format!("sqlite:{}", path.display())
+            }
+        };
+        Ok(connection_str)
+    }
+
     /// Create a new database with the given name
     pub async fn create_new_database(
         &mut self,
         db_name: String,
         set_as_default: bool,
     ) -> Result<()> {
-        // Use data directory to standardize storage
-        let data_dir = dirs::data_dir()
-            .ok_or_else(|| color_eyre::eyre::eyre!("Could not find data directory"))?
-            .join("judo");
-
-        // Create directory if it doesn't exist
-        std::fs::create_dir_all(&data_dir)
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to create data directory: {}", e))?;
-
-        // Create path to new db file
-        let db_file = format!("{}.db", db_name);
-        let path = data_dir.join(db_file);
-
-        // Create connection string (only SQLite is admissible)
-        let connection_str = format!("sqlite:{}", path.display());
+        let connection_str = self.new_db_connection_str(&db_name)?;
 
         // Create new database config
         let new_db_config = DBConfig {
             name: db_name.clone(),
             connection_str: connection_str.clone(),
+            git_sync: None,
+            encrypted: false,
+            caldav: None,
         };
 
         // Initialize the new database (this creates the file and runs migrations)
-        init_db(&connection_str)
+        open_store(&connection_str)
             .await
             .map_err(|e| color_eyre::eyre::eyre!("Failed to initialize new database: {}", e))?;
 
@@ -252,14 +753,7 @@ impl App {
         }
 
         // Write updated config to file
-        let config_dir = dirs::config_dir()
-            .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?
-            .join("judo");
-        let config_path = config_dir.join("judo.toml");
-
-        self.config
-            .write(&config_path)
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to save config: {}", e))?;
+        self.save_config()?;
 
         // Update selected index to point to the new database
         self.selected_db_index = self.config.dbs.len() - 1;
@@ -267,8 +761,215 @@ impl App {
         Ok(())
     }
 
-    /// Handle key events and delegate to appropriate handler
-    async fn handle_key_event(&mut self, key: KeyEvent) {
+    /// Set up the git and CalDAV syncs of the current database (if configured)
+    /// and sync it
+    pub async fn start_sync(&mut self) {
+        self.git_sync = None;
+        self.pending_git_job = None;
+        self.caldav_sync = None;
+        let mut messages = Vec::new();
+
+        if let Some(sync_config) = &self.current_db_config.git_sync {
+            match GitSync::new(sync_config, &self.current_db_config.name).await {
+                Ok(git_sync) => self.git_sync = Some(git_sync),
+                Err(e) => messages.push(format!("Failed to set up git sync: {}", e)),
+            }
+        }
+        if let Some(caldav_config) = &self.current_db_config.caldav {
+            match CaldavSync::new(caldav_config, &self.current_db_config.name) {
+                Ok(caldav_sync) => self.caldav_sync = Some(caldav_sync),
+                Err(e) => messages.push(format!("Failed to set up CalDAV sync: {}", e)),
+            }
+        }
+
+        self.sync_now().await;
+        messages.append(&mut self.sync_messages);
+        self.sync_messages = messages;
+    }
+
+    /// Sync the current database with its git remote and its CalDAV server.
+    /// Both syncs go on in the background, see [`App::handle_git_sync`] and
+    /// [`App::handle_caldav_sync`]: their conflicts and errors are shown in the
+    /// sync report pop-up
+    pub async fn sync_now(&mut self) {
+        if self.git_sync.is_none() && self.caldav_sync.is_none() && self.running_git_sync.is_none()
+        {
+            return;
+        }
+        self.sync_messages.clear();
+        self.start_git_job(GitJob::Sync);
+        // A sync of this database may be running already
+        let running_here = self
+            .running_caldav_sync
+            .as_ref()
+            .is_some_and(|(db_name, _)| *db_name == self.current_db_config.name);
+        if !running_here && let Some(caldav_sync) = self.caldav_sync.take() {
+            let running = caldav_sync.spawn(self.store.clone());
+            self.running_caldav_sync = Some((self.current_db_config.name.clone(), running));
+        }
+    }
+
+    /// Start a job of the git sync in the background, or keep it for when the
+    /// running one is over
+    fn start_git_job(&mut self, job: GitJob) {
+        if self.running_git_sync.is_some() {
+            if self.pending_git_job != Some(GitJob::Sync) {
+                self.pending_git_job = Some(job);
+            }
+            return;
+        }
+        let Some(pool) = self.store.sqlite_pool().cloned() else {
+            return;
+        };
+        let Some(git_sync) = self.git_sync.take() else {
+            return;
+        };
+        let running = match job {
+            GitJob::Commit => git_sync.spawn_commit(pool),
+            GitJob::Sync => git_sync.spawn_sync(pool),
+        };
+        self.running_git_sync = Some((self.current_db_config.name.clone(), job, running));
+    }
+
+    /// Once the git job running in the background is over, report its
+    /// conflicts and errors, reload the lists with what it pulled and start the
+    /// job asked for in the meantime
+    pub async fn handle_git_sync(&mut self) {
+        if !self
+            .running_git_sync
+            .as_ref()
+            .is_some_and(|(_, _, running)| running.is_finished())
+        {
+            return;
+        }
+        let Some((db_name, job, running)) = self.running_git_sync.take() else {
+            return;
+        };
+        let (git_sync, result) = running.join().await;
+        // Another database was opened during the sync
+        if db_name == self.current_db_config.name && self.git_sync.is_none() {
+            self.git_sync = git_sync;
+            match job {
+                GitJob::Commit => {
+                    if let Err(e) = result {
+                        eprintln!("Failed to commit changes: {}", e);
+                    }
+                }
+                GitJob::Sync => {
+                    self.sync_messages.extend(sync_report("Git", result));
+                    self.reload_lists().await;
+                }
+            }
+        }
+        match self.pending_git_job.take() {
+            Some(GitJob::Commit) => self.commit_sync_changes().await,
+            Some(GitJob::Sync) => self.start_git_job(GitJob::Sync),
+            None => {}
+        }
+    }
+
+    /// Once the CalDAV sync running in the background is over, report its
+    /// conflicts and errors and reload the lists with what it pulled
+    pub async fn handle_caldav_sync(&mut self) {
+        if !self
+            .running_caldav_sync
+            .as_ref()
+            .is_some_and(|(_, running)| running.is_finished())
+        {
+            return;
+        }
+        let Some((db_name, running)) = self.running_caldav_sync.take() else {
+            return;
+        };
+        let (caldav_sync, result) = running.join().await;
+        // Another database was opened during the sync
+        if db_name != self.current_db_config.name {
+            return;
+        }
+        if self.caldav_sync.is_none() {
+            self.caldav_sync = caldav_sync;
+        }
+        self.sync_messages.extend(sync_report("CalDAV", result));
+        self.reload_lists().await;
+    }
+
+    /// Record the changes just made in the change log, so that they are stamped
+    /// with the time they were made rather than the time of the next export.
+    /// This is a single query when the event wrote nothing
+    pub async fn record_changes(&mut self) {
+        // A running script writes to the database from its own thread: its
+        // changes are recorded once it is over
+        if self.is_locked() || self.running_script.is_some() {
+            return;
+        }
+        if let Some(pool) = self.store.sqlite_pool()
+            && let Err(e) = change_log::record_local_changes(pool).await
+        {
+            eprintln!("Failed to record changes: {}", e);
+        }
+    }
+
+    /// Commit changes to the git sync directory in the background, if any
+    pub async fn commit_sync_changes(&mut self) {
+        if self.is_locked() || self.running_script.is_some() {
+            return;
+        }
+        self.start_git_job(GitJob::Commit);
+    }
+
+    /// Handle a terminal event, then record the changes it made
+    pub async fn handle_event(&mut self, event: Event) {
+        if let Some(key) = event.as_key_press_event() {
+            self.handle_key_event(key).await;
+        } else if let Some(mouse) = event.as_mouse_event() {
+            EventHandler::handle_mouse_event(self, mouse).await;
+        } else if let Event::Paste(text) = &event {
+            EventHandler::handle_paste(self, text).await;
+        }
+
+        // Record changes in the change log, then mirror them to the git sync directory
+        self.record_changes().await;
+        self.commit_sync_changes().await;
+    }
+
+    /// Handle a key press based on the current screen
+    pub async fn handle_key_event(&mut self, key: KeyEvent) {
+        // The sync report is shown on top of any screen until dismissed
+        if !self.sync_messages.is_empty() {
+            EventHandler::handle_sync_report_key(self, key).await;
+            return;
+        }
+        if self.shows_script_output() {
+            EventHandler::handle_script_output_key(self, key).await;
+            return;
+        }
+        if !self.template_messages.is_empty() {
+            EventHandler::handle_template_report_key(self, key).await;
+            return;
+        }
+
+        if key.code == KeyCode::Char('p')
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+            && palette::has_commands(&self.current_screen)
+        {
+            self.enter_command_palette();
+            return;
+        }
+
+        // Keys bound to scripts come before the keys of the main screens
+        if matches!(
+            self.current_screen,
+            CurrentScreen::ListSelection | CurrentScreen::ItemSelection
+        ) && let Some((_, name)) = self
+            .script_keys
+            .iter()
+            .find(|(binding, _)| binding.matches(&key))
+        {
+            let name = name.clone();
+            self.run_script(&name);
+            return;
+        }
+
         match self.current_screen {
             CurrentScreen::AddList | CurrentScreen::ModifyList => {
                 EventHandler::handle_add_or_modify_list_screen_key(self, key).await
@@ -300,6 +1001,34 @@ impl App {
             CurrentScreen::DeleteDatabaseConfirmation => {
                 EventHandler::handle_delete_database_confirmation_key(self, key).await
             }
+            CurrentScreen::ThemeSelection => {
+                EventHandler::handle_theme_selection_screen_key(self, key).await
+            }
+            CurrentScreen::EnterPassphrase => {
+                EventHandler::handle_passphrase_screen_key(self, key).await
+            }
+            CurrentScreen::Calendar => EventHandler::handle_calendar_screen_key(self, key).await,
+            CurrentScreen::Board => EventHandler::handle_board_screen_key(self, key).await,
+            CurrentScreen::EditStatuses => {
+                EventHandler::handle_edit_statuses_screen_key(self, key).await
+            }
+            CurrentScreen::HookLog => EventHandler::handle_hook_log_screen_key(self, key).await,
+            CurrentScreen::ScriptPrompt => {
+                EventHandler::handle_script_prompt_screen_key(self, key).await
+            }
+            CurrentScreen::ScriptSelect => {
+                EventHandler::handle_script_select_screen_key(self, key).await
+            }
+            CurrentScreen::CommandPalette => {
+                EventHandler::handle_command_palette_screen_key(self, key).await
+            }
+            CurrentScreen::ExLine => EventHandler::handle_ex_line_screen_key(self, key).await,
+            CurrentScreen::TemplateSelect => {
+                EventHandler::handle_template_select_screen_key(self, key).await
+            }
+            CurrentScreen::TemplateVariable => {
+                EventHandler::handle_template_variable_screen_key(self, key).await
+            }
         }
     }
 
@@ -309,12 +1038,23 @@ impl App {
         self.current_screen = CurrentScreen::AddList;
     }
 
+    /// Enter the "Add List" screen to insert the new list right below (or above)
+    /// the selected one
+    pub fn enter_insert_list_screen(&mut self, below: bool) {
+        self.enter_add_list_screen();
+        self.input_state.insert_position = self
+            .lists_component
+            .selected()
+            .map(|i| if below { i + 1 } else { i });
+    }
+
     /// Enter the "Modify List" screen by opening the corresponding pop-up
     pub fn enter_modify_list_screen(&mut self, selected_list: &TodoList) {
         self.input_state = InputState {
             current_input: selected_list.name.clone(),
             cursor_pos: 0,
             is_modifying: true,
+            insert_position: None,
         };
         self.current_screen = CurrentScreen::ModifyList;
     }
@@ -327,6 +1067,18 @@ impl App {
         }
     }
 
+    /// Enter the "Add Item" screen to insert the new item right below (or above)
+    /// the selected one
+    pub fn enter_insert_item_screen(&mut self, below: bool) {
+        self.enter_add_item_screen();
+        if let Some(ui_list) = self.lists_component.get_selected_list() {
+            self.input_state.insert_position = ui_list
+                .item_state
+                .selected()
+                .map(|j| if below { j + 1 } else { j });
+        }
+    }
+
     /// Enter the "Modify Item" screen by opening the corresponding pop-up
     pub fn enter_modify_item_screen(&mut self, ui_list: &UIList) {
         if self.lists_component.selected().is_some()
@@ -338,6 +1090,7 @@ impl App {
                 current_input: selected_item.name.clone(),
                 cursor_pos: 0,
                 is_modifying: true,
+                insert_position: None,
             };
             self.current_screen = CurrentScreen::ModifyItem;
         }
@@ -403,38 +1156,120 @@ impl App {
         self.selected_db_index = (self.selected_db_index + 1) % self.config.dbs.len();
     }
 
-    /// Switch to the selected database
+    /// Switch to the selected database. Encrypted databases ask for their
+    /// passphrase first
     pub async fn switch_to_selected_db(&mut self) -> Result<()> {
-        if let Some(selected_db) = self.config.dbs.get(self.selected_db_index) {
+        if let Some(selected_db) = self.config.dbs.get(self.selected_db_index).cloned() {
             // Initialize connection to the new database
-            let new_pool = init_db(&selected_db.connection_str)
+            let new_store = open_store(&selected_db.connection_str)
                 .await
                 .map_err(|e| color_eyre::eyre::eyre!("Failed to connect to database: {}", e))?;
 
-            // Update app state
-            self.current_db_config = selected_db.clone();
-            self.pool = new_pool;
+            if let Some(pending_unlock) = PendingUnlock::for_db(&selected_db, &new_store).await {
+                self.pending_unlock = Some(pending_unlock);
+                self.input_state.clear();
+                self.current_screen = CurrentScreen::EnterPassphrase;
+                return Ok(());
+            }
 
-            // Reload all lists from the new database
-            self.lists_component = ListsComponent::new();
-            self.lists_component
-                .load_lists(&self.pool)
-                .await
-                .map_err(|e| color_eyre::eyre::eyre!("Failed to load lists: {}", e))?;
+            self.open_db(selected_db, new_store).await?;
+        }
+        Ok(())
+    }
 
-            // Select the first list if available
-            if !self.lists_component.lists.is_empty() {
-                self.lists_component.list_state.select(Some(0));
-            } else {
-                self.lists_component.list_state.select(None);
-            }
+    /// Make the given database the current one and load its lists
+    async fn open_db(&mut self, db_config: DBConfig, store: Arc<dyn TodoStore>) -> Result<()> {
+        // Update app state
+        let switched = self.current_db_config.name != db_config.name;
+        self.store = with_hooks(store, self.hooks.as_ref(), &db_config.name);
+        self.current_db_config = db_config;
+        if switched && let Some(hooks) = &self.hooks {
+            let name = &self.current_db_config.name;
+            hooks.fire(HookEvent::new(HookKind::DbSwitched, name, None, None));
+        }
 
-            // Return to main screen
-            self.current_screen = CurrentScreen::ListSelection;
+        // Reload all lists from the new database
+        self.lists_component = ListsComponent::new();
+        self.lists_component
+            .load_lists(&*self.store)
+            .await
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to load lists: {}", e))?;
+
+        // Select the first list if available
+        if !self.lists_component.lists.is_empty() {
+            self.lists_component.list_state.select(Some(0));
+        } else {
+            self.lists_component.list_state.select(None);
+        }
+
+        // Return to main screen
+        self.current_screen = CurrentScreen::ListSelection;
+
+        // Pull changes from other machines
+        self.start_sync().await;
+
+        Ok(())
+    }
+
+    /// Unlock the database waiting for its passphrase (or encrypt it, the first
+    /// time) and open it. A wrong passphrase is reported in the pop-up
+    pub async fn submit_passphrase(&mut self) -> Result<()> {
+        let Some(mut pending_unlock) = self.pending_unlock.take() else {
+            return Ok(());
+        };
+
+        let passphrase = self.input_state.get_text().to_string();
+        self.input_state.clear();
+
+        let result = if pending_unlock.is_new_passphrase {
+            crypto::encrypt_database(&pending_unlock.pool, &passphrase).await
+        } else {
+            crypto::unlock(&pending_unlock.pool, &passphrase).await
+        };
+
+        match result {
+            Ok(()) => {
+                self.open_db(pending_unlock.db_config, pending_unlock.store)
+                    .await?
+            }
+            Err(e) => {
+                pending_unlock.error = Some(e.to_string());
+                self.pending_unlock = Some(pending_unlock);
+            }
         }
+
         Ok(())
     }
 
+    /// Give up opening the encrypted database. At startup, there is nothing else
+    /// to show, so the application exits
+    pub fn cancel_passphrase(&mut self) {
+        self.pending_unlock = None;
+        self.input_state.clear();
+
+        if self.is_locked() {
+            self.exit = true;
+        } else {
+            self.current_screen = CurrentScreen::DBSelection;
+        }
+    }
+
+    /// Whether the current database is encrypted and hasn't been unlocked
+    pub fn is_locked(&self) -> bool {
+        self.current_db_config.encrypted
+            && self
+                .store
+                .sqlite_pool()
+                .is_some_and(|pool| !crypto::is_unlocked(pool))
+    }
+
+    /// Write the config to judo.toml
+    fn save_config(&self) -> Result<()> {
+        self.config
+            .write(&self.config_path)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to save config: {}", e))
+    }
+
     /// Set the selected database as default
     pub async fn set_selected_db_as_default(&mut self) -> Result<()> {
         if let Some(selected_db) = self.config.dbs.get(self.selected_db_index) {
@@ -442,21 +1277,26 @@ impl App {
             self.config.default = selected_db.name.clone();
 
             // Write updated config to file
-            let config_dir = dirs::config_dir()
-                .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?
-                .join("judo");
-            let config_path = config_dir.join("judo.toml");
-
-            self.config
-                .write(&config_path)
-                .map_err(|e| color_eyre::eyre::eyre!("Failed to save config: {}", e))?;
+            self.save_config()?;
         }
         Ok(())
     }
 
-    /// Delete the selected database
-    pub async fn delete_selected_db(&mut self) -> Result<()> {
+    /// Remove the selected database from the config, and delete its file if
+    /// `delete_file` (not while it is open)
+    pub async fn delete_selected_db(&mut self, delete_file: bool) -> Result<()> {
         if self.selected_db_index < self.config.dbs.len() {
+            if delete_file {
+                let selected_db = &self.config.dbs[self.selected_db_index];
+                if selected_db.name == self.current_db_config.name {
+                    return Err(color_eyre::eyre::eyre!(
+                        "Switch to another database before deleting the file of {}",
+                        selected_db.name
+                    ));
+                }
+                manage::delete_db_file(&selected_db.connection_str)
+                    .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            }
             let removed_db = self.config.dbs.remove(self.selected_db_index);
 
             // If the removed DB was the default, clear or update the default
@@ -469,24 +1309,41 @@ impl App {
             }
 
             // Write updated config to file
-            let config_dir = dirs::config_dir()
-                .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?
-                .join("judo");
-            let config_path = config_dir.join("judo.toml");
-
-            self.config
-                .write(&config_path)
-                .map_err(|e| color_eyre::eyre::eyre!("Failed to save config: {}", e))?;
+            self.save_config()?;
         }
         Ok(())
     }
 
-    /// Modify the selected database's name and/or connection string
+    /// Rename the selected database, and its SQLite or JSON file along with
+    /// it, or change its connection string
     pub async fn modify_selected_db(
         &mut self,
         new_name: Option<String>,
         new_connection_str: Option<String>,
     ) -> Result<()> {
+        let Some(selected_db) = self.config.dbs.get(self.selected_db_index).cloned() else {
+            return Ok(());
+        };
+        if let Some(name) = &new_name
+            && *name != selected_db.name
+        {
+            if self.config.dbs.iter().any(|db| db.name == *name) {
+                return Err(color_eyre::eyre::eyre!(
+                    "A database named '{}' already exists",
+                    name
+                ));
+            }
+            manage::check_file_name(name).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+            if new_connection_str.is_none()
+                && let Some(path) = manage::db_file(&selected_db.connection_str)
+                && path.exists()
+            {
+                let new_path = manage::renamed_file(&path, name)
+                    .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+                self.move_selected_db_file(&new_path).await?;
+            }
+        }
+
         if let Some(selected_db) = self.config.dbs.get_mut(self.selected_db_index) {
             // Update name if provided
             if let Some(name) = new_name {
@@ -494,6 +1351,9 @@ impl App {
                 if self.config.default == selected_db.name {
                     self.config.default = name.clone();
                 }
+                if self.current_db_config.name == selected_db.name {
+                    self.current_db_config.name = name.clone();
+                }
                 selected_db.name = name;
             }
             // Update connection string if provided
@@ -502,39 +1362,894 @@ impl App {
             }
 
             // Write updated config to file
-            let config_dir = dirs::config_dir()
-                .ok_or_else(|| color_eyre::eyre::eyre!("Could not find config directory"))?
-                .join("judo");
-            let config_path = config_dir.join("judo.toml");
-
-            self.config
-                .write(&config_path)
-                .map_err(|e| color_eyre::eyre::eyre!("Failed to save config: {}", e))?;
+            self.save_config()?;
         }
         Ok(())
     }
 
-    /// Enter the "Modify DB" screen by opening the corresponding pop-up
-    pub fn enter_modify_db_screen(&mut self) {
-        if let Some(selected_db) = self.config.dbs.get(self.selected_db_index) {
-            self.input_state = InputState {
-                current_input: selected_db.name.clone(),
-                cursor_pos: 0,
-                is_modifying: true,
-            };
-            self.current_screen = CurrentScreen::ModifyDB;
+    /// Path of a database file typed by the user: `~/` is the home directory,
+    /// and relative paths are in the data directory
+    pub fn db_file_path(&self, path: &str) -> Result<PathBuf> {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err(color_eyre::eyre::eyre!("Missing path"));
         }
+        Ok(match path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()
+                .ok_or_else(|| color_eyre::eyre::eyre!("Could not find home directory"))?
+                .join(rest),
+            None => self.data_dir.join(path),
+        })
     }
 
-    /// Exit the Modify DB screen without saving
-    pub fn exit_modify_db_without_saving(&mut self) {
-        self.current_screen = CurrentScreen::DBSelection;
-        self.input_state.clear();
+    /// Move the file of the selected database, updating its connection string.
+    /// The database is closed while its file moves if it is open, and reopened
+    pub async fn move_selected_db_file(&mut self, new_path: &Path) -> Result<()> {
+        let Some(selected_db) = self.config.dbs.get(self.selected_db_index).cloned() else {
+            return Ok(());
+        };
+        let is_open = selected_db.name == self.current_db_config.name;
+        if is_open && let Some(pool) = self.store.sqlite_pool() {
+            pool.close().await;
+        }
+
+        let moved = manage::move_db_file(&selected_db.connection_str, new_path)
+            .map_err(|e| color_eyre::eyre::eyre!("{}", e));
+        if let Ok(connection_str) = &moved {
+            self.config.dbs[self.selected_db_index].connection_str = connection_str.clone();
+            self.save_config()?;
+        }
+        if is_open {
+            // Reopen it where it is now, its lists and screen unchanged
+            let db_config = self.config.dbs[self.selected_db_index].clone();
+            let store = open_store(&db_config.connection_str)
+                .await
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to connect to database: {}", e))?;
+            self.store = with_hooks(store, self.hooks.as_ref(), &db_config.name);
+            self.current_db_config = db_config;
+        }
+        moved.map(|_| ())
+    }
+
+    /// Copy the selected database to a new database named `name`. SQLite and
+    /// JSON files are copied next to the original (encryption included); other
+    /// databases are copied list by list into a new database
+    pub async fn duplicate_selected_db(&mut self, name: String) -> Result<()> {
+        let name = name.trim().to_string();
+        let Some(source) = self.config.dbs.get(self.selected_db_index).cloned() else {
+            return Ok(());
+        };
+        if name.is_empty() {
+            return Ok(());
+        }
+        if self.config.dbs.iter().any(|db| db.name == name) {
+            return Err(color_eyre::eyre::eyre!(
+                "A database named '{}' already exists",
+                name
+            ));
+        }
+
+        let connection_str = match manage::db_file(&source.connection_str) {
+            Some(path) => {
+                let new_path = manage::renamed_file(&path, &name)
+                    .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+                manage::duplicate_db_file(&source.connection_str, &new_path)
+                    .await
+                    .map_err(|e| color_eyre::eyre::eyre!("{}", e))?
+            }
+            None => {
+                let connection_str = self.new_db_connection_str(&name)?;
+                let from = self.db_store(&source).await?;
+                let to = open_store(&connection_str)
+                    .await
+                    .map_err(|e| color_eyre::eyre::eyre!("Failed to create database: {}", e))?;
+                manage::copy_lists(&*from, &*to, &source.name)
+                    .await
+                    .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+                connection_str
+            }
+        };
+
+        self.config.dbs.push(DBConfig {
+            name,
+            connection_str,
+            git_sync: None,
+            encrypted: source.encrypted,
+            caldav: None,
+        });
+        self.save_config()?;
+        self.selected_db_index = self.config.dbs.len() - 1;
+        Ok(())
+    }
+
+    /// Copy the lists of the selected database (with their items) into another
+    /// database. Lists named like one already there get the name of the
+    /// selected database after theirs, e.g. `Groceries (home)`
+    pub async fn merge_selected_db_into(&mut self, target_name: &str) -> Result<()> {
+        let Some(source) = self.config.dbs.get(self.selected_db_index).cloned() else {
+            return Ok(());
+        };
+        let target = self
+            .config
+            .dbs
+            .iter()
+            .find(|db| db.name == target_name)
+            .cloned()
+            .ok_or_else(|| color_eyre::eyre::eyre!("No database named '{}'", target_name))?;
+        if target.name == source.name {
+            return Err(color_eyre::eyre::eyre!(
+                "Can't merge a database into itself"
+            ));
+        }
+
+        let from = self.db_store(&source).await?;
+        let to = self.db_store(&target).await?;
+        // Writes to a locked encrypted database fail: say so before starting
+        if let Some(pool) = to.sqlite_pool()
+            && crypto::is_encrypted(pool).await.unwrap_or(false)
+            && !crypto::is_unlocked(pool)
+        {
+            return Err(color_eyre::eyre::eyre!(
+                "Open {} and enter its passphrase before merging into it",
+                target.name
+            ));
+        }
+        manage::copy_lists(&*from, &*to, &source.name)
+            .await
+            .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+
+        if target.name == self.current_db_config.name {
+            self.reload_lists().await;
+        }
+        Ok(())
+    }
+
+    /// Store of a database of the config: the open one, or a new connection
+    async fn db_store(&self, db_config: &DBConfig) -> Result<Arc<dyn TodoStore>> {
+        if db_config.name == self.current_db_config.name {
+            return Ok(self.store.clone());
+        }
+        open_store(&db_config.connection_str)
+            .await
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to connect to {}: {}", db_config.name, e))
+    }
+
+    /// Enter the calendar, on today's month
+    pub fn enter_calendar_screen(&mut self) {
+        self.calendar_component = CalendarComponent::new();
+        self.current_screen = CurrentScreen::Calendar;
+    }
+
+    /// Enter the board of the selected list, on the card of the selected item
+    pub fn enter_board_screen(&mut self) {
+        let Some(ui_list) = self.lists_component.get_selected_list() else {
+            return;
+        };
+        self.board_component = BoardComponent::new();
+        if let Some(ui_item) = ui_list
+            .item_state
+            .selected()
+            .and_then(|j| ui_list.items.get(j))
+        {
+            self.board_component.select_item(ui_list, ui_item.item.id);
+        }
+        self.current_screen = CurrentScreen::Board;
+    }
+
+    /// Enter the "Edit Statuses" screen for the board of the selected list
+    pub fn enter_edit_statuses_screen(&mut self) {
+        if let Some(ui_list) = self.lists_component.get_selected_list() {
+            self.input_state = InputState {
+                current_input: ui_list.list.statuses().join(","),
+                cursor_pos: 0,
+                is_modifying: true,
+                insert_position: None,
+            };
+            self.current_screen = CurrentScreen::EditStatuses;
+        }
+    }
+
+    /// Enter the hook log screen, listing the hooks that failed
+    pub fn enter_hook_log_screen(&mut self) {
+        self.current_screen = CurrentScreen::HookLog;
+    }
+
+    /// Open the command palette, listing the actions of the current screen
+    pub fn enter_command_palette(&mut self) {
+        let mut command_palette = CommandPalette::new(self.current_screen.clone());
+        command_palette.lists = self
+            .lists_component
+            .lists
+            .iter()
+            .map(|ui_list| ui_list.list.name.clone())
+            .collect();
+        command_palette.databases = self.config.dbs.iter().map(|db| db.name.clone()).collect();
+        command_palette.scripts = script::list_scripts(&script::scripts_dir(&self.config_path))
+            .into_iter()
+            .map(|script| script.name)
+            .collect();
+        self.palette = Some(command_palette);
+        self.current_screen = CurrentScreen::CommandPalette;
+    }
+
+    /// Open the command palette with some text typed, e.g. a command waiting
+    /// for its argument
+    pub fn enter_command_palette_with(&mut self, text: &str) {
+        self.enter_command_palette();
+        if let Some(command_palette) = &mut self.palette {
+            for c in text.chars() {
+                command_palette.input.add_char(c);
+            }
+        }
+    }
+
+    /// Close the command palette and run an entry on the screen it was opened from
+    pub async fn run_palette_entry(&mut self, entry: PaletteEntry) {
+        let Some(command_palette) = self.palette.take() else {
+            return;
+        };
+        self.current_screen = command_palette.screen;
+
+        let result = match (entry.command.action, entry.argument) {
+            (Action::Keys(keys), _) => {
+                self.reset_number_modifier();
+                for key in keys {
+                    match key.parse::<KeyBinding>() {
+                        Ok(binding) => Box::pin(self.handle_key_event(binding.into())).await,
+                        Err(e) => eprintln!("Invalid key of {}: {}", entry.command.name, e),
+                    }
+                }
+                Ok(())
+            }
+            (Action::Rename, Some(name)) => self.rename_selected(name).await,
+            (Action::MoveTo, Some(list)) => self.move_selected_item_to(&list).await,
+            (Action::SwitchDb, Some(name)) => {
+                match self.config.dbs.iter().position(|db| db.name == name) {
+                    Some(index) => {
+                        self.selected_db_index = index;
+                        self.switch_to_selected_db().await
+                    }
+                    None => Err(color_eyre::eyre::eyre!("No database named '{}'", name)),
+                }
+            }
+            (Action::RunScript, Some(name)) => {
+                self.run_script(&name);
+                Ok(())
+            }
+            (Action::UseTheme, Some(name)) => {
+                match THEME_PRESETS.iter().position(|preset| *preset == name) {
+                    Some(index) => {
+                        self.selected_theme_index = index;
+                        self.save_selected_theme()
+                    }
+                    None => Err(color_eyre::eyre::eyre!("No theme named '{}'", name)),
+                }
+            }
+            (Action::DuplicateDb, Some(name)) => self.duplicate_selected_db(name).await,
+            (Action::MergeDbInto, Some(name)) => self.merge_selected_db_into(&name).await,
+            (Action::MoveDbFile, Some(path)) => match self.db_file_path(&path) {
+                Ok(path) => self.move_selected_db_file(&path).await,
+                Err(e) => Err(e),
+            },
+            // Commands taking an argument can't run without it
+            (_, None) => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to run {}: {}", entry.command.name, e);
+        }
+    }
+
+    /// Rename the selected list, or the selected item in the items panel
+    async fn rename_selected(&mut self, name: String) -> Result<()> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Ok(());
+        }
+        let result = if self.current_screen == CurrentScreen::ItemSelection {
+            match self.lists_component.get_selected_list_mut() {
+                Some(selected_list) => {
+                    ItemsComponent::update_item(selected_list, name, &*self.store).await
+                }
+                None => Ok(()),
+            }
+        } else {
+            ListsComponent::update_list(&mut self.lists_component, name, &*self.store).await
+        };
+        result.map_err(|e| color_eyre::eyre::eyre!("{}", e))
+    }
+
+    /// Move the selected item to the end of the list with this name
+    async fn move_selected_item_to(&mut self, list_name: &str) -> Result<()> {
+        let target = self
+            .lists_component
+            .lists
+            .iter()
+            .find(|ui_list| ui_list.list.name == list_name)
+            .map(|ui_list| ui_list.list.id)
+            .ok_or_else(|| color_eyre::eyre::eyre!("No list named '{}'", list_name))?;
+        let Some(item) = self
+            .lists_component
+            .get_selected_list()
+            .and_then(|ui_list| {
+                let j = ui_list.item_state.selected()?;
+                ui_list.items.get(j).map(|ui_item| ui_item.item.clone())
+            })
+        else {
+            return Ok(());
+        };
+
+        item.move_to_list(&*self.store, target)
+            .await
+            .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+        self.reload_lists().await;
+        Ok(())
+    }
+
+    /// Open the ex command line. A count typed before `:` starts a range from
+    /// the selected item (`3:` gives `:.,.+2`)
+    pub fn enter_ex_line(&mut self) {
+        let mut ex_line = ExLine::new(self.current_screen.clone(), self.number_modifier);
+        self.reset_number_modifier();
+        ex_line.lists = self
+            .lists_component
+            .lists
+            .iter()
+            .map(|ui_list| ui_list.list.name.clone())
+            .collect();
+        ex_line.databases = self.config.dbs.iter().map(|db| db.name.clone()).collect();
+        self.ex_line = Some(ex_line);
+        self.current_screen = CurrentScreen::ExLine;
+    }
+
+    /// Run the command typed on the ex command line, from the screen it was
+    /// opened from. The command line stays open with the error if it fails
+    pub async fn run_ex_line(&mut self) {
+        let Some(ex_line) = &self.ex_line else {
+            return;
+        };
+        let text = ex_line.input.get_text().to_string();
+        self.current_screen = ex_line.screen.clone();
+        if text.trim().is_empty() {
+            self.ex_line = None;
+            return;
+        }
+        if let Err(e) = self.ex_history.add(&text) {
+            eprintln!("Failed to save command history: {}", e);
+        }
+
+        let result = match ex::parse(&text) {
+            Ok(command) => self.run_ex_command(command).await,
+            Err(e) => Err(e),
+        };
+        match (result, &mut self.ex_line) {
+            (Err(e), Some(ex_line)) => {
+                ex_line.error = Some(e.to_string());
+                self.current_screen = CurrentScreen::ExLine;
+            }
+            _ => self.ex_line = None,
+        }
+    }
+
+    /// Run a command of the ex command line. Ranges address the items of the
+    /// selected list
+    async fn run_ex_command(&mut self, command: ExCommand) -> Result<()> {
+        match command {
+            ExCommand::Go(address) => {
+                let ui_list = self
+                    .lists_component
+                    .get_selected_list_mut()
+                    .ok_or_else(|| color_eyre::eyre::eyre!("No list selected"))?;
+                let len = ui_list.items.len();
+                let line = address.resolve(ui_list.item_state.selected(), len)?;
+                if line < 1 || line > len as i64 {
+                    return Err(color_eyre::eyre::eyre!("Invalid address"));
+                }
+                ui_list.item_state.select(Some(line as usize - 1));
+                self.current_screen = CurrentScreen::ItemSelection;
+                self.last_active_screen = CurrentScreen::ItemSelection;
+            }
+            ExCommand::Delete(range) => {
+                let (start, end) = self.selected_items_range(range)?;
+                let items: Vec<TodoItem> = self
+                    .lists_component
+                    .get_selected_list()
+                    .map(|ui_list| {
+                        ui_list.items[start..=end]
+                            .iter()
+                            .map(|ui_item| ui_item.item.clone())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                for item in items {
+                    item.delete(&*self.store)
+                        .await
+                        .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+                }
+                self.reload_lists().await;
+                self.select_item(start);
+            }
+            ExCommand::Move(range, address) => {
+                let (start, end) = self.selected_items_range(range)?;
+                let (selected, len) = self.selected_item_and_count();
+                let target = address.resolve(selected, len)?;
+                if target < 0 {
+                    return Err(color_eyre::eyre::eyre!("Invalid address"));
+                }
+                let order = ex::moved_order(len, start, end, target as usize)?;
+                self.reorder_selected_items(&order).await?;
+                // The last item moved is selected, as in vim
+                if let Some(position) = order.iter().position(|&i| i == end) {
+                    self.select_item(position);
+                }
+            }
+            ExCommand::Sort { key, reverse } => {
+                let Some(ui_list) = self.lists_component.get_selected_list() else {
+                    return Err(color_eyre::eyre::eyre!("No list selected"));
+                };
+                let items: Vec<&TodoItem> =
+                    ui_list.items.iter().map(|ui_item| &ui_item.item).collect();
+                let order = ex::sorted_order(&items, key, reverse);
+                self.reorder_selected_items(&order).await?;
+            }
+            ExCommand::Write { path, force } => {
+                let markdown = self
+                    .lists_component
+                    .yank_selected_list()
+                    .ok_or_else(|| color_eyre::eyre::eyre!("No list selected"))?;
+                if path.exists() && !force {
+                    return Err(color_eyre::eyre::eyre!(
+                        "{} exists (add ! to overwrite)",
+                        path.display()
+                    ));
+                }
+                std::fs::write(&path, markdown)?;
+            }
+            ExCommand::Edit(name) => {
+                let index = self
+                    .config
+                    .dbs
+                    .iter()
+                    .position(|db| db.name == name)
+                    .ok_or_else(|| color_eyre::eyre::eyre!("No database named '{}'", name))?;
+                self.selected_db_index = index;
+                self.switch_to_selected_db().await?;
+            }
+            ExCommand::Buffer(name) => {
+                let index = self
+                    .lists_component
+                    .lists
+                    .iter()
+                    .position(|ui_list| ui_list.list.name == name)
+                    .ok_or_else(|| color_eyre::eyre::eyre!("No list named '{}'", name))?;
+                self.lists_component.list_state.select(Some(index));
+            }
+            ExCommand::Quit => self.exit = true,
+        }
+        Ok(())
+    }
+
+    /// Index of the selected item and number of items of the selected list
+    fn selected_item_and_count(&self) -> (Option<usize>, usize) {
+        self.lists_component
+            .get_selected_list()
+            .map_or((None, 0), |ui_list| {
+                (ui_list.item_state.selected(), ui_list.items.len())
+            })
+    }
+
+    /// Indices of the first and last items of a range of the selected list,
+    /// the selected item if there is no range
+    fn selected_items_range(&self, range: Option<ex::Range>) -> Result<(usize, usize)> {
+        let (selected, len) = self.selected_item_and_count();
+        match range {
+            Some(range) => range.resolve(selected, len),
+            None => selected
+                .filter(|&j| j < len)
+                .map(|j| (j, j))
+                .ok_or_else(|| color_eyre::eyre::eyre!("No item selected")),
+        }
+    }
+
+    /// Select an item of the selected list, or the last one if there are fewer
+    fn select_item(&mut self, j: usize) {
+        if let Some(ui_list) = self.lists_component.get_selected_list_mut() {
+            let selected = (!ui_list.items.is_empty()).then(|| j.min(ui_list.items.len() - 1));
+            ui_list.item_state.select(selected);
+        }
+    }
+
+    /// Reorder the items of the selected list, `order` holding the indices of
+    /// the items in their new order
+    async fn reorder_selected_items(&mut self, order: &[usize]) -> Result<()> {
+        let Some(ui_list) = self.lists_component.get_selected_list() else {
+            return Ok(());
+        };
+        let mut ids: Vec<i64> = ui_list
+            .items
+            .iter()
+            .map(|ui_item| ui_item.item.id)
+            .collect();
+        let new_ids: Vec<i64> = order.iter().map(|&j| ids[j]).collect();
+
+        for (position, id) in new_ids.into_iter().enumerate() {
+            let Some(from) = ids.iter().position(|&other| other == id) else {
+                continue;
+            };
+            if from != position {
+                self.store
+                    .move_item(id, position as i64 - from as i64)
+                    .await
+                    .map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+                ids.remove(from);
+                ids.insert(position, id);
+            }
+        }
+        self.reload_lists().await;
+        Ok(())
+    }
+
+    /// Enter the template picker, listing the templates of the templates directory
+    pub fn enter_template_picker(&mut self) {
+        self.templates = templates::list_templates(&templates::templates_dir(&self.config_path));
+        self.selected_template = 0;
+        self.current_screen = CurrentScreen::TemplateSelect;
+    }
+
+    /// Start filling in the template selected in the template picker
+    pub async fn choose_template(&mut self) {
+        let Some(template) = self.templates.get(self.selected_template).cloned() else {
+            self.current_screen = CurrentScreen::ListSelection;
+            return;
+        };
+        match TemplateForm::load(template) {
+            Ok(form) => {
+                self.template_form = Some(form);
+                self.ask_template_variable().await;
+            }
+            Err(e) => {
+                self.template_messages = vec![format!("{:#}", e)];
+                self.current_screen = CurrentScreen::ListSelection;
+            }
+        }
+    }
+
+    /// Give the value of the variable of the template asked for (`None` to
+    /// cancel the list)
+    pub async fn answer_template_variable(&mut self, value: Option<String>) {
+        self.input_state.clear();
+        match (value, self.template_form.as_mut()) {
+            (Some(value), Some(form)) => {
+                form.answer(value);
+                self.ask_template_variable().await;
+            }
+            _ => {
+                self.template_form = None;
+                self.current_screen = CurrentScreen::ListSelection;
+            }
+        }
+    }
+
+    /// Ask for the next variable of the template, or create its list below the
+    /// selected one once all are known
+    async fn ask_template_variable(&mut self) {
+        let Some(form) = &self.template_form else {
+            return;
+        };
+        if let Some(variable) = form.next_variable() {
+            let default = templates::default_value(variable);
+            self.input_state = InputState {
+                cursor_pos: default.chars().count(),
+                current_input: default,
+                is_modifying: false,
+                insert_position: None,
+            };
+            self.current_screen = CurrentScreen::TemplateVariable;
+            return;
+        }
+
+        let markdown = form.markdown();
+        self.template_form = None;
+        self.current_screen = CurrentScreen::ListSelection;
+        let position = self
+            .lists_component
+            .selected()
+            .map_or(self.lists_component.lists.len(), |i| i + 1);
+        if let Err(e) =
+            ListsComponent::paste_list(&mut self.lists_component, &markdown, position, &*self.store)
+                .await
+        {
+            self.template_messages = vec![format!("Failed to create list from template: {}", e)];
+        }
+    }
+
+    /// Save the selected list as a template named after it, and tell where
+    pub fn save_selected_list_as_template(&mut self) {
+        let Some(ui_list) = self.lists_component.get_selected_list() else {
+            return;
+        };
+        let dir = templates::templates_dir(&self.config_path);
+        let message = match templates::save_list(&dir, ui_list) {
+            Ok(path) => format!("Saved template to {}", path.display()),
+            Err(e) => format!("Failed to save template: {:#}", e),
+        };
+        self.template_messages = vec![message];
+    }
+
+    /// Enter the script picker, listing the scripts of the scripts directory
+    pub fn enter_script_picker(&mut self) {
+        self.scripts = script::list_scripts(&script::scripts_dir(&self.config_path));
+        let options = self
+            .scripts
+            .iter()
+            .map(|script| match &script.description {
+                Some(description) => format!("{} - {}", script.name, description),
+                None => script.name.clone(),
+            })
+            .collect();
+        self.script_question = Some(ScriptQuestion {
+            title: "Scripts".to_string(),
+            options,
+            selected: 0,
+            reply: None,
+            return_screen: self.last_active_screen.clone(),
+        });
+        self.current_screen = CurrentScreen::ScriptSelect;
+    }
+
+    /// Run the script of the scripts directory with this name
+    pub fn run_script(&mut self, name: &str) {
+        let scripts = script::list_scripts(&script::scripts_dir(&self.config_path));
+        match scripts.into_iter().find(|script| script.name == name) {
+            Some(script) => self.start_script(&script),
+            None => self.script_output = vec![format!("No script named '{}'", name)],
+        }
+    }
+
+    /// Start a script in the background, with the selected list and item
+    fn start_script(&mut self, script: &Script) {
+        if self.running_script.is_some() {
+            self.script_output = vec!["A script is already running".to_string()];
+            return;
+        }
+        if self.is_locked() {
+            return;
+        }
+
+        let selected_list = self.lists_component.get_selected_list();
+        let context = ScriptContext {
+            db: self.current_db_config.name.clone(),
+            selected_list: selected_list.map(|ui_list| ui_list.list.clone()),
+            selected_item: selected_list.and_then(|ui_list| {
+                let j = ui_list.item_state.selected()?;
+                ui_list.items.get(j).map(|ui_item| ui_item.item.clone())
+            }),
+        };
+        match RunningScript::start(script, self.store.clone(), context) {
+            Ok(running_script) => self.running_script = Some(running_script),
+            Err(e) => self.script_output = vec![format!("{:#}", e)],
+        }
+    }
+
+    /// Answer the question of the running script (`None` if cancelled), or run
+    /// the script chosen in the script picker
+    pub fn answer_script_question(&mut self, answer: Option<String>) {
+        let Some(question) = self.script_question.take() else {
+            return;
+        };
+        self.input_state.clear();
+        self.current_screen = question.return_screen;
+
+        match question.reply {
+            // The script fails on its own if it is gone
+            Some(reply) => {
+                let _ = reply.send(answer);
+            }
+            None => {
+                if answer.is_some()
+                    && let Some(script) = self.scripts.get(question.selected).cloned()
+                {
+                    self.start_script(&script);
+                }
+            }
+        }
+    }
+
+    /// Handle the requests of the running script: notifications are kept for
+    /// when it is over, questions are asked one at a time. Once the script is
+    /// over, the lists are reloaded with its changes
+    pub async fn handle_script_requests(&mut self) {
+        let Some(finished) = self.running_script.as_ref().map(|s| s.is_finished()) else {
+            return;
+        };
+
+        while self.script_question.is_none()
+            && let Some(request) = self.running_script.as_ref().and_then(|s| s.try_recv())
+        {
+            let (title, options, reply) = match request {
+                ScriptRequest::Notify(text) => {
+                    self.script_output.push(text);
+                    continue;
+                }
+                ScriptRequest::Prompt {
+                    question,
+                    default,
+                    reply,
+                } => {
+                    self.input_state = InputState {
+                        cursor_pos: default.chars().count(),
+                        current_input: default,
+                        is_modifying: false,
+                        insert_position: None,
+                    };
+                    (question, Vec::new(), reply)
+                }
+                ScriptRequest::Select {
+                    title,
+                    options,
+                    reply,
+                } => (title, options, reply),
+            };
+
+            let screen = if options.is_empty() {
+                CurrentScreen::ScriptPrompt
+            } else {
+                CurrentScreen::ScriptSelect
+            };
+            self.script_question = Some(ScriptQuestion {
+                title,
+                options,
+                selected: 0,
+                reply: Some(reply),
+                return_screen: std::mem::replace(&mut self.current_screen, screen),
+            });
+            // Show what the script changed so far
+            self.reload_lists().await;
+        }
+
+        // Requests sent before the end of the script have all been handled
+        if !finished || self.script_question.is_some() {
+            return;
+        }
+        if let Some(mut running_script) = self.running_script.take()
+            && let Err(e) = running_script.join()
+        {
+            self.script_output
+                .push(format!("Script '{}' failed: {}", running_script.name, e));
+        }
+        self.reload_lists().await;
+        self.record_changes().await;
+        self.commit_sync_changes().await;
+    }
+
+    /// Whether the output of the last script is shown (once it is over)
+    pub fn shows_script_output(&self) -> bool {
+        !self.script_output.is_empty() && self.running_script.is_none()
+    }
+
+    /// Reload the lists after they were changed behind the back of the
+    /// components (e.g. by a script), keeping the selected items where possible
+    async fn reload_lists(&mut self) {
+        let selected_items: Vec<(i64, Option<usize>)> = self
+            .lists_component
+            .lists
+            .iter()
+            .map(|ui_list| (ui_list.list.id, ui_list.item_state.selected()))
+            .collect();
+        if let Err(e) = self.lists_component.refresh_lists(&*self.store).await {
+            eprintln!("Failed to reload lists: {}", e);
+            return;
+        }
+
+        for ui_list in &mut self.lists_component.lists {
+            let selected = selected_items
+                .iter()
+                .find(|(id, _)| *id == ui_list.list.id)
+                .and_then(|(_, selected)| *selected);
+            if let Some(j) = selected
+                && !ui_list.items.is_empty()
+            {
+                ui_list
+                    .item_state
+                    .select(Some(j.min(ui_list.items.len() - 1)));
+            }
+        }
+    }
+
+    /// Enter the theme selector, starting from the configured theme
+    pub fn enter_theme_selection_screen(&mut self) {
+        let current = self.config.theme.clone().unwrap_or_default();
+        self.selected_theme_index = THEME_PRESETS
+            .iter()
+            .position(|name| *name == current.preset_name())
+            .unwrap_or(0);
+        self.current_screen = CurrentScreen::ThemeSelection;
+    }
+
+    /// Preview the theme currently selected in the theme selector
+    fn preview_selected_theme(&mut self) {
+        let name = THEME_PRESETS[self.selected_theme_index];
+        let theme_config = self
+            .config
+            .theme
+            .clone()
+            .unwrap_or_default()
+            .with_preset(name);
+        if let Ok(theme) = theme_config.resolve() {
+            self.theme = theme.adapted_to(self.color_support);
+        }
+    }
+
+    /// Move selection up in the theme selector and preview the theme
+    pub fn select_previous_theme(&mut self) {
+        self.selected_theme_index = if self.selected_theme_index == 0 {
+            THEME_PRESETS.len() - 1
+        } else {
+            self.selected_theme_index - 1
+        };
+        self.preview_selected_theme();
+    }
+
+    /// Move selection down in the theme selector and preview the theme
+    pub fn select_next_theme(&mut self) {
+        self.selected_theme_index = (self.selected_theme_index + 1) % THEME_PRESETS.len();
+        self.preview_selected_theme();
+    }
+
+    /// Use the selected theme and save it in the config
+    pub fn save_selected_theme(&mut self) -> Result<()> {
+        let name = THEME_PRESETS[self.selected_theme_index];
+        let theme_config = self
+            .config
+            .theme
+            .clone()
+            .unwrap_or_default()
+            .with_preset(name);
+
+        self.theme = theme_config
+            .resolve()
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to load theme: {}", e))?
+            .adapted_to(self.color_support);
+        self.config.theme = Some(theme_config);
+
+        // Write updated config to file
+        self.save_config()?;
+
+        Ok(())
+    }
+
+    /// Exit the theme selector restoring the configured theme
+    pub fn exit_theme_selection_without_saving(&mut self) {
+        if let Ok(theme) = self.config.theme.clone().unwrap_or_default().resolve() {
+            self.theme = theme.adapted_to(self.color_support);
+        }
+        self.go_back();
+    }
+
+    /// Enter the "Modify DB" screen by opening the corresponding pop-up
+    pub fn enter_modify_db_screen(&mut self) {
+        if let Some(selected_db) = self.config.dbs.get(self.selected_db_index) {
+            self.input_state = InputState {
+                current_input: selected_db.name.clone(),
+                cursor_pos: 0,
+                is_modifying: true,
+                insert_position: None,
+            };
+            self.current_screen = CurrentScreen::ModifyDB;
+        }
+    }
+
+    /// Exit the Modify DB screen without saving
+    pub fn exit_modify_db_without_saving(&mut self) {
+        self.current_screen = CurrentScreen::DBSelection;
+        self.input_state.clear();
     }
 }
 
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        // Remember the frame area to map mouse events back to components
+        self.last_frame_area = area;
+
         // Render background
         AppLayout::render_background(area, buf, &self.theme);
 
@@ -602,12 +2317,117 @@ impl Widget for &mut App {
             CurrentScreen::LeaderHelp => {
                 LeaderHelpPopUp::render(area, buf, &self.theme);
             }
+            CurrentScreen::HookLog => {
+                let failures = self
+                    .hooks
+                    .as_ref()
+                    .map(|hooks| hooks.failures())
+                    .unwrap_or_default();
+                HookLogPopUp::render(area, buf, &self.theme, &failures);
+            }
+            CurrentScreen::ScriptPrompt => {
+                if let Some(question) = &self.script_question {
+                    ScriptPromptPopUp::render(
+                        &self.input_state,
+                        items_area,
+                        buf,
+                        &self.theme,
+                        &question.title,
+                    );
+                }
+            }
+            CurrentScreen::ScriptSelect => {
+                if let Some(question) = &self.script_question {
+                    ScriptSelectPopUp::render(
+                        area,
+                        buf,
+                        &self.theme,
+                        &question.title,
+                        &question.options,
+                        question.selected,
+                    );
+                }
+            }
+            CurrentScreen::CommandPalette => {
+                if let Some(command_palette) = &self.palette {
+                    CommandPalettePopUp::render(
+                        area,
+                        buf,
+                        &self.theme,
+                        &command_palette.input,
+                        &command_palette.entries(),
+                        command_palette.selected,
+                    );
+                }
+            }
+            CurrentScreen::ExLine => {
+                if let Some(ex_line) = &self.ex_line {
+                    ExLinePopUp::render(area, buf, &self.theme, ex_line);
+                }
+            }
+            CurrentScreen::TemplateSelect => {
+                let names: Vec<String> = self.templates.iter().map(|t| t.name.clone()).collect();
+                TemplateSelectPopUp::render(area, buf, &self.theme, &names, self.selected_template);
+            }
+            CurrentScreen::TemplateVariable => {
+                if let Some(form) = &self.template_form {
+                    TemplateVariablePopUp::render(
+                        &self.input_state,
+                        items_area,
+                        buf,
+                        &self.theme,
+                        &form.template.name,
+                        form.next_variable().unwrap_or_default(),
+                    );
+                }
+            }
+            CurrentScreen::ThemeSelection => {
+                ThemeSelectionPopUp::render(
+                    area,
+                    buf,
+                    &self.theme,
+                    self.selected_theme_index,
+                    self.color_support,
+                );
+            }
             CurrentScreen::DeleteListConfirmation => {
                 use crate::ui::components::popups::DeleteListConfirmationPopUp;
                 if let Some(ref list_name) = self.pending_delete_list_name {
                     DeleteListConfirmationPopUp::render(lists_area, buf, &self.theme, list_name);
                 }
             }
+            CurrentScreen::Calendar => {
+                self.calendar_component.render(
+                    &self.lists_component.lists,
+                    lists_area.union(items_area),
+                    buf,
+                    &self.theme,
+                );
+            }
+            CurrentScreen::Board | CurrentScreen::EditStatuses => {
+                self.board_component.render(
+                    self.lists_component.get_selected_list(),
+                    lists_area.union(items_area),
+                    buf,
+                    &self.theme,
+                );
+                if self.current_screen == CurrentScreen::EditStatuses {
+                    EditStatusesPopUp::render(&self.input_state, items_area, buf, &self.theme);
+                }
+            }
+            CurrentScreen::EnterPassphrase => {
+                if let Some(pending_unlock) = &self.pending_unlock {
+                    PassphrasePopUp::render(
+                        area,
+                        buf,
+                        &self.theme,
+                        &pending_unlock.db_config.name,
+                        self.input_state.get_text().chars().count(),
+                        pending_unlock.is_new_passphrase,
+                        pending_unlock.error.as_deref(),
+                    );
+                }
+            }
             CurrentScreen::DeleteDatabaseConfirmation => {
                 use crate::ui::components::popups::DeleteDatabaseConfirmationPopUp;
                 if let Some(ref db_name) = self.pending_delete_db_name {
@@ -621,5 +2441,45 @@ impl Widget for &mut App {
             }
             _ => {}
         }
+
+        if let Some((reminder, _)) = &self.toast {
+            ToastPopUp::render(area, buf, &self.theme, reminder);
+        }
+
+        if self.shows_script_output() {
+            ScriptOutputPopUp::render(area, buf, &self.theme, &self.script_output);
+        }
+
+        if !self.template_messages.is_empty() {
+            TemplateReportPopUp::render(area, buf, &self.theme, &self.template_messages);
+        }
+
+        // Render the sync report on top of everything
+        if !self.sync_messages.is_empty() {
+            SyncReportPopUp::render(area, buf, &self.theme, &self.sync_messages);
+        }
+    }
+}
+
+/// Store firing the hooks of the config, if any
+fn with_hooks(
+    store: Arc<dyn TodoStore>,
+    hooks: Option<&Arc<Hooks>>,
+    db_name: &str,
+) -> Arc<dyn TodoStore> {
+    match hooks {
+        Some(hooks) => Arc::new(HookedStore::new(store, hooks.clone(), db_name)),
+        None => store,
+    }
+}
+
+/// Lines of the sync report for the result of a sync
+fn sync_report(kind: &str, result: anyhow::Result<Vec<SyncConflict>>) -> Vec<String> {
+    match result {
+        Ok(conflicts) => conflicts
+            .iter()
+            .map(|c| format!("{}: {}", c.list_name, c.description))
+            .collect(),
+        Err(e) => vec![format!("{} sync failed: {}", kind, e)],
     }
 }
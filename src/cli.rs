@@ -0,0 +1,500 @@
+//! Command line interface. Without a subcommand, the TUI is started
+
+use crate::api;
+#[cfg(unix)]
+use crate::ctl::{self, Message, Request};
+use crate::db::config::{Config, DBConfig};
+use crate::db::connections::init_db;
+use crate::db::crypto;
+use crate::db::models::{Priority, TodoItem, TodoList};
+use crate::db::store::{TodoStore, open_store};
+use crate::remind::{Reminders, SystemClock};
+use crate::sync::caldav::CaldavSync;
+use crate::sync::change_log;
+use crate::sync::ical::{self, VCalendar};
+use crate::sync::taskwarrior;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use sqlx::SqlitePool;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug, Parser)]
+#[command(name = "judo", version, about = "Judo - TUI for ToDo lists")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Write the change log of a database to a shared folder (not available for
+    /// encrypted databases)
+    ExportChanges {
+        /// Folder shared between machines (e.g. synced with Syncthing or Dropbox)
+        dir: PathBuf,
+        /// Database to export (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Merge the change logs found in a shared folder into a database
+    ApplyChanges {
+        /// Folder shared between machines
+        dir: PathBuf,
+        /// Database to merge into (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Encrypt the lists and items of a database in place with a passphrase
+    Encrypt {
+        /// Database to encrypt (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Decrypt an encrypted database in place, removing its passphrase
+    Decrypt {
+        /// Database to decrypt (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Check that lists and items have unique orderings, and repair them if not
+    Check {
+        /// Database to check (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Write the items of a database (or of one of its lists) to an iCalendar file
+    ExportIcs {
+        /// File to write (`-` for the standard output)
+        file: PathBuf,
+        /// List to export (defaults to every list, named in CATEGORIES)
+        #[arg(long)]
+        list: Option<String>,
+        /// Database to export (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Import the to-dos of an iCalendar file, updating the items already imported
+    ImportIcs {
+        /// File to read
+        file: PathBuf,
+        /// List receiving the to-dos (defaults to their category or the calendar name)
+        #[arg(long)]
+        list: Option<String>,
+        /// Database to import into (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Write the items of a database (or of one of its lists) as Taskwarrior
+    /// JSON, as `task export` does
+    ExportTaskwarrior {
+        /// File to write (`-` for the standard output)
+        file: PathBuf,
+        /// List to export (defaults to every list, as projects)
+        #[arg(long)]
+        list: Option<String>,
+        /// Database to export (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Import the output of `task export`, updating the items already imported,
+    /// and report the fields that Judo doesn't support
+    ImportTaskwarrior {
+        /// File to read (`-` for the standard input)
+        file: PathBuf,
+        /// List receiving the tasks (defaults to their project)
+        #[arg(long)]
+        list: Option<String>,
+        /// Database to import into (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Sync a database with the calendars of its CalDAV server (configured in
+    /// its [dbs.caldav] table)
+    Caldav {
+        /// Database to sync (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Serve a database over a local HTTP/JSON API, authenticated with the
+    /// token in JUDO_API_TOKEN (or a random one, printed at startup)
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = api::DEFAULT_BIND)]
+        bind: String,
+        /// Database to serve (defaults to the default database)
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Drive a running TUI through its control socket
+    #[cfg(unix)]
+    Ctl {
+        /// Socket of the instance (defaults to JUDO_SOCKET, or the only
+        /// instance running)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
+    /// Keep running and send reminders of the items of all databases whose due
+    /// date is approaching or has passed (configured in the [remind] table)
+    Remind {
+        /// Minutes before the due date when items are reminded
+        #[arg(long)]
+        lead: Option<i64>,
+        /// Scan the databases once and exit (e.g. from cron)
+        #[arg(long)]
+        once: bool,
+    },
+}
+
+/// Requests of `judo ctl`
+#[cfg(unix)]
+#[derive(Debug, Subcommand)]
+pub enum CtlCommand {
+    /// Add an item at the end of a list
+    AddItem {
+        name: String,
+        /// List to add to (defaults to the selected one)
+        #[arg(long)]
+        list: Option<String>,
+        /// Priority of the item (high, medium or low)
+        #[arg(long)]
+        priority: Option<Priority>,
+        /// Due date, in RFC 3339 (e.g. 2025-10-03T17:00:00Z)
+        #[arg(long)]
+        due: Option<DateTime<Utc>>,
+    },
+    /// Select a list
+    SelectList { name: String },
+    /// Toggle whether an item is done
+    Toggle {
+        /// Item to toggle (defaults to the selected one)
+        item: Option<String>,
+        /// List of the item (defaults to the selected one)
+        #[arg(long)]
+        list: Option<String>,
+    },
+    /// Reload the lists, e.g. after writing to the database directly
+    Refresh,
+    /// Open another database
+    SwitchDb { name: String },
+    /// Print the current database, screen and selection as JSON
+    State,
+    /// Print the events of the app as JSON lines until it exits
+    Watch,
+    /// Send a request written in JSON and print the reply
+    Send { json: String },
+}
+
+#[cfg(unix)]
+impl CtlCommand {
+    /// Request to send, or `None` to only watch events
+    fn into_request(self) -> Result<Option<Request>> {
+        let request = match self {
+            CtlCommand::AddItem {
+                name,
+                list,
+                priority,
+                due,
+            } => Request::AddItem {
+                name,
+                list,
+                priority,
+                due_date: due,
+            },
+            CtlCommand::SelectList { name } => Request::SelectList { name },
+            CtlCommand::Toggle { item, list } => Request::Toggle { item, list },
+            CtlCommand::Refresh => Request::Refresh,
+            CtlCommand::SwitchDb { name } => Request::SwitchDb { name },
+            CtlCommand::State => Request::State,
+            CtlCommand::Watch => return Ok(None),
+            CtlCommand::Send { json } => {
+                serde_json::from_str(&json).with_context(|| "Invalid request")?
+            }
+        };
+        Ok(Some(request))
+    }
+}
+
+impl Command {
+    /// Run the subcommand
+    pub async fn run(self) -> Result<()> {
+        match self {
+            Command::ExportChanges { dir, db } => {
+                let (_, pool) = open_db(db.as_deref()).await?;
+                let path = change_log::export_changes(&pool, &dir).await?;
+                println!("Exported changes to {}", path.display());
+            }
+            Command::ApplyChanges { dir, db } => {
+                let (_, pool) = open_db(db.as_deref()).await?;
+                unlock_if_encrypted(&pool).await?;
+                let applied = change_log::apply_changes(&pool, &dir).await?;
+                println!("Applied {} new changes", applied);
+            }
+            Command::Encrypt { db } => {
+                let (db_config, pool) = open_db(db.as_deref()).await?;
+                if crypto::is_encrypted(&pool).await? {
+                    anyhow::bail!("Database '{}' is already encrypted", db_config.name);
+                }
+
+                let passphrase = read_passphrase("New passphrase: ")?;
+                if read_passphrase("Repeat passphrase: ")? != passphrase {
+                    anyhow::bail!("Passphrases don't match");
+                }
+                crypto::encrypt_database(&pool, &passphrase).await?;
+                set_encrypted(&db_config.name, true)?;
+                println!("Encrypted database '{}'", db_config.name);
+            }
+            Command::Decrypt { db } => {
+                let (db_config, pool) = open_db(db.as_deref()).await?;
+                let passphrase = unlock_if_encrypted(&pool)
+                    .await?
+                    .with_context(|| format!("Database '{}' is not encrypted", db_config.name))?;
+                crypto::decrypt_database(&pool, &passphrase).await?;
+                set_encrypted(&db_config.name, false)?;
+                println!("Decrypted database '{}'", db_config.name);
+            }
+            Command::Check { db } => {
+                let config = Config::read()?;
+                let db_config = find_db(&config, db.as_deref())?;
+                let store = open_store(&db_config.connection_str).await?;
+                match store.repair_orderings().await? {
+                    0 => println!("Database '{}' is consistent", db_config.name),
+                    repaired => println!(
+                        "Repaired the ordering of {} rows in '{}'",
+                        repaired, db_config.name
+                    ),
+                }
+            }
+            Command::ExportIcs { file, list, db } => {
+                let (_, store) = open_db_store(db.as_deref()).await?;
+                let calendar = match list {
+                    Some(name) => {
+                        let lists = TodoList::get_all(&*store).await?;
+                        let list = lists
+                            .iter()
+                            .find(|l| l.name == name)
+                            .with_context(|| format!("No list named '{}'", name))?;
+                        let items = TodoItem::get_by_list_id(&*store, list.id).await?;
+                        VCalendar::from_list(list, &items)
+                    }
+                    None => VCalendar::from_store(&*store).await?,
+                };
+
+                if file.as_os_str() == "-" {
+                    print!("{}", calendar.to_ics());
+                } else {
+                    std::fs::write(&file, calendar.to_ics())
+                        .with_context(|| format!("Failed to write {}", file.display()))?;
+                    println!(
+                        "Exported {} items to {}",
+                        calendar.todos.len(),
+                        file.display()
+                    );
+                }
+            }
+            Command::ImportIcs { file, list, db } => {
+                let (_, store) = open_db_store(db.as_deref()).await?;
+                let ics = std::fs::read_to_string(&file)
+                    .with_context(|| format!("Failed to read {}", file.display()))?;
+                let calendar = VCalendar::parse(&ics)
+                    .with_context(|| format!("Failed to parse {}", file.display()))?;
+                let summary = ical::import_calendar(&*store, &calendar, list.as_deref()).await?;
+                println!(
+                    "Imported {} new items and updated {}",
+                    summary.created, summary.updated
+                );
+            }
+            Command::ExportTaskwarrior { file, list, db } => {
+                let (_, store) = open_db_store(db.as_deref()).await?;
+                let tasks = taskwarrior::export_tasks(&*store, list.as_deref()).await?;
+                let json = serde_json::to_string_pretty(&tasks)?;
+
+                if file.as_os_str() == "-" {
+                    println!("{}", json);
+                } else {
+                    std::fs::write(&file, json)
+                        .with_context(|| format!("Failed to write {}", file.display()))?;
+                    println!("Exported {} tasks to {}", tasks.len(), file.display());
+                }
+            }
+            Command::ImportTaskwarrior { file, list, db } => {
+                let (_, store) = open_db_store(db.as_deref()).await?;
+                let json = if file.as_os_str() == "-" {
+                    std::io::read_to_string(std::io::stdin())?
+                } else {
+                    std::fs::read_to_string(&file)
+                        .with_context(|| format!("Failed to read {}", file.display()))?
+                };
+                let tasks = taskwarrior::parse_tasks(&json)
+                    .with_context(|| format!("Failed to parse {}", file.display()))?;
+                let report = taskwarrior::import_tasks(&*store, &tasks, list.as_deref()).await?;
+                for line in report.lines() {
+                    println!("{}", line);
+                }
+            }
+            Command::Caldav { db } => {
+                let (db_config, store) = open_db_store(db.as_deref()).await?;
+                let caldav_config = db_config.caldav.as_ref().with_context(|| {
+                    format!("Database '{}' has no CalDAV server", db_config.name)
+                })?;
+                let conflicts = CaldavSync::new(caldav_config, &db_config.name)?
+                    .sync(&*store)
+                    .await?;
+                for conflict in &conflicts {
+                    println!("{}: {}", conflict.list_name, conflict.description);
+                }
+                println!("Synced '{}' with its CalDAV server", db_config.name);
+            }
+            Command::Serve { bind, db } => {
+                let (db_config, store) = open_db_store(db.as_deref()).await?;
+                let (token, generated) = api::token_from_env_or_random()?;
+                println!("Serving '{}' on http://{}", db_config.name, bind);
+                if generated {
+                    println!("Token: {}", token);
+                }
+                api::serve(&bind, store, token).await?;
+            }
+            #[cfg(unix)]
+            Command::Ctl { socket, command } => {
+                let mut client = ctl::Client::connect(&ctl::find_socket(socket)?).await?;
+                match command.into_request()? {
+                    Some(request) => match client.send(&request).await? {
+                        Message::Error { message } => anyhow::bail!(message),
+                        Message::Ok => {}
+                        reply => println!("{}", serde_json::to_string(&reply)?),
+                    },
+                    None => {
+                        while let Some(message) = client.next_message().await? {
+                            println!("{}", serde_json::to_string(&message)?);
+                        }
+                    }
+                }
+            }
+            Command::Remind { lead, once } => {
+                let config = Config::read()?;
+                let remind_config = config.remind.clone().unwrap_or_default();
+                let mut reminders = Reminders::new(
+                    Reminders::open_databases(&config).await,
+                    remind_config.notifier()?,
+                    Arc::new(SystemClock),
+                    lead.unwrap_or(remind_config.lead_minutes),
+                );
+
+                if once {
+                    println!("Sent {} reminders", reminders.tick().await);
+                } else {
+                    reminders.run(remind_config.interval_secs).await;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Find a database in the config by name (the default one if no name is given)
+pub fn find_db(config: &Config, name: Option<&str>) -> Result<DBConfig> {
+    match name {
+        Some(name) => config
+            .dbs
+            .iter()
+            .find(|db| db.name == name)
+            .cloned()
+            .with_context(|| format!("No database named '{}' in judo.toml", name)),
+        None => config.get_default(),
+    }
+}
+
+/// Open the store of a database of the config, whatever its backend, asking
+/// for the passphrase if it is encrypted
+async fn open_db_store(name: Option<&str>) -> Result<(DBConfig, Arc<dyn TodoStore>)> {
+    let config = Config::read()?;
+    let db_config = find_db(&config, name)?;
+    let store = open_store(&db_config.connection_str).await?;
+    if let Some(pool) = store.sqlite_pool() {
+        unlock_if_encrypted(pool).await?;
+    }
+    Ok((db_config, store))
+}
+
+/// Connect to a SQLite database of the config
+async fn open_db(name: Option<&str>) -> Result<(DBConfig, SqlitePool)> {
+    let config = Config::read()?;
+    let db_config = find_db(&config, name)?;
+    if !db_config.connection_str.starts_with("sqlite:") {
+        anyhow::bail!("Database '{}' is not a SQLite database", db_config.name);
+    }
+    let pool = init_db(&db_config.connection_str).await?;
+    Ok((db_config, pool))
+}
+
+/// Ask for the passphrase of an encrypted database and unlock it. Returns the
+/// passphrase, or `None` if the database isn't encrypted
+async fn unlock_if_encrypted(pool: &SqlitePool) -> Result<Option<String>> {
+    if !crypto::is_encrypted(pool).await? {
+        return Ok(None);
+    }
+
+    let passphrase = read_passphrase("Passphrase: ")?;
+    crypto::unlock(pool, &passphrase).await?;
+    Ok(Some(passphrase))
+}
+
+/// Record in judo.toml whether a database is encrypted
+fn set_encrypted(db_name: &str, encrypted: bool) -> Result<()> {
+    let mut config = Config::read()?;
+    if let Some(db) = config.dbs.iter_mut().find(|db| db.name == db_name) {
+        db.encrypted = encrypted;
+    }
+
+    config.write(&Config::default_path()?)
+}
+
+/// Read a passphrase from the terminal without echoing it (or a line of the
+/// standard input when it isn't a terminal)
+fn read_passphrase(prompt: &str) -> Result<String> {
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    terminal::enable_raw_mode()?;
+    let result = read_hidden_line();
+    terminal::disable_raw_mode()?;
+    eprintln!();
+
+    result
+}
+
+fn read_hidden_line() -> Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Esc => anyhow::bail!("Cancelled"),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                anyhow::bail!("Cancelled")
+            }
+            KeyCode::Char(c) => line.push(c),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,491 @@
+//! Control channel of a running TUI, so that scripts can drive and observe it
+//! (`judo ctl`).
+//!
+//! Every instance of the TUI listens on a Unix socket of its own, named after
+//! its process id in the runtime directory. Clients write requests as JSON,
+//! one per line (e.g. `{"cmd": "add_item", "name": "Milk"}`), and each request
+//! gets a reply: `{"event": "ok"}`, `{"event": "error", "message": "..."}` or
+//! `{"event": "state", ...}`. Every connection also receives the events of the
+//! app, whether they come from a client or from the keyboard: `db_switched`,
+//! `list_selected`, `item_selected` and `changed` (lists or items were
+//! modified).
+
+use crate::db::models::{Priority, UIList};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::OwnedReadHalf;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+/// Environment variable holding the socket `judo ctl` connects to
+pub const SOCKET_ENV: &str = "JUDO_SOCKET";
+
+/// Number of events kept for a connection that doesn't read them fast enough
+const EVENT_BUFFER: usize = 64;
+
+/// Request of a client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Request {
+    /// Add an item at the end of a list (the selected one by default)
+    AddItem {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        list: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        priority: Option<Priority>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        due_date: Option<DateTime<Utc>>,
+    },
+    /// Select a list by name
+    SelectList { name: String },
+    /// Toggle an item by name (the selected one by default) in a list (the
+    /// selected one by default)
+    Toggle {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        list: Option<String>,
+    },
+    /// Reload the lists from the database, e.g. after writing to it directly
+    Refresh,
+    /// Open another database of the config
+    SwitchDb { name: String },
+    /// Get the current database, screen and selection
+    State,
+}
+
+/// Reply to a request, or event of the app
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Message {
+    Ok,
+    Error { message: String },
+    State(Snapshot),
+    DbSwitched { db: String },
+    ListSelected { list: Option<String> },
+    ItemSelected { item: Option<String> },
+    Changed,
+}
+
+impl Message {
+    /// Whether the message replies to a request (rather than being an event)
+    pub fn is_reply(&self) -> bool {
+        matches!(
+            self,
+            Message::Ok | Message::Error { .. } | Message::State(_)
+        )
+    }
+}
+
+/// What clients can observe of the app
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub db: String,
+    pub screen: String,
+    pub list: Option<String>,
+    pub item: Option<String>,
+    /// Hash of the lists and items, to notice when they change
+    #[serde(skip)]
+    pub contents: u64,
+}
+
+impl Snapshot {
+    pub fn new(db: &str, screen: &str, lists: &[UIList], selected: Option<usize>) -> Self {
+        let selected_list = selected.and_then(|i| lists.get(i));
+        let item = selected_list.and_then(|ui_list| {
+            let j = ui_list.item_state.selected()?;
+            ui_list
+                .items
+                .get(j)
+                .map(|ui_item| ui_item.item.name.clone())
+        });
+
+        let mut hasher = DefaultHasher::new();
+        for ui_list in lists {
+            let list = &ui_list.list;
+            (list.id, &list.name, list.ordering, &list.statuses).hash(&mut hasher);
+            for ui_item in &ui_list.items {
+                let item = &ui_item.item;
+                (item.id, &item.name, item.is_done, item.ordering).hash(&mut hasher);
+                item.updated_at.timestamp_micros().hash(&mut hasher);
+            }
+        }
+
+        Snapshot {
+            db: db.to_string(),
+            screen: screen.to_string(),
+            list: selected_list.map(|ui_list| ui_list.list.name.clone()),
+            item,
+            contents: hasher.finish(),
+        }
+    }
+
+    /// Events telling what changed since a previous snapshot
+    pub fn events_since(&self, previous: &Snapshot) -> Vec<Message> {
+        let mut events = Vec::new();
+        if self.db != previous.db {
+            events.push(Message::DbSwitched {
+                db: self.db.clone(),
+            });
+        }
+        if self.list != previous.list {
+            events.push(Message::ListSelected {
+                list: self.list.clone(),
+            });
+        }
+        if self.item != previous.item {
+            events.push(Message::ItemSelected {
+                item: self.item.clone(),
+            });
+        }
+        if self.db == previous.db && self.contents != previous.contents {
+            events.push(Message::Changed);
+        }
+        events
+    }
+}
+
+/// Request waiting for the app to handle it
+pub struct PendingRequest {
+    pub request: Request,
+    reply: oneshot::Sender<Message>,
+}
+
+impl PendingRequest {
+    pub fn reply(self, message: Message) {
+        // The client may be gone already
+        let _ = self.reply.send(message);
+    }
+}
+
+/// Socket of a running TUI, removed when dropped
+pub struct ControlServer {
+    path: PathBuf,
+    requests: mpsc::UnboundedReceiver<PendingRequest>,
+    events: broadcast::Sender<Message>,
+}
+
+impl ControlServer {
+    /// Directory of the sockets of the running instances
+    pub fn socket_dir() -> PathBuf {
+        match std::env::var_os("XDG_RUNTIME_DIR") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("judo"),
+            _ => {
+                let user = std::env::var("USER").unwrap_or_default();
+                std::env::temp_dir().join(format!("judo-{}", user))
+            }
+        }
+    }
+
+    /// Socket of this instance in a directory
+    pub fn default_path(dir: &Path) -> PathBuf {
+        dir.join(format!("{}.sock", std::process::id()))
+    }
+
+    /// Listen on the socket of this instance in a directory shared by the
+    /// instances of the user (e.g. [`ControlServer::socket_dir`]). The
+    /// directory may have been there already, e.g. created in /tmp by another
+    /// user to catch the requests: it must be private to the current user
+    pub fn bind_in(dir: &Path) -> Result<Self> {
+        create_private_dir(dir)?;
+        check_private_dir(dir)?;
+        Self::bind(&Self::default_path(dir))
+    }
+
+    /// Listen on a socket in the background. Must be called within a tokio
+    /// runtime
+    pub fn bind(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            create_private_dir(dir)?;
+        }
+        // Left over by an instance that didn't exit cleanly
+        if path.exists() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+
+        let (request_sender, requests) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let accepted_events = events.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_connection(
+                    stream,
+                    request_sender.clone(),
+                    accepted_events.subscribe(),
+                ));
+            }
+        });
+
+        Ok(ControlServer {
+            path: path.to_path_buf(),
+            requests,
+            events,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Next request waiting for the app, if any
+    pub fn try_recv(&mut self) -> Option<PendingRequest> {
+        self.requests.try_recv().ok()
+    }
+
+    /// Whether any client is connected to receive events
+    pub fn has_clients(&self) -> bool {
+        self.events.receiver_count() > 0
+    }
+
+    /// Send an event to every connected client
+    pub fn emit(&self, event: Message) {
+        // Nobody may be listening
+        let _ = self.events.send(event);
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))
+}
+
+/// Fail unless a directory is owned by the current user and only accessible
+/// to them
+fn check_private_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::symlink_metadata(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?;
+    // SAFETY: getuid has no preconditions and cannot fail
+    let uid = unsafe { libc::getuid() };
+    if !metadata.is_dir() || metadata.uid() != uid {
+        bail!(
+            "{} is not a directory of yours, refusing to use it",
+            dir.display()
+        );
+    }
+    if metadata.mode() & 0o777 != 0o700 {
+        bail!(
+            "{} is accessible to other users (mode {:o}), refusing to use it",
+            dir.display(),
+            metadata.mode() & 0o777
+        );
+    }
+    Ok(())
+}
+
+/// Pass the requests of a client to the app, and send it the replies and
+/// events
+async fn serve_connection(
+    stream: UnixStream,
+    requests: mpsc::UnboundedSender<PendingRequest>,
+    mut events: broadcast::Receiver<Message>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        let message = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => match serde_json::from_str(&line) {
+                    Ok(request) => {
+                        let (reply, receiver) = oneshot::channel();
+                        if requests.send(PendingRequest { request, reply }).is_err() {
+                            break;
+                        }
+                        match receiver.await {
+                            Ok(message) => message,
+                            Err(_) => break,
+                        }
+                    }
+                    Err(e) => Message::Error {
+                        message: format!("Invalid request: {}", e),
+                    },
+                },
+                _ => break,
+            },
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+        if write_message(&mut writer, &message).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn write_message<W: AsyncWriteExt + Unpin>(
+    writer: &mut W,
+    message: &impl Serialize,
+) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// Socket of the running instance: the given one, the one in `JUDO_SOCKET`,
+/// or the only one in the socket directory
+pub fn find_socket(socket: Option<PathBuf>) -> Result<PathBuf> {
+    if let Some(socket) = socket {
+        return Ok(socket);
+    }
+    if let Some(socket) = std::env::var_os(SOCKET_ENV).filter(|s| !s.is_empty()) {
+        return Ok(PathBuf::from(socket));
+    }
+
+    let dir = ControlServer::socket_dir();
+    if dir.exists() {
+        check_private_dir(&dir)?;
+    }
+    let mut sockets: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "sock"))
+                // Sockets of instances that crashed refuse connections
+                .filter(|path| std::os::unix::net::UnixStream::connect(path).is_ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    sockets.sort();
+
+    match sockets.as_slice() {
+        [] => bail!("No running instance of judo found in {}", dir.display()),
+        [socket] => Ok(socket.clone()),
+        _ => bail!(
+            "Several instances of judo are running, choose one with --socket or {}:\n{}",
+            SOCKET_ENV,
+            sockets
+                .iter()
+                .map(|socket| socket.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+    }
+}
+
+/// Connection to a running TUI
+pub struct Client {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: tokio::net::unix::OwnedWriteHalf,
+}
+
+impl Client {
+    pub async fn connect(path: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(path)
+            .await
+            .with_context(|| format!("Failed to connect to {}", path.display()))?;
+        let (reader, writer) = stream.into_split();
+        Ok(Client {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        })
+    }
+
+    /// Send a request and wait for its reply, skipping the events received
+    /// meanwhile
+    pub async fn send(&mut self, request: &Request) -> Result<Message> {
+        write_message(&mut self.writer, request).await?;
+        loop {
+            let message = self
+                .next_message()
+                .await?
+                .with_context(|| "The connection was closed before replying")?;
+            if message.is_reply() {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Next reply or event, or `None` once the app exited
+    pub async fn next_message(&mut self) -> Result<Option<Message>> {
+        let Some(line) = self.lines.next_line().await? else {
+            return Ok(None);
+        };
+        let message =
+            serde_json::from_str(&line).with_context(|| format!("Invalid message: {}", line))?;
+        Ok(Some(message))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_request_json() {
+        let request: Request =
+            serde_json::from_str(r#"{"cmd": "add_item", "name": "Milk", "priority": "high"}"#)
+                .unwrap();
+        assert_eq!(
+            request,
+            Request::AddItem {
+                name: "Milk".to_string(),
+                list: None,
+                priority: Some(Priority::High),
+                due_date: None,
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&Request::Refresh).unwrap(),
+            r#"{"cmd":"refresh"}"#
+        );
+        assert!(serde_json::from_str::<Request>(r#"{"cmd": "fly"}"#).is_err());
+    }
+
+    #[test]
+    fn test_events_since() {
+        let snapshot = |db: &str, list: Option<&str>, contents| Snapshot {
+            db: db.to_string(),
+            screen: "ListSelection".to_string(),
+            list: list.map(str::to_string),
+            item: None,
+            contents,
+        };
+        let before = snapshot("dojo", Some("Groceries"), 1);
+        assert!(before.events_since(&before).is_empty());
+        assert_eq!(
+            snapshot("dojo", Some("Chores"), 2).events_since(&before),
+            vec![
+                Message::ListSelected {
+                    list: Some("Chores".to_string())
+                },
+                Message::Changed
+            ]
+        );
+        assert_eq!(
+            snapshot("work", None, 3).events_since(&before),
+            vec![
+                Message::DbSwitched {
+                    db: "work".to_string()
+                },
+                Message::ListSelected { list: None }
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&Message::State(before)).unwrap(),
+            r#"{"event":"state","db":"dojo","screen":"ListSelection","list":"Groceries","item":null}"#
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use crate::ui::theme::Theme;
+use crate::hooks::HooksConfig;
+use crate::remind::RemindConfig;
+use crate::script::ScriptsConfig;
+use crate::ui::theme::ThemeConfig;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -13,7 +16,16 @@ const DEFAULT_DB_FILE: &str = "judo.db";
 pub struct Config {
     pub default: String,
     pub dbs: Vec<DBConfig>,
-    pub theme: Option<Theme>,
+    pub theme: Option<ThemeConfig>,
+    /// Reminders of items due soon (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remind: Option<RemindConfig>,
+    /// Commands run when lists and items change (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HooksConfig>,
+    /// Keys bound to scripts (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scripts: Option<ScriptsConfig>,
 }
 
 /// Database configuration
@@ -21,6 +33,40 @@ pub struct Config {
 pub struct DBConfig {
     pub name: String,
     pub connection_str: String,
+    /// Mirror the database to a git working tree (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_sync: Option<GitSyncConfig>,
+    /// Encrypt the names of lists and items with a key derived from a passphrase
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
+    /// Sync the lists with the task collections of a CalDAV server (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caldav: Option<CaldavConfig>,
+}
+
+/// Configuration of the git sync of a database
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct GitSyncConfig {
+    /// Directory inside a git working tree where lists are written (one file per list)
+    pub path: String,
+    /// Remote to pull from and push to (defaults to "origin")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+}
+
+/// Configuration of the CalDAV sync of a database
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CaldavConfig {
+    /// Calendar home of the user, where each list is a calendar collection
+    /// (e.g. "https://dav.example.com/calendars/me/")
+    pub url: String,
+    /// User name for basic authentication (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Environment variable holding the password. Without it, the password is
+    /// read from the keyring of the system
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_env: Option<String>,
 }
 
 impl Default for DBConfig {
@@ -40,6 +86,9 @@ impl Default for DBConfig {
         Self {
             name: DEFAULT_DB_NAME.to_string(),
             connection_str,
+            git_sync: None,
+            encrypted: false,
+            caldav: None,
         }
     }
 }
@@ -50,7 +99,10 @@ impl Default for Config {
         Self {
             default: DEFAULT_DB_NAME.to_string(),
             dbs: vec![DBConfig::default()],
-            theme: Some(Theme::default()),
+            theme: Some(ThemeConfig::default()),
+            remind: None,
+            hooks: None,
+            scripts: None,
         }
     }
 }
@@ -73,13 +125,29 @@ impl Config {
         Ok(())
     }
 
+    /// Path of the judo.toml file in the standard config directory
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(dirs::config_dir()
+            .with_context(|| "Could not find config directory")?
+            .join("judo")
+            .join("judo.toml"))
+    }
+
+    /// Directory where databases are stored by default
+    pub fn default_data_dir() -> Result<PathBuf> {
+        Ok(dirs::data_dir()
+            .with_context(|| "Could not find data directory")?
+            .join("judo"))
+    }
+
     /// Read and serialize a judo.toml file
     pub fn read() -> Result<Self> {
         // Use config directory to standardize storage of config file
-        let config_dir = dirs::config_dir().unwrap().join("judo");
-
-        // Define the config file path
-        let config_path = config_dir.join("judo.toml");
+        let config_path = Self::default_path()?;
+        let config_dir = config_path
+            .parent()
+            .with_context(|| "Invalid config path")?
+            .to_path_buf();
 
         // Create config if not existing
         if !config_dir.exists() | !config_path.exists() {
@@ -123,3 +191,58 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_round_trip_with_theme_preset() -> Result<()> {
+        let config = Config {
+            default: "dojo".to_string(),
+            dbs: vec![DBConfig {
+                name: "dojo".to_string(),
+                connection_str: "sqlite::memory:".to_string(),
+                git_sync: None,
+                encrypted: false,
+                caldav: None,
+            }],
+            theme: Some(ThemeConfig::Preset("nord".to_string())),
+            remind: None,
+            hooks: None,
+            scripts: None,
+        };
+
+        let content = toml::to_string_pretty(&config)?;
+        let parsed: Config = toml::from_str(&content)?;
+
+        assert_eq!(parsed.theme.unwrap().preset_name(), "nord");
+        assert_eq!(parsed.dbs.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_with_full_theme_table_still_parses() -> Result<()> {
+        let content = r##"
+            default = "dojo"
+
+            [theme]
+            background = "#020202"
+            foreground = "#AAAAAA"
+            accent = "#FF0A0A"
+            border = "#AAAAAA"
+            highlight_bg = "#AAAAAA"
+            highlight_fg = "#020202"
+
+            [[dbs]]
+            name = "dojo"
+            connection_str = "sqlite::memory:"
+        "##;
+
+        let config: Config = toml::from_str(content)?;
+        let theme = config.theme.unwrap().resolve()?;
+
+        assert_eq!(theme.accent, "#FF0A0A");
+        Ok(())
+    }
+}
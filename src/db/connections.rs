@@ -1,3 +1,4 @@
+use crate::db::crypto;
 use anyhow::{Context, Result};
 use sqlx::migrate::Migrator;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
@@ -38,6 +39,8 @@ pub async fn init_db(connection_str: &str) -> Result<SqlitePool> {
 
     // Always run migrations on startup - they're idempotent and fast
     run_migrations(&pool).await?;
+    // Noted so that writes fail until the database is unlocked
+    crypto::is_encrypted(&pool).await?;
 
     Ok(pool)
 }
@@ -0,0 +1,353 @@
+//! At-rest encryption of the names of lists and items.
+//!
+//! The key is derived from a passphrase with Argon2id and a random salt stored in
+//! the database; names are encrypted with XChaCha20-Poly1305 under a random nonce
+//! and stored as `enc1:<base64 nonce + ciphertext>`. Keys are cached for the
+//! session once a database is unlocked, so that the rest of the code can keep
+//! passing a pool around.
+
+use anyhow::{Context, Result};
+use argon2::Argon2;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// Prefix of encrypted values
+const ENCRYPTED_PREFIX: &str = "enc1:";
+
+/// Known plaintext stored encrypted to check passphrases
+const VERIFIER: &str = "judo";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Ciphers of the unlocked databases, by database file
+fn session_keys() -> &'static Mutex<HashMap<String, Cipher>> {
+    static KEYS: OnceLock<Mutex<HashMap<String, Cipher>>> = OnceLock::new();
+    KEYS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Databases known to be encrypted, by database file, so that writes to them
+/// fail while they are locked instead of storing names in clear
+fn encrypted_dbs() -> &'static Mutex<HashSet<String>> {
+    static DBS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    DBS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Identify the database of a pool in the key cache
+fn db_key(pool: &SqlitePool) -> String {
+    pool.connect_options().get_filename().display().to_string()
+}
+
+/// Symmetric cipher derived from a passphrase
+#[derive(Clone)]
+pub struct Cipher {
+    aead: XChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// Derive the key from a passphrase and a salt with Argon2id
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Cipher> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Failed to derive key: {}", e))?;
+
+        Ok(Cipher {
+            aead: XChaCha20Poly1305::new(&key.into()),
+        })
+    }
+
+    /// Encrypt a text under a fresh random nonce
+    pub fn encrypt(&self, text: &str) -> Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::fill(&mut nonce).map_err(|e| anyhow::anyhow!("No randomness: {}", e))?;
+
+        let ciphertext = self
+            .aead
+            .encrypt(XNonce::from_slice(&nonce), text.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt"))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(payload)))
+    }
+
+    /// Decrypt a text produced by [`Cipher::encrypt`]
+    pub fn decrypt(&self, text: &str) -> Result<String> {
+        let payload = text
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .with_context(|| "Value is not encrypted")?;
+        let payload = BASE64
+            .decode(payload)
+            .with_context(|| "Invalid encrypted value")?;
+        if payload.len() < NONCE_LEN {
+            anyhow::bail!("Invalid encrypted value");
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .aead
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt (wrong passphrase?)"))?;
+
+        String::from_utf8(plaintext).with_context(|| "Decrypted value is not valid UTF-8")
+    }
+}
+
+/// Whether a stored value is encrypted
+pub fn is_encrypted_text(text: &str) -> bool {
+    text.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Encrypt a name before storing it. Names of unencrypted databases are stored
+/// as they are; an encrypted database must be unlocked first
+pub fn seal_text(pool: &SqlitePool, text: &str) -> Result<String> {
+    let key = db_key(pool);
+    match session_keys().lock().unwrap().get(&key) {
+        Some(cipher) => cipher.encrypt(text),
+        None if encrypted_dbs().lock().unwrap().contains(&key) => {
+            anyhow::bail!("Database is locked: enter its passphrase first")
+        }
+        None => Ok(text.to_string()),
+    }
+}
+
+/// Decrypt a stored name. Plain names are returned as they are
+pub fn open_text(pool: &SqlitePool, text: String) -> Result<String> {
+    if !is_encrypted_text(&text) {
+        return Ok(text);
+    }
+
+    match session_keys().lock().unwrap().get(&db_key(pool)) {
+        Some(cipher) => cipher.decrypt(&text),
+        None => anyhow::bail!("Database is locked: enter its passphrase first"),
+    }
+}
+
+/// Whether the database has been encrypted
+pub async fn is_encrypted(pool: &SqlitePool) -> Result<bool> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM encryption")
+        .fetch_one(pool)
+        .await
+        .with_context(|| "Failed to read encryption settings")?;
+    if count > 0 {
+        encrypted_dbs().lock().unwrap().insert(db_key(pool));
+    }
+    Ok(count > 0)
+}
+
+/// Whether the key of the database is cached for this session
+pub fn is_unlocked(pool: &SqlitePool) -> bool {
+    session_keys().lock().unwrap().contains_key(&db_key(pool))
+}
+
+/// Check the passphrase of an encrypted database and cache its key for the session
+pub async fn unlock(pool: &SqlitePool, passphrase: &str) -> Result<()> {
+    let (salt, verifier): (String, String) =
+        sqlx::query_as("SELECT salt, verifier FROM encryption WHERE id = 1")
+            .fetch_optional(pool)
+            .await
+            .with_context(|| "Failed to read encryption settings")?
+            .with_context(|| "Database is not encrypted")?;
+
+    let salt = BASE64.decode(salt).with_context(|| "Invalid salt")?;
+    let cipher = Cipher::derive(passphrase, &salt)?;
+    if cipher.decrypt(&verifier).ok().as_deref() != Some(VERIFIER) {
+        anyhow::bail!("Wrong passphrase");
+    }
+
+    session_keys().lock().unwrap().insert(db_key(pool), cipher);
+    Ok(())
+}
+
+/// Forget the key of the database
+pub fn lock(pool: &SqlitePool) {
+    session_keys().lock().unwrap().remove(&db_key(pool));
+}
+
+/// Keep a database unlocked after its file moved
+pub fn move_key(from: &Path, to: &Path) {
+    let (from, to) = (from.display().to_string(), to.display().to_string());
+    let mut keys = session_keys().lock().unwrap();
+    if let Some(cipher) = keys.remove(&from) {
+        keys.insert(to.clone(), cipher);
+    }
+    let mut encrypted = encrypted_dbs().lock().unwrap();
+    if encrypted.remove(&from) {
+        encrypted.insert(to);
+    }
+}
+
+/// Unlock the copy of a database with the key of the original, if unlocked
+pub fn copy_key(from: &Path, to: &Path) {
+    let (from, to) = (from.display().to_string(), to.display().to_string());
+    let mut keys = session_keys().lock().unwrap();
+    if let Some(cipher) = keys.get(&from).cloned() {
+        keys.insert(to.clone(), cipher);
+    }
+    let mut encrypted = encrypted_dbs().lock().unwrap();
+    if encrypted.contains(&from) {
+        encrypted.insert(to);
+    }
+}
+
+/// Encrypt an existing database in place with a new passphrase, and unlock it
+pub async fn encrypt_database(pool: &SqlitePool, passphrase: &str) -> Result<()> {
+    if is_encrypted(pool).await? {
+        anyhow::bail!("Database is already encrypted");
+    }
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase cannot be empty");
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    getrandom::fill(&mut salt).map_err(|e| anyhow::anyhow!("No randomness: {}", e))?;
+    let cipher = Cipher::derive(passphrase, &salt)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .with_context(|| "Failed to start transaction")?;
+
+    sqlx::query("INSERT INTO encryption (id, salt, verifier) VALUES (1, ?1, ?2)")
+        .bind(BASE64.encode(salt))
+        .bind(cipher.encrypt(VERIFIER)?)
+        .execute(&mut *tx)
+        .await
+        .with_context(|| "Failed to save encryption settings")?;
+    rewrite_names(&mut tx, |name| {
+        if is_encrypted_text(name) {
+            Ok(name.to_string())
+        } else {
+            cipher.encrypt(name)
+        }
+    })
+    .await?;
+
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+    purge_free_pages(pool).await?;
+
+    session_keys().lock().unwrap().insert(db_key(pool), cipher);
+    encrypted_dbs().lock().unwrap().insert(db_key(pool));
+    Ok(())
+}
+
+/// Decrypt an encrypted database in place, removing its passphrase
+pub async fn decrypt_database(pool: &SqlitePool, passphrase: &str) -> Result<()> {
+    unlock(pool, passphrase).await?;
+    let cipher = session_keys()
+        .lock()
+        .unwrap()
+        .get(&db_key(pool))
+        .cloned()
+        .with_context(|| "Database is locked")?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .with_context(|| "Failed to start transaction")?;
+
+    rewrite_names(&mut tx, |name| {
+        if is_encrypted_text(name) {
+            cipher.decrypt(name)
+        } else {
+            Ok(name.to_string())
+        }
+    })
+    .await?;
+    sqlx::query("DELETE FROM encryption")
+        .execute(&mut *tx)
+        .await
+        .with_context(|| "Failed to remove encryption settings")?;
+
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+
+    lock(pool);
+    encrypted_dbs().lock().unwrap().remove(&db_key(pool));
+    Ok(())
+}
+
+/// Columns holding names: those of lists and items, and the names recorded in
+/// the change log
+const NAME_COLUMNS: [(&str, &str, &str); 3] = [
+    ("todo_lists", "name", ""),
+    ("todo_items", "name", ""),
+    (
+        "sync_changes",
+        "value",
+        " WHERE field = 'name' AND value IS NOT NULL",
+    ),
+];
+
+/// Apply a transformation to the names of all lists and items, and to those of
+/// the change log
+async fn rewrite_names(
+    conn: &mut SqliteConnection,
+    transform: impl Fn(&str) -> Result<String>,
+) -> Result<()> {
+    for (table, column, filter) in NAME_COLUMNS {
+        let rows: Vec<(i64, String)> =
+            sqlx::query_as(&format!("SELECT id, {} FROM {}{}", column, table, filter))
+                .fetch_all(&mut *conn)
+                .await
+                .with_context(|| format!("Failed to read {}", table))?;
+
+        for (id, name) in rows {
+            sqlx::query(&format!(
+                "UPDATE {} SET {} = ?1 WHERE id = ?2",
+                table, column
+            ))
+            .bind(transform(&name)?)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to update {}", table))?;
+        }
+    }
+    Ok(())
+}
+
+/// Rebuild the database file, so that the plain names replaced by encrypted
+/// ones don't linger in its free pages or journal
+async fn purge_free_pages(pool: &SqlitePool) -> Result<()> {
+    sqlx::query("VACUUM")
+        .execute(pool)
+        .await
+        .with_context(|| "Failed to vacuum database")?;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await
+        .with_context(|| "Failed to checkpoint database")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cipher_round_trip() -> Result<()> {
+        let cipher = Cipher::derive("correct horse", b"0123456789abcdef")?;
+        let encrypted = cipher.encrypt("Call Alice about the contract")?;
+
+        assert!(is_encrypted_text(&encrypted));
+        assert!(!encrypted.contains("Alice"));
+        // Fresh nonce every time
+        assert_ne!(encrypted, cipher.encrypt("Call Alice about the contract")?);
+        assert_eq!(cipher.decrypt(&encrypted)?, "Call Alice about the contract");
+
+        let other = Cipher::derive("wrong horse", b"0123456789abcdef")?;
+        assert!(other.decrypt(&encrypted).is_err());
+        Ok(())
+    }
+}
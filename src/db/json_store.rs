@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::db::memory_store::StoreData;
+use crate::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use crate::db::store::TodoStore;
+
+/// Store that keeps everything in a plain JSON file, rewritten after every change
+pub struct JsonStore {
+    path: PathBuf,
+    data: Mutex<StoreData>,
+}
+
+impl JsonStore {
+    /// Open a JSON file, creating it if missing
+    pub fn open(path: &str) -> Result<JsonStore> {
+        let path = PathBuf::from(path);
+
+        let data = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            StoreData::default()
+        };
+
+        let store = JsonStore {
+            path,
+            data: Mutex::new(data),
+        };
+        store.save(&store.data.lock().unwrap())?;
+        Ok(store)
+    }
+
+    /// Write the data to the file, atomically
+    fn save(&self, data: &StoreData) -> Result<()> {
+        if let Some(dir) = self.path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let content = serde_json::to_string_pretty(data).with_context(|| "Failed to serialize")?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    /// Apply a change to the data and save it
+    fn change<T>(&self, change: impl FnOnce(&mut StoreData) -> Result<T>) -> Result<T> {
+        let mut data = self.data.lock().unwrap();
+        let result = change(&mut data)?;
+        self.save(&data)?;
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl TodoStore for JsonStore {
+    async fn create_list(&self, new_list: NewTodoList) -> Result<TodoList> {
+        self.change(|data| data.create_list(new_list, usize::MAX))
+    }
+
+    async fn create_list_at(&self, new_list: NewTodoList, position: usize) -> Result<TodoList> {
+        self.change(|data| data.create_list(new_list, position))
+    }
+
+    async fn get_lists(&self) -> Result<Vec<TodoList>> {
+        Ok(self.data.lock().unwrap().get_lists())
+    }
+
+    async fn get_list(&self, id: i64) -> Result<Option<TodoList>> {
+        Ok(self.data.lock().unwrap().get_list(id))
+    }
+
+    async fn update_list(&self, list: &TodoList) -> Result<()> {
+        self.change(|data| {
+            data.update_list(list);
+            Ok(())
+        })
+    }
+
+    async fn delete_list(&self, id: i64) -> Result<()> {
+        self.change(|data| {
+            data.delete_list(id);
+            Ok(())
+        })
+    }
+
+    async fn move_list(&self, id: i64, offset: i64) -> Result<i64> {
+        self.change(|data| data.move_list(id, offset))
+    }
+
+    async fn create_item(&self, new_item: NewTodoItem) -> Result<TodoItem> {
+        self.change(|data| data.create_item(new_item, usize::MAX))
+    }
+
+    async fn create_item_at(&self, new_item: NewTodoItem, position: usize) -> Result<TodoItem> {
+        self.change(|data| data.create_item(new_item, position))
+    }
+
+    async fn get_items(&self, list_id: i64) -> Result<Vec<TodoItem>> {
+        Ok(self.data.lock().unwrap().get_items(list_id))
+    }
+
+    async fn get_item(&self, id: i64) -> Result<Option<TodoItem>> {
+        Ok(self.data.lock().unwrap().get_item(id))
+    }
+
+    async fn update_item(&self, item: &TodoItem) -> Result<()> {
+        self.change(|data| {
+            data.update_item(item);
+            Ok(())
+        })
+    }
+
+    async fn delete_item(&self, id: i64) -> Result<()> {
+        self.change(|data| {
+            data.delete_item(id);
+            Ok(())
+        })
+    }
+
+    async fn move_item(&self, id: i64, offset: i64) -> Result<i64> {
+        self.change(|data| data.move_item(id, offset))
+    }
+
+    async fn move_item_to_list(&self, id: i64, list_id: i64, position: usize) -> Result<TodoItem> {
+        self.change(|data| data.move_item_to_list(id, list_id, position))
+    }
+
+    async fn claim_list(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        self.change(|data| Ok(data.claim_list(id, updated_at, now)))
+    }
+
+    async fn claim_item(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        self.change(|data| Ok(data.claim_item(id, updated_at, now)))
+    }
+
+    async fn set_item_origin(&self, id: i64, uid: &str, created_at: DateTime<Utc>) -> Result<()> {
+        self.change(|data| {
+            data.set_item_origin(id, uid, created_at);
+            Ok(())
+        })
+    }
+
+    async fn repair_orderings(&self) -> Result<usize> {
+        self.change(|data| Ok(data.repair_orderings()))
+    }
+}
@@ -0,0 +1,364 @@
+//! Operations on whole databases: moving and deleting the files of SQLite and
+//! JSON databases, duplicating them, and copying the lists of one database
+//! into another.
+
+use crate::db::connections::init_db;
+use crate::db::crypto;
+use crate::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use crate::db::store::{TodoStore, open_store};
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+/// File of a database stored in a file (SQLite or JSON), from its connection
+/// string. In-memory and PostgreSQL databases have none
+pub fn db_file(connection_str: &str) -> Option<PathBuf> {
+    let (scheme, location) = connection_str.split_once(':')?;
+    let location = match scheme {
+        "sqlite" => {
+            let location = location.strip_prefix("//").unwrap_or(location);
+            location.split('?').next().unwrap_or_default()
+        }
+        "json" => location,
+        _ => return None,
+    };
+    (!location.is_empty() && location != ":memory:").then(|| PathBuf::from(location))
+}
+
+/// Connection string of the same database with its file at another path
+/// (keeping the options of SQLite connection strings)
+pub fn with_file(connection_str: &str, path: &Path) -> Result<String> {
+    match connection_str.split_once(':') {
+        Some(("sqlite", location)) => match location.split_once('?') {
+            Some((_, options)) => Ok(format!("sqlite:{}?{}", path.display(), options)),
+            None => Ok(format!("sqlite:{}", path.display())),
+        },
+        Some(("json", _)) => Ok(format!("json:{}", path.display())),
+        _ => anyhow::bail!("Only SQLite and JSON databases are stored in files"),
+    }
+}
+
+/// Check that a database name can name its file: it must not contain path
+/// separators, which would put the file in another directory
+pub fn check_file_name(name: &str) -> Result<()> {
+    anyhow::ensure!(
+        !name.contains(['/', '\\']) && name != "." && name != "..",
+        "A database name can't contain path separators: {}",
+        name
+    );
+    Ok(())
+}
+
+/// Path of a database file renamed after a database, in the same directory and
+/// with the same extension
+pub fn renamed_file(path: &Path, name: &str) -> Result<PathBuf> {
+    check_file_name(name)?;
+    Ok(match path.extension() {
+        Some(extension) => path.with_file_name(format!("{}.{}", name, extension.display())),
+        None => path.with_file_name(name),
+    })
+}
+
+/// Journal files SQLite keeps next to a database
+fn journal_files(path: &Path) -> Vec<(PathBuf, &'static str)> {
+    ["-wal", "-shm", "-journal"]
+        .into_iter()
+        .map(|suffix| {
+            (
+                PathBuf::from(format!("{}{}", path.display(), suffix)),
+                suffix,
+            )
+        })
+        .filter(|(journal, _)| journal.exists())
+        .collect()
+}
+
+/// Move a file, copying it when it can't be renamed (e.g. to another disk)
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)
+            .with_context(|| format!("Failed to copy {} to {}", from.display(), to.display()))?;
+        std::fs::remove_file(from)
+            .with_context(|| format!("Failed to remove {}", from.display()))?;
+    }
+    Ok(())
+}
+
+/// Move the file of a database (with its SQLite journals) to a new path, and
+/// return the connection string of the database there. The database must be
+/// closed, and stays unlocked if it was
+pub fn move_db_file(connection_str: &str, new_path: &Path) -> Result<String> {
+    let path = db_file(connection_str)
+        .with_context(|| "Only SQLite and JSON databases are stored in files")?;
+    if new_path == path {
+        return Ok(connection_str.to_string());
+    }
+    anyhow::ensure!(!new_path.exists(), "{} already exists", new_path.display());
+    if let Some(dir) = new_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    move_file(&path, new_path)?;
+    for (journal, suffix) in journal_files(&path) {
+        move_file(
+            &journal,
+            Path::new(&format!("{}{}", new_path.display(), suffix)),
+        )?;
+    }
+    crypto::move_key(&path, new_path);
+    with_file(connection_str, new_path)
+}
+
+/// Delete the file of a database (with its SQLite journals). Returns whether
+/// there was a file to delete
+pub fn delete_db_file(connection_str: &str) -> Result<bool> {
+    let Some(path) = db_file(connection_str) else {
+        return Ok(false);
+    };
+    for (journal, _) in journal_files(&path) {
+        std::fs::remove_file(&journal)
+            .with_context(|| format!("Failed to remove {}", journal.display()))?;
+    }
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+    }
+}
+
+/// Copy a database to a new file, and return the connection string of the
+/// copy. SQLite databases are copied with `VACUUM INTO`, which is consistent
+/// even while the database is open, and keep their encryption. The copy is a
+/// database of its own: its lists and items get new uids, and it starts a new
+/// change log, so that it never syncs as the original
+pub async fn duplicate_db_file(connection_str: &str, new_path: &Path) -> Result<String> {
+    let path = db_file(connection_str)
+        .with_context(|| "Only SQLite and JSON databases are stored in files")?;
+    anyhow::ensure!(!new_path.exists(), "{} already exists", new_path.display());
+    let new_connection_str = with_file(connection_str, new_path)?;
+
+    if connection_str.starts_with("sqlite:") {
+        let pool = init_db(connection_str).await?;
+        let result = sqlx::query("VACUUM INTO ?")
+            .bind(new_path.display().to_string())
+            .execute(&pool)
+            .await;
+        pool.close().await;
+        result.with_context(|| format!("Failed to copy the database to {}", new_path.display()))?;
+
+        let pool = init_db(&new_connection_str).await?;
+        let result = reset_identity(&pool).await;
+        pool.close().await;
+        result?;
+        crypto::copy_key(&path, new_path);
+    } else {
+        // Lists copied one by one get new uids
+        let from = open_store(connection_str).await?;
+        let to = open_store(&new_connection_str).await?;
+        copy_lists(&*from, &*to, "").await?;
+    }
+    Ok(new_connection_str)
+}
+
+/// Give a copied SQLite database an identity of its own: a new node id in the
+/// change log, new uids and no history
+async fn reset_identity(pool: &SqlitePool) -> Result<()> {
+    let mut tx = pool
+        .begin()
+        .await
+        .with_context(|| "Failed to start transaction")?;
+    for query in [
+        "UPDATE sync_state SET node_id = lower(hex(randomblob(8))), last_hlc = NULL",
+        "DELETE FROM sync_changes",
+        "UPDATE todo_lists SET uid = lower(hex(randomblob(16))), hlc = NULL",
+        "UPDATE todo_items SET uid = lower(hex(randomblob(16))), hlc = NULL",
+    ] {
+        sqlx::query(query)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| "Failed to reset the identity of the copy")?;
+    }
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+    Ok(())
+}
+
+/// Name of a list copied next to lists with these names: lists named like one
+/// of them get the suffix (`Groceries (home)`, then `Groceries (home 2)`...)
+fn unclashed_name(name: &str, taken: &[String], suffix: &str) -> String {
+    if !taken.iter().any(|t| t == name) {
+        return name.to_string();
+    }
+    (1..)
+        .map(|n| match n {
+            1 => format!("{} ({})", name, suffix),
+            n => format!("{} ({} {})", name, suffix, n),
+        })
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or_default()
+}
+
+/// Copy the lists of a store, with their items, after the lists of another.
+/// Lists named like a list already there get `suffix` after their name.
+/// Returns the number of lists copied
+pub async fn copy_lists(from: &dyn TodoStore, to: &dyn TodoStore, suffix: &str) -> Result<usize> {
+    let lists = TodoList::get_all(from).await?;
+    let mut taken: Vec<String> = TodoList::get_all(to)
+        .await?
+        .into_iter()
+        .map(|list| list.name)
+        .collect();
+
+    for list in &lists {
+        let name = unclashed_name(&list.name, &taken, suffix);
+        let mut copy = TodoList::create(to, NewTodoList { name: name.clone() }).await?;
+        if list.statuses.is_some() {
+            copy.update_statuses(to, list.statuses.clone()).await?;
+        }
+        taken.push(name);
+
+        let statuses = list.statuses();
+        for item in TodoItem::get_by_list_id(from, list.id).await? {
+            let new_item = NewTodoItem {
+                list_id: copy.id,
+                name: item.name.clone(),
+                priority: item.priority.clone(),
+                due_date: item.due_date,
+            };
+            let mut item_copy = TodoItem::create(to, new_item).await?;
+            match &item.status {
+                Some(status) => item_copy.update_status(to, &statuses, status).await?,
+                None if item.is_done => item_copy.toggle_done(to).await?,
+                None => {}
+            }
+        }
+    }
+    Ok(lists.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::memory_store::MemoryStore;
+
+    #[test]
+    fn test_db_file() {
+        assert_eq!(
+            db_file("sqlite:/data/judo/dojo.db"),
+            Some(PathBuf::from("/data/judo/dojo.db"))
+        );
+        assert_eq!(
+            db_file("sqlite:///data/dojo.db?mode=rwc"),
+            Some(PathBuf::from("/data/dojo.db"))
+        );
+        assert_eq!(
+            db_file("json:lists.json"),
+            Some(PathBuf::from("lists.json"))
+        );
+        assert_eq!(db_file("sqlite::memory:"), None);
+        assert_eq!(db_file("memory:scratch"), None);
+        assert_eq!(db_file("postgres://localhost/judo"), None);
+
+        let path = Path::new("/data/work.db");
+        assert_eq!(
+            with_file("sqlite:/data/dojo.db?mode=rwc", path).unwrap(),
+            "sqlite:/data/work.db?mode=rwc"
+        );
+        assert!(with_file("memory:scratch", path).is_err());
+        assert_eq!(
+            renamed_file(Path::new("/data/dojo.db"), "home").unwrap(),
+            PathBuf::from("/data/home.db")
+        );
+        for name in ["../x", "a/b", "a\\b", ".."] {
+            assert!(renamed_file(Path::new("/data/dojo.db"), name).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_move_duplicate_and_delete_files() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let connection_str = format!("sqlite:{}", dir.path().join("dojo.db").display());
+        let pool = init_db(&connection_str).await?;
+        TodoList::create(
+            &pool,
+            NewTodoList {
+                name: "Chores".to_string(),
+            },
+        )
+        .await?;
+        pool.close().await;
+
+        let moved = dir.path().join("archive/home.db");
+        let connection_str = move_db_file(&connection_str, &moved)?;
+        assert!(moved.exists() && !dir.path().join("dojo.db").exists());
+
+        let identity = async |connection_str: &str| -> Result<(String, String)> {
+            let pool = init_db(connection_str).await?;
+            let node_id = sqlx::query_scalar("SELECT node_id FROM sync_state")
+                .fetch_one(&pool)
+                .await?;
+            let uid = TodoList::get_all(&pool).await?.remove(0).uid;
+            pool.close().await;
+            Ok((node_id, uid))
+        };
+        let copy = dir.path().join("copy.db");
+        let copy_str = duplicate_db_file(&connection_str, &copy).await?;
+        let pool = init_db(&copy_str).await?;
+        assert_eq!(TodoList::get_all(&pool).await?[0].name, "Chores");
+        pool.close().await;
+        let ((node_id, uid), (copy_node_id, copy_uid)) =
+            (identity(&connection_str).await?, identity(&copy_str).await?);
+        assert_ne!(node_id, copy_node_id);
+        assert_ne!(uid, copy_uid);
+        assert!(duplicate_db_file(&connection_str, &copy).await.is_err());
+
+        assert!(delete_db_file(&copy_str)?);
+        assert!(!copy.exists());
+        assert!(!delete_db_file("memory:scratch")?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_lists() -> Result<()> {
+        let (from, to) = (MemoryStore::default(), MemoryStore::default());
+        for store in [&from, &to] {
+            TodoList::create(
+                store,
+                NewTodoList {
+                    name: "Groceries".to_string(),
+                },
+            )
+            .await?;
+        }
+        let list = TodoList::get_all(&from).await?.remove(0);
+        let new_item = NewTodoItem {
+            list_id: list.id,
+            name: "Milk".to_string(),
+            priority: None,
+            due_date: None,
+        };
+        TodoItem::create(&from, new_item)
+            .await?
+            .toggle_done(&from)
+            .await?;
+
+        assert_eq!(copy_lists(&from, &to, "home").await?, 1);
+        assert_eq!(copy_lists(&from, &to, "home").await?, 1);
+        let names: Vec<String> = TodoList::get_all(&to)
+            .await?
+            .into_iter()
+            .map(|list| list.name)
+            .collect();
+        assert_eq!(
+            names,
+            ["Groceries", "Groceries (home)", "Groceries (home 2)"]
+        );
+
+        let copied = TodoList::get_all(&to).await?.remove(1);
+        let items = TodoItem::get_by_list_id(&to, copied.id).await?;
+        assert_eq!((items[0].name.as_str(), items[0].is_done), ("Milk", true));
+        Ok(())
+    }
+}
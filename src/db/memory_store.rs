@@ -0,0 +1,434 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use crate::db::store::{TodoStore, plan_insert, plan_move, plan_repair};
+
+/// Lists and items of a store kept in memory
+#[derive(Default, Serialize, Deserialize)]
+pub struct StoreData {
+    #[serde(default)]
+    next_list_id: i64,
+    #[serde(default)]
+    next_item_id: i64,
+    #[serde(default)]
+    lists: Vec<TodoList>,
+    #[serde(default)]
+    items: Vec<TodoItem>,
+}
+
+impl StoreData {
+    pub fn create_list(&mut self, new_list: NewTodoList, position: usize) -> Result<TodoList> {
+        let now = Utc::now();
+        let mut rows: Vec<(i64, &mut i64)> = self
+            .lists
+            .iter_mut()
+            .map(|l| (l.id, &mut l.ordering))
+            .collect();
+        let ordering = insert_among(&mut rows, position);
+
+        let list = TodoList {
+            id: next_id(&mut self.next_list_id, self.lists.iter().map(|l| l.id)),
+            uid: new_uid()?,
+            name: new_list.name,
+            ordering,
+            statuses: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.lists.push(list.clone());
+        Ok(list)
+    }
+
+    pub fn get_lists(&self) -> Vec<TodoList> {
+        let mut lists = self.lists.clone();
+        lists.sort_by_key(|list| list.ordering);
+        lists
+    }
+
+    pub fn get_list(&self, id: i64) -> Option<TodoList> {
+        self.lists.iter().find(|list| list.id == id).cloned()
+    }
+
+    pub fn update_list(&mut self, list: &TodoList) {
+        if let Some(stored) = self.lists.iter_mut().find(|l| l.id == list.id) {
+            stored.name = list.name.clone();
+            stored.statuses = list.statuses.clone();
+            stored.updated_at = list.updated_at;
+        }
+    }
+
+    /// Set the `updated_at` of a list to `now` if it is still `updated_at`
+    pub fn claim_list(&mut self, id: i64, updated_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        match self.lists.iter_mut().find(|l| l.id == id) {
+            Some(list) if list.updated_at == updated_at => {
+                list.updated_at = now;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn delete_list(&mut self, id: i64) {
+        self.lists.retain(|list| list.id != id);
+        self.items.retain(|item| item.list_id != id);
+    }
+
+    pub fn move_list(&mut self, id: i64, offset: i64) -> Result<i64> {
+        let rows = self.lists.iter_mut().map(|l| (l.id, &mut l.ordering));
+        move_among(rows.collect(), id, offset)
+    }
+
+    pub fn repair_orderings(&mut self) -> usize {
+        let mut changed = repair_among(
+            self.lists
+                .iter_mut()
+                .map(|l| (l.id, &mut l.ordering))
+                .collect(),
+        );
+
+        let mut list_ids: Vec<i64> = self.items.iter().map(|i| i.list_id).collect();
+        list_ids.sort();
+        list_ids.dedup();
+        for list_id in list_ids {
+            let rows = self
+                .items
+                .iter_mut()
+                .filter(|i| i.list_id == list_id)
+                .map(|i| (i.id, &mut i.ordering));
+            changed += repair_among(rows.collect());
+        }
+        changed
+    }
+
+    pub fn create_item(&mut self, new_item: NewTodoItem, position: usize) -> Result<TodoItem> {
+        if self.get_list(new_item.list_id).is_none() {
+            anyhow::bail!("No list with id {}", new_item.list_id);
+        }
+
+        let now = Utc::now();
+        let mut rows: Vec<(i64, &mut i64)> = self
+            .items
+            .iter_mut()
+            .filter(|i| i.list_id == new_item.list_id)
+            .map(|i| (i.id, &mut i.ordering))
+            .collect();
+        let ordering = insert_among(&mut rows, position);
+
+        let item = TodoItem {
+            id: next_id(&mut self.next_item_id, self.items.iter().map(|i| i.id)),
+            uid: new_uid()?,
+            list_id: new_item.list_id,
+            name: new_item.name,
+            is_done: false,
+            priority: new_item.priority,
+            due_date: new_item.due_date,
+            ordering,
+            status: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.items.push(item.clone());
+        Ok(item)
+    }
+
+    pub fn get_items(&self, list_id: i64) -> Vec<TodoItem> {
+        let mut items: Vec<TodoItem> = self
+            .items
+            .iter()
+            .filter(|item| item.list_id == list_id)
+            .cloned()
+            .collect();
+        items.sort_by_key(|item| item.ordering);
+        items
+    }
+
+    pub fn get_item(&self, id: i64) -> Option<TodoItem> {
+        self.items.iter().find(|item| item.id == id).cloned()
+    }
+
+    pub fn update_item(&mut self, item: &TodoItem) {
+        if let Some(stored) = self.items.iter_mut().find(|i| i.id == item.id) {
+            *stored = TodoItem {
+                list_id: stored.list_id,
+                ordering: stored.ordering,
+                ..item.clone()
+            };
+        }
+    }
+
+    /// Set the `updated_at` of an item to `now` if it is still `updated_at`
+    pub fn claim_item(&mut self, id: i64, updated_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        match self.items.iter_mut().find(|i| i.id == id) {
+            Some(item) if item.updated_at == updated_at => {
+                item.updated_at = now;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn set_item_origin(&mut self, id: i64, uid: &str, created_at: DateTime<Utc>) {
+        if let Some(item) = self.items.iter_mut().find(|i| i.id == id) {
+            item.uid = uid.to_string();
+            item.created_at = created_at;
+        }
+    }
+
+    pub fn delete_item(&mut self, id: i64) {
+        self.items.retain(|i| i.id != id);
+    }
+
+    pub fn move_item(&mut self, id: i64, offset: i64) -> Result<i64> {
+        let list_id = self
+            .get_item(id)
+            .with_context(|| format!("No item with id {}", id))?
+            .list_id;
+
+        let rows = self
+            .items
+            .iter_mut()
+            .filter(|i| i.list_id == list_id)
+            .map(|i| (i.id, &mut i.ordering));
+        move_among(rows.collect(), id, offset)
+    }
+
+    pub fn move_item_to_list(
+        &mut self,
+        id: i64,
+        list_id: i64,
+        position: usize,
+    ) -> Result<TodoItem> {
+        if self.get_list(list_id).is_none() {
+            anyhow::bail!("No list with id {}", list_id);
+        }
+        if self.get_item(id).is_none() {
+            anyhow::bail!("No item with id {}", id);
+        }
+
+        let mut rows: Vec<(i64, &mut i64)> = self
+            .items
+            .iter_mut()
+            .filter(|i| i.list_id == list_id && i.id != id)
+            .map(|i| (i.id, &mut i.ordering))
+            .collect();
+        let ordering = insert_among(&mut rows, position);
+
+        let item = self
+            .items
+            .iter_mut()
+            .find(|i| i.id == id)
+            .with_context(|| format!("No item with id {}", id))?;
+        item.list_id = list_id;
+        item.ordering = ordering;
+        item.updated_at = Utc::now();
+        Ok(item.clone())
+    }
+}
+
+/// Store that keeps everything in memory, for throwaway databases and tests
+#[derive(Default)]
+pub struct MemoryStore {
+    data: Mutex<StoreData>,
+}
+
+#[async_trait]
+impl TodoStore for MemoryStore {
+    async fn create_list(&self, new_list: NewTodoList) -> Result<TodoList> {
+        self.data.lock().unwrap().create_list(new_list, usize::MAX)
+    }
+
+    async fn create_list_at(&self, new_list: NewTodoList, position: usize) -> Result<TodoList> {
+        self.data.lock().unwrap().create_list(new_list, position)
+    }
+
+    async fn get_lists(&self) -> Result<Vec<TodoList>> {
+        Ok(self.data.lock().unwrap().get_lists())
+    }
+
+    async fn get_list(&self, id: i64) -> Result<Option<TodoList>> {
+        Ok(self.data.lock().unwrap().get_list(id))
+    }
+
+    async fn update_list(&self, list: &TodoList) -> Result<()> {
+        self.data.lock().unwrap().update_list(list);
+        Ok(())
+    }
+
+    async fn delete_list(&self, id: i64) -> Result<()> {
+        self.data.lock().unwrap().delete_list(id);
+        Ok(())
+    }
+
+    async fn move_list(&self, id: i64, offset: i64) -> Result<i64> {
+        self.data.lock().unwrap().move_list(id, offset)
+    }
+
+    async fn create_item(&self, new_item: NewTodoItem) -> Result<TodoItem> {
+        self.data.lock().unwrap().create_item(new_item, usize::MAX)
+    }
+
+    async fn create_item_at(&self, new_item: NewTodoItem, position: usize) -> Result<TodoItem> {
+        self.data.lock().unwrap().create_item(new_item, position)
+    }
+
+    async fn get_items(&self, list_id: i64) -> Result<Vec<TodoItem>> {
+        Ok(self.data.lock().unwrap().get_items(list_id))
+    }
+
+    async fn get_item(&self, id: i64) -> Result<Option<TodoItem>> {
+        Ok(self.data.lock().unwrap().get_item(id))
+    }
+
+    async fn update_item(&self, item: &TodoItem) -> Result<()> {
+        self.data.lock().unwrap().update_item(item);
+        Ok(())
+    }
+
+    async fn delete_item(&self, id: i64) -> Result<()> {
+        self.data.lock().unwrap().delete_item(id);
+        Ok(())
+    }
+
+    async fn move_item(&self, id: i64, offset: i64) -> Result<i64> {
+        self.data.lock().unwrap().move_item(id, offset)
+    }
+
+    async fn move_item_to_list(&self, id: i64, list_id: i64, position: usize) -> Result<TodoItem> {
+        self.data
+            .lock()
+            .unwrap()
+            .move_item_to_list(id, list_id, position)
+    }
+
+    async fn claim_list(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        Ok(self.data.lock().unwrap().claim_list(id, updated_at, now))
+    }
+
+    async fn claim_item(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        Ok(self.data.lock().unwrap().claim_item(id, updated_at, now))
+    }
+
+    async fn set_item_origin(&self, id: i64, uid: &str, created_at: DateTime<Utc>) -> Result<()> {
+        self.data
+            .lock()
+            .unwrap()
+            .set_item_origin(id, uid, created_at);
+        Ok(())
+    }
+
+    async fn repair_orderings(&self) -> Result<usize> {
+        Ok(self.data.lock().unwrap().repair_orderings())
+    }
+}
+
+/// Next id of a table, never reusing the ids of deleted rows
+fn next_id(counter: &mut i64, ids: impl Iterator<Item = i64>) -> i64 {
+    *counter = (*counter).max(ids.max().unwrap_or(0)) + 1;
+    *counter
+}
+
+/// Random identifier, stable across databases (as generated by SQLite)
+fn new_uid() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| anyhow::anyhow!("No randomness: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Move the row `id` by `offset` positions among its siblings. Returns the new
+/// ordering of the row
+fn move_among(mut rows: Vec<(i64, &mut i64)>, id: i64, offset: i64) -> Result<i64> {
+    let siblings: Vec<(i64, i64)> = rows
+        .iter()
+        .map(|(id, ordering)| (*id, **ordering))
+        .collect();
+    let (ordering, writes) = plan_move(&siblings, id, offset)?;
+    apply_writes(&mut rows, writes);
+    Ok(ordering)
+}
+
+/// Make room for a row inserted at `position` among its siblings. Returns the
+/// ordering of the new row
+fn insert_among(rows: &mut [(i64, &mut i64)], position: usize) -> i64 {
+    let siblings: Vec<(i64, i64)> = rows
+        .iter()
+        .map(|(id, ordering)| (*id, **ordering))
+        .collect();
+    let (ordering, writes) = plan_insert(&siblings, position);
+    apply_writes(rows, writes);
+    ordering
+}
+
+/// Repair the orderings of siblings. Returns the number of rows changed
+fn repair_among(mut rows: Vec<(i64, &mut i64)>) -> usize {
+    let siblings: Vec<(i64, i64)> = rows
+        .iter()
+        .map(|(id, ordering)| (*id, **ordering))
+        .collect();
+    let writes = plan_repair(&siblings);
+    apply_writes(&mut rows, writes);
+
+    let changed = rows.iter().zip(&siblings);
+    changed.filter(|((_, new), (_, old))| **new != *old).count()
+}
+
+fn apply_writes(rows: &mut [(i64, &mut i64)], writes: Vec<(i64, i64)>) {
+    for (id, ordering) in writes {
+        if let Some((_, row_ordering)) = rows.iter_mut().find(|(row_id, _)| *row_id == id) {
+            **row_ordering = ordering;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_repair_orderings_spaces_out_duplicates() -> Result<()> {
+        let mut data = StoreData::default();
+        let list = data.create_list(
+            NewTodoList {
+                name: "Groceries".to_string(),
+            },
+            usize::MAX,
+        )?;
+        for name in ["Milk", "Bread", "Eggs"] {
+            let new_item = NewTodoItem {
+                name: name.to_string(),
+                list_id: list.id,
+                priority: None,
+                due_date: None,
+            };
+            data.create_item(new_item, usize::MAX)?;
+        }
+        assert_eq!(data.repair_orderings(), 0);
+
+        for item in data.items.iter_mut() {
+            item.ordering = 1;
+        }
+        assert_eq!(data.repair_orderings(), 3);
+
+        let items = data.get_items(list.id);
+        let names: Vec<&str> = items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["Milk", "Bread", "Eggs"]);
+        let orderings: Vec<i64> = items.iter().map(|i| i.ordering).collect();
+        assert_eq!(orderings, [1024, 2048, 3072]);
+        Ok(())
+    }
+}
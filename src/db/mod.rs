@@ -1,4 +1,11 @@
 pub mod config;
 pub mod connections;
+pub mod crypto;
+pub mod json_store;
+pub mod manage;
+pub mod memory_store;
 pub mod models;
 pub mod ops;
+pub mod postgres_store;
+pub mod sqlite_store;
+pub mod store;
@@ -1,37 +1,119 @@
 use chrono::{DateTime, Utc};
 use ratatui::widgets::ListState;
+use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, Type};
+use std::str::FromStr;
 
-#[derive(Debug, Clone, PartialEq, Eq, Type)]
+#[derive(Debug, Clone, PartialEq, Eq, Type, Serialize, Deserialize)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum Priority {
     High,
     Medium,
     Low,
 }
 
-#[derive(Debug, FromRow, Clone)]
+impl Priority {
+    /// Lowercase name of the priority, as stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "high" => Ok(Priority::High),
+            "medium" => Ok(Priority::Medium),
+            "low" => Ok(Priority::Low),
+            _ => anyhow::bail!("Invalid priority '{}'", s),
+        }
+    }
+}
+
+/// Statuses of the board of a list whose statuses aren't configured
+pub const DEFAULT_STATUSES: [&str; 4] = ["todo", "doing", "blocked", "done"];
+
+#[derive(Debug, FromRow, Clone, Serialize, Deserialize)]
 pub struct TodoList {
     pub id: i64,
+    /// Identifier that is stable across databases (used for syncing)
+    pub uid: String,
     pub name: String,
     pub ordering: i64,
+    /// Comma-separated statuses of the board of the list, the last one meaning
+    /// done (`None` for the default ones)
+    #[serde(default)]
+    pub statuses: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, FromRow, Clone)]
+#[derive(Debug, FromRow, Clone, Serialize, Deserialize)]
 pub struct TodoItem {
     pub id: i64,
+    /// Identifier that is stable across databases (used for syncing)
+    pub uid: String,
     pub list_id: i64,
     pub name: String,
     pub is_done: bool,
     pub priority: Option<Priority>,
     pub due_date: Option<DateTime<Utc>>,
     pub ordering: i64,
+    /// Status of the item on the board of its list (see [`TodoItem::status_in`])
+    #[serde(default)]
+    pub status: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl TodoList {
+    /// Statuses of the board of the list, in order. The last one means done
+    pub fn statuses(&self) -> Vec<String> {
+        let statuses = self.statuses.as_deref().map(parse_statuses);
+        match statuses {
+            Some(statuses) if statuses.len() >= 2 => statuses,
+            _ => DEFAULT_STATUSES.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+/// Statuses written as comma-separated names. Blank and repeated names are skipped
+pub fn parse_statuses(text: &str) -> Vec<String> {
+    let mut statuses: Vec<String> = Vec::new();
+    for status in text.split(',').map(str::trim) {
+        if !status.is_empty() && !statuses.iter().any(|s| s == status) {
+            statuses.push(status.to_string());
+        }
+    }
+    statuses
+}
+
+impl TodoItem {
+    /// Status of the item among the statuses of its list. Done items are in the
+    /// last status whatever their status says, and other items without a known
+    /// status are in the first one
+    pub fn status_in<'a>(&self, statuses: &'a [String]) -> &'a str {
+        let Some((last, others)) = statuses.split_last() else {
+            return "";
+        };
+        if self.is_done {
+            return last;
+        }
+        others
+            .iter()
+            .find(|s| self.status.as_ref() == Some(*s))
+            .or(others.first())
+            .unwrap_or(last)
+    }
+}
+
 // Structs for creating new records (without id and timestamps)
 #[derive(Debug)]
 pub struct NewTodoList {
@@ -1,525 +1,279 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use sqlx::SqlitePool;
 
 use crate::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList, UIItem, UIList};
+use crate::db::store::TodoStore;
 use ratatui::widgets::ListState;
 
 impl TodoList {
     /// Create a new todo list
-    pub async fn create(pool: &SqlitePool, new_list: NewTodoList) -> Result<TodoList> {
-        let now = Utc::now();
-
-        // Get the next ordering value (max + 1)
-        let next_ordering: i64 =
-            sqlx::query_scalar("SELECT COALESCE(MAX(ordering), 0) + 1 FROM todo_lists")
-                .fetch_one(pool)
-                .await
-                .with_context(|| "Failed to get next ordering value")?;
-
-        // Use query_as to map results to a struct
-        let row = sqlx::query_as::<_, TodoList>(
-            r#"
-            INSERT INTO todo_lists (name, ordering, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4)
-            RETURNING id, name, ordering, created_at, updated_at
-            "#,
-        )
-        .bind(&new_list.name)
-        .bind(next_ordering)
-        .bind(now)
-        .bind(now)
-        .fetch_one(pool)
-        .await
-        .with_context(|| "Failed to create todo list")?;
+    pub async fn create(store: &dyn TodoStore, new_list: NewTodoList) -> Result<TodoList> {
+        store.create_list(new_list).await
+    }
 
-        Ok(row)
+    /// Create a new todo list at `position` among the lists
+    pub async fn create_at(
+        store: &dyn TodoStore,
+        new_list: NewTodoList,
+        position: usize,
+    ) -> Result<TodoList> {
+        store.create_list_at(new_list, position).await
     }
 
     /// Get all todo lists
-    pub async fn get_all(pool: &SqlitePool) -> Result<Vec<TodoList>> {
-        let lists = sqlx::query_as::<_, TodoList>(
-            "SELECT id, name, ordering, created_at, updated_at FROM todo_lists ORDER BY ordering",
-        )
-        .fetch_all(pool)
-        .await
-        .with_context(|| "Failed to fetch all todo lists")?;
-
-        Ok(lists)
+    pub async fn get_all(store: &dyn TodoStore) -> Result<Vec<TodoList>> {
+        store.get_lists().await
     }
 
     /// Get a specific todo list by ID
-    pub async fn get_by_id(pool: &SqlitePool, id: i64) -> Result<Option<TodoList>> {
-        let list = sqlx::query_as::<_, TodoList>(
-            "SELECT id, name, ordering, created_at, updated_at FROM todo_lists WHERE id = ?1",
-        )
-        .bind(id)
-        .fetch_optional(pool)
-        .await
-        .with_context(|| "Failed to fetch todo list by id")?;
-
-        Ok(list)
+    pub async fn get_by_id(store: &dyn TodoStore, id: i64) -> Result<Option<TodoList>> {
+        store.get_list(id).await
     }
 
     /// Update todo list name
-    pub async fn update_name(&mut self, pool: &SqlitePool, new_name: String) -> Result<()> {
-        let now = Utc::now();
-
-        sqlx::query("UPDATE todo_lists SET name = ?1, updated_at = ?2 WHERE id = ?3")
-            .bind(&new_name)
-            .bind(now)
-            .bind(self.id)
-            .execute(pool)
-            .await
-            .with_context(|| "Failed to update todo list")?;
-
-        self.name = new_name;
-        self.updated_at = now;
+    pub async fn update_name(&mut self, store: &dyn TodoStore, new_name: String) -> Result<()> {
+        let updated = TodoList {
+            name: new_name,
+            updated_at: Utc::now(),
+            ..self.clone()
+        };
+        store.update_list(&updated).await?;
+
+        *self = updated;
         Ok(())
     }
 
-    /// Delete todo list (and all its items due to CASCADE)
-    pub async fn delete(self, pool: &SqlitePool) -> Result<()> {
-        sqlx::query("DELETE FROM todo_lists WHERE id = ?1")
-            .bind(self.id)
-            .execute(pool)
+    /// Update the statuses of the board of the list (`None` for the default ones)
+    pub async fn update_statuses(
+        &mut self,
+        store: &dyn TodoStore,
+        statuses: Option<String>,
+    ) -> Result<()> {
+        let updated = TodoList {
+            statuses,
+            updated_at: Utc::now(),
+            ..self.clone()
+        };
+        store
+            .update_list(&updated)
             .await
-            .with_context(|| "Failed to delete todo list")?;
+            .with_context(|| "Failed to update todo list statuses")?;
 
+        *self = updated;
         Ok(())
     }
 
-    /// Move list up (decrease ordering, swap with previous)
-    pub async fn move_up(&mut self, pool: &SqlitePool) -> Result<()> {
-        // Find the list with the next lower ordering value
-        let prev_list: Option<(i64, i64)> = sqlx::query_as(
-            "SELECT id, ordering FROM todo_lists WHERE ordering < ?1 ORDER BY ordering DESC LIMIT 1"
-        )
-        .bind(self.ordering)
-        .fetch_optional(pool)
-        .await
-        .with_context(|| "Failed to find previous list")?;
-
-        if let Some((prev_id, prev_ordering)) = prev_list {
-            // Swap orderings
-            sqlx::query("UPDATE todo_lists SET ordering = ?1 WHERE id = ?2")
-                .bind(self.ordering)
-                .bind(prev_id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update previous list ordering")?;
+    /// Delete todo list (and all its items)
+    pub async fn delete(self, store: &dyn TodoStore) -> Result<()> {
+        store.delete_list(self.id).await
+    }
 
-            sqlx::query("UPDATE todo_lists SET ordering = ?1 WHERE id = ?2")
-                .bind(prev_ordering)
-                .bind(self.id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update current list ordering")?;
+    /// Move list up (swap with previous)
+    pub async fn move_up(&mut self, store: &dyn TodoStore) -> Result<()> {
+        self.move_up_by(store, 1).await
+    }
 
-            self.ordering = prev_ordering;
+    /// Move list up by `amount` positions among the lists
+    pub async fn move_up_by(&mut self, store: &dyn TodoStore, amount: usize) -> Result<()> {
+        if amount == 0 {
+            return Ok(()); // nothing to do
         }
 
+        self.ordering = store.move_list(self.id, -(amount as i64)).await?;
         Ok(())
     }
 
-    /// Move list down (increase ordering, swap with next)
-    pub async fn move_down(&mut self, pool: &SqlitePool) -> Result<()> {
-        // Find the list with the next higher ordering value
-        let next_list: Option<(i64, i64)> = sqlx::query_as(
-            "SELECT id, ordering FROM todo_lists WHERE ordering > ?1 ORDER BY ordering ASC LIMIT 1",
-        )
-        .bind(self.ordering)
-        .fetch_optional(pool)
-        .await
-        .with_context(|| "Failed to find next list")?;
-
-        if let Some((next_id, next_ordering)) = next_list {
-            // Swap orderings
-            sqlx::query("UPDATE todo_lists SET ordering = ?1 WHERE id = ?2")
-                .bind(self.ordering)
-                .bind(next_id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update next list ordering")?;
-
-            sqlx::query("UPDATE todo_lists SET ordering = ?1 WHERE id = ?2")
-                .bind(next_ordering)
-                .bind(self.id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update current list ordering")?;
+    /// Move list down (swap with next)
+    pub async fn move_down(&mut self, store: &dyn TodoStore) -> Result<()> {
+        self.move_down_by(store, 1).await
+    }
 
-            self.ordering = next_ordering;
+    /// Move list down by `amount` positions among the lists
+    pub async fn move_down_by(&mut self, store: &dyn TodoStore, amount: usize) -> Result<()> {
+        if amount == 0 {
+            return Ok(()); // nothing to do
         }
 
+        self.ordering = store.move_list(self.id, amount as i64).await?;
         Ok(())
     }
 }
 
 impl TodoItem {
     /// Create a new todo item
-    pub async fn create(pool: &SqlitePool, new_item: NewTodoItem) -> Result<TodoItem> {
-        let now = Utc::now();
-
-        // Get the next ordering value for this list (max + 1)
-        let next_ordering: i64 = sqlx::query_scalar(
-            "SELECT COALESCE(MAX(ordering), 0) + 1 FROM todo_items WHERE list_id = ?1",
-        )
-        .bind(new_item.list_id)
-        .fetch_one(pool)
-        .await
-        .with_context(|| "Failed to get next ordering value")?;
-
-        let row = sqlx::query_as::<_, TodoItem>(
-            r#"
-            INSERT INTO todo_items (list_id, name, is_done, priority, due_date, ordering, created_at, updated_at)
-            VALUES (?1, ?2, FALSE, ?3, ?4, ?5, ?6, ?7)
-            RETURNING id, list_id, name, is_done, priority, due_date, ordering, created_at, updated_at
-            "#,
-        )
-        .bind(new_item.list_id)
-        .bind(&new_item.name)
-        .bind(&new_item.priority)
-        .bind(new_item.due_date)
-        .bind(next_ordering)
-        .bind(now)
-        .bind(now)
-        .fetch_one(pool)
-        .await
-        .with_context(|| "Failed to create todo item")?;
+    pub async fn create(store: &dyn TodoStore, new_item: NewTodoItem) -> Result<TodoItem> {
+        store.create_item(new_item).await
+    }
 
-        Ok(row)
+    /// Create a new todo item at `position` in its list
+    pub async fn create_at(
+        store: &dyn TodoStore,
+        new_item: NewTodoItem,
+        position: usize,
+    ) -> Result<TodoItem> {
+        store.create_item_at(new_item, position).await
     }
 
     /// Get all items for a specific list
-    pub async fn get_by_list_id(pool: &SqlitePool, list_id: i64) -> Result<Vec<TodoItem>> {
-        let items = sqlx::query_as::<_, TodoItem>(
-            r#"
-            SELECT id, list_id, name, is_done, priority, due_date, ordering, created_at, updated_at
-            FROM todo_items
-            WHERE list_id = ?1
-            ORDER BY ordering
-            "#,
-        )
-        .bind(list_id)
-        .fetch_all(pool)
-        .await
-        .with_context(|| "Failed to fetch todo items")?;
-
-        Ok(items)
+    pub async fn get_by_list_id(store: &dyn TodoStore, list_id: i64) -> Result<Vec<TodoItem>> {
+        store.get_items(list_id).await
     }
 
     /// Get item with a specific id
-    pub async fn get_by_id(pool: &SqlitePool, id: i64) -> Result<Option<TodoItem>> {
-        let item = sqlx::query_as::<_, TodoItem>(
-            r#"
-            SELECT id, list_id, name, is_done, priority, due_date, ordering, created_at, updated_at
-            FROM todo_items
-            WHERE id = ?1
-            "#,
-        )
-        .bind(id)
-        .fetch_optional(pool)
-        .await
-        .with_context(|| "Failed to fetch todo item")?;
-
-        Ok(item)
+    pub async fn get_by_id(store: &dyn TodoStore, id: i64) -> Result<Option<TodoItem>> {
+        store.get_item(id).await
     }
 
-    /// Update to-do item name
-    pub async fn update_name(&mut self, pool: &SqlitePool, new_name: String) -> Result<()> {
-        let now = Utc::now();
-
-        sqlx::query("UPDATE todo_items SET name = ?1, updated_at = ?2 WHERE id = ?3")
-            .bind(&new_name)
-            .bind(now)
-            .bind(self.id)
-            .execute(pool)
-            .await
-            .with_context(|| "Failed to update todo item name")?;
-
-        self.name = new_name;
-        self.updated_at = now;
-
+    /// Save a modified copy of the item, and keep it if successful
+    async fn update(
+        &mut self,
+        store: &dyn TodoStore,
+        modify: impl FnOnce(&mut TodoItem),
+    ) -> Result<()> {
+        let mut updated = TodoItem {
+            updated_at: Utc::now(),
+            ..self.clone()
+        };
+        modify(&mut updated);
+        store.update_item(&updated).await?;
+
+        *self = updated;
         Ok(())
     }
 
-    /// Toggle item completion status (from false to true or from true to false)
-    pub async fn toggle_done(&mut self, pool: &SqlitePool) -> Result<()> {
-        let now = Utc::now();
-        let new_status = !self.is_done;
-
-        sqlx::query("UPDATE todo_items SET is_done = ?1, updated_at = ?2 WHERE id = ?3")
-            .bind(new_status)
-            .bind(now)
-            .bind(self.id)
-            .execute(pool)
+    /// Update to-do item name
+    pub async fn update_name(&mut self, store: &dyn TodoStore, new_name: String) -> Result<()> {
+        self.update(store, |item| item.name = new_name)
             .await
-            .with_context(|| "Failed to update todo item status")?;
+            .with_context(|| "Failed to update todo item name")
+    }
 
-        self.is_done = new_status;
-        self.updated_at = now;
+    /// Toggle item completion status (from false to true or from true to false).
+    /// The item leaves its board status: it goes to the last status when done,
+    /// and to the first one when not done anymore
+    pub async fn toggle_done(&mut self, store: &dyn TodoStore) -> Result<()> {
+        self.update(store, |item| {
+            item.is_done = !item.is_done;
+            item.status = None;
+        })
+        .await
+        .with_context(|| "Failed to update todo item status")
+    }
 
-        Ok(())
+    /// Move the item to a status of the board of its list. The item is done
+    /// when the status is the last one
+    pub async fn update_status(
+        &mut self,
+        store: &dyn TodoStore,
+        statuses: &[String],
+        status: &str,
+    ) -> Result<()> {
+        let is_done = statuses.last().is_some_and(|last| last == status);
+        self.update(store, |item| {
+            item.is_done = is_done;
+            item.status = Some(status.to_string());
+        })
+        .await
+        .with_context(|| "Failed to update todo item status")
     }
 
     /// Update item priority
     pub async fn update_priority(
         &mut self,
-        pool: &SqlitePool,
+        store: &dyn TodoStore,
         new_priority: Priority,
     ) -> Result<()> {
-        let now = Utc::now();
-
-        sqlx::query("UPDATE todo_items SET priority = ?1, updated_at = ?2 WHERE id = ?3")
-            .bind(&new_priority)
-            .bind(now)
-            .bind(self.id)
-            .execute(pool)
+        self.update(store, |item| item.priority = Some(new_priority))
             .await
-            .with_context(|| "Failed to update todo item priority")?;
-
-        self.priority = Some(new_priority);
-        self.updated_at = now;
-
-        Ok(())
-    }
-
-    /// Normalize ordering for all items in a list (make contiguous: 0, 1, 2, ...)
-    pub async fn normalize_ordering(pool: &SqlitePool, list_id: i64) -> Result<()> {
-        // Fetch all items for the list, ordered by current ordering
-        let items =
-            sqlx::query("SELECT * FROM todo_items WHERE list_id = ?1 ORDER BY ordering ASC")
-                .bind(list_id)
-                .fetch_all(pool)
-                .await
-                .with_context(|| "Failed to fetch todo items for normalization")?;
-
-        // Update ordering to be contiguous
-        use sqlx::Row;
-        for (i, item) in items.iter().enumerate() {
-            let id: i64 = item.get("id");
-            let ordering: i64 = item.get("ordering");
-            if ordering != i as i64 {
-                sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
-                    .bind(i as i64 + 1)
-                    .bind(id)
-                    .execute(pool)
-                    .await
-                    .with_context(|| format!("Failed to update ordering for item {}", id))?;
-            }
-        }
-        Ok(())
+            .with_context(|| "Failed to update todo item priority")
     }
 
     /// Update item due date
     pub async fn update_due_date(
         &mut self,
-        pool: &SqlitePool,
+        store: &dyn TodoStore,
         new_due_date: DateTime<Utc>,
     ) -> Result<()> {
-        let now = Utc::now();
-
-        sqlx::query("UPDATE todo_items SET due_date = ?1, updated_at = ?2 WHERE id = ?3")
-            .bind(new_due_date)
-            .bind(now)
-            .bind(self.id)
-            .execute(pool)
+        self.update(store, |item| item.due_date = Some(new_due_date))
             .await
-            .with_context(|| "Failed to update todo item priority")?;
+            .with_context(|| "Failed to update todo item due date")
+    }
 
-        self.due_date = Some(new_due_date);
-        self.updated_at = now;
-        Ok(())
+    /// Replace the name, completion, priority and due date of the item at once
+    /// (e.g. with those of an imported task). The item leaves its board status
+    /// when its completion changes
+    pub async fn update_details(
+        &mut self,
+        store: &dyn TodoStore,
+        name: String,
+        is_done: bool,
+        priority: Option<Priority>,
+        due_date: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        self.update(store, |item| {
+            if item.is_done != is_done {
+                item.status = None;
+            }
+            item.name = name;
+            item.is_done = is_done;
+            item.priority = priority;
+            item.due_date = due_date;
+        })
+        .await
+        .with_context(|| "Failed to update todo item")
     }
 
     /// Delete todo item
-    pub async fn delete(self, pool: &SqlitePool) -> Result<()> {
-        sqlx::query("DELETE FROM todo_items WHERE id = ?1")
-            .bind(self.id)
-            .execute(pool)
-            .await
-            .with_context(|| "Failed to delete todo item")?;
-
-        TodoItem::normalize_ordering(pool, self.list_id).await?;
-
-        Ok(())
+    pub async fn delete(self, store: &dyn TodoStore) -> Result<()> {
+        store.delete_item(self.id).await
     }
 
-    /// Move item up (decrease ordering, swap with previous in same list)
-    pub async fn move_up(&mut self, pool: &SqlitePool) -> Result<()> {
-        // Find the item with the next lower ordering value in the same list
-        let prev_item: Option<(i64, i64)> = sqlx::query_as(
-            "SELECT id, ordering FROM todo_items WHERE list_id = ?1 AND ordering < ?2 ORDER BY ordering DESC LIMIT 1"
-        )
-        .bind(self.list_id)
-        .bind(self.ordering)
-        .fetch_optional(pool)
-        .await
-        .with_context(|| "Failed to find previous item")?;
-
-        if let Some((prev_id, prev_ordering)) = prev_item {
-            // Swap orderings
-            sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
-                .bind(self.ordering)
-                .bind(prev_id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update previous item ordering")?;
-
-            sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
-                .bind(prev_ordering)
-                .bind(self.id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update current item ordering")?;
-
-            self.ordering = prev_ordering;
+    /// Move the item to the end of another list, keeping its uid, creation
+    /// time and other fields. Returns the moved item
+    pub async fn move_to_list(self, store: &dyn TodoStore, list_id: i64) -> Result<TodoItem> {
+        if self.list_id == list_id {
+            return Ok(self);
         }
 
-        Ok(())
+        store.move_item_to_list(self.id, list_id, usize::MAX).await
     }
 
-    pub async fn move_up_by(&mut self, pool: &SqlitePool, amount: usize) -> Result<()> {
-        if amount <= 0 {
-            return Ok(()); // nothing to do
-        }
-
-        // 1. Find the `amount`-th item above this one, by ordering
-        let target_item: Option<(i64, i64)> = sqlx::query_as(
-            "SELECT id, ordering FROM todo_items
-             WHERE list_id = ?1 AND ordering < ?2
-             ORDER BY ordering DESC
-             LIMIT 1 OFFSET ?3",
-        )
-        .bind(self.list_id)
-        .bind(self.ordering)
-        .bind((amount as i64 - 1).max(0)) // OFFSET is zero-based
-        .fetch_optional(pool)
-        .await
-        .with_context(|| "Failed to find target item above")?;
-
-        if let Some((_target_id, target_ordering)) = target_item {
-            // 2. Shift all items between target_ordering and self.ordering upward by 1
-            sqlx::query(
-                "UPDATE todo_items
-                 SET ordering = ordering + 1
-                 WHERE list_id = ?1
-                   AND ordering >= ?2
-                   AND ordering < ?3",
-            )
-            .bind(self.list_id)
-            .bind(target_ordering)
-            .bind(self.ordering)
-            .execute(pool)
-            .await
-            .with_context(|| "Failed to shift intermediate items")?;
-
-            // 3. Move current item into the freed spot
-            sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
-                .bind(target_ordering)
-                .bind(self.id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update current item ordering")?;
+    /// Move item up (swap with previous in same list)
+    pub async fn move_up(&mut self, store: &dyn TodoStore) -> Result<()> {
+        self.move_up_by(store, 1).await
+    }
 
-            // 4. Update self
-            self.ordering = target_ordering;
+    /// Move item up by `amount` positions in its list
+    pub async fn move_up_by(&mut self, store: &dyn TodoStore, amount: usize) -> Result<()> {
+        if amount == 0 {
+            return Ok(()); // nothing to do
         }
 
+        self.ordering = store.move_item(self.id, -(amount as i64)).await?;
         Ok(())
     }
 
-    /// Move item down (increase ordering, swap with next in same list)
-    pub async fn move_down(&mut self, pool: &SqlitePool) -> Result<()> {
-        // Find the item with the next higher ordering value in the same list
-        let next_item: Option<(i64, i64)> = sqlx::query_as(
-            "SELECT id, ordering FROM todo_items WHERE list_id = ?1 AND ordering > ?2 ORDER BY ordering ASC LIMIT 1"
-        )
-        .bind(self.list_id)
-        .bind(self.ordering)
-        .fetch_optional(pool)
-        .await
-        .with_context(|| "Failed to find next item")?;
-
-        if let Some((next_id, next_ordering)) = next_item {
-            // Swap orderings
-            sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
-                .bind(self.ordering)
-                .bind(next_id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update next item ordering")?;
-
-            sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
-                .bind(next_ordering)
-                .bind(self.id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update current item ordering")?;
-
-            self.ordering = next_ordering;
-        }
-
-        Ok(())
+    /// Move item down (swap with next in same list)
+    pub async fn move_down(&mut self, store: &dyn TodoStore) -> Result<()> {
+        self.move_down_by(store, 1).await
     }
 
-    pub async fn move_down_by(&mut self, pool: &SqlitePool, amount: usize) -> Result<()> {
-        if amount <= 0 {
+    /// Move item down by `amount` positions in its list
+    pub async fn move_down_by(&mut self, store: &dyn TodoStore, amount: usize) -> Result<()> {
+        if amount == 0 {
             return Ok(()); // nothing to do
         }
 
-        // 1. Find the `amount`-th item below this one, by ordering
-        let target_item: Option<(i64, i64)> = sqlx::query_as(
-            "SELECT id, ordering FROM todo_items
-             WHERE list_id = ?1 AND ordering > ?2
-             ORDER BY ordering ASC
-             LIMIT 1 OFFSET ?3",
-        )
-        .bind(self.list_id)
-        .bind(self.ordering)
-        .bind((amount as i64 - 1).max(0)) // OFFSET is zero-based
-        .fetch_optional(pool)
-        .await
-        .with_context(|| "Failed to find target item below")?;
-
-        if let Some((_target_id, target_ordering)) = target_item {
-            // 2. Shift all items between self.ordering and target_ordering downward by 1
-            sqlx::query(
-                "UPDATE todo_items
-                 SET ordering = ordering - 1
-                 WHERE list_id = ?1
-                   AND ordering <= ?2
-                   AND ordering > ?3",
-            )
-            .bind(self.list_id)
-            .bind(target_ordering)
-            .bind(self.ordering)
-            .execute(pool)
-            .await
-            .with_context(|| "Failed to shift intermediate items")?;
-
-            // 3. Move current item into the freed spot
-            sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE id = ?2")
-                .bind(target_ordering)
-                .bind(self.id)
-                .execute(pool)
-                .await
-                .with_context(|| "Failed to update current item ordering")?;
-
-            // 4. Update struct field
-            self.ordering = target_ordering;
-        }
-
+        self.ordering = store.move_item(self.id, amount as i64).await?;
         Ok(())
     }
 }
 
 impl UIList {
     /// Get all lists in db already attached to their items
-    pub async fn get_all(pool: &SqlitePool) -> Result<Vec<UIList>> {
+    pub async fn get_all(store: &dyn TodoStore) -> Result<Vec<UIList>> {
         // Fetch all lists
-        let lists = TodoList::get_all(pool)
+        let lists = TodoList::get_all(store)
             .await
             .with_context(|| "Failed to fetch lists from db")?;
 
@@ -527,7 +281,7 @@ impl UIList {
 
         // For each list, fetch its items and create a UIList
         for list in lists {
-            let items = TodoItem::get_by_list_id(pool, list.id)
+            let items = TodoItem::get_by_list_id(store, list.id)
                 .await
                 .with_context(|| format!("Failed to fetch items for list {}", list.id))?
                 .iter()
@@ -549,9 +303,9 @@ impl UIList {
 
     /// Update items when something changes (new item, deleted item).
     /// Keeps the same list state instead of reinitializing it
-    pub async fn update_items(&mut self, pool: &SqlitePool) -> Result<()> {
+    pub async fn update_items(&mut self, store: &dyn TodoStore) -> Result<()> {
         // Re-fetch the items but don't change the list state
-        let items = TodoItem::get_by_list_id(pool, self.list.id)
+        let items = TodoItem::get_by_list_id(store, self.list.id)
             .await
             .with_context(|| "Failed to fetch items for list")?
             .iter()
@@ -0,0 +1,543 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgConnectOptions, PgConnection, PgPool, PgRow};
+use std::str::FromStr;
+
+use crate::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use crate::db::store::{ORDERING_GAP, TodoStore, plan_insert, plan_move, plan_repair};
+
+/// Key of the advisory lock taken to change the ordering of the lists. The
+/// ordering of the items of a list is locked with the id of the list
+const LISTS_ORDERING_LOCK: i64 = -1;
+
+const ITEM_COLUMNS: &str =
+    "id, uid, list_id, name, is_done, priority, due_date, ordering, status, created_at, updated_at";
+
+/// Store in a PostgreSQL database, which can be shared by several users.
+///
+/// Changes to the ordering of lists and items are made in transactions that
+/// hold an advisory lock, so that concurrent users wait for each other instead
+/// of failing on the unique index of the orderings
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    /// Connect to a PostgreSQL database and run its migrations
+    pub async fn connect(connection_str: &str) -> Result<PostgresStore> {
+        let options = PgConnectOptions::from_str(connection_str)
+            .with_context(|| "Invalid PostgreSQL connection string")?;
+        PostgresStore::connect_with(options).await
+    }
+
+    /// Connect to a PostgreSQL database with the given options and run its migrations
+    pub async fn connect_with(options: PgConnectOptions) -> Result<PostgresStore> {
+        let pool = PgPool::connect_with(options)
+            .await
+            .with_context(|| "Failed to connect to PostgreSQL")?;
+
+        // Embed the PostgreSQL migration files into binary
+        static MIGRATOR: Migrator = sqlx::migrate!("./migrations_postgres");
+        MIGRATOR
+            .run(&pool)
+            .await
+            .with_context(|| "Failed to run database migrations")?;
+
+        Ok(PostgresStore { pool })
+    }
+
+    /// Connection pool to the database
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+}
+
+/// Build an item from a row (priorities are stored as plain text)
+fn item_from_row(row: PgRow) -> Result<TodoItem> {
+    let priority: Option<String> = row.try_get("priority")?;
+    Ok(TodoItem {
+        id: row.try_get("id")?,
+        uid: row.try_get("uid")?,
+        list_id: row.try_get("list_id")?,
+        name: row.try_get("name")?,
+        is_done: row.try_get("is_done")?,
+        priority: priority.map(|p| p.parse()).transpose()?,
+        due_date: row.try_get("due_date")?,
+        ordering: row.try_get("ordering")?,
+        status: row.try_get("status")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+/// Wait for the other transactions changing the same ordering (of the lists, or
+/// of the items of a list) to finish. Released at the end of the transaction
+async fn lock_ordering(conn: &mut PgConnection, list_id: Option<i64>) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(list_id.unwrap_or(LISTS_ORDERING_LOCK))
+        .execute(conn)
+        .await
+        .with_context(|| "Failed to lock ordering")?;
+    Ok(())
+}
+
+#[async_trait]
+impl TodoStore for PostgresStore {
+    async fn create_list(&self, new_list: NewTodoList) -> Result<TodoList> {
+        let now = Utc::now();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .with_context(|| "Failed to start transaction")?;
+        lock_ordering(&mut tx, None).await?;
+
+        let list = sqlx::query_as::<_, TodoList>(
+            r#"
+            INSERT INTO todo_lists (uid, name, ordering, created_at, updated_at)
+            SELECT replace(gen_random_uuid()::text, '-', ''), $1, COALESCE(MAX(ordering), 0) + $2, $3, $4
+            FROM todo_lists
+            RETURNING id, uid, name, ordering, statuses, created_at, updated_at
+            "#,
+        )
+        .bind(&new_list.name)
+        .bind(ORDERING_GAP)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .with_context(|| "Failed to create todo list")?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit transaction")?;
+        Ok(list)
+    }
+
+    async fn create_list_at(&self, new_list: NewTodoList, position: usize) -> Result<TodoList> {
+        let now = Utc::now();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .with_context(|| "Failed to start transaction")?;
+        lock_ordering(&mut tx, None).await?;
+
+        let (table, siblings) = siblings(&mut tx, None).await?;
+        let (ordering, writes) = plan_insert(&siblings, position);
+        write_orderings(&mut tx, table, writes).await?;
+
+        let list = sqlx::query_as::<_, TodoList>(
+            r#"
+            INSERT INTO todo_lists (uid, name, ordering, created_at, updated_at)
+            VALUES (replace(gen_random_uuid()::text, '-', ''), $1, $2, $3, $4)
+            RETURNING id, uid, name, ordering, statuses, created_at, updated_at
+            "#,
+        )
+        .bind(&new_list.name)
+        .bind(ordering)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .with_context(|| "Failed to create todo list")?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit transaction")?;
+        Ok(list)
+    }
+
+    async fn get_lists(&self) -> Result<Vec<TodoList>> {
+        sqlx::query_as::<_, TodoList>(
+            "SELECT id, uid, name, ordering, statuses, created_at, updated_at FROM todo_lists ORDER BY ordering, id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| "Failed to fetch all todo lists")
+    }
+
+    async fn get_list(&self, id: i64) -> Result<Option<TodoList>> {
+        sqlx::query_as::<_, TodoList>(
+            "SELECT id, uid, name, ordering, statuses, created_at, updated_at FROM todo_lists WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .with_context(|| "Failed to fetch todo list by id")
+    }
+
+    async fn update_list(&self, list: &TodoList) -> Result<()> {
+        sqlx::query(
+            "UPDATE todo_lists SET name = $1, statuses = $2, updated_at = $3 WHERE id = $4",
+        )
+        .bind(&list.name)
+        .bind(&list.statuses)
+        .bind(list.updated_at)
+        .bind(list.id)
+        .execute(&self.pool)
+        .await
+        .with_context(|| "Failed to update todo list")?;
+
+        Ok(())
+    }
+
+    async fn delete_list(&self, id: i64) -> Result<()> {
+        // Items are deleted too due to CASCADE
+        sqlx::query("DELETE FROM todo_lists WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| "Failed to delete todo list")?;
+
+        Ok(())
+    }
+
+    async fn move_list(&self, id: i64, offset: i64) -> Result<i64> {
+        move_row(&self.pool, None, id, offset).await
+    }
+
+    async fn create_item(&self, new_item: NewTodoItem) -> Result<TodoItem> {
+        let now = Utc::now();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .with_context(|| "Failed to start transaction")?;
+        lock_ordering(&mut tx, Some(new_item.list_id)).await?;
+
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO todo_items (uid, list_id, name, is_done, priority, due_date, ordering, created_at, updated_at)
+            SELECT replace(gen_random_uuid()::text, '-', ''), $1, $2, FALSE, $3, $4, COALESCE(MAX(ordering), 0) + $5, $6, $7
+            FROM todo_items
+            WHERE list_id = $1
+            RETURNING {}
+            "#,
+            ITEM_COLUMNS
+        ))
+        .bind(new_item.list_id)
+        .bind(&new_item.name)
+        .bind(new_item.priority.as_ref().map(Priority::as_str))
+        .bind(new_item.due_date)
+        .bind(ORDERING_GAP)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .with_context(|| "Failed to create todo item")?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit transaction")?;
+        item_from_row(row)
+    }
+
+    async fn create_item_at(&self, new_item: NewTodoItem, position: usize) -> Result<TodoItem> {
+        let now = Utc::now();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .with_context(|| "Failed to start transaction")?;
+        lock_ordering(&mut tx, Some(new_item.list_id)).await?;
+
+        let (table, siblings) = siblings(&mut tx, Some(new_item.list_id)).await?;
+        let (ordering, writes) = plan_insert(&siblings, position);
+        write_orderings(&mut tx, table, writes).await?;
+
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO todo_items (uid, list_id, name, is_done, priority, due_date, ordering, created_at, updated_at)
+            VALUES (replace(gen_random_uuid()::text, '-', ''), $1, $2, FALSE, $3, $4, $5, $6, $7)
+            RETURNING {}
+            "#,
+            ITEM_COLUMNS
+        ))
+        .bind(new_item.list_id)
+        .bind(&new_item.name)
+        .bind(new_item.priority.as_ref().map(Priority::as_str))
+        .bind(new_item.due_date)
+        .bind(ordering)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .with_context(|| "Failed to create todo item")?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit transaction")?;
+        item_from_row(row)
+    }
+
+    async fn get_items(&self, list_id: i64) -> Result<Vec<TodoItem>> {
+        sqlx::query(&format!(
+            "SELECT {} FROM todo_items WHERE list_id = $1 ORDER BY ordering, id",
+            ITEM_COLUMNS
+        ))
+        .bind(list_id)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| "Failed to fetch todo items")?
+        .into_iter()
+        .map(item_from_row)
+        .collect()
+    }
+
+    async fn get_item(&self, id: i64) -> Result<Option<TodoItem>> {
+        sqlx::query(&format!(
+            "SELECT {} FROM todo_items WHERE id = $1",
+            ITEM_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .with_context(|| "Failed to fetch todo item")?
+        .map(item_from_row)
+        .transpose()
+    }
+
+    async fn update_item(&self, item: &TodoItem) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE todo_items
+            SET name = $1, is_done = $2, priority = $3, due_date = $4, status = $5, updated_at = $6
+            WHERE id = $7
+            "#,
+        )
+        .bind(&item.name)
+        .bind(item.is_done)
+        .bind(item.priority.as_ref().map(Priority::as_str))
+        .bind(item.due_date)
+        .bind(&item.status)
+        .bind(item.updated_at)
+        .bind(item.id)
+        .execute(&self.pool)
+        .await
+        .with_context(|| "Failed to update todo item")?;
+
+        Ok(())
+    }
+
+    async fn delete_item(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM todo_items WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| "Failed to delete todo item")?;
+
+        Ok(())
+    }
+
+    async fn move_item(&self, id: i64, offset: i64) -> Result<i64> {
+        let list_id = item_list_id(&self.pool, id)
+            .await?
+            .with_context(|| format!("No item with id {}", id))?;
+
+        move_row(&self.pool, Some(list_id), id, offset).await
+    }
+
+    async fn move_item_to_list(&self, id: i64, list_id: i64, position: usize) -> Result<TodoItem> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .with_context(|| "Failed to start transaction")?;
+        lock_ordering(&mut tx, Some(list_id)).await?;
+
+        let (table, mut siblings) = siblings(&mut tx, Some(list_id)).await?;
+        siblings.retain(|&(sibling_id, _)| sibling_id != id);
+        let (ordering, writes) = plan_insert(&siblings, position);
+        write_orderings(&mut tx, table, writes).await?;
+
+        let row = sqlx::query(&format!(
+            r#"
+            UPDATE todo_items
+            SET list_id = $1, ordering = $2, updated_at = $3
+            WHERE id = $4
+            RETURNING {}
+            "#,
+            ITEM_COLUMNS
+        ))
+        .bind(list_id)
+        .bind(ordering)
+        .bind(Utc::now())
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .with_context(|| "Failed to move todo item")?
+        .with_context(|| format!("No item with id {}", id))?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit transaction")?;
+        item_from_row(row)
+    }
+
+    async fn claim_list(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        claim_row(&self.pool, "todo_lists", id, updated_at, now).await
+    }
+
+    async fn claim_item(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        claim_row(&self.pool, "todo_items", id, updated_at, now).await
+    }
+
+    async fn set_item_origin(&self, id: i64, uid: &str, created_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE todo_items SET uid = $1, created_at = $2 WHERE id = $3")
+            .bind(uid)
+            .bind(created_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| "Failed to update todo item")?;
+
+        Ok(())
+    }
+
+    async fn repair_orderings(&self) -> Result<usize> {
+        let mut changed = repair_siblings(&self.pool, None).await?;
+
+        let list_ids: Vec<i64> = sqlx::query_scalar("SELECT DISTINCT list_id FROM todo_items")
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| "Failed to fetch todo items")?;
+        for list_id in list_ids {
+            changed += repair_siblings(&self.pool, Some(list_id)).await?;
+        }
+
+        Ok(changed)
+    }
+}
+
+/// List of an item, if it exists
+async fn item_list_id(pool: &PgPool, id: i64) -> Result<Option<i64>> {
+    sqlx::query_scalar("SELECT list_id FROM todo_items WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .with_context(|| "Failed to fetch todo item")
+}
+
+/// Table and `(id, ordering)` of the lists (`list_id` is `None`) or of the
+/// items of a list, sorted by ordering
+async fn siblings(
+    conn: &mut PgConnection,
+    list_id: Option<i64>,
+) -> Result<(&'static str, Vec<(i64, i64)>)> {
+    let (table, query) = match list_id {
+        Some(list_id) => (
+            "todo_items",
+            sqlx::query_as(
+                "SELECT id, ordering FROM todo_items WHERE list_id = $1 ORDER BY ordering, id",
+            )
+            .bind(list_id),
+        ),
+        None => (
+            "todo_lists",
+            sqlx::query_as("SELECT id, ordering FROM todo_lists ORDER BY ordering, id"),
+        ),
+    };
+    let siblings = query
+        .fetch_all(&mut *conn)
+        .await
+        .with_context(|| format!("Failed to read the ordering of {}", table))?;
+
+    Ok((table, siblings))
+}
+
+/// Apply planned `(id, ordering)` writes to the rows of a table
+async fn write_orderings(
+    conn: &mut PgConnection,
+    table: &str,
+    writes: Vec<(i64, i64)>,
+) -> Result<()> {
+    let query = format!("UPDATE {} SET ordering = $1 WHERE id = $2", table);
+    for (id, ordering) in writes {
+        sqlx::query(&query)
+            .bind(ordering)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to update ordering of row {} in {}", id, table))?;
+    }
+    Ok(())
+}
+
+/// Move a list (`list_id` is `None`) or an item of a list by `offset`
+/// positions. The orderings are read and written while holding the lock of the
+/// ordering, so concurrent moves apply one after the other
+async fn move_row(pool: &PgPool, list_id: Option<i64>, id: i64, offset: i64) -> Result<i64> {
+    let mut tx = pool
+        .begin()
+        .await
+        .with_context(|| "Failed to start transaction")?;
+    lock_ordering(&mut tx, list_id).await?;
+
+    let (table, siblings) = siblings(&mut tx, list_id).await?;
+    let (ordering, writes) = plan_move(&siblings, id, offset)
+        .with_context(|| format!("Failed to move row {} in {}", id, table))?;
+    write_orderings(&mut tx, table, writes).await?;
+
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+
+    Ok(ordering)
+}
+
+/// Repair the orderings of the lists (`list_id` is `None`) or of the items of a
+/// list, while holding the lock of the ordering. Returns the number of rows changed
+async fn repair_siblings(pool: &PgPool, list_id: Option<i64>) -> Result<usize> {
+    let mut tx = pool
+        .begin()
+        .await
+        .with_context(|| "Failed to start transaction")?;
+    lock_ordering(&mut tx, list_id).await?;
+
+    let (table, siblings) = siblings(&mut tx, list_id).await?;
+    let writes = plan_repair(&siblings);
+    let changed = writes.iter().filter(|&&(_, ordering)| ordering > 0).count();
+    write_orderings(&mut tx, table, writes).await?;
+
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+
+    Ok(changed)
+}
+
+/// Set the `updated_at` of a row to `now` if it is still `updated_at`, with a
+/// conditional update
+async fn claim_row(
+    pool: &PgPool,
+    table: &str,
+    id: i64,
+    updated_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<bool> {
+    let result = sqlx::query(&format!(
+        "UPDATE {} SET updated_at = $1 WHERE id = $2 AND updated_at = $3",
+        table
+    ))
+    .bind(now)
+    .bind(id)
+    .bind(updated_at)
+    .execute(pool)
+    .await
+    .with_context(|| format!("Failed to update {}", table))?;
+    Ok(result.rows_affected() == 1)
+}
@@ -0,0 +1,511 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction};
+
+use crate::db::crypto::{open_text, seal_text};
+use crate::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use crate::db::store::{ORDERING_GAP, TodoStore, plan_insert, plan_move, plan_repair};
+
+/// Decrypt the name of a list read from an encrypted database
+fn open_list(pool: &SqlitePool, list: TodoList) -> Result<TodoList> {
+    Ok(TodoList {
+        name: open_text(pool, list.name)?,
+        ..list
+    })
+}
+
+/// Decrypt the name of an item read from an encrypted database
+fn open_item(pool: &SqlitePool, item: TodoItem) -> Result<TodoItem> {
+    Ok(TodoItem {
+        name: open_text(pool, item.name)?,
+        ..item
+    })
+}
+
+#[async_trait]
+impl TodoStore for SqlitePool {
+    async fn create_list(&self, new_list: NewTodoList) -> Result<TodoList> {
+        let now = Utc::now();
+
+        // The ordering is computed by the insert itself, so that concurrent
+        // inserts can't take the same one
+        let row = sqlx::query_as::<_, TodoList>(
+            r#"
+            INSERT INTO todo_lists (uid, name, ordering, created_at, updated_at)
+            SELECT lower(hex(randomblob(16))), ?1, COALESCE(MAX(ordering), 0) + ?2, ?3, ?4
+            FROM todo_lists
+            RETURNING id, uid, name, ordering, statuses, created_at, updated_at
+            "#,
+        )
+        .bind(seal_text(self, &new_list.name)?)
+        .bind(ORDERING_GAP)
+        .bind(now)
+        .bind(now)
+        .fetch_one(self)
+        .await
+        .with_context(|| "Failed to create todo list")?;
+
+        Ok(TodoList {
+            name: new_list.name,
+            ..row
+        })
+    }
+
+    async fn create_list_at(&self, new_list: NewTodoList, position: usize) -> Result<TodoList> {
+        let now = Utc::now();
+        let mut tx = begin_write(self).await?;
+
+        let (table, siblings) = siblings(&mut tx, None).await?;
+        let (ordering, writes) = plan_insert(&siblings, position);
+        write_orderings(&mut tx, table, writes).await?;
+
+        let row = sqlx::query_as::<_, TodoList>(
+            r#"
+            INSERT INTO todo_lists (uid, name, ordering, created_at, updated_at)
+            VALUES (lower(hex(randomblob(16))), ?1, ?2, ?3, ?4)
+            RETURNING id, uid, name, ordering, statuses, created_at, updated_at
+            "#,
+        )
+        .bind(seal_text(self, &new_list.name)?)
+        .bind(ordering)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .with_context(|| "Failed to create todo list")?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit transaction")?;
+
+        Ok(TodoList {
+            name: new_list.name,
+            ..row
+        })
+    }
+
+    async fn get_lists(&self) -> Result<Vec<TodoList>> {
+        let lists = sqlx::query_as::<_, TodoList>(
+            "SELECT id, uid, name, ordering, statuses, created_at, updated_at FROM todo_lists ORDER BY ordering",
+        )
+        .fetch_all(self)
+        .await
+        .with_context(|| "Failed to fetch all todo lists")?;
+
+        lists
+            .into_iter()
+            .map(|list| open_list(self, list))
+            .collect()
+    }
+
+    async fn get_list(&self, id: i64) -> Result<Option<TodoList>> {
+        let list = sqlx::query_as::<_, TodoList>(
+            "SELECT id, uid, name, ordering, statuses, created_at, updated_at FROM todo_lists WHERE id = ?1",
+        )
+        .bind(id)
+        .fetch_optional(self)
+        .await
+        .with_context(|| "Failed to fetch todo list by id")?;
+
+        list.map(|list| open_list(self, list)).transpose()
+    }
+
+    async fn update_list(&self, list: &TodoList) -> Result<()> {
+        sqlx::query(
+            "UPDATE todo_lists SET name = ?1, statuses = ?2, updated_at = ?3 WHERE id = ?4",
+        )
+        .bind(seal_text(self, &list.name)?)
+        .bind(&list.statuses)
+        .bind(list.updated_at)
+        .bind(list.id)
+        .execute(self)
+        .await
+        .with_context(|| "Failed to update todo list")?;
+
+        Ok(())
+    }
+
+    async fn delete_list(&self, id: i64) -> Result<()> {
+        // Items are deleted too due to CASCADE
+        sqlx::query("DELETE FROM todo_lists WHERE id = ?1")
+            .bind(id)
+            .execute(self)
+            .await
+            .with_context(|| "Failed to delete todo list")?;
+
+        Ok(())
+    }
+
+    async fn move_list(&self, id: i64, offset: i64) -> Result<i64> {
+        move_row(self, None, id, offset).await
+    }
+
+    async fn create_item(&self, new_item: NewTodoItem) -> Result<TodoItem> {
+        let now = Utc::now();
+
+        // The ordering is computed by the insert itself, so that concurrent
+        // inserts can't take the same one
+        let row = sqlx::query_as::<_, TodoItem>(
+            r#"
+            INSERT INTO todo_items (uid, list_id, name, is_done, priority, due_date, ordering, created_at, updated_at)
+            SELECT lower(hex(randomblob(16))), ?1, ?2, FALSE, ?3, ?4, COALESCE(MAX(ordering), 0) + ?5, ?6, ?7
+            FROM todo_items
+            WHERE list_id = ?1
+            RETURNING id, uid, list_id, name, is_done, priority, due_date, ordering, status, created_at, updated_at
+            "#,
+        )
+        .bind(new_item.list_id)
+        .bind(seal_text(self, &new_item.name)?)
+        .bind(&new_item.priority)
+        .bind(new_item.due_date)
+        .bind(ORDERING_GAP)
+        .bind(now)
+        .bind(now)
+        .fetch_one(self)
+        .await
+        .with_context(|| "Failed to create todo item")?;
+
+        Ok(TodoItem {
+            name: new_item.name,
+            ..row
+        })
+    }
+
+    async fn create_item_at(&self, new_item: NewTodoItem, position: usize) -> Result<TodoItem> {
+        let now = Utc::now();
+        let mut tx = begin_write(self).await?;
+
+        let (table, siblings) = siblings(&mut tx, Some(new_item.list_id)).await?;
+        let (ordering, writes) = plan_insert(&siblings, position);
+        write_orderings(&mut tx, table, writes).await?;
+
+        let row = sqlx::query_as::<_, TodoItem>(
+            r#"
+            INSERT INTO todo_items (uid, list_id, name, is_done, priority, due_date, ordering, created_at, updated_at)
+            VALUES (lower(hex(randomblob(16))), ?1, ?2, FALSE, ?3, ?4, ?5, ?6, ?7)
+            RETURNING id, uid, list_id, name, is_done, priority, due_date, ordering, status, created_at, updated_at
+            "#,
+        )
+        .bind(new_item.list_id)
+        .bind(seal_text(self, &new_item.name)?)
+        .bind(&new_item.priority)
+        .bind(new_item.due_date)
+        .bind(ordering)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .with_context(|| "Failed to create todo item")?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit transaction")?;
+
+        Ok(TodoItem {
+            name: new_item.name,
+            ..row
+        })
+    }
+
+    async fn get_items(&self, list_id: i64) -> Result<Vec<TodoItem>> {
+        let items = sqlx::query_as::<_, TodoItem>(
+            r#"
+            SELECT id, uid, list_id, name, is_done, priority, due_date, ordering, status, created_at, updated_at
+            FROM todo_items
+            WHERE list_id = ?1
+            ORDER BY ordering
+            "#,
+        )
+        .bind(list_id)
+        .fetch_all(self)
+        .await
+        .with_context(|| "Failed to fetch todo items")?;
+
+        items
+            .into_iter()
+            .map(|item| open_item(self, item))
+            .collect()
+    }
+
+    async fn get_item(&self, id: i64) -> Result<Option<TodoItem>> {
+        let item = sqlx::query_as::<_, TodoItem>(
+            r#"
+            SELECT id, uid, list_id, name, is_done, priority, due_date, ordering, status, created_at, updated_at
+            FROM todo_items
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(self)
+        .await
+        .with_context(|| "Failed to fetch todo item")?;
+
+        item.map(|item| open_item(self, item)).transpose()
+    }
+
+    async fn update_item(&self, item: &TodoItem) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE todo_items
+            SET name = ?1, is_done = ?2, priority = ?3, due_date = ?4, status = ?5, updated_at = ?6
+            WHERE id = ?7
+            "#,
+        )
+        .bind(seal_text(self, &item.name)?)
+        .bind(item.is_done)
+        .bind(&item.priority)
+        .bind(item.due_date)
+        .bind(&item.status)
+        .bind(item.updated_at)
+        .bind(item.id)
+        .execute(self)
+        .await
+        .with_context(|| "Failed to update todo item")?;
+
+        Ok(())
+    }
+
+    async fn delete_item(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM todo_items WHERE id = ?1")
+            .bind(id)
+            .execute(self)
+            .await
+            .with_context(|| "Failed to delete todo item")?;
+
+        Ok(())
+    }
+
+    async fn move_item(&self, id: i64, offset: i64) -> Result<i64> {
+        let list_id: i64 = sqlx::query_scalar("SELECT list_id FROM todo_items WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(self)
+            .await
+            .with_context(|| "Failed to fetch todo item")?
+            .with_context(|| format!("No item with id {}", id))?;
+
+        move_row(self, Some(list_id), id, offset).await
+    }
+
+    async fn move_item_to_list(&self, id: i64, list_id: i64, position: usize) -> Result<TodoItem> {
+        let mut tx = begin_write(self).await?;
+
+        let (table, mut siblings) = siblings(&mut tx, Some(list_id)).await?;
+        siblings.retain(|&(sibling_id, _)| sibling_id != id);
+        let (ordering, writes) = plan_insert(&siblings, position);
+        write_orderings(&mut tx, table, writes).await?;
+
+        let item = sqlx::query_as::<_, TodoItem>(
+            r#"
+            UPDATE todo_items
+            SET list_id = ?1, ordering = ?2, updated_at = ?3
+            WHERE id = ?4
+            RETURNING id, uid, list_id, name, is_done, priority, due_date, ordering, status, created_at, updated_at
+            "#,
+        )
+        .bind(list_id)
+        .bind(ordering)
+        .bind(Utc::now())
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .with_context(|| "Failed to move todo item")?
+        .with_context(|| format!("No item with id {}", id))?;
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit transaction")?;
+
+        open_item(self, item)
+    }
+
+    async fn claim_list(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        claim_row(self, "todo_lists", id, updated_at, now).await
+    }
+
+    async fn claim_item(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        claim_row(self, "todo_items", id, updated_at, now).await
+    }
+
+    async fn set_item_origin(&self, id: i64, uid: &str, created_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE todo_items SET uid = ?1, created_at = ?2 WHERE id = ?3")
+            .bind(uid)
+            .bind(created_at)
+            .bind(id)
+            .execute(self)
+            .await
+            .with_context(|| "Failed to update todo item")?;
+
+        Ok(())
+    }
+
+    async fn repair_orderings(&self) -> Result<usize> {
+        let mut tx = begin_write(self).await?;
+
+        let mut changed = repair_siblings(&mut tx, None).await?;
+        let list_ids: Vec<i64> = sqlx::query_scalar("SELECT DISTINCT list_id FROM todo_items")
+            .fetch_all(&mut *tx)
+            .await
+            .with_context(|| "Failed to fetch todo items")?;
+        for list_id in list_ids {
+            changed += repair_siblings(&mut tx, Some(list_id)).await?;
+        }
+
+        tx.commit()
+            .await
+            .with_context(|| "Failed to commit transaction")?;
+
+        Ok(changed)
+    }
+
+    fn sqlite_pool(&self) -> Option<&SqlitePool> {
+        Some(self)
+    }
+}
+
+/// Start a transaction holding the write lock of the database from the start,
+/// so that concurrent changes to the ordering wait for each other
+async fn begin_write(pool: &SqlitePool) -> Result<Transaction<'static, Sqlite>> {
+    pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .with_context(|| "Failed to start transaction")
+}
+
+/// Set the `updated_at` of a row to `now` if it is still `updated_at`. The
+/// timestamps are compared once read (they are stored as text, in more than
+/// one format), in a transaction holding the write lock
+async fn claim_row(
+    pool: &SqlitePool,
+    table: &str,
+    id: i64,
+    updated_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<bool> {
+    let mut tx = begin_write(pool).await?;
+    let current: Option<DateTime<Utc>> =
+        sqlx::query_scalar(&format!("SELECT updated_at FROM {} WHERE id = ?1", table))
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to read {}", table))?;
+    if current != Some(updated_at) {
+        return Ok(false);
+    }
+
+    sqlx::query(&format!(
+        "UPDATE {} SET updated_at = ?1 WHERE id = ?2",
+        table
+    ))
+    .bind(now)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .with_context(|| format!("Failed to update {}", table))?;
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+    Ok(true)
+}
+
+/// Table and `(id, ordering)` of the lists (`list_id` is `None`) or of the
+/// items of a list, sorted by ordering
+async fn siblings(
+    conn: &mut SqliteConnection,
+    list_id: Option<i64>,
+) -> Result<(&'static str, Vec<(i64, i64)>)> {
+    let (table, query) = match list_id {
+        Some(list_id) => (
+            "todo_items",
+            sqlx::query_as(
+                "SELECT id, ordering FROM todo_items WHERE list_id = ?1 ORDER BY ordering, id",
+            )
+            .bind(list_id),
+        ),
+        None => (
+            "todo_lists",
+            sqlx::query_as("SELECT id, ordering FROM todo_lists ORDER BY ordering, id"),
+        ),
+    };
+    let siblings = query
+        .fetch_all(&mut *conn)
+        .await
+        .with_context(|| format!("Failed to read the ordering of {}", table))?;
+
+    Ok((table, siblings))
+}
+
+/// Apply planned `(id, ordering)` writes to the rows of a table
+async fn write_orderings(
+    conn: &mut SqliteConnection,
+    table: &str,
+    writes: Vec<(i64, i64)>,
+) -> Result<()> {
+    let query = format!("UPDATE {} SET ordering = ?1 WHERE id = ?2", table);
+    for (id, ordering) in writes {
+        sqlx::query(&query)
+            .bind(ordering)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to update ordering of row {} in {}", id, table))?;
+    }
+    Ok(())
+}
+
+/// Move a list (`list_id` is `None`) or an item of a list by `offset`
+/// positions, in a transaction. Returns the new ordering of the row
+async fn move_row(pool: &SqlitePool, list_id: Option<i64>, id: i64, offset: i64) -> Result<i64> {
+    let mut tx = begin_write(pool).await?;
+
+    let (table, siblings) = siblings(&mut tx, list_id).await?;
+    let (ordering, writes) = plan_move(&siblings, id, offset)
+        .with_context(|| format!("Failed to move row {} in {}", id, table))?;
+    write_orderings(&mut tx, table, writes).await?;
+
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+
+    Ok(ordering)
+}
+
+/// Repair the orderings of the lists (`list_id` is `None`) or of the items of a
+/// list. Returns the number of rows changed
+async fn repair_siblings(conn: &mut SqliteConnection, list_id: Option<i64>) -> Result<usize> {
+    let (table, siblings) = siblings(conn, list_id).await?;
+    let writes = plan_repair(&siblings);
+    let changed = writes.iter().filter(|&&(_, ordering)| ordering > 0).count();
+    write_orderings(conn, table, writes).await?;
+    Ok(changed)
+}
+
+/// Park the rows of a table with the given uids on distinct orderings below
+/// those of all other rows, so that they can then take new orderings one at a
+/// time without breaking the unique index on orderings
+pub(crate) async fn park_orderings(
+    conn: &mut SqliteConnection,
+    table: &str,
+    uids: &[&str],
+) -> Result<()> {
+    let query = format!(
+        "UPDATE {table} SET ordering = (SELECT MIN(MIN(ordering), 0) - 1 FROM {table}) - id WHERE uid = ?1"
+    );
+    for uid in uids {
+        sqlx::query(&query)
+            .bind(uid)
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to update ordering of {} in {}", uid, table))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,353 @@
+//! Storage abstraction. The UI only talks to a [`TodoStore`], so that other
+//! backends than SQLite can be plugged in. The backend of a database is chosen
+//! by the scheme of its connection string (see [`open_store`])
+
+use crate::db::connections::init_db;
+use crate::db::json_store::JsonStore;
+use crate::db::memory_store::MemoryStore;
+use crate::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use crate::db::postgres_store::PostgresStore;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Storage of todo lists and their items.
+///
+/// Lists and items are kept sorted by their `ordering`, which is unique among
+/// siblings. Orderings are spaced out by [`ORDERING_GAP`], so that a move only
+/// has to change the moved row. Updates write every field but the ordering,
+/// which is only changed by the `move_*` methods
+#[async_trait]
+pub trait TodoStore: Send + Sync {
+    /// Create a list at the end of the ordering
+    async fn create_list(&self, new_list: NewTodoList) -> Result<TodoList>;
+
+    /// Create a list at `position` among the lists (at the end if past the last
+    /// one), making room in the ordering if needed
+    async fn create_list_at(&self, new_list: NewTodoList, position: usize) -> Result<TodoList>;
+
+    /// Get all lists, sorted by ordering
+    async fn get_lists(&self) -> Result<Vec<TodoList>>;
+
+    /// Get a list by id
+    async fn get_list(&self, id: i64) -> Result<Option<TodoList>>;
+
+    /// Save the fields of a list (but its ordering)
+    async fn update_list(&self, list: &TodoList) -> Result<()>;
+
+    /// Delete a list and all its items
+    async fn delete_list(&self, id: i64) -> Result<()>;
+
+    /// Move a list by `offset` positions (up if negative). Returns the new
+    /// ordering of the list
+    async fn move_list(&self, id: i64, offset: i64) -> Result<i64>;
+
+    /// Create an item at the end of its list
+    async fn create_item(&self, new_item: NewTodoItem) -> Result<TodoItem>;
+
+    /// Create an item at `position` in its list (at the end if past the last
+    /// one), making room in the ordering if needed
+    async fn create_item_at(&self, new_item: NewTodoItem, position: usize) -> Result<TodoItem>;
+
+    /// Get the items of a list, sorted by ordering
+    async fn get_items(&self, list_id: i64) -> Result<Vec<TodoItem>>;
+
+    /// Get an item by id
+    async fn get_item(&self, id: i64) -> Result<Option<TodoItem>>;
+
+    /// Save the fields of an item (but its list and ordering)
+    async fn update_item(&self, item: &TodoItem) -> Result<()>;
+
+    /// Delete an item
+    async fn delete_item(&self, id: i64) -> Result<()>;
+
+    /// Move an item by `offset` positions within its list (up if negative).
+    /// Returns the new ordering of the item
+    async fn move_item(&self, id: i64, offset: i64) -> Result<i64>;
+
+    /// Move an item to `position` in another list (at the end if past the last
+    /// one), in one atomic step keeping its uid, creation time and other
+    /// fields. Returns the moved item
+    async fn move_item_to_list(&self, id: i64, list_id: i64, position: usize) -> Result<TodoItem>;
+
+    /// Set the `updated_at` of a list to `now` if it is still `updated_at`, in
+    /// one atomic step, so that only one of concurrent writers expecting the
+    /// same version goes on. Returns whether it was (false if the list changed
+    /// since or is gone)
+    async fn claim_list(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool>;
+
+    /// Same as [`TodoStore::claim_list`] for an item
+    async fn claim_item(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool>;
+
+    /// Give an item the uid and creation time it had in another app, when it
+    /// is imported from it. The uid must not be used by another item
+    async fn set_item_origin(&self, id: i64, uid: &str, created_at: DateTime<Utc>) -> Result<()>;
+
+    /// Find lists and items whose orderings are duplicated or not positive (as
+    /// left by older versions) and space them out again, keeping their order.
+    /// Returns the number of rows that were changed
+    async fn repair_orderings(&self) -> Result<usize>;
+
+    /// SQLite pool behind the store, for the features that work on the database
+    /// directly (sync, change log and encryption)
+    fn sqlite_pool(&self) -> Option<&SqlitePool> {
+        None
+    }
+}
+
+/// Open the store of a connection string:
+/// - `sqlite:<path>` for a SQLite database (created if missing)
+/// - `postgres://<user>@<host>/<database>` for a PostgreSQL database, which
+///   can be shared by a team
+/// - `json:<path>` for a plain JSON file (created if missing)
+/// - `memory:<name>` for a store that lives as long as the application
+pub async fn open_store(connection_str: &str) -> Result<Arc<dyn TodoStore>> {
+    let (scheme, location) = connection_str
+        .split_once(':')
+        .with_context(|| format!("Missing scheme in connection string '{}'", connection_str))?;
+
+    match scheme {
+        "sqlite" => Ok(Arc::new(init_db(connection_str).await?)),
+        "postgres" | "postgresql" => Ok(Arc::new(PostgresStore::connect(connection_str).await?)),
+        "json" => Ok(Arc::new(JsonStore::open(location)?)),
+        "memory" => Ok(memory_stores()
+            .lock()
+            .unwrap()
+            .entry(connection_str.to_string())
+            .or_insert_with(|| Arc::new(MemoryStore::default()))
+            .clone()),
+        _ => anyhow::bail!(
+            "Unsupported connection string '{}' (use sqlite:, postgres://, json: or memory:)",
+            connection_str
+        ),
+    }
+}
+
+/// In-memory stores opened in this session, by connection string, so that
+/// switching back to one finds its lists again
+fn memory_stores() -> &'static Mutex<HashMap<String, Arc<MemoryStore>>> {
+    static STORES: OnceLock<Mutex<HashMap<String, Arc<MemoryStore>>>> = OnceLock::new();
+    STORES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Space left between the orderings of consecutive rows, so that a row can
+/// be moved between two others without renumbering them
+pub const ORDERING_GAP: i64 = 1024;
+
+/// Position of a row among its siblings (sorted by ordering) after moving it by
+/// `offset`, clamped to the first and last positions
+pub(crate) fn target_position(position: usize, offset: i64, len: usize) -> usize {
+    (position as i64 + offset).clamp(0, len.saturating_sub(1) as i64) as usize
+}
+
+/// Plan the insertion of a row at `position` among its siblings, given as
+/// `(id, ordering)`. The row gets an ordering halfway between its neighbours;
+/// only when there's no room left are the siblings spaced out again.
+///
+/// Returns the ordering of the new row and the `(id, ordering)` writes to apply
+/// to the siblings beforehand, in order (see [`ordering_writes`])
+pub(crate) fn plan_insert(siblings: &[(i64, i64)], position: usize) -> (i64, Vec<(i64, i64)>) {
+    let mut siblings = siblings.to_vec();
+    siblings.sort_by_key(|&(id, ordering)| (ordering, id));
+
+    let (ordering, updates) = insertion(&siblings, position);
+    (ordering, ordering_writes(&siblings, updates))
+}
+
+/// Plan the move of the row `id` by `offset` positions among its siblings,
+/// given as `(id, ordering)`, like the insertion of the row at its new position.
+///
+/// Returns the new ordering of the row and the `(id, ordering)` writes to apply,
+/// in order (see [`ordering_writes`])
+pub(crate) fn plan_move(
+    siblings: &[(i64, i64)],
+    id: i64,
+    offset: i64,
+) -> Result<(i64, Vec<(i64, i64)>)> {
+    let mut others = siblings.to_vec();
+    others.sort_by_key(|&(id, ordering)| (ordering, id));
+
+    let position = others
+        .iter()
+        .position(|&(row_id, _)| row_id == id)
+        .with_context(|| format!("No row with id {}", id))?;
+    let target = target_position(position, offset, others.len());
+    let (_, ordering) = others.remove(position);
+    if target == position {
+        return Ok((ordering, Vec::new()));
+    }
+
+    let (new_ordering, mut updates) = insertion(&others, target);
+    updates.push((id, new_ordering));
+    Ok((new_ordering, ordering_writes(siblings, updates)))
+}
+
+/// Ordering of a row inserted at `position` among sorted siblings, and the new
+/// orderings of the siblings when they have to be spaced out to make room
+fn insertion(siblings: &[(i64, i64)], position: usize) -> (i64, Vec<(i64, i64)>) {
+    let position = position.min(siblings.len());
+    let before = position.checked_sub(1).map_or(0, |i| siblings[i].1);
+
+    match siblings.get(position) {
+        Some(&(_, after)) if after - before >= 2 => (before + (after - before) / 2, Vec::new()),
+        Some(_) => {
+            // No room between the neighbours: space everything out again
+            let ordering = (position as i64 + 1) * ORDERING_GAP;
+            (ordering, respace(siblings, position))
+        }
+        None => (before + ORDERING_GAP, Vec::new()),
+    }
+}
+
+/// Plan the repair of siblings given as `(id, ordering)`: if two of them share
+/// an ordering or one isn't positive, all of them are spaced out again.
+///
+/// Returns the `(id, ordering)` writes to apply, in order (see [`ordering_writes`])
+pub(crate) fn plan_repair(siblings: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut siblings = siblings.to_vec();
+    siblings.sort_by_key(|&(id, ordering)| (ordering, id));
+
+    let consistent = siblings.first().is_none_or(|&(_, ordering)| ordering > 0)
+        && siblings.windows(2).all(|pair| pair[0].1 < pair[1].1);
+    if consistent {
+        return Vec::new();
+    }
+
+    let updates = respace(&siblings, siblings.len());
+    ordering_writes(&siblings, updates)
+}
+
+/// Orderings of sorted siblings spaced out by [`ORDERING_GAP`], leaving a free
+/// spot at position `hole`, for the rows whose ordering changes
+fn respace(siblings: &[(i64, i64)], hole: usize) -> Vec<(i64, i64)> {
+    siblings
+        .iter()
+        .enumerate()
+        .map(|(i, &(id, _))| {
+            let spot = if i < hole { i + 1 } else { i + 2 };
+            (id, spot as i64 * ORDERING_GAP)
+        })
+        .filter(|update| !siblings.contains(update))
+        .collect()
+}
+
+/// Writes giving rows their new orderings one at a time, without two siblings
+/// ever sharing one: when several rows change, they are first parked on
+/// distinct orderings below those of all siblings
+pub(crate) fn ordering_writes(
+    siblings: &[(i64, i64)],
+    updates: Vec<(i64, i64)>,
+) -> Vec<(i64, i64)> {
+    if updates.len() < 2 {
+        return updates;
+    }
+
+    let lowest = siblings
+        .iter()
+        .map(|&(_, ordering)| ordering)
+        .min()
+        .unwrap_or(0)
+        .min(0);
+    let parked = updates.iter().map(|&(id, _)| (id, lowest - id.abs() - 1));
+    parked
+        .collect::<Vec<_>>()
+        .into_iter()
+        .chain(updates)
+        .collect()
+}
+
+/// Make the given `(key, ordering)` rows strictly increasing and positive,
+/// keeping them sorted by ordering and then key: rows sharing an ordering (like
+/// rows added on two machines) are pushed after each other. Returns the new
+/// ordering of every row
+pub(crate) fn deduplicate_orderings<K: Ord + Clone>(rows: &[(K, i64)]) -> Vec<(K, i64)> {
+    let mut rows = rows.to_vec();
+    rows.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+
+    let mut previous = 0;
+    for (_, ordering) in rows.iter_mut() {
+        *ordering = (*ordering).max(previous + 1);
+        previous = *ordering;
+    }
+    rows
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_plan_move_only_changes_the_moved_row() -> Result<()> {
+        let siblings = [(1, 1024), (2, 2048), (3, 3072)];
+
+        // Between two rows, at the top and at the bottom
+        assert_eq!(plan_move(&siblings, 3, -1)?, (1536, vec![(3, 1536)]));
+        assert_eq!(plan_move(&siblings, 3, -10)?, (512, vec![(3, 512)]));
+        assert_eq!(plan_move(&siblings, 1, 10)?, (4096, vec![(1, 4096)]));
+
+        // Nowhere to go
+        assert_eq!(plan_move(&siblings, 1, -1)?, (1024, vec![]));
+        assert!(plan_move(&siblings, 4, 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_move_respaces_when_there_is_no_room() -> Result<()> {
+        let siblings = [(1, 1), (2, 2), (3, 5)];
+
+        let (ordering, writes) = plan_move(&siblings, 3, -1)?;
+        assert_eq!(ordering, 2048);
+
+        // Changed rows are parked below the others before taking their place
+        assert_eq!(
+            writes,
+            vec![(1, -2), (2, -3), (3, -4), (1, 1024), (2, 3072), (3, 2048)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_insert() {
+        let siblings = [(1, 1024), (2, 2048), (3, 2049)];
+
+        assert_eq!(plan_insert(&siblings, 0), (512, vec![]));
+        assert_eq!(plan_insert(&siblings, 1), (1536, vec![]));
+        assert_eq!(plan_insert(&siblings, 10), (3073, vec![]));
+        assert_eq!(plan_insert(&[], 0), (1024, vec![]));
+
+        // No room between the last two: the last one moves on
+        assert_eq!(plan_insert(&siblings, 2), (3072, vec![(3, 4096)]));
+    }
+
+    #[test]
+    fn test_plan_repair() {
+        assert_eq!(plan_repair(&[(1, 1024), (2, 1500)]), vec![]);
+        assert_eq!(plan_repair(&[(1, 0)]), vec![(1, 1024)]);
+        assert_eq!(
+            plan_repair(&[(1, 1024), (2, 3), (3, 3)]),
+            vec![(2, -3), (3, -4), (1, -2), (2, 1024), (3, 2048), (1, 3072)]
+        );
+    }
+
+    #[test]
+    fn test_deduplicate_orderings() {
+        let rows = [("b", 2), ("a", 2), ("c", 3), ("d", -1)];
+        assert_eq!(
+            deduplicate_orderings(&rows),
+            vec![("d", 1), ("a", 2), ("b", 3), ("c", 4)]
+        );
+    }
+}
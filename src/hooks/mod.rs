@@ -0,0 +1,305 @@
+//! Hooks: commands of the user run when lists and items change, e.g. to post
+//! to a webhook when a list is done or to append completed items to a worklog.
+//!
+//! Hooks are configured in the `[hooks]` table of judo.toml, with a list of
+//! commands per event. Each command is run with the shell, gets the event as
+//! JSON on its standard input (and its name in `JUDO_EVENT`), and is killed if
+//! it runs longer than the timeout. Commands run in the background so that they
+//! never hold up the UI; their failures are kept in the hook log.
+
+pub mod store;
+
+pub use store::HookedStore;
+
+use crate::db::models::{TodoItem, TodoList};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Seconds a hook may run before being killed, by default
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Number of failures kept in the hook log
+const LOG_SIZE: usize = 100;
+
+/// Configuration of the hooks, in the `[hooks]` table of judo.toml
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct HooksConfig {
+    /// Seconds a hook may run before being killed
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_item_created: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_item_done: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_item_undone: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_item_deleted: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_list_created: Vec<String>,
+    /// Run when the last item of a list not done yet is done
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_list_done: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_list_deleted: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_db_switched: Vec<String>,
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            on_item_created: Vec::new(),
+            on_item_done: Vec::new(),
+            on_item_undone: Vec::new(),
+            on_item_deleted: Vec::new(),
+            on_list_created: Vec::new(),
+            on_list_done: Vec::new(),
+            on_list_deleted: Vec::new(),
+            on_db_switched: Vec::new(),
+        }
+    }
+}
+
+impl HooksConfig {
+    /// Commands to run for an event
+    pub fn commands(&self, kind: HookKind) -> &[String] {
+        match kind {
+            HookKind::ItemCreated => &self.on_item_created,
+            HookKind::ItemDone => &self.on_item_done,
+            HookKind::ItemUndone => &self.on_item_undone,
+            HookKind::ItemDeleted => &self.on_item_deleted,
+            HookKind::ListCreated => &self.on_list_created,
+            HookKind::ListDone => &self.on_list_done,
+            HookKind::ListDeleted => &self.on_list_deleted,
+            HookKind::DbSwitched => &self.on_db_switched,
+        }
+    }
+}
+
+/// Events hooks can be run for
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookKind {
+    ItemCreated,
+    ItemDone,
+    ItemUndone,
+    ItemDeleted,
+    ListCreated,
+    ListDone,
+    ListDeleted,
+    DbSwitched,
+}
+
+impl HookKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookKind::ItemCreated => "item_created",
+            HookKind::ItemDone => "item_done",
+            HookKind::ItemUndone => "item_undone",
+            HookKind::ItemDeleted => "item_deleted",
+            HookKind::ListCreated => "list_created",
+            HookKind::ListDone => "list_done",
+            HookKind::ListDeleted => "list_deleted",
+            HookKind::DbSwitched => "db_switched",
+        }
+    }
+}
+
+/// Event given to hooks on their standard input
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HookEvent {
+    pub event: HookKind,
+    pub db: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list: Option<TodoList>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<TodoItem>,
+    pub time: DateTime<Utc>,
+}
+
+impl HookEvent {
+    pub fn new(event: HookKind, db: &str, list: Option<TodoList>, item: Option<TodoItem>) -> Self {
+        Self {
+            event,
+            db: db.to_string(),
+            list,
+            item,
+            time: Utc::now(),
+        }
+    }
+}
+
+/// Hook that failed, as shown in the hook log
+#[derive(Clone, Debug, PartialEq)]
+pub struct HookFailure {
+    pub time: DateTime<Utc>,
+    pub event: HookKind,
+    pub command: String,
+    pub error: String,
+}
+
+/// Runner of the hooks of the config
+pub struct Hooks {
+    config: HooksConfig,
+    /// Latest failures, the most recent last
+    log: Arc<Mutex<VecDeque<HookFailure>>>,
+}
+
+impl Hooks {
+    pub fn new(config: HooksConfig) -> Self {
+        Self {
+            config,
+            log: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Whether any command is configured for an event
+    pub fn wants(&self, kind: HookKind) -> bool {
+        !self.config.commands(kind).is_empty()
+    }
+
+    /// Run the commands of an event in the background. Must be called within a
+    /// tokio runtime
+    pub fn fire(&self, event: HookEvent) {
+        let commands = self.config.commands(event.event);
+        if commands.is_empty() {
+            return;
+        }
+        let payload = match serde_json::to_vec(&event) {
+            Ok(payload) => Arc::new(payload),
+            Err(e) => {
+                eprintln!("Failed to serialize hook event: {}", e);
+                return;
+            }
+        };
+
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        for command in commands {
+            let command = command.clone();
+            let payload = payload.clone();
+            let log = self.log.clone();
+            let kind = event.event;
+            tokio::spawn(async move {
+                if let Err(e) = run_hook(&command, kind, &payload, timeout).await {
+                    let mut log = log.lock().unwrap();
+                    if log.len() == LOG_SIZE {
+                        log.pop_front();
+                    }
+                    log.push_back(HookFailure {
+                        time: Utc::now(),
+                        event: kind,
+                        command,
+                        error: format!("{:#}", e),
+                    });
+                }
+            });
+        }
+    }
+
+    /// Latest failures, the most recent first
+    pub fn failures(&self) -> Vec<HookFailure> {
+        self.log.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    pub fn clear_failures(&self) {
+        self.log.lock().unwrap().clear();
+    }
+}
+
+/// Run a hook with the shell, the event on its standard input
+async fn run_hook(command: &str, kind: HookKind, payload: &[u8], timeout: Duration) -> Result<()> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .env("JUDO_EVENT", kind.as_str())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to run '{}'", command))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // Hooks don't have to read the event
+        let _ = stdin.write_all(payload).await;
+    }
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow::anyhow!("Timed out after {:?}", timeout))??;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.trim().lines().last() {
+            Some(line) => anyhow::bail!("{}: {}", output.status, line),
+            None => anyhow::bail!("{}", output.status),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_from_toml() {
+        let config: HooksConfig = toml::from_str(
+            r#"
+            on_item_done = ["cat >> ~/worklog.jsonl"]
+            on_list_done = ["curl -d @- https://example.com/hook"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.timeout_secs, DEFAULT_TIMEOUT_SECS);
+        assert_eq!(config.commands(HookKind::ItemDone).len(), 1);
+        assert!(config.commands(HookKind::ItemCreated).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_hook() -> Result<()> {
+        let timeout = Duration::from_secs(5);
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("event");
+        let command = format!(
+            "cat > '{}'; echo $JUDO_EVENT >> '{}'",
+            path.display(),
+            path.display()
+        );
+        run_hook(&command, HookKind::ItemDone, b"{}", timeout).await?;
+        assert_eq!(std::fs::read_to_string(&path)?, "{}item_done\n");
+
+        let error = run_hook("echo oops >&2; exit 3", HookKind::ItemDone, b"", timeout)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().ends_with("oops"), "{}", error);
+
+        let error = run_hook(
+            "sleep 5",
+            HookKind::ItemDone,
+            b"",
+            Duration::from_millis(50),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Timed out after 50ms");
+        Ok(())
+    }
+}
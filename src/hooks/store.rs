@@ -0,0 +1,178 @@
+use crate::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use crate::db::store::TodoStore;
+use crate::hooks::{HookEvent, HookKind, Hooks};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+/// Store firing the hooks of the changes made through it, whatever part of the
+/// UI makes them
+pub struct HookedStore {
+    inner: Arc<dyn TodoStore>,
+    hooks: Arc<Hooks>,
+    db_name: String,
+}
+
+impl HookedStore {
+    pub fn new(inner: Arc<dyn TodoStore>, hooks: Arc<Hooks>, db_name: &str) -> Self {
+        Self {
+            inner,
+            hooks,
+            db_name: db_name.to_string(),
+        }
+    }
+
+    fn fire(&self, kind: HookKind, list: Option<TodoList>, item: Option<TodoItem>) {
+        self.hooks
+            .fire(HookEvent::new(kind, &self.db_name, list, item));
+    }
+
+    /// Fire an event of an item, with its list
+    async fn fire_item(&self, kind: HookKind, item: TodoItem) -> Result<()> {
+        if self.hooks.wants(kind) {
+            let list = self.inner.get_list(item.list_id).await?;
+            self.fire(kind, list, Some(item));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TodoStore for HookedStore {
+    async fn create_list(&self, new_list: NewTodoList) -> Result<TodoList> {
+        let list = self.inner.create_list(new_list).await?;
+        self.fire(HookKind::ListCreated, Some(list.clone()), None);
+        Ok(list)
+    }
+
+    async fn create_list_at(&self, new_list: NewTodoList, position: usize) -> Result<TodoList> {
+        let list = self.inner.create_list_at(new_list, position).await?;
+        self.fire(HookKind::ListCreated, Some(list.clone()), None);
+        Ok(list)
+    }
+
+    async fn get_lists(&self) -> Result<Vec<TodoList>> {
+        self.inner.get_lists().await
+    }
+
+    async fn get_list(&self, id: i64) -> Result<Option<TodoList>> {
+        self.inner.get_list(id).await
+    }
+
+    async fn update_list(&self, list: &TodoList) -> Result<()> {
+        self.inner.update_list(list).await
+    }
+
+    async fn delete_list(&self, id: i64) -> Result<()> {
+        let list = match self.hooks.wants(HookKind::ListDeleted) {
+            true => self.inner.get_list(id).await?,
+            false => None,
+        };
+        self.inner.delete_list(id).await?;
+        if list.is_some() {
+            self.fire(HookKind::ListDeleted, list, None);
+        }
+        Ok(())
+    }
+
+    async fn move_list(&self, id: i64, offset: i64) -> Result<i64> {
+        self.inner.move_list(id, offset).await
+    }
+
+    async fn create_item(&self, new_item: NewTodoItem) -> Result<TodoItem> {
+        let item = self.inner.create_item(new_item).await?;
+        self.fire_item(HookKind::ItemCreated, item.clone()).await?;
+        Ok(item)
+    }
+
+    async fn create_item_at(&self, new_item: NewTodoItem, position: usize) -> Result<TodoItem> {
+        let item = self.inner.create_item_at(new_item, position).await?;
+        self.fire_item(HookKind::ItemCreated, item.clone()).await?;
+        Ok(item)
+    }
+
+    async fn get_items(&self, list_id: i64) -> Result<Vec<TodoItem>> {
+        self.inner.get_items(list_id).await
+    }
+
+    async fn get_item(&self, id: i64) -> Result<Option<TodoItem>> {
+        self.inner.get_item(id).await
+    }
+
+    async fn update_item(&self, item: &TodoItem) -> Result<()> {
+        let wanted = [HookKind::ItemDone, HookKind::ItemUndone, HookKind::ListDone];
+        let was_done = match wanted.into_iter().any(|kind| self.hooks.wants(kind)) {
+            true => self.inner.get_item(item.id).await?.map(|i| i.is_done),
+            false => None,
+        };
+        self.inner.update_item(item).await?;
+
+        match (was_done, item.is_done) {
+            (Some(false), true) => {
+                self.fire_item(HookKind::ItemDone, item.clone()).await?;
+                if self.hooks.wants(HookKind::ListDone) {
+                    let items = self.inner.get_items(item.list_id).await?;
+                    if items.iter().all(|i| i.is_done) {
+                        let list = self.inner.get_list(item.list_id).await?;
+                        self.fire(HookKind::ListDone, list, None);
+                    }
+                }
+            }
+            (Some(true), false) => self.fire_item(HookKind::ItemUndone, item.clone()).await?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn delete_item(&self, id: i64) -> Result<()> {
+        let item = match self.hooks.wants(HookKind::ItemDeleted) {
+            true => self.inner.get_item(id).await?,
+            false => None,
+        };
+        self.inner.delete_item(id).await?;
+        if let Some(item) = item {
+            self.fire_item(HookKind::ItemDeleted, item).await?;
+        }
+        Ok(())
+    }
+
+    async fn move_item(&self, id: i64, offset: i64) -> Result<i64> {
+        self.inner.move_item(id, offset).await
+    }
+
+    async fn move_item_to_list(&self, id: i64, list_id: i64, position: usize) -> Result<TodoItem> {
+        self.inner.move_item_to_list(id, list_id, position).await
+    }
+
+    async fn claim_list(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        self.inner.claim_list(id, updated_at, now).await
+    }
+
+    async fn claim_item(
+        &self,
+        id: i64,
+        updated_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        self.inner.claim_item(id, updated_at, now).await
+    }
+
+    async fn set_item_origin(&self, id: i64, uid: &str, created_at: DateTime<Utc>) -> Result<()> {
+        self.inner.set_item_origin(id, uid, created_at).await
+    }
+
+    async fn repair_orderings(&self) -> Result<usize> {
+        self.inner.repair_orderings().await
+    }
+
+    fn sqlite_pool(&self) -> Option<&SqlitePool> {
+        self.inner.sqlite_pool()
+    }
+}
@@ -1,3 +1,11 @@
+pub mod api;
 pub mod app;
+pub mod cli;
+#[cfg(unix)]
+pub mod ctl;
 pub mod db;
+pub mod hooks;
+pub mod remind;
+pub mod script;
+pub mod sync;
 pub mod ui;
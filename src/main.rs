@@ -1,25 +1,52 @@
 //! Judo - A terminal-based todo list application
 
+use clap::Parser;
 use color_eyre::Result;
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+};
+use crossterm::execute;
 use judo::app::App;
+use judo::cli::Cli;
 
 /// Application entry point
 ///
-/// Initializes the terminal, creates the application instance, runs the main loop,
+/// Runs the given subcommand if any. Otherwise initializes the terminal, creates the application instance, runs the main loop,
 /// and properly restores the terminal on exit.
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Set the terminal up
+    if let Some(command) = Cli::parse().command {
+        return command
+            .run()
+            .await
+            .map_err(|e| color_eyre::eyre::eyre!("{:#}", e));
+    }
+
+    // Set the terminal up, restored when the guard is dropped (even on panic)
     let mut terminal = ratatui::init();
+    let _guard = TerminalGuard;
+
+    // Capture mouse events (clicks, scroll and drag), and pasted text as a whole
+    execute!(std::io::stdout(), EnableMouseCapture, EnableBracketedPaste)?;
 
     // Set up the app
     let app = App::new().await;
 
     // Create and run the app
-    let app_result = app.run(&mut terminal).await;
+    app.run(&mut terminal).await
+}
 
-    // Restore terminal to original state
-    ratatui::restore();
+/// Restores the terminal to its original state when dropped
+struct TerminalGuard;
 
-    app_result
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // Restore the terminal even if the events can't be released
+        let _ = execute!(
+            std::io::stdout(),
+            DisableMouseCapture,
+            DisableBracketedPaste
+        );
+        ratatui::restore();
+    }
 }
@@ -0,0 +1,450 @@
+//! Reminders of items whose due date is approaching or has passed.
+//!
+//! The databases of the config are scanned periodically, either by the
+//! long-running `judo remind` command or by a background task of the TUI. Each
+//! item not done yet is reminded once when its due date gets closer than the
+//! configured lead time, and once more when it passes. Reminders are delivered
+//! by a [`Notifier`]: desktop notifications over D-Bus, a terminal bell, or a
+//! command of the user.
+
+pub mod notifier;
+
+pub use notifier::{BellNotifier, ChannelNotifier, CommandNotifier, DesktopNotifier, Notifier};
+
+use crate::db::config::Config;
+use crate::db::crypto;
+use crate::db::models::{TodoItem, TodoList};
+use crate::db::store::{TodoStore, open_store};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Minutes before the due date when items are reminded, by default
+const DEFAULT_LEAD_MINUTES: i64 = 15;
+
+/// Seconds between two scans of the databases, by default
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+/// Configuration of the reminders, in the `[remind]` table of judo.toml
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RemindConfig {
+    /// Minutes before the due date when items are reminded
+    #[serde(default = "default_lead_minutes")]
+    pub lead_minutes: i64,
+    /// Seconds between two scans of the databases
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// How `judo remind` delivers reminders
+    #[serde(default)]
+    pub notifier: NotifierKind,
+    /// Command run for each reminder by the "command" notifier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Also scan the databases while the TUI runs, showing reminders in it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tui: bool,
+}
+
+fn default_lead_minutes() -> i64 {
+    DEFAULT_LEAD_MINUTES
+}
+
+fn default_interval_secs() -> u64 {
+    DEFAULT_INTERVAL_SECS
+}
+
+impl Default for RemindConfig {
+    fn default() -> Self {
+        Self {
+            lead_minutes: DEFAULT_LEAD_MINUTES,
+            interval_secs: DEFAULT_INTERVAL_SECS,
+            notifier: NotifierKind::default(),
+            command: None,
+            tui: false,
+        }
+    }
+}
+
+/// Notifiers available in judo.toml
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifierKind {
+    /// Desktop notification through the freedesktop D-Bus interface
+    #[default]
+    Desktop,
+    /// Terminal bell, with the reminder printed on the standard output
+    Bell,
+    /// Command of the user, with the reminder in environment variables
+    Command,
+}
+
+impl RemindConfig {
+    /// Notifier configured for `judo remind`
+    pub fn notifier(&self) -> Result<Arc<dyn Notifier>> {
+        Ok(match self.notifier {
+            NotifierKind::Desktop => Arc::new(DesktopNotifier),
+            NotifierKind::Bell => Arc::new(BellNotifier),
+            NotifierKind::Command => {
+                let command = self
+                    .command
+                    .clone()
+                    .with_context(|| "The command notifier needs a `command` in [remind]")?;
+                Arc::new(CommandNotifier::new(command))
+            }
+        })
+    }
+}
+
+/// Source of the current time, replaced by a fake clock in tests
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock of the system
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Whether the due date of an item is approaching or has passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReminderKind {
+    Upcoming,
+    Overdue,
+}
+
+/// Reminder of an item due soon
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    pub db_name: String,
+    pub list_name: String,
+    pub item_name: String,
+    pub item_uid: String,
+    pub due_date: DateTime<Utc>,
+    pub kind: ReminderKind,
+}
+
+impl Reminder {
+    /// One-line summary, e.g. "Milk is due at 14:30"
+    pub fn summary(&self) -> String {
+        let due = self.due_date.with_timezone(&Local);
+        match self.kind {
+            ReminderKind::Upcoming => {
+                format!("{} is due at {}", self.item_name, due.format("%H:%M"))
+            }
+            ReminderKind::Overdue => format!("{} is overdue", self.item_name),
+        }
+    }
+
+    /// Where the item is and when it is due
+    pub fn body(&self) -> String {
+        let due = self.due_date.with_timezone(&Local);
+        format!(
+            "{} ({}), due {}",
+            self.list_name,
+            self.db_name,
+            due.format("%Y-%m-%d %H:%M")
+        )
+    }
+}
+
+/// Periodic scan of databases, sending each reminder once
+pub struct Reminders {
+    /// Databases to scan, by name
+    dbs: Vec<(String, Arc<dyn TodoStore>)>,
+    notifier: Arc<dyn Notifier>,
+    clock: Arc<dyn Clock>,
+    /// How long before their due date items are reminded
+    lead: Duration,
+    /// Reminders already sent, by database, item uid, due date and kind. An
+    /// item is reminded again if its due date changes
+    sent: HashSet<(String, String, DateTime<Utc>, ReminderKind)>,
+}
+
+impl Reminders {
+    pub fn new(
+        dbs: Vec<(String, Arc<dyn TodoStore>)>,
+        notifier: Arc<dyn Notifier>,
+        clock: Arc<dyn Clock>,
+        lead_minutes: i64,
+    ) -> Self {
+        Self {
+            dbs,
+            notifier,
+            clock,
+            lead: Duration::minutes(lead_minutes),
+            sent: HashSet::new(),
+        }
+    }
+
+    /// Open the databases of the config that can be scanned. Encrypted
+    /// databases are skipped, since the names of their items can't be read
+    /// without the passphrase
+    pub async fn open_databases(config: &Config) -> Vec<(String, Arc<dyn TodoStore>)> {
+        let mut dbs = Vec::new();
+        for db_config in &config.dbs {
+            let store = match open_store(&db_config.connection_str).await {
+                Ok(store) => store,
+                Err(e) => {
+                    eprintln!("Skipping database '{}': {:#}", db_config.name, e);
+                    continue;
+                }
+            };
+
+            if let Some(pool) = store.sqlite_pool()
+                && crypto::is_encrypted(pool).await.unwrap_or(false)
+            {
+                eprintln!("Skipping encrypted database '{}'", db_config.name);
+                continue;
+            }
+            dbs.push((db_config.name.clone(), store));
+        }
+        dbs
+    }
+
+    /// Reminders due now in a database that haven't been sent yet
+    async fn due_in(&self, db_name: &str, store: &dyn TodoStore) -> Result<Vec<Reminder>> {
+        let now = self.clock.now();
+        let mut reminders = Vec::new();
+
+        for list in TodoList::get_all(store).await? {
+            for item in TodoItem::get_by_list_id(store, list.id).await? {
+                let Some(due_date) = item.due_date else {
+                    continue;
+                };
+                if item.is_done || due_date > now + self.lead {
+                    continue;
+                }
+
+                let kind = if due_date <= now {
+                    ReminderKind::Overdue
+                } else {
+                    ReminderKind::Upcoming
+                };
+                let key = (db_name.to_string(), item.uid.clone(), due_date, kind);
+                if self.sent.contains(&key) {
+                    continue;
+                }
+
+                reminders.push(Reminder {
+                    db_name: db_name.to_string(),
+                    list_name: list.name.clone(),
+                    item_name: item.name,
+                    item_uid: item.uid,
+                    due_date,
+                    kind,
+                });
+            }
+        }
+        Ok(reminders)
+    }
+
+    /// Scan every database once and send the new reminders. Returns the number
+    /// of reminders sent
+    pub async fn tick(&mut self) -> usize {
+        let mut sent = 0;
+        for (db_name, store) in &self.dbs {
+            let reminders = match self.due_in(db_name, &**store).await {
+                Ok(reminders) => reminders,
+                Err(e) => {
+                    eprintln!("Failed to scan database '{}': {:#}", db_name, e);
+                    continue;
+                }
+            };
+
+            for reminder in reminders {
+                // Failed reminders are retried on the next scan
+                if let Err(e) = self.notifier.notify(&reminder) {
+                    eprintln!("Failed to send reminder: {:#}", e);
+                    continue;
+                }
+                self.sent.insert((
+                    reminder.db_name,
+                    reminder.item_uid,
+                    reminder.due_date,
+                    reminder.kind,
+                ));
+                sent += 1;
+            }
+        }
+        sent
+    }
+
+    /// Scan the databases every `interval_secs` seconds, forever
+    pub async fn run(mut self, interval_secs: u64) {
+        let interval = std::time::Duration::from_secs(interval_secs.max(1));
+        loop {
+            self.tick().await;
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::memory_store::MemoryStore;
+    use crate::db::models::{NewTodoItem, NewTodoList};
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    /// Notifier keeping the reminders it is given
+    #[derive(Default)]
+    struct MockNotifier {
+        reminders: Mutex<Vec<Reminder>>,
+    }
+
+    impl Notifier for MockNotifier {
+        fn notify(&self, reminder: &Reminder) -> Result<()> {
+            self.reminders.lock().unwrap().push(reminder.clone());
+            Ok(())
+        }
+    }
+
+    impl MockNotifier {
+        fn take(&self) -> Vec<(String, ReminderKind)> {
+            let reminders = std::mem::take(&mut *self.reminders.lock().unwrap());
+            reminders
+                .into_iter()
+                .map(|r| (r.item_name, r.kind))
+                .collect()
+        }
+    }
+
+    /// Clock set by the test
+    struct FakeClock(Mutex<DateTime<Utc>>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    impl FakeClock {
+        fn set(&self, hour: u32, minute: u32) {
+            *self.0.lock().unwrap() = at(hour, minute);
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 5, 14, hour, minute, 0).unwrap()
+    }
+
+    /// Groceries with Milk due at 12:00, Bread due at 18:00, Eggs done and due
+    /// at 9:00, and Butter without a due date
+    async fn groceries() -> Result<Arc<dyn TodoStore>> {
+        let store: Arc<dyn TodoStore> = Arc::new(MemoryStore::default());
+        let list = TodoList::create(
+            &*store,
+            NewTodoList {
+                name: "Groceries".to_string(),
+            },
+        )
+        .await?;
+
+        for (name, due_date) in [
+            ("Milk", Some(at(12, 0))),
+            ("Bread", Some(at(18, 0))),
+            ("Eggs", Some(at(9, 0))),
+            ("Butter", None),
+        ] {
+            let mut item = TodoItem::create(
+                &*store,
+                NewTodoItem {
+                    list_id: list.id,
+                    name: name.to_string(),
+                    priority: None,
+                    due_date,
+                },
+            )
+            .await?;
+            if name == "Eggs" {
+                item.toggle_done(&*store).await?;
+            }
+        }
+        Ok(store)
+    }
+
+    #[tokio::test]
+    async fn test_items_are_reminded_once_before_and_once_after() -> Result<()> {
+        let notifier = Arc::new(MockNotifier::default());
+        let clock = Arc::new(FakeClock(Mutex::new(at(11, 0))));
+        let dbs = vec![("dojo".to_string(), groceries().await?)];
+        let mut reminders = Reminders::new(dbs, notifier.clone(), clock.clone(), 15);
+
+        // Nothing is due within 15 minutes
+        assert_eq!(reminders.tick().await, 0);
+
+        clock.set(11, 50);
+        reminders.tick().await;
+        assert_eq!(
+            notifier.take(),
+            [("Milk".to_string(), ReminderKind::Upcoming)]
+        );
+
+        // Already reminded
+        clock.set(11, 55);
+        assert_eq!(reminders.tick().await, 0);
+
+        clock.set(12, 0);
+        reminders.tick().await;
+        assert_eq!(
+            notifier.take(),
+            [("Milk".to_string(), ReminderKind::Overdue)]
+        );
+
+        // Items found overdue are only reminded as such
+        clock.set(19, 0);
+        reminders.tick().await;
+        assert_eq!(
+            notifier.take(),
+            [("Bread".to_string(), ReminderKind::Overdue)]
+        );
+        assert_eq!(reminders.tick().await, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rescheduled_items_are_reminded_again() -> Result<()> {
+        let notifier = Arc::new(MockNotifier::default());
+        let clock = Arc::new(FakeClock(Mutex::new(at(12, 30))));
+        let store = groceries().await?;
+        let dbs = vec![("dojo".to_string(), store.clone())];
+        let mut reminders = Reminders::new(dbs, notifier.clone(), clock.clone(), 15);
+
+        reminders.tick().await;
+        assert_eq!(
+            notifier.take(),
+            [("Milk".to_string(), ReminderKind::Overdue)]
+        );
+
+        let list = &TodoList::get_all(&*store).await?[0];
+        let mut milk = TodoItem::get_by_list_id(&*store, list.id).await?.remove(0);
+        milk.update_due_date(&*store, at(13, 0)).await?;
+
+        clock.set(12, 50);
+        reminders.tick().await;
+        assert_eq!(
+            notifier.take(),
+            [("Milk".to_string(), ReminderKind::Upcoming)]
+        );
+
+        // Done items aren't reminded
+        milk.toggle_done(&*store).await?;
+        clock.set(13, 0);
+        assert_eq!(reminders.tick().await, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_notifier_needs_a_command() {
+        let config: RemindConfig = toml::from_str("notifier = \"command\"").unwrap();
+        assert_eq!(config.lead_minutes, DEFAULT_LEAD_MINUTES);
+        assert!(config.notifier().is_err());
+    }
+}
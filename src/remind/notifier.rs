@@ -0,0 +1,170 @@
+use crate::remind::{Reminder, ReminderKind};
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::Command;
+use std::sync::mpsc::Sender;
+
+/// Milliseconds a desktop notification stays on screen
+const DESKTOP_TIMEOUT_MS: u32 = 10_000;
+
+/// Delivery of reminders
+pub trait Notifier: Send + Sync {
+    fn notify(&self, reminder: &Reminder) -> Result<()>;
+}
+
+/// Desktop notifications through the freedesktop notification service, called
+/// over D-Bus with `gdbus`
+pub struct DesktopNotifier;
+
+impl Notifier for DesktopNotifier {
+    fn notify(&self, reminder: &Reminder) -> Result<()> {
+        let output = Command::new("gdbus")
+            .args([
+                "call",
+                "--session",
+                "--dest=org.freedesktop.Notifications",
+                "--object-path=/org/freedesktop/Notifications",
+                "--method=org.freedesktop.Notifications.Notify",
+                "judo",
+                "0",
+                "''",
+                &gvariant_string(&reminder.summary()),
+                &gvariant_string(&reminder.body()),
+                "[]",
+                "{}",
+                &DESKTOP_TIMEOUT_MS.to_string(),
+            ])
+            .output()
+            .with_context(|| "Failed to run gdbus")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Desktop notification failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Quote a string in the text format of GVariant, as expected by `gdbus`
+fn gvariant_string(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Terminal bell, with the reminder printed on the standard output
+pub struct BellNotifier;
+
+impl Notifier for BellNotifier {
+    fn notify(&self, reminder: &Reminder) -> Result<()> {
+        let mut stdout = std::io::stdout();
+        writeln!(stdout, "\x07{} - {}", reminder.summary(), reminder.body())?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// Command run with the shell for each reminder. The reminder is given in the
+/// environment variables `JUDO_DB`, `JUDO_LIST`, `JUDO_ITEM`, `JUDO_DUE` (RFC
+/// 3339), `JUDO_OVERDUE` ("1" or "0") and `JUDO_SUMMARY`
+pub struct CommandNotifier {
+    command: String,
+}
+
+impl CommandNotifier {
+    pub fn new(command: String) -> Self {
+        Self { command }
+    }
+}
+
+impl Notifier for CommandNotifier {
+    fn notify(&self, reminder: &Reminder) -> Result<()> {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let overdue = reminder.kind == ReminderKind::Overdue;
+
+        let status = Command::new(shell)
+            .args([flag, &self.command])
+            .env("JUDO_DB", &reminder.db_name)
+            .env("JUDO_LIST", &reminder.list_name)
+            .env("JUDO_ITEM", &reminder.item_name)
+            .env("JUDO_DUE", reminder.due_date.to_rfc3339())
+            .env("JUDO_OVERDUE", if overdue { "1" } else { "0" })
+            .env("JUDO_SUMMARY", reminder.summary())
+            .status()
+            .with_context(|| format!("Failed to run '{}'", self.command))?;
+
+        if !status.success() {
+            anyhow::bail!("'{}' failed with {}", self.command, status);
+        }
+        Ok(())
+    }
+}
+
+/// Reminders sent to the TUI, which rings the bell and shows them as toasts
+pub struct ChannelNotifier {
+    sender: Sender<Reminder>,
+}
+
+impl ChannelNotifier {
+    pub fn new(sender: Sender<Reminder>) -> Self {
+        Self { sender }
+    }
+}
+
+impl Notifier for ChannelNotifier {
+    fn notify(&self, reminder: &Reminder) -> Result<()> {
+        self.sender
+            .send(reminder.clone())
+            .with_context(|| "The TUI is no longer running")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn milk() -> Reminder {
+        Reminder {
+            db_name: "dojo".to_string(),
+            list_name: "Groceries".to_string(),
+            item_name: "Rock 'n' roll milk".to_string(),
+            item_uid: "0123".to_string(),
+            due_date: Utc.with_ymd_and_hms(2026, 5, 14, 12, 0, 0).unwrap(),
+            kind: ReminderKind::Overdue,
+        }
+    }
+
+    #[test]
+    fn test_gvariant_string_escapes_quotes() {
+        assert_eq!(gvariant_string(r"it's \o/"), r"'it\'s \\o/'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_notifier_passes_the_reminder() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("reminder");
+        let command = format!(
+            "printf '%s|%s|%s|%s' \"$JUDO_DB\" \"$JUDO_ITEM\" \"$JUDO_DUE\" \"$JUDO_OVERDUE\" > '{}'",
+            path.display()
+        );
+
+        CommandNotifier::new(command).notify(&milk())?;
+        assert_eq!(
+            std::fs::read_to_string(&path)?,
+            "dojo|Rock 'n' roll milk|2026-05-14T12:00:00+00:00|1"
+        );
+
+        assert!(
+            CommandNotifier::new("exit 3".to_string())
+                .notify(&milk())
+                .is_err()
+        );
+        Ok(())
+    }
+}
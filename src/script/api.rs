@@ -0,0 +1,439 @@
+//! Functions and constants scripts can use.
+//!
+//! Lists and items are object maps with the fields of their JSON form (`id`,
+//! `name`, `is_done`, `priority`, `due_date`...); a map changed by a script
+//! is saved with `update_item` or `update_list`.
+//!
+//! | Function                      | Returns                                   |
+//! |-------------------------------|-------------------------------------------|
+//! | `lists()`                     | All the lists                             |
+//! | `list(name)`                  | The list with this name, or `()`          |
+//! | `items(list)`                 | The items of a list                       |
+//! | `create_list(name)`           | The new list, at the end                  |
+//! | `create_item(list, name)`     | The new item, at the end of the list      |
+//! | `update_list(list)`           | The list, saved                           |
+//! | `update_item(item)`           | The item, saved                           |
+//! | `move_list(list, by)`         | The list, moved by positions (up if < 0)  |
+//! | `move_item(item, by)`         | The item, moved by positions (up if < 0)  |
+//! | `move_to_list(item, list)`    | The item, moved to the end of another list|
+//! | `delete_list(list)`           |                                           |
+//! | `delete_item(item)`           |                                           |
+//! | `notify(text)` or `print(..)` | Shows the text once the script is over    |
+//! | `prompt(question[, default])` | The text typed, or `()` if cancelled      |
+//! | `select(title, options)`      | The option chosen, or `()` if cancelled   |
+//!
+//! The constants `DB`, `SELECTED_LIST` and `SELECTED_ITEM` hold the name of the
+//! current database and the list and item selected when the script started
+//! (or `()`).
+
+use crate::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use crate::db::store::TodoStore;
+use crate::script::{ScriptContext, ScriptRequest};
+use chrono::Utc;
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, INT, Map, Scope};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Sender};
+use tokio::runtime::Handle;
+
+/// Operations a script may run before being stopped, so that an endless loop
+/// doesn't run forever
+const MAX_OPERATIONS: u64 = 50_000_000;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Run a script to its end, returning its error if any
+pub fn run(
+    source: &str,
+    store: Arc<dyn TodoStore>,
+    runtime: Handle,
+    ui: Sender<ScriptRequest>,
+    context: ScriptContext,
+) -> Result<(), String> {
+    let engine = engine(store, runtime, ui);
+    let mut scope = Scope::new();
+    scope.push_constant("DB", context.db);
+    scope.push_constant(
+        "SELECTED_LIST",
+        optional(context.selected_list).map_err(|e| e.to_string())?,
+    );
+    scope.push_constant(
+        "SELECTED_ITEM",
+        optional(context.selected_item).map_err(|e| e.to_string())?,
+    );
+    engine
+        .run_with_scope(&mut scope, source)
+        .map_err(|e| e.to_string())
+}
+
+/// Engine with the functions of the API
+pub fn engine(store: Arc<dyn TodoStore>, runtime: Handle, ui: Sender<ScriptRequest>) -> Engine {
+    let mut engine = Engine::new();
+    // Scripts only get the API: no loading of other files
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(64);
+    engine.set_max_expr_depths(64, 64);
+
+    // Printing to the terminal would garble the UI
+    let printer = ui.clone();
+    engine.on_print(move |text| {
+        let _ = printer.send(ScriptRequest::Notify(text.to_string()));
+    });
+    let debugger = ui.clone();
+    engine.on_debug(move |text, _, _| {
+        let _ = debugger.send(ScriptRequest::Notify(text.to_string()));
+    });
+
+    let api = Api { store, runtime, ui };
+    let a = api.clone();
+    engine.register_fn("lists", move || a.lists());
+    let a = api.clone();
+    engine.register_fn("list", move |name: &str| a.list(name));
+    let a = api.clone();
+    engine.register_fn("items", move |list: Map| a.items(list));
+    let a = api.clone();
+    engine.register_fn("create_list", move |name: &str| a.create_list(name));
+    let a = api.clone();
+    engine.register_fn("create_item", move |list: Map, name: &str| {
+        a.create_item(list, name)
+    });
+    let a = api.clone();
+    engine.register_fn("update_list", move |list: Map| a.update_list(list));
+    let a = api.clone();
+    engine.register_fn("update_item", move |item: Map| a.update_item(item));
+    let a = api.clone();
+    engine.register_fn("move_list", move |list: Map, by: INT| a.move_list(list, by));
+    let a = api.clone();
+    engine.register_fn("move_item", move |item: Map, by: INT| a.move_item(item, by));
+    let a = api.clone();
+    engine.register_fn("move_to_list", move |item: Map, list: Map| {
+        a.move_to_list(item, list)
+    });
+    let a = api.clone();
+    engine.register_fn("delete_list", move |list: Map| a.delete_list(list));
+    let a = api.clone();
+    engine.register_fn("delete_item", move |item: Map| a.delete_item(item));
+    let a = api.clone();
+    engine.register_fn("notify", move |text: &str| a.notify(text));
+    let a = api.clone();
+    engine.register_fn("prompt", move |question: &str| a.prompt(question, ""));
+    let a = api.clone();
+    engine.register_fn("prompt", move |question: &str, default: &str| {
+        a.prompt(question, default)
+    });
+    let a = api;
+    engine.register_fn("select", move |title: &str, options: Array| {
+        a.select(title, options)
+    });
+    engine
+}
+
+/// Value of a script, or `()` for `None`
+fn optional<T: Serialize>(value: Option<T>) -> ScriptResult<Dynamic> {
+    match value {
+        Some(value) => to_dynamic(value),
+        None => Ok(Dynamic::UNIT),
+    }
+}
+
+/// Id of a list or item given by a script
+fn id_of(map: &Map) -> ScriptResult<i64> {
+    map.get("id")
+        .and_then(|id| id.as_int().ok())
+        .ok_or_else(|| "Expected a list or an item, with an id".into())
+}
+
+fn valid_name(name: &str) -> ScriptResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("The name can't be empty".into());
+    }
+    Ok(name.to_string())
+}
+
+#[derive(Clone)]
+struct Api {
+    store: Arc<dyn TodoStore>,
+    runtime: Handle,
+    ui: Sender<ScriptRequest>,
+}
+
+impl Api {
+    /// Run an operation of the store from the thread of the script
+    fn block_on<T>(&self, operation: impl Future<Output = anyhow::Result<T>>) -> ScriptResult<T> {
+        self.runtime
+            .block_on(operation)
+            .map_err(|e| format!("{:#}", e).into())
+    }
+
+    fn find_list(&self, map: &Map) -> ScriptResult<TodoList> {
+        let id = id_of(map)?;
+        self.block_on(TodoList::get_by_id(&*self.store, id))?
+            .ok_or_else(|| format!("No list with id {}", id).into())
+    }
+
+    fn find_item(&self, map: &Map) -> ScriptResult<TodoItem> {
+        let id = id_of(map)?;
+        self.block_on(TodoItem::get_by_id(&*self.store, id))?
+            .ok_or_else(|| format!("No item with id {}", id).into())
+    }
+
+    fn lists(&self) -> ScriptResult<Array> {
+        let lists = self.block_on(TodoList::get_all(&*self.store))?;
+        lists.into_iter().map(to_dynamic).collect()
+    }
+
+    fn list(&self, name: &str) -> ScriptResult<Dynamic> {
+        let lists = self.block_on(TodoList::get_all(&*self.store))?;
+        optional(lists.into_iter().find(|list| list.name == name))
+    }
+
+    fn items(&self, list: Map) -> ScriptResult<Array> {
+        let items = self.block_on(TodoItem::get_by_list_id(&*self.store, id_of(&list)?))?;
+        items.into_iter().map(to_dynamic).collect()
+    }
+
+    fn create_list(&self, name: &str) -> ScriptResult<Dynamic> {
+        let new_list = NewTodoList {
+            name: valid_name(name)?,
+        };
+        to_dynamic(self.block_on(TodoList::create(&*self.store, new_list))?)
+    }
+
+    fn create_item(&self, list: Map, name: &str) -> ScriptResult<Dynamic> {
+        let new_item = NewTodoItem {
+            list_id: self.find_list(&list)?.id,
+            name: valid_name(name)?,
+            priority: None,
+            due_date: None,
+        };
+        to_dynamic(self.block_on(TodoItem::create(&*self.store, new_item))?)
+    }
+
+    fn update_list(&self, list: Map) -> ScriptResult<Dynamic> {
+        let mut list: TodoList = from_dynamic(&list.into())?;
+        list.name = valid_name(&list.name)?;
+        list.updated_at = Utc::now();
+        self.block_on(self.store.update_list(&list))?;
+        to_dynamic(list)
+    }
+
+    fn update_item(&self, item: Map) -> ScriptResult<Dynamic> {
+        let mut item: TodoItem = from_dynamic(&item.into())?;
+        item.name = valid_name(&item.name)?;
+        item.updated_at = Utc::now();
+        self.block_on(self.store.update_item(&item))?;
+        to_dynamic(item)
+    }
+
+    fn move_list(&self, list: Map, by: INT) -> ScriptResult<Dynamic> {
+        let mut list = self.find_list(&list)?;
+        let amount = by.unsigned_abs() as usize;
+        if by < 0 {
+            self.block_on(list.move_up_by(&*self.store, amount))?;
+        } else {
+            self.block_on(list.move_down_by(&*self.store, amount))?;
+        }
+        to_dynamic(list)
+    }
+
+    fn move_item(&self, item: Map, by: INT) -> ScriptResult<Dynamic> {
+        let mut item = self.find_item(&item)?;
+        let amount = by.unsigned_abs() as usize;
+        if by < 0 {
+            self.block_on(item.move_up_by(&*self.store, amount))?;
+        } else {
+            self.block_on(item.move_down_by(&*self.store, amount))?;
+        }
+        to_dynamic(item)
+    }
+
+    fn move_to_list(&self, item: Map, list: Map) -> ScriptResult<Dynamic> {
+        let item = self.find_item(&item)?;
+        let list = self.find_list(&list)?;
+        to_dynamic(self.block_on(item.move_to_list(&*self.store, list.id))?)
+    }
+
+    fn delete_list(&self, list: Map) -> ScriptResult<()> {
+        let list = self.find_list(&list)?;
+        self.block_on(list.delete(&*self.store))
+    }
+
+    fn delete_item(&self, item: Map) -> ScriptResult<()> {
+        let item = self.find_item(&item)?;
+        self.block_on(item.delete(&*self.store))
+    }
+
+    fn notify(&self, text: &str) -> ScriptResult<()> {
+        self.ui
+            .send(ScriptRequest::Notify(text.to_string()))
+            .map_err(|_| "The app is gone".into())
+    }
+
+    fn prompt(&self, question: &str, default: &str) -> ScriptResult<Dynamic> {
+        let (reply, answer) = mpsc::channel();
+        let request = ScriptRequest::Prompt {
+            question: question.to_string(),
+            default: default.to_string(),
+            reply,
+        };
+        self.ui.send(request).map_err(|_| "The app is gone")?;
+        let answer = answer.recv().map_err(|_| "The app is gone")?;
+        Ok(answer.map(Dynamic::from).unwrap_or(Dynamic::UNIT))
+    }
+
+    fn select(&self, title: &str, options: Array) -> ScriptResult<Dynamic> {
+        let options: Vec<String> = options
+            .into_iter()
+            .map(|option| option.to_string())
+            .collect();
+        if options.is_empty() {
+            return Ok(Dynamic::UNIT);
+        }
+        let (reply, answer) = mpsc::channel();
+        let request = ScriptRequest::Select {
+            title: title.to_string(),
+            options,
+            reply,
+        };
+        self.ui.send(request).map_err(|_| "The app is gone")?;
+        let answer = answer.recv().map_err(|_| "The app is gone")?;
+        Ok(answer.map(Dynamic::from).unwrap_or(Dynamic::UNIT))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::memory_store::MemoryStore;
+
+    /// Run a script against a memory store, answering its prompts with the
+    /// given answers in order. Returns its notifications
+    fn run_script(store: Arc<dyn TodoStore>, source: &str, answers: Vec<&str>) -> Vec<String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let (sender, requests) = mpsc::channel();
+        let handle = runtime.handle().clone();
+        let source = source.to_string();
+        let thread = std::thread::spawn(move || {
+            run(&source, store, handle, sender, ScriptContext::default())
+        });
+
+        let mut answers = answers.into_iter();
+        let mut notifications = Vec::new();
+        for request in requests {
+            match request {
+                ScriptRequest::Notify(text) => notifications.push(text),
+                ScriptRequest::Prompt { reply, .. } | ScriptRequest::Select { reply, .. } => {
+                    reply.send(answers.next().map(str::to_string)).unwrap();
+                }
+            }
+        }
+        thread.join().unwrap().unwrap();
+        notifications
+    }
+
+    #[test]
+    fn test_script_api() {
+        let store: Arc<dyn TodoStore> = Arc::new(MemoryStore::default());
+        let notifications = run_script(
+            store.clone(),
+            r#"
+            let groceries = create_list("Groceries");
+            for name in ["Milk", "Bread", "Eggs"] {
+                create_item(groceries, name);
+            }
+            let milk = items(groceries)[0];
+            milk.is_done = true;
+            milk.priority = "high";
+            update_item(milk);
+
+            let archive = create_list(prompt("Archive to?"));
+            for item in items(groceries) {
+                if item.is_done {
+                    move_to_list(item, archive);
+                }
+            }
+            let eggs = items(groceries)[1];
+            move_item(eggs, -1);
+            print(select("Pick", ["a", "b"]));
+            notify(`${items(list("Archive")).len()} archived`);
+            "#,
+            vec!["Archive", "b"],
+        );
+        assert_eq!(notifications, vec!["b", "1 archived"]);
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let lists = TodoList::get_all(&*store).await.unwrap();
+            let names = |items: Vec<TodoItem>| -> Vec<String> {
+                items.into_iter().map(|item| item.name).collect()
+            };
+            let groceries = TodoItem::get_by_list_id(&*store, lists[0].id)
+                .await
+                .unwrap();
+            assert_eq!(names(groceries), vec!["Eggs", "Bread"]);
+            let archive = TodoItem::get_by_list_id(&*store, lists[1].id)
+                .await
+                .unwrap();
+            assert!(archive[0].is_done);
+            assert_eq!(archive[0].priority, Some(crate::db::models::Priority::High));
+        });
+    }
+
+    #[test]
+    fn test_scripts_are_stopped() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let store: Arc<dyn TodoStore> = Arc::new(MemoryStore::default());
+        let (sender, _requests) = mpsc::channel();
+        let error = run(
+            "loop {}",
+            store.clone(),
+            runtime.handle().clone(),
+            sender.clone(),
+            ScriptContext::default(),
+        )
+        .unwrap_err();
+        assert!(error.contains("Too many operations"), "{}", error);
+
+        let error = run(
+            r#"create_item(#{id: 42}, "Milk")"#,
+            store,
+            runtime.handle().clone(),
+            sender,
+            ScriptContext::default(),
+        )
+        .unwrap_err();
+        assert!(error.contains("No list with id 42"), "{}", error);
+    }
+
+    #[test]
+    fn test_scripts_cant_import_modules() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let store: Arc<dyn TodoStore> = Arc::new(MemoryStore::default());
+        let (sender, _requests) = mpsc::channel();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("x.rhai"), "export const A = 1;").unwrap();
+
+        let source = format!(r#"import "{}/x" as x; x::A"#, dir.path().display());
+        let error = run(
+            &source,
+            store,
+            runtime.handle().clone(),
+            sender,
+            ScriptContext::default(),
+        )
+        .unwrap_err();
+        assert!(error.contains("Module not found"), "{}", error);
+    }
+}
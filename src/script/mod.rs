@@ -0,0 +1,187 @@
+//! Scripts: commands of the user written in [Rhai](https://rhai.rs), e.g. to
+//! move the items done to an archive list or to create a standup template.
+//!
+//! Scripts are the `.rhai` files of the `scripts` directory next to judo.toml;
+//! a comment on their first line describes them. They are run from the script
+//! picker (`SPACE + x`) or with the keys bound to them in the `[scripts]` table
+//! of judo.toml. A script can read and change the lists and items of the
+//! current database and talk to the user through the UI (see [`api`]), but
+//! can't touch files or the network.
+//!
+//! Scripts run on a thread of their own, so that the UI keeps drawing while
+//! they wait for the user: their UI requests are passed to the app as
+//! [`ScriptRequest`]s.
+
+pub mod api;
+
+use crate::db::models::{TodoItem, TodoList};
+use crate::db::store::TodoStore;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+
+/// Extension of script files
+const EXTENSION: &str = "rhai";
+
+/// Configuration of the scripts, in the `[scripts]` table of judo.toml
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ScriptsConfig {
+    /// Scripts by key, in vim notation (e.g. `"<C-a>" = "archive_done"`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, String>,
+}
+
+/// Script found in the scripts directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    /// Name of the file, without extension
+    pub name: String,
+    pub path: PathBuf,
+    /// Comment on the first line of the script
+    pub description: Option<String>,
+}
+
+/// Directory of the scripts, next to judo.toml
+pub fn scripts_dir(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .map(|dir| dir.join("scripts"))
+        .unwrap_or_else(|| PathBuf::from("scripts"))
+}
+
+/// Scripts of a directory, sorted by name. A missing directory has none
+pub fn list_scripts(dir: &Path) -> Vec<Script> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut scripts: Vec<Script> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            let description = std::fs::read_to_string(&path).ok().and_then(|source| {
+                let comment = source.lines().next()?.trim().strip_prefix("//")?;
+                Some(comment.trim().to_string()).filter(|c| !c.is_empty())
+            });
+            Some(Script {
+                name,
+                path,
+                description,
+            })
+        })
+        .collect();
+    scripts.sort_by(|a, b| a.name.cmp(&b.name));
+    scripts
+}
+
+/// What a script knows of the app when it starts
+#[derive(Debug, Clone, Default)]
+pub struct ScriptContext {
+    pub db: String,
+    pub selected_list: Option<TodoList>,
+    pub selected_item: Option<TodoItem>,
+}
+
+/// Request of a script to the UI. Prompts and selections are answered with
+/// `None` when cancelled
+#[derive(Debug)]
+pub enum ScriptRequest {
+    Notify(String),
+    Prompt {
+        question: String,
+        default: String,
+        reply: Sender<Option<String>>,
+    },
+    Select {
+        title: String,
+        options: Vec<String>,
+        reply: Sender<Option<String>>,
+    },
+}
+
+/// Script running in the background
+pub struct RunningScript {
+    pub name: String,
+    requests: Receiver<ScriptRequest>,
+    thread: Option<JoinHandle<Result<(), String>>>,
+}
+
+impl RunningScript {
+    /// Run a script on a thread of its own. Must be called within a tokio
+    /// runtime, which runs the changes the script makes to the store
+    pub fn start(
+        script: &Script,
+        store: Arc<dyn TodoStore>,
+        context: ScriptContext,
+    ) -> Result<Self> {
+        let source = std::fs::read_to_string(&script.path)
+            .with_context(|| format!("Failed to read {}", script.path.display()))?;
+        let runtime = tokio::runtime::Handle::current();
+        let (sender, requests) = mpsc::channel();
+
+        let thread = std::thread::Builder::new()
+            .name(format!("script {}", script.name))
+            .spawn(move || api::run(&source, store, runtime, sender, context))
+            .with_context(|| "Failed to start the script")?;
+
+        Ok(RunningScript {
+            name: script.name.clone(),
+            requests,
+            thread: Some(thread),
+        })
+    }
+
+    /// Next request of the script, if any
+    pub fn try_recv(&self) -> Option<ScriptRequest> {
+        self.requests.try_recv().ok()
+    }
+
+    /// Whether the script is over (its requests may still have to be handled)
+    pub fn is_finished(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_none_or(|thread| thread.is_finished())
+    }
+
+    /// Wait for the script to end and return its error, if any
+    pub fn join(&mut self) -> Result<()> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        match thread.join() {
+            Ok(result) => result.map_err(|e| anyhow::anyhow!(e)),
+            Err(_) => anyhow::bail!("The script panicked"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_list_scripts() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("standup.rhai"),
+            "// Create a standup template\n",
+        )?;
+        std::fs::write(dir.path().join("archive_done.rhai"), "let x = 1;\n")?;
+        std::fs::write(dir.path().join("notes.txt"), "// Not a script\n")?;
+
+        let scripts = list_scripts(dir.path());
+        let names: Vec<_> = scripts.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["archive_done", "standup"]);
+        assert_eq!(scripts[0].description, None);
+        assert_eq!(
+            scripts[1].description.as_deref(),
+            Some("Create a standup template")
+        );
+        assert!(list_scripts(&dir.path().join("missing")).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,654 @@
+use crate::db::config::{CaldavConfig, Config};
+use crate::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use crate::db::store::TodoStore;
+use crate::sync::dav::{DavClient, DavResource, split_url};
+use crate::sync::ical::{VCalendar, VTodo};
+use crate::sync::merge::{SyncConflict, merge_value};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// Service under which passwords are stored in the keyring
+const KEYRING_SERVICE: &str = "judo";
+
+/// CalDAV sync running in the background, see [`CaldavSync::spawn`]
+pub struct RunningCaldavSync(JoinHandle<(CaldavSync, Result<Vec<SyncConflict>>)>);
+
+impl RunningCaldavSync {
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+
+    /// Wait for the end of the sync, and get the sync back (unless it panicked)
+    /// with its result
+    pub async fn join(self) -> (Option<CaldavSync>, Result<Vec<SyncConflict>>) {
+        match self.0.await {
+            Ok((sync, result)) => (Some(sync), result),
+            Err(e) => (None, Err(e.into())),
+        }
+    }
+}
+
+/// Fields of an item that are synced with its to-do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TodoFields {
+    name: String,
+    is_done: bool,
+    priority: Option<Priority>,
+    due_date: Option<DateTime<Utc>>,
+}
+
+impl TodoFields {
+    fn of_item(item: &TodoItem) -> TodoFields {
+        TodoFields {
+            name: item.name.clone(),
+            is_done: item.is_done,
+            priority: item.priority.clone(),
+            due_date: item.due_date,
+        }
+    }
+
+    fn of_todo(todo: &VTodo) -> TodoFields {
+        TodoFields {
+            name: todo.summary.clone(),
+            is_done: todo.is_done,
+            priority: todo.priority.clone(),
+            due_date: todo.due,
+        }
+    }
+
+    /// Three-way merge, field by field. Returns the merged fields and whether
+    /// both sides changed a field in different ways (in which case ours is kept)
+    fn merge(base: &TodoFields, ours: &TodoFields, theirs: &TodoFields) -> (TodoFields, bool) {
+        let (name, name_conflict) = merge_value(Some(&base.name), &ours.name, &theirs.name);
+        let (is_done, done_conflict) =
+            merge_value(Some(&base.is_done), &ours.is_done, &theirs.is_done);
+        let (priority, priority_conflict) =
+            merge_value(Some(&base.priority), &ours.priority, &theirs.priority);
+        let (due_date, due_date_conflict) =
+            merge_value(Some(&base.due_date), &ours.due_date, &theirs.due_date);
+
+        let merged = TodoFields {
+            name,
+            is_done,
+            priority,
+            due_date,
+        };
+        let conflict = name_conflict || done_conflict || priority_conflict || due_date_conflict;
+        (merged, conflict)
+    }
+}
+
+/// State of the last sync, used as the base of three-way merges and to skip
+/// downloading to-dos whose ETag didn't change
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    /// Calendars of the lists, by list uid
+    lists: BTreeMap<String, CalendarState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CalendarState {
+    href: String,
+    name: String,
+    /// To-dos of the calendar, by href
+    todos: BTreeMap<String, TodoState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TodoState {
+    item_uid: String,
+    /// UID of the to-do, which differs from the uid of the item when the to-do
+    /// was created by another client
+    todo_uid: String,
+    etag: Option<String>,
+    fields: TodoFields,
+}
+
+/// CalDAV sync of a single database. Each list is a calendar collection of the
+/// calendar home of the user, and each item a to-do resource of that
+/// collection. Changes are detected with ETags and merged both ways, field by
+/// field; deletions propagate unless the other side changed what was deleted
+pub struct CaldavSync {
+    client: DavClient,
+    /// Path of the calendar home, ending with a slash
+    home: String,
+    state_path: PathBuf,
+    state: SyncState,
+}
+
+impl CaldavSync {
+    /// CalDAV sync configured for a database, keeping its state in the data
+    /// directory
+    pub fn new(config: &CaldavConfig, db_name: &str) -> Result<CaldavSync> {
+        let state_path = Config::default_data_dir()?
+            .join("caldav")
+            .join(format!("{}.json", db_name));
+        CaldavSync::open(config, credentials(config, db_name)?, &state_path)
+    }
+
+    /// CalDAV sync with the given credentials and state file
+    pub fn open(
+        config: &CaldavConfig,
+        credentials: Option<(String, String)>,
+        state_path: &Path,
+    ) -> Result<CaldavSync> {
+        let (_, path) = split_url(&config.url)?;
+        let home = format!("{}/", path.trim_end_matches('/'));
+
+        let state = match std::fs::read_to_string(state_path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("Failed to parse {}", state_path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => SyncState::default(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", state_path.display()));
+            }
+        };
+
+        Ok(CaldavSync {
+            client: DavClient::new(&config.url, credentials)?,
+            home,
+            state_path: state_path.to_path_buf(),
+            state,
+        })
+    }
+
+    /// Sync the lists of the store with the calendars of the server. Returns
+    /// the conflicts that were resolved by keeping the local version
+    pub async fn sync(&mut self, store: &dyn TodoStore) -> Result<Vec<SyncConflict>> {
+        let mut conflicts = Vec::new();
+        let result = self.sync_lists(store, &mut conflicts).await;
+        // Whatever was synced before an error is recorded
+        self.save_state()?;
+        result.map(|_| conflicts)
+    }
+
+    /// Sync in a task of its own, so that the TUI keeps handling keys while
+    /// waiting for the server
+    pub fn spawn(mut self, store: Arc<dyn TodoStore>) -> RunningCaldavSync {
+        RunningCaldavSync(tokio::spawn(async move {
+            let result = self.sync(&*store).await;
+            (self, result)
+        }))
+    }
+
+    async fn sync_lists(
+        &mut self,
+        store: &dyn TodoStore,
+        conflicts: &mut Vec<SyncConflict>,
+    ) -> Result<()> {
+        let client = &self.client;
+        let state = &mut self.state;
+
+        let mut calendars: HashMap<String, DavResource> = client
+            .calendars(&self.home)
+            .await?
+            .into_iter()
+            .map(|calendar| (calendar.href.clone(), calendar))
+            .collect();
+        let lists = TodoList::get_all(store).await?;
+
+        // Lists synced before
+        for uid in state.lists.keys().cloned().collect::<Vec<_>>() {
+            let Some(mut calendar_state) = state.lists.remove(&uid) else {
+                continue;
+            };
+            let list = lists.iter().find(|list| list.uid == uid);
+            let calendar = calendars.remove(&calendar_state.href);
+
+            match (list, calendar) {
+                (Some(list), Some(calendar)) => {
+                    let theirs = calendar
+                        .display_name
+                        .unwrap_or_else(|| calendar_state.name.clone());
+                    let (name, conflict) =
+                        merge_value(Some(&calendar_state.name), &list.name, &theirs);
+                    if conflict {
+                        conflicts.push(SyncConflict {
+                            list_name: name.clone(),
+                            description: format!(
+                                "List renamed on both sides; kept '{}'",
+                                list.name
+                            ),
+                        });
+                    }
+
+                    let mut list = list.clone();
+                    if name != list.name {
+                        list.update_name(store, name.clone()).await?;
+                    }
+                    if name != theirs {
+                        client.set_display_name(&calendar_state.href, &name).await?;
+                    }
+                    calendar_state.name = name;
+
+                    sync_todos(client, store, &list, &mut calendar_state, conflicts).await?;
+                    state.lists.insert(uid, calendar_state);
+                }
+                (Some(list), None) => {
+                    // Deleted on the server: only honour the deletion if the
+                    // list didn't change since the last sync
+                    let items = TodoItem::get_by_list_id(store, list.id).await?;
+                    let unchanged = list.name == calendar_state.name
+                        && items.len() == calendar_state.todos.len()
+                        && items.iter().all(|item| {
+                            calendar_state.todos.values().any(|todo| {
+                                todo.item_uid == item.uid
+                                    && todo.fields == TodoFields::of_item(item)
+                            })
+                        });
+                    if unchanged {
+                        list.clone().delete(store).await?;
+                    } else {
+                        conflicts.push(SyncConflict {
+                            list_name: list.name.clone(),
+                            description: "Calendar was deleted on the server and the list \
+                                          modified locally; uploaded it again"
+                                .to_string(),
+                        });
+                    }
+                }
+                (None, Some(calendar)) => {
+                    // Deleted locally: only delete the calendar if none of its
+                    // to-dos changed since the last sync
+                    let todos = client.todos(&calendar_state.href).await?;
+                    let unchanged = calendar
+                        .display_name
+                        .as_ref()
+                        .is_none_or(|name| *name == calendar_state.name)
+                        && todos.len() == calendar_state.todos.len()
+                        && todos.iter().all(|todo| {
+                            todo.etag.is_some()
+                                && calendar_state
+                                    .todos
+                                    .get(&todo.href)
+                                    .is_some_and(|known| known.etag == todo.etag)
+                        });
+                    if unchanged {
+                        client.delete(&calendar_state.href, None).await?;
+                    } else {
+                        conflicts.push(SyncConflict {
+                            list_name: calendar_state.name.clone(),
+                            description: "List was deleted locally and the calendar modified \
+                                          on the server; restored it"
+                                .to_string(),
+                        });
+                        calendars.insert(calendar.href.clone(), calendar);
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+
+        // New local lists
+        for list in &lists {
+            if state.lists.contains_key(&list.uid) {
+                continue;
+            }
+            let href = format!("{}{}/", self.home, list.uid);
+            match calendars.remove(&href) {
+                // Synced before, but the state was lost
+                Some(calendar) => {
+                    if calendar.display_name.as_ref() != Some(&list.name) {
+                        client.set_display_name(&href, &list.name).await?;
+                    }
+                }
+                None => client.make_calendar(&href, &list.name).await?,
+            }
+
+            let mut calendar_state = CalendarState {
+                href,
+                name: list.name.clone(),
+                todos: BTreeMap::new(),
+            };
+            sync_todos(client, store, list, &mut calendar_state, conflicts).await?;
+            state.lists.insert(list.uid.clone(), calendar_state);
+        }
+
+        // New calendars
+        let mut new_calendars: Vec<DavResource> = calendars.into_values().collect();
+        new_calendars.sort_by(|a, b| a.href.cmp(&b.href));
+        for calendar in new_calendars {
+            let name = calendar.display_name.clone().unwrap_or_else(|| {
+                let path = calendar.href.trim_end_matches('/');
+                path.rsplit('/').next().unwrap_or(path).to_string()
+            });
+            let list = TodoList::create(store, NewTodoList { name: name.clone() }).await?;
+
+            let mut calendar_state = CalendarState {
+                href: calendar.href,
+                name,
+                todos: BTreeMap::new(),
+            };
+            sync_todos(client, store, &list, &mut calendar_state, conflicts).await?;
+            state.lists.insert(list.uid, calendar_state);
+        }
+
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<()> {
+        if let Some(dir) = self.state_path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let text = serde_json::to_string_pretty(&self.state)?;
+        std::fs::write(&self.state_path, text)
+            .with_context(|| format!("Failed to write {}", self.state_path.display()))
+    }
+}
+
+/// Sync the items of a list with the to-dos of its calendar
+async fn sync_todos(
+    client: &DavClient,
+    store: &dyn TodoStore,
+    list: &TodoList,
+    calendar: &mut CalendarState,
+    conflicts: &mut Vec<SyncConflict>,
+) -> Result<()> {
+    let mut remote: HashMap<String, Option<String>> = client
+        .todos(&calendar.href)
+        .await?
+        .into_iter()
+        .map(|todo| (todo.href, todo.etag))
+        .collect();
+    let items = TodoItem::get_by_list_id(store, list.id).await?;
+    let mut unclaimed: HashSet<&str> = items.iter().map(|item| item.uid.as_str()).collect();
+    let find_item = |uid: &str| items.iter().find(|item| item.uid == uid);
+    let mut conflict = |description: String| {
+        conflicts.push(SyncConflict {
+            list_name: list.name.clone(),
+            description,
+        })
+    };
+
+    // To-dos synced before
+    for (href, known) in std::mem::take(&mut calendar.todos) {
+        let item = unclaimed
+            .remove(known.item_uid.as_str())
+            .then(|| find_item(&known.item_uid))
+            .flatten();
+        let etag = remote.remove(&href);
+        let changed_remotely = |etag: &Option<String>| etag.is_none() || *etag != known.etag;
+
+        match (item, etag) {
+            (Some(item), Some(etag)) => {
+                let ours = TodoFields::of_item(item);
+                let (theirs, etag) = if changed_remotely(&etag) {
+                    match fetch_todo(client, &href).await? {
+                        Some((todo, etag)) => (TodoFields::of_todo(&todo), etag),
+                        None => (known.fields.clone(), etag),
+                    }
+                } else {
+                    (known.fields.clone(), etag)
+                };
+
+                let (merged, is_conflict) = TodoFields::merge(&known.fields, &ours, &theirs);
+                if is_conflict {
+                    conflict(format!(
+                        "Item '{}' changed on both sides; kept the local version",
+                        ours.name
+                    ));
+                }
+                if merged != ours {
+                    update_item(store, item, &merged).await?;
+                }
+                let etag = if merged != theirs {
+                    let ics = todo_ics(item, &merged, &known.todo_uid);
+                    client.put(&href, &ics, etag.as_deref()).await?
+                } else {
+                    etag
+                };
+
+                calendar.todos.insert(
+                    href,
+                    TodoState {
+                        etag,
+                        fields: merged,
+                        ..known
+                    },
+                );
+            }
+            (Some(item), None) => {
+                let ours = TodoFields::of_item(item);
+                if ours == known.fields {
+                    item.clone().delete(store).await?;
+                } else {
+                    conflict(format!(
+                        "Item '{}' was deleted on the server and modified locally; \
+                         uploaded it again",
+                        ours.name
+                    ));
+                    let etag = client
+                        .put(&href, &todo_ics(item, &ours, &known.todo_uid), None)
+                        .await?;
+                    calendar.todos.insert(
+                        href,
+                        TodoState {
+                            etag,
+                            fields: ours,
+                            ..known
+                        },
+                    );
+                }
+            }
+            (None, Some(etag)) => {
+                if !changed_remotely(&etag) {
+                    client.delete(&href, etag.as_deref()).await?;
+                } else if let Some((todo, etag)) = fetch_todo(client, &href).await? {
+                    let theirs = TodoFields::of_todo(&todo);
+                    conflict(format!(
+                        "Item '{}' was deleted locally and modified on the server; restored it",
+                        theirs.name
+                    ));
+                    let item = create_item(store, list, &theirs).await?;
+                    calendar.todos.insert(
+                        href,
+                        TodoState {
+                            item_uid: item.uid,
+                            todo_uid: known.todo_uid,
+                            etag,
+                            fields: theirs,
+                        },
+                    );
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // New to-dos, added to the list in the order they were created
+    let mut new_todos = Vec::new();
+    for href in remote.into_keys() {
+        if let Some((todo, etag)) = fetch_todo(client, &href).await? {
+            new_todos.push((href, todo, etag));
+        }
+    }
+    new_todos
+        .sort_by(|(a_href, a, _), (b_href, b, _)| (a.created, a_href).cmp(&(b.created, b_href)));
+    for (href, todo, etag) in new_todos {
+        let theirs = TodoFields::of_todo(&todo);
+        if theirs.name.trim().is_empty() {
+            continue;
+        }
+
+        // An item with the same uid was synced before the state was lost
+        let paired = unclaimed
+            .remove(todo.uid.as_str())
+            .then(|| find_item(&todo.uid))
+            .flatten();
+        let (item_uid, fields, etag) = match paired {
+            Some(item) => {
+                let ours = TodoFields::of_item(item);
+                let etag = if ours != theirs {
+                    conflict(format!(
+                        "Item '{}' changed on both sides; kept the local version",
+                        ours.name
+                    ));
+                    client
+                        .put(&href, &todo_ics(item, &ours, &todo.uid), etag.as_deref())
+                        .await?
+                } else {
+                    etag
+                };
+                (item.uid.clone(), ours, etag)
+            }
+            None => {
+                let item = create_item(store, list, &theirs).await?;
+                (item.uid, theirs, etag)
+            }
+        };
+
+        calendar.todos.insert(
+            href,
+            TodoState {
+                item_uid,
+                todo_uid: todo.uid,
+                etag,
+                fields,
+            },
+        );
+    }
+
+    // New items
+    for item in items
+        .iter()
+        .filter(|item| unclaimed.contains(item.uid.as_str()))
+    {
+        let href = format!("{}{}.ics", calendar.href, item.uid);
+        let fields = TodoFields::of_item(item);
+        let etag = client
+            .put(&href, &todo_ics(item, &fields, &item.uid), None)
+            .await?;
+        calendar.todos.insert(
+            href,
+            TodoState {
+                item_uid: item.uid.clone(),
+                todo_uid: item.uid.clone(),
+                etag,
+                fields,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Download a to-do resource. Returns its first to-do, if any, with its ETag
+async fn fetch_todo(client: &DavClient, href: &str) -> Result<Option<(VTodo, Option<String>)>> {
+    let response = client.get(href).await?;
+    let calendar = VCalendar::parse(&response.body)
+        .with_context(|| format!("Failed to parse {}", client.url(href)))?;
+    Ok(calendar
+        .todos
+        .into_iter()
+        .next()
+        .map(|todo| (todo, response.etag)))
+}
+
+/// Calendar resource of an item with the given fields
+fn todo_ics(item: &TodoItem, fields: &TodoFields, todo_uid: &str) -> String {
+    let now = Utc::now();
+    let todo = VTodo {
+        uid: todo_uid.to_string(),
+        summary: fields.name.clone(),
+        is_done: fields.is_done,
+        completed: fields.is_done.then_some(now),
+        priority: fields.priority.clone(),
+        due: fields.due_date,
+        last_modified: Some(now),
+        ..VTodo::from_item(item, None)
+    };
+    VCalendar {
+        name: None,
+        todos: vec![todo],
+    }
+    .to_ics()
+}
+
+async fn create_item(
+    store: &dyn TodoStore,
+    list: &TodoList,
+    fields: &TodoFields,
+) -> Result<TodoItem> {
+    let new_item = NewTodoItem {
+        list_id: list.id,
+        name: fields.name.clone(),
+        priority: fields.priority.clone(),
+        due_date: fields.due_date,
+    };
+    let mut item = TodoItem::create(store, new_item).await?;
+    if fields.is_done {
+        item.toggle_done(store).await?;
+    }
+    Ok(item)
+}
+
+async fn update_item(store: &dyn TodoStore, item: &TodoItem, fields: &TodoFields) -> Result<()> {
+    item.clone()
+        .update_details(
+            store,
+            fields.name.clone(),
+            fields.is_done,
+            fields.priority.clone(),
+            fields.due_date,
+        )
+        .await
+}
+
+/// Credentials of the CalDAV server of a database. The password is read from
+/// the environment variable named in the config, or else from the keyring of
+/// the system (service "judo", account named after the database)
+pub fn credentials(config: &CaldavConfig, db_name: &str) -> Result<Option<(String, String)>> {
+    let Some(username) = &config.username else {
+        return Ok(None);
+    };
+
+    let password = match &config.password_env {
+        Some(variable) => std::env::var(variable)
+            .with_context(|| format!("Environment variable {} is not set", variable))?,
+        None => keyring_password(db_name)?,
+    };
+    Ok(Some((username.clone(), password)))
+}
+
+/// Password stored in the keyring: with `secret-tool` (libsecret) on Linux and
+/// `security` (Keychain) on macOS
+fn keyring_password(db_name: &str) -> Result<String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("security");
+        command.args([
+            "find-generic-password",
+            "-s",
+            KEYRING_SERVICE,
+            "-a",
+            db_name,
+            "-w",
+        ]);
+        command
+    } else {
+        let mut command = Command::new("secret-tool");
+        command.args(["lookup", "service", KEYRING_SERVICE, "account", db_name]);
+        command
+    };
+
+    let output = command
+        .output()
+        .with_context(|| "Failed to read the keyring; set password_env instead")?;
+    let password = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches('\n')
+        .to_string();
+    if !output.status.success() || password.is_empty() {
+        anyhow::bail!(
+            "No CalDAV password in the keyring for service '{}' and account '{}'",
+            KEYRING_SERVICE,
+            db_name
+        );
+    }
+    Ok(password)
+}
@@ -0,0 +1,616 @@
+use crate::db::crypto::{self, open_text, seal_text};
+use crate::db::models::Priority;
+use crate::db::sqlite_store::park_orderings;
+use crate::db::store::deduplicate_orderings;
+use crate::sync::changes::{
+    Change, ChangeState, DELETED_FIELD, Entity, ITEM_FIELDS, LIST_FIELDS, RowState,
+};
+use crate::sync::hlc::{Hlc, HlcClock};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Extension of the files holding the change log of a database in a shared folder
+pub const CHANGES_FILE_EXTENSION: &str = "changes";
+
+/// Values of the tracked fields of the rows of the database, by kind and uid
+type Rows = BTreeMap<(Entity, String), Vec<(&'static str, Option<String>)>>;
+
+/// Record the changes made to the database since the last call (edits, new rows
+/// and deletions) in the change log, stamped with the clock of this database.
+/// Returns the number of changes recorded
+pub async fn record_local_changes(pool: &SqlitePool) -> Result<usize> {
+    let mut tx = pool
+        .begin()
+        .await
+        .with_context(|| "Failed to start transaction")?;
+    let recorded = record_changes(&mut tx, pool).await?;
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+
+    Ok(recorded)
+}
+
+/// Write the whole change log of the database to `<node id>.changes` in the given
+/// directory (one JSON change per line), so that other databases sharing the
+/// directory can apply it. Returns the path of the file. Encrypted databases
+/// are not exported, since the file would hold their names in clear
+pub async fn export_changes(pool: &SqlitePool, dir: &Path) -> Result<PathBuf> {
+    if crypto::is_encrypted(pool).await? {
+        anyhow::bail!("Change logs of encrypted databases are not exported");
+    }
+    record_local_changes(pool).await?;
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .with_context(|| "Failed to acquire connection")?;
+    let (node_id, _) = load_node(&mut conn).await?;
+    let changes = load_changes(&mut conn, pool).await?;
+
+    let mut text = String::new();
+    for change in &changes {
+        text.push_str(&serde_json::to_string(change)?);
+        text.push('\n');
+    }
+
+    // Write then rename, so that readers of the shared folder never see half a file
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+    let path = dir.join(format!("{}.{}", node_id, CHANGES_FILE_EXTENSION));
+    let tmp_path = dir.join(format!(".{}.{}.tmp", node_id, CHANGES_FILE_EXTENSION));
+    std::fs::write(&tmp_path, text)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Apply the change logs found in the given directory to the database. Concurrent
+/// edits are merged field by field (the latest change wins) and deletions win
+/// over edits. Returns the number of changes that were new to this database
+pub async fn apply_changes(pool: &SqlitePool, dir: &Path) -> Result<usize> {
+    let mut received = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let is_changes_file = path
+            .extension()
+            .is_some_and(|extension| extension == CHANGES_FILE_EXTENSION);
+        if !is_changes_file {
+            continue;
+        }
+
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let change: Change = serde_json::from_str(line)
+                .with_context(|| format!("Invalid change at {}:{}", path.display(), i + 1))?;
+            received.push(change);
+        }
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .with_context(|| "Failed to start transaction")?;
+
+    // Local changes must be in the log before merging, or they would be overwritten
+    record_changes(&mut tx, pool).await?;
+
+    let (node_id, last) = load_node(&mut tx).await?;
+    let mut clock = HlcClock::new(&node_id, last);
+    let mut new_changes = 0;
+    for change in &received {
+        clock.observe(&change.hlc);
+        new_changes += insert_change(&mut tx, pool, change).await?;
+    }
+    save_clock(&mut tx, &clock).await?;
+
+    let state = ChangeState::from_changes(&load_changes(&mut tx, pool).await?);
+    materialize(&mut tx, pool, &state).await?;
+    mark_recorded(&mut tx).await?;
+
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+
+    Ok(new_changes)
+}
+
+/// Id of this database in the change logs and last clock it issued
+async fn load_node(conn: &mut SqliteConnection) -> Result<(String, Option<Hlc>)> {
+    let (node_id, last_hlc): (String, Option<String>) =
+        sqlx::query_as("SELECT node_id, last_hlc FROM sync_state WHERE id = 1")
+            .fetch_one(&mut *conn)
+            .await
+            .with_context(|| "Failed to read sync state")?;
+
+    let last = last_hlc.as_deref().map(Hlc::from_str).transpose()?;
+    Ok((node_id, last))
+}
+
+async fn save_clock(conn: &mut SqliteConnection, clock: &HlcClock) -> Result<()> {
+    sqlx::query("UPDATE sync_state SET last_hlc = ?1 WHERE id = 1")
+        .bind(clock.last().to_string())
+        .execute(&mut *conn)
+        .await
+        .with_context(|| "Failed to save clock")?;
+    Ok(())
+}
+
+/// All changes of the log, oldest first. Names of encrypted databases are
+/// decrypted
+async fn load_changes(conn: &mut SqliteConnection, pool: &SqlitePool) -> Result<Vec<Change>> {
+    let rows: Vec<(String, String, String, String, Option<String>)> = sqlx::query_as(
+        "SELECT hlc, entity, uid, field, value FROM sync_changes ORDER BY hlc, entity, uid, field",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .with_context(|| "Failed to read change log")?;
+
+    rows.into_iter()
+        .map(|(hlc, entity, uid, field, value)| {
+            let value = match value {
+                Some(value) if field == "name" => Some(open_text(pool, value)?),
+                value => value,
+            };
+            Ok(Change {
+                hlc: Hlc::from_str(&hlc)?,
+                entity: Entity::from_str(&entity)?,
+                uid,
+                field,
+                value,
+            })
+        })
+        .collect()
+}
+
+/// Add a change to the log, unless a later change of the same field or the
+/// deletion of the row supersedes it, and drop the changes it supersedes: the
+/// log only keeps the winning change of each field, and nothing but the
+/// tombstone of deleted rows. Names are encrypted like those of the rows, so
+/// that the log doesn't leak them. Returns 1 if it was added, 0 otherwise
+async fn insert_change(
+    conn: &mut SqliteConnection,
+    pool: &SqlitePool,
+    change: &Change,
+) -> Result<usize> {
+    let hlc = change.hlc.to_string();
+    let superseded: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM sync_changes
+            WHERE entity = ?1 AND uid = ?2 AND ((field = ?3 AND hlc >= ?4) OR field = ?5)
+        )
+        "#,
+    )
+    .bind(change.entity.as_str())
+    .bind(&change.uid)
+    .bind(&change.field)
+    .bind(&hlc)
+    .bind(DELETED_FIELD)
+    .fetch_one(&mut *conn)
+    .await
+    .with_context(|| "Failed to read change log")?;
+    if superseded {
+        return Ok(0);
+    }
+
+    let value = match &change.value {
+        Some(value) if change.field == "name" => Some(seal_text(pool, value)?),
+        value => value.clone(),
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO sync_changes (hlc, entity, uid, field, value)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+    )
+    .bind(&hlc)
+    .bind(change.entity.as_str())
+    .bind(&change.uid)
+    .bind(&change.field)
+    .bind(value)
+    .execute(&mut *conn)
+    .await
+    .with_context(|| "Failed to record change")?;
+
+    let query = if change.field == DELETED_FIELD {
+        "DELETE FROM sync_changes WHERE entity = ?1 AND uid = ?2 AND field != ?3"
+    } else {
+        "DELETE FROM sync_changes WHERE entity = ?1 AND uid = ?2 AND field = ?3 AND hlc < ?4"
+    };
+    sqlx::query(query)
+        .bind(change.entity.as_str())
+        .bind(&change.uid)
+        .bind(&change.field)
+        .bind(&hlc)
+        .execute(&mut *conn)
+        .await
+        .with_context(|| "Failed to compact change log")?;
+
+    Ok(1)
+}
+
+/// Whether lists or items were written since the changes were last recorded
+async fn has_unrecorded_writes(conn: &mut SqliteConnection) -> Result<bool> {
+    let (data_version, recorded_version): (i64, Option<i64>) =
+        sqlx::query_as("SELECT data_version, recorded_version FROM sync_state WHERE id = 1")
+            .fetch_one(&mut *conn)
+            .await
+            .with_context(|| "Failed to read sync state")?;
+    Ok(recorded_version != Some(data_version))
+}
+
+/// Remember that the database matches the change log
+async fn mark_recorded(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query("UPDATE sync_state SET recorded_version = data_version WHERE id = 1")
+        .execute(&mut *conn)
+        .await
+        .with_context(|| "Failed to save sync state")?;
+    Ok(())
+}
+
+/// Read the tracked fields of all lists and items, in the text form used by the
+/// log. Names of encrypted databases are decrypted
+async fn current_rows(conn: &mut SqliteConnection, pool: &SqlitePool) -> Result<Rows> {
+    let mut rows = Rows::new();
+
+    let lists: Vec<(String, String, i64, Option<String>)> =
+        sqlx::query_as("SELECT uid, name, ordering, statuses FROM todo_lists")
+            .fetch_all(&mut *conn)
+            .await
+            .with_context(|| "Failed to fetch lists")?;
+    for (uid, name, ordering, statuses) in lists {
+        let name = open_text(pool, name)?;
+        rows.insert(
+            (Entity::List, uid),
+            LIST_FIELDS
+                .into_iter()
+                .zip([Some(name), Some(ordering.to_string()), statuses])
+                .collect(),
+        );
+    }
+
+    #[allow(clippy::type_complexity)]
+    let items: Vec<(
+        String,
+        String,
+        String,
+        bool,
+        Option<String>,
+        Option<DateTime<Utc>>,
+        i64,
+        Option<String>,
+    )> = sqlx::query_as(
+        r#"
+        SELECT i.uid, l.uid, i.name, i.is_done, i.priority, i.due_date, i.ordering, i.status
+        FROM todo_items i JOIN todo_lists l ON l.id = i.list_id
+        "#,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .with_context(|| "Failed to fetch items")?;
+    for (uid, list_uid, name, is_done, priority, due_date, ordering, status) in items {
+        let name = open_text(pool, name)?;
+        rows.insert(
+            (Entity::Item, uid),
+            ITEM_FIELDS
+                .into_iter()
+                .zip([
+                    Some(list_uid),
+                    Some(name),
+                    Some(is_done.to_string()),
+                    priority,
+                    due_date.map(|d| d.to_rfc3339()),
+                    Some(ordering.to_string()),
+                    status,
+                ])
+                .collect(),
+        );
+    }
+
+    Ok(rows)
+}
+
+/// Whether a row of the log should exist in the database: not deleted, created
+/// with a name and, for items, in a list that exists
+fn is_live(state: &ChangeState, entity: Entity, row: &RowState) -> bool {
+    if row.is_deleted() || !row.has("name") {
+        return false;
+    }
+    match entity {
+        Entity::List => true,
+        Entity::Item => row
+            .value("list")
+            .and_then(|list_uid| state.row(Entity::List, list_uid))
+            .is_some_and(|list| is_live(state, Entity::List, list)),
+    }
+}
+
+/// Diff the database against the state of the log and record the differences.
+/// Nothing is read when nothing was written since the last call
+async fn record_changes(conn: &mut SqliteConnection, pool: &SqlitePool) -> Result<usize> {
+    if !has_unrecorded_writes(conn).await? {
+        return Ok(0);
+    }
+    let (node_id, last) = load_node(conn).await?;
+    let mut clock = HlcClock::new(&node_id, last);
+    let state = ChangeState::from_changes(&load_changes(conn, pool).await?);
+    let rows = current_rows(conn, pool).await?;
+
+    let mut changes = Vec::new();
+    for ((entity, uid), fields) in &rows {
+        let row = state.row(*entity, uid);
+        // Deleted elsewhere: the row is removed at the next merge
+        if row.is_some_and(|r| r.is_deleted()) {
+            continue;
+        }
+        for (field, value) in fields {
+            // Fields never set are empty, so fields added to the schema later
+            // don't produce a change for every existing row
+            let unchanged = row.and_then(|r| r.value(field)) == value.as_deref();
+            if !unchanged {
+                changes.push(Change {
+                    hlc: clock.tick(),
+                    entity: *entity,
+                    uid: uid.clone(),
+                    field: field.to_string(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+
+    // Rows that exist in the log but not in the database anymore were deleted
+    for entity in [Entity::List, Entity::Item] {
+        for (uid, row) in state.rows(entity) {
+            if is_live(&state, entity, row) && !rows.contains_key(&(entity, uid.to_string())) {
+                changes.push(Change {
+                    hlc: clock.tick(),
+                    entity,
+                    uid: uid.to_string(),
+                    field: DELETED_FIELD.to_string(),
+                    value: Some("true".to_string()),
+                });
+            }
+        }
+    }
+
+    for change in &changes {
+        insert_change(conn, pool, change).await?;
+        set_row_clock(conn, change.entity, &change.uid, &change.hlc).await?;
+    }
+    save_clock(conn, &clock).await?;
+    mark_recorded(conn).await?;
+
+    Ok(changes.len())
+}
+
+async fn set_row_clock(
+    conn: &mut SqliteConnection,
+    entity: Entity,
+    uid: &str,
+    hlc: &Hlc,
+) -> Result<()> {
+    let query = match entity {
+        Entity::List => "UPDATE todo_lists SET hlc = ?1 WHERE uid = ?2",
+        Entity::Item => "UPDATE todo_items SET hlc = ?1 WHERE uid = ?2",
+    };
+    sqlx::query(query)
+        .bind(hlc.to_string())
+        .bind(uid)
+        .execute(&mut *conn)
+        .await
+        .with_context(|| "Failed to update row clock")?;
+    Ok(())
+}
+
+/// Names as stored in a table (encrypted in encrypted databases), by uid
+async fn stored_names(conn: &mut SqliteConnection, table: &str) -> Result<HashMap<String, String>> {
+    let rows: Vec<(String, String)> = sqlx::query_as(&format!("SELECT uid, name FROM {}", table))
+        .fetch_all(&mut *conn)
+        .await
+        .with_context(|| format!("Failed to read {}", table))?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Value to store for the name of a row. An unchanged name keeps its stored
+/// value: encrypting it again (under a new nonce) would look like a change
+fn name_to_store(pool: &SqlitePool, stored: Option<&String>, name: Option<&str>) -> Result<String> {
+    let name = name.unwrap_or_default();
+    if let Some(stored) = stored
+        && open_text(pool, stored.clone())? == name
+    {
+        return Ok(stored.clone());
+    }
+    seal_text(pool, name)
+}
+
+/// Make the database match the state of the log
+async fn materialize(
+    conn: &mut SqliteConnection,
+    pool: &SqlitePool,
+    state: &ChangeState,
+) -> Result<()> {
+    let now = Utc::now();
+
+    // Rows of the database are all in the log, so those that aren't live were deleted
+    let existing = current_rows(conn, pool).await?;
+    for (entity, uid) in existing.keys() {
+        let live = state
+            .row(*entity, uid)
+            .is_some_and(|row| is_live(state, *entity, row));
+        if !live {
+            let query = match entity {
+                Entity::List => "DELETE FROM todo_lists WHERE uid = ?1",
+                Entity::Item => "DELETE FROM todo_items WHERE uid = ?1",
+            };
+            sqlx::query(query)
+                .bind(uid)
+                .execute(&mut *conn)
+                .await
+                .with_context(|| "Failed to delete row")?;
+        }
+    }
+
+    let ordering = |row: &RowState| -> i64 {
+        row.value("ordering")
+            .and_then(|o| o.parse().ok())
+            .unwrap_or(0)
+    };
+    let clock = |row: &RowState| row.clock().map(|hlc| hlc.to_string());
+
+    // Orderings are unique among siblings, even when rows were given the same
+    // one on different machines: rows that change their ordering are moved out
+    // of the way first
+    let live_rows = |entity| {
+        state
+            .rows(entity)
+            .filter(move |(_, row)| is_live(state, entity, row))
+    };
+    let list_rows: Vec<(&str, i64)> = live_rows(Entity::List)
+        .map(|(uid, row)| (uid, ordering(row)))
+        .collect();
+    let mut orderings: HashMap<(Entity, &str), i64> = deduplicate_orderings(&list_rows)
+        .into_iter()
+        .map(|(uid, ordering)| ((Entity::List, uid), ordering))
+        .collect();
+
+    let mut item_rows: BTreeMap<&str, Vec<(&str, i64)>> = BTreeMap::new();
+    for (uid, row) in live_rows(Entity::Item) {
+        let list_uid = row.value("list").unwrap_or_default();
+        item_rows
+            .entry(list_uid)
+            .or_default()
+            .push((uid, ordering(row)));
+    }
+    for rows in item_rows.values() {
+        for (uid, ordering) in deduplicate_orderings(rows) {
+            orderings.insert((Entity::Item, uid), ordering);
+        }
+    }
+
+    for entity in [Entity::List, Entity::Item] {
+        let moved: Vec<&str> = existing
+            .iter()
+            .filter(|((e, _), _)| *e == entity)
+            .filter_map(|((_, uid), fields)| {
+                let new_ordering = orderings.get(&(entity, uid.as_str()))?.to_string();
+                let row = state.row(entity, uid)?;
+                let unchanged = fields.iter().all(|(field, value)| match *field {
+                    "ordering" => value.as_deref() == Some(new_ordering.as_str()),
+                    "list" => value.as_deref() == row.value("list"),
+                    _ => true,
+                });
+                (!unchanged).then_some(uid.as_str())
+            })
+            .collect();
+        let table = match entity {
+            Entity::List => "todo_lists",
+            Entity::Item => "todo_items",
+        };
+        park_orderings(conn, table, &moved).await?;
+    }
+
+    let list_names = stored_names(conn, "todo_lists").await?;
+    for (uid, row) in state.rows(Entity::List) {
+        if !is_live(state, Entity::List, row) {
+            continue;
+        }
+        let name = name_to_store(pool, list_names.get(uid), row.value("name"))?;
+        sqlx::query(
+            r#"
+            INSERT INTO todo_lists (uid, name, ordering, statuses, hlc, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+            ON CONFLICT(uid) DO UPDATE SET
+                updated_at = CASE
+                    WHEN name != excluded.name OR ordering != excluded.ordering
+                        OR statuses IS NOT excluded.statuses
+                    THEN excluded.updated_at ELSE updated_at END,
+                name = excluded.name,
+                ordering = excluded.ordering,
+                statuses = excluded.statuses,
+                hlc = excluded.hlc
+            WHERE name != excluded.name OR ordering != excluded.ordering
+                OR statuses IS NOT excluded.statuses OR hlc IS NOT excluded.hlc
+            "#,
+        )
+        .bind(uid)
+        .bind(name)
+        .bind(orderings[&(Entity::List, uid)])
+        .bind(row.value("statuses"))
+        .bind(clock(row))
+        .bind(now)
+        .execute(&mut *conn)
+        .await
+        .with_context(|| "Failed to save list")?;
+    }
+
+    let item_names = stored_names(conn, "todo_items").await?;
+    for (uid, row) in state.rows(Entity::Item) {
+        if !is_live(state, Entity::Item, row) {
+            continue;
+        }
+        let name = name_to_store(pool, item_names.get(uid), row.value("name"))?;
+        let due_date = row
+            .value("due_date")
+            .map(|d| DateTime::parse_from_rfc3339(d).map(|d| d.with_timezone(&Utc)))
+            .transpose()
+            .with_context(|| format!("Invalid due date for item {}", uid))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO todo_items (uid, list_id, name, is_done, priority, due_date, ordering, status, hlc, created_at, updated_at)
+            VALUES (?1, (SELECT id FROM todo_lists WHERE uid = ?2), ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)
+            ON CONFLICT(uid) DO UPDATE SET
+                updated_at = CASE
+                    WHEN list_id != excluded.list_id OR name != excluded.name
+                        OR is_done != excluded.is_done OR priority IS NOT excluded.priority
+                        OR due_date IS NOT excluded.due_date OR ordering != excluded.ordering
+                        OR status IS NOT excluded.status
+                    THEN excluded.updated_at ELSE updated_at END,
+                list_id = excluded.list_id,
+                name = excluded.name,
+                is_done = excluded.is_done,
+                priority = excluded.priority,
+                due_date = excluded.due_date,
+                ordering = excluded.ordering,
+                status = excluded.status,
+                hlc = excluded.hlc
+            WHERE list_id != excluded.list_id OR name != excluded.name
+                OR is_done != excluded.is_done OR priority IS NOT excluded.priority
+                OR due_date IS NOT excluded.due_date OR ordering != excluded.ordering
+                OR status IS NOT excluded.status OR hlc IS NOT excluded.hlc
+            "#,
+        )
+        .bind(uid)
+        .bind(row.value("list"))
+        .bind(name)
+        .bind(row.value("is_done") == Some("true"))
+        .bind(
+            row.value("priority")
+                .filter(|priority| Priority::from_str(priority).is_ok()),
+        )
+        .bind(due_date)
+        .bind(orderings[&(Entity::Item, uid)])
+        .bind(row.value("status"))
+        .bind(clock(row))
+        .bind(now)
+        .execute(&mut *conn)
+        .await
+        .with_context(|| "Failed to save item")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,342 @@
+use crate::sync::hlc::Hlc;
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Field recording the deletion of a row (tombstone). Once a row has it, the row
+/// stays deleted whatever other changes are applied
+pub const DELETED_FIELD: &str = "deleted";
+
+/// Fields of a list tracked by the change log
+pub const LIST_FIELDS: [&str; 3] = ["name", "ordering", "statuses"];
+
+/// Fields of an item tracked by the change log. `list` holds the uid of the list
+pub const ITEM_FIELDS: [&str; 7] = [
+    "list", "name", "is_done", "priority", "due_date", "ordering", "status",
+];
+
+/// Kind of row a change applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Entity {
+    List,
+    Item,
+}
+
+impl Entity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Entity::List => "list",
+            Entity::Item => "item",
+        }
+    }
+}
+
+impl FromStr for Entity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "list" => Ok(Entity::List),
+            "item" => Ok(Entity::Item),
+            other => anyhow::bail!("Invalid entity '{}'", other),
+        }
+    }
+}
+
+impl Serialize for Hlc {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Hlc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Hlc::from_str(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+/// New value of a field of a row, stamped with the clock of the node that made
+/// the change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change {
+    pub hlc: Hlc,
+    pub entity: Entity,
+    pub uid: String,
+    pub field: String,
+    pub value: Option<String>,
+}
+
+/// Current value of the fields of a row, with the clock of the change that set them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowState {
+    pub fields: BTreeMap<String, (Hlc, Option<String>)>,
+}
+
+impl RowState {
+    /// Whether the row has been deleted
+    pub fn is_deleted(&self) -> bool {
+        self.fields.contains_key(DELETED_FIELD)
+    }
+
+    /// Current value of a field (`None` when unset or null)
+    pub fn value(&self, field: &str) -> Option<&str> {
+        self.fields
+            .get(field)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// Whether the field has ever been set
+    pub fn has(&self, field: &str) -> bool {
+        self.fields.contains_key(field)
+    }
+
+    /// Clock of the last change of the row
+    pub fn clock(&self) -> Option<&Hlc> {
+        self.fields.values().map(|(hlc, _)| hlc).max()
+    }
+}
+
+/// State obtained by folding a set of changes: last writer wins per field.
+///
+/// Applying changes is commutative, associative and idempotent, so every node
+/// that has seen the same set of changes ends up in the same state, whatever the
+/// order it received them in. Ties on the clock (which only happen with
+/// corrupted logs, since a node never reuses a timestamp) are broken by value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeState {
+    rows: BTreeMap<(Entity, String), RowState>,
+}
+
+impl ChangeState {
+    /// Fold a set of changes
+    pub fn from_changes<'a>(changes: impl IntoIterator<Item = &'a Change>) -> ChangeState {
+        let mut state = ChangeState::default();
+        for change in changes {
+            state.apply(change);
+        }
+        state
+    }
+
+    /// Apply a change. Returns whether it became the current value of its field
+    pub fn apply(&mut self, change: &Change) -> bool {
+        let row = self
+            .rows
+            .entry((change.entity, change.uid.clone()))
+            .or_default();
+
+        let wins = match row.fields.get(&change.field) {
+            Some((hlc, value)) => (&change.hlc, &change.value) > (hlc, value),
+            None => true,
+        };
+        if wins {
+            row.fields.insert(
+                change.field.clone(),
+                (change.hlc.clone(), change.value.clone()),
+            );
+        }
+        wins
+    }
+
+    /// State of a row, if any change was applied to it
+    pub fn row(&self, entity: Entity, uid: &str) -> Option<&RowState> {
+        self.rows.get(&(entity, uid.to_string()))
+    }
+
+    /// All rows of a kind, by uid
+    pub fn rows(&self, entity: Entity) -> impl Iterator<Item = (&str, &RowState)> {
+        self.rows
+            .iter()
+            .filter(move |((e, _), _)| *e == entity)
+            .map(|((_, uid), row)| (uid.as_str(), row))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sync::hlc::HlcClock;
+    use proptest::prelude::*;
+
+    fn change(millis: u64, node: &str, uid: &str, field: &str, value: &str) -> Change {
+        Change {
+            hlc: Hlc {
+                millis,
+                counter: 0,
+                node: node.to_string(),
+            },
+            entity: Entity::Item,
+            uid: uid.to_string(),
+            field: field.to_string(),
+            value: Some(value.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_last_writer_wins_per_field() {
+        let state = ChangeState::from_changes(&[
+            change(1, "a", "x", "name", "Milk"),
+            change(3, "b", "x", "is_done", "true"),
+            change(2, "b", "x", "name", "Oat milk"),
+            change(2, "a", "x", "name", "Soy milk"),
+        ]);
+
+        let row = state.row(Entity::Item, "x").unwrap();
+        // Same millisecond: the node id breaks the tie
+        assert_eq!(row.value("name"), Some("Oat milk"));
+        assert_eq!(row.value("is_done"), Some("true"));
+        assert_eq!(row.clock().unwrap().millis, 3);
+        assert!(!row.is_deleted());
+    }
+
+    #[test]
+    fn test_tombstones_are_sticky() {
+        let state = ChangeState::from_changes(&[
+            change(1, "a", "x", "name", "Milk"),
+            change(2, "a", "x", DELETED_FIELD, "true"),
+            change(5, "b", "x", "name", "Oat milk"),
+        ]);
+        assert!(state.row(Entity::Item, "x").unwrap().is_deleted());
+    }
+
+    #[test]
+    fn test_change_json_round_trip() -> Result<()> {
+        let change = change(42, "node-1", "x", "name", "Milk");
+        let json = serde_json::to_string(&change)?;
+        assert_eq!(serde_json::from_str::<Change>(&json)?, change);
+        Ok(())
+    }
+
+    fn arb_change() -> impl Strategy<Value = Change> {
+        (
+            0u64..4,
+            0u32..2,
+            prop::sample::select(vec!["a", "b", "c"]),
+            prop::sample::select(vec![Entity::List, Entity::Item]),
+            prop::sample::select(vec!["x", "y"]),
+            prop::sample::select(vec!["name", "ordering", DELETED_FIELD]),
+            prop::option::of(prop::sample::select(vec!["1", "2", "3"])),
+        )
+            .prop_map(
+                |(millis, counter, node, entity, uid, field, value)| Change {
+                    hlc: Hlc {
+                        millis,
+                        counter,
+                        node: node.to_string(),
+                    },
+                    entity,
+                    uid: uid.to_string(),
+                    field: field.to_string(),
+                    value: value.map(|v| v.to_string()),
+                },
+            )
+    }
+
+    /// Operation of the replica simulation: a local edit on a replica, or a replica
+    /// receiving all the changes known to another one
+    #[derive(Debug, Clone)]
+    enum Op {
+        Edit {
+            replica: usize,
+            uid: &'static str,
+            field: &'static str,
+            value: u8,
+            now: u64,
+        },
+        Sync {
+            from: usize,
+            to: usize,
+        },
+    }
+
+    fn arb_op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            3 => (
+                0usize..3,
+                prop::sample::select(vec!["x", "y", "z"]),
+                prop::sample::select(vec!["name", "ordering", "is_done", DELETED_FIELD]),
+                any::<u8>(),
+                0u64..20,
+            )
+                .prop_map(|(replica, uid, field, value, now)| Op::Edit {
+                    replica,
+                    uid,
+                    field,
+                    value,
+                    now,
+                }),
+            1 => (0usize..3, 0usize..3).prop_map(|(from, to)| Op::Sync { from, to }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_order_and_duplicates_do_not_matter(
+            changes in prop::collection::vec(arb_change(), 0..40),
+            seed in any::<u64>(),
+        ) {
+            let mut shuffled = changes.clone();
+            // Duplicate some changes, as when receiving the same file twice
+            shuffled.extend(changes.iter().step_by(3).cloned());
+            let len = shuffled.len();
+            if len > 1 {
+                for i in 0..len {
+                    shuffled.swap(i, (seed as usize).wrapping_add(i * 7919) % len);
+                }
+            }
+
+            prop_assert_eq!(
+                ChangeState::from_changes(&changes),
+                ChangeState::from_changes(&shuffled)
+            );
+        }
+
+        #[test]
+        fn prop_replicas_converge(ops in prop::collection::vec(arb_op(), 0..60)) {
+            let nodes = ["a", "b", "c"];
+            let mut clocks: Vec<HlcClock> = nodes.iter().map(|n| HlcClock::new(n, None)).collect();
+            let mut logs: Vec<Vec<Change>> = vec![Vec::new(); nodes.len()];
+
+            let sync = |logs: &mut Vec<Vec<Change>>, clocks: &mut Vec<HlcClock>, from: usize, to: usize| {
+                let received = logs[from].clone();
+                for change in &received {
+                    clocks[to].observe(&change.hlc);
+                }
+                logs[to].extend(received);
+            };
+
+            for op in ops {
+                match op {
+                    Op::Edit { replica, uid, field, value, now } => {
+                        let hlc = clocks[replica].tick_at(now);
+                        // A node never issues the same timestamp twice
+                        if let Some(previous) = logs[replica].iter().filter(|c| c.hlc.node == nodes[replica]).map(|c| &c.hlc).max() {
+                            prop_assert!(&hlc > previous);
+                        }
+                        logs[replica].push(Change {
+                            hlc,
+                            entity: Entity::Item,
+                            uid: uid.to_string(),
+                            field: field.to_string(),
+                            value: Some(value.to_string()),
+                        });
+                    }
+                    Op::Sync { from, to } => sync(&mut logs, &mut clocks, from, to),
+                }
+            }
+
+            // Gossip around the ring until every replica has seen every change
+            for _ in 0..2 {
+                for from in 0..nodes.len() {
+                    sync(&mut logs, &mut clocks, from, (from + 1) % nodes.len());
+                }
+            }
+
+            let states: Vec<ChangeState> = logs.iter().map(ChangeState::from_changes).collect();
+            prop_assert_eq!(&states[0], &states[1]);
+            prop_assert_eq!(&states[1], &states[2]);
+        }
+    }
+}
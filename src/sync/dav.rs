@@ -0,0 +1,547 @@
+use anyhow::{Context, Result};
+use roxmltree::{Document, Node};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Seconds after which a request is abandoned
+const REQUEST_TIMEOUT_SECS: u32 = 30;
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="utf-8"?>"#;
+/// Namespaces of the request bodies
+const NAMESPACES: &str = r#"xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav""#;
+const DAV: &str = "DAV:";
+const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+
+const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+const CALENDAR_CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+
+/// Response to a WebDAV request
+#[derive(Debug)]
+pub struct DavResponse {
+    pub status: u16,
+    pub etag: Option<String>,
+    pub body: String,
+}
+
+impl DavResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Resource listed in a multistatus response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DavResource {
+    /// Path of the resource, as given by the server
+    pub href: String,
+    pub etag: Option<String>,
+    pub display_name: Option<String>,
+    pub is_calendar: bool,
+    /// Components the calendar accepts (empty when the server doesn't say)
+    pub components: Vec<String>,
+}
+
+impl DavResource {
+    /// Whether the resource is a calendar collection that can hold to-dos
+    pub fn holds_todos(&self) -> bool {
+        self.is_calendar
+            && (self.components.is_empty()
+                || self
+                    .components
+                    .iter()
+                    .any(|c| c.eq_ignore_ascii_case("VTODO")))
+    }
+}
+
+/// Minimal WebDAV/CalDAV client running the curl command line, the same way
+/// git sync runs git. curl runs as a child process awaited by the runtime, so
+/// that the TUI stays responsive during a sync. Credentials are passed to curl
+/// through its standard input, so they never show up in the list of processes
+pub struct DavClient {
+    /// Scheme and authority of the server (e.g. "https://dav.example.com")
+    origin: String,
+    credentials: Option<(String, String)>,
+}
+
+impl DavClient {
+    /// Client of the server hosting `url`, authenticating with basic
+    /// authentication when credentials are given
+    pub fn new(url: &str, credentials: Option<(String, String)>) -> Result<DavClient> {
+        let (origin, _) = split_url(url)?;
+        Ok(DavClient {
+            origin: origin.to_string(),
+            credentials,
+        })
+    }
+
+    /// Absolute URL of a path of the server
+    pub fn url(&self, href: &str) -> String {
+        if href.starts_with("http://") || href.starts_with("https://") {
+            href.to_string()
+        } else {
+            format!("{}{}", self.origin, href)
+        }
+    }
+
+    /// Send a request and return the response, whatever its status
+    pub async fn request(
+        &self,
+        method: &str,
+        href: &str,
+        headers: &[(&str, &str)],
+        body: Option<&str>,
+    ) -> Result<DavResponse> {
+        let url = self.url(href);
+
+        // Options are given as a curl config file on the standard input
+        let mut config = String::new();
+        let mut option = |name: &str, value: &str| {
+            config.push_str(&format!("{} = \"{}\"\n", name, escape_config(value)));
+        };
+        option("url", &url);
+        option("request", method);
+        option("max-time", &REQUEST_TIMEOUT_SECS.to_string());
+        // No "100 Continue" round trip before sending the body
+        option("header", "Expect:");
+        for (name, value) in headers {
+            option("header", &format!("{}: {}", name, value));
+        }
+        if let Some((username, password)) = &self.credentials {
+            option("user", &format!("{}:{}", username, password));
+        }
+        if let Some(body) = body {
+            option("data-raw", body);
+        }
+
+        let mut child = Command::new("curl")
+            .args(["--silent", "--show-error", "--include", "--config", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| "Failed to run curl")?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .with_context(|| "Failed to open the input of curl")?;
+        stdin.write_all(config.as_bytes()).await?;
+        // Close the input so that curl reads the end of its config
+        drop(stdin);
+        let output = child.wait_with_output().await?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "{} {} failed: {}",
+                method,
+                url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        parse_response(&String::from_utf8_lossy(&output.stdout))
+            .with_context(|| format!("Invalid response to {} {}", method, url))
+    }
+
+    /// Send a request and fail unless it succeeds
+    async fn expect_success(
+        &self,
+        method: &str,
+        href: &str,
+        headers: &[(&str, &str)],
+        body: Option<&str>,
+    ) -> Result<DavResponse> {
+        let response = self.request(method, href, headers, body).await?;
+        match response.status {
+            401 | 403 => anyhow::bail!("{} {}: access denied", method, self.url(href)),
+            _ if !response.is_success() => {
+                anyhow::bail!(
+                    "{} {} failed with {}",
+                    method,
+                    self.url(href),
+                    response.status
+                )
+            }
+            _ => Ok(response),
+        }
+    }
+
+    /// Calendar collections under a collection (the calendar home of a user)
+    /// that can hold to-dos
+    pub async fn calendars(&self, home: &str) -> Result<Vec<DavResource>> {
+        let body = format!(
+            "{}<d:propfind {}><d:prop><d:resourcetype/><d:displayname/>\
+             <c:supported-calendar-component-set/></d:prop></d:propfind>",
+            XML_HEADER, NAMESPACES
+        );
+        let response = self
+            .expect_success(
+                "PROPFIND",
+                home,
+                &[("Depth", "1"), ("Content-Type", XML_CONTENT_TYPE)],
+                Some(&body),
+            )
+            .await?;
+
+        Ok(parse_multistatus(&response.body)?
+            .into_iter()
+            .filter(|r| !same_path(&r.href, home) && r.holds_todos())
+            .collect())
+    }
+
+    /// To-do resources of a calendar, with their ETags
+    pub async fn todos(&self, calendar: &str) -> Result<Vec<DavResource>> {
+        let body = format!(
+            "{}<c:calendar-query {}><d:prop><d:getetag/></d:prop><c:filter>\
+             <c:comp-filter name=\"VCALENDAR\"><c:comp-filter name=\"VTODO\"/>\
+             </c:comp-filter></c:filter></c:calendar-query>",
+            XML_HEADER, NAMESPACES
+        );
+        let response = self
+            .expect_success(
+                "REPORT",
+                calendar,
+                &[("Depth", "1"), ("Content-Type", XML_CONTENT_TYPE)],
+                Some(&body),
+            )
+            .await?;
+
+        Ok(parse_multistatus(&response.body)?
+            .into_iter()
+            .filter(|r| !same_path(&r.href, calendar))
+            .collect())
+    }
+
+    /// Content and ETag of a resource
+    pub async fn get(&self, href: &str) -> Result<DavResponse> {
+        self.expect_success("GET", href, &[], None).await
+    }
+
+    /// Write a calendar resource. With an ETag, the resource is only replaced
+    /// if it didn't change on the server; without one, it must not exist yet.
+    /// Returns the new ETag, if the server gives it
+    pub async fn put(&self, href: &str, ics: &str, etag: Option<&str>) -> Result<Option<String>> {
+        let condition = match etag {
+            Some(etag) => ("If-Match", etag),
+            None => ("If-None-Match", "*"),
+        };
+        let response = self
+            .request(
+                "PUT",
+                href,
+                &[("Content-Type", CALENDAR_CONTENT_TYPE), condition],
+                Some(ics),
+            )
+            .await?;
+        if response.status == 412 {
+            anyhow::bail!("{} changed on the server during the sync", self.url(href));
+        }
+        if !response.is_success() {
+            anyhow::bail!("PUT {} failed with {}", self.url(href), response.status);
+        }
+        Ok(response.etag)
+    }
+
+    /// Delete a resource. A resource that is already gone is not an error
+    pub async fn delete(&self, href: &str, etag: Option<&str>) -> Result<()> {
+        let headers: Vec<(&str, &str)> = etag.map(|etag| ("If-Match", etag)).into_iter().collect();
+        let response = self.request("DELETE", href, &headers, None).await?;
+        if response.status == 412 {
+            anyhow::bail!("{} changed on the server during the sync", self.url(href));
+        }
+        if !response.is_success() && response.status != 404 {
+            anyhow::bail!("DELETE {} failed with {}", self.url(href), response.status);
+        }
+        Ok(())
+    }
+
+    /// Create a calendar collection holding to-dos
+    pub async fn make_calendar(&self, href: &str, name: &str) -> Result<()> {
+        let body = format!(
+            "{}<c:mkcalendar {}><d:set><d:prop><d:displayname>{}</d:displayname>\
+             <c:supported-calendar-component-set><c:comp name=\"VTODO\"/>\
+             </c:supported-calendar-component-set></d:prop></d:set></c:mkcalendar>",
+            XML_HEADER,
+            NAMESPACES,
+            escape_xml(name)
+        );
+        self.expect_success(
+            "MKCALENDAR",
+            href,
+            &[("Content-Type", XML_CONTENT_TYPE)],
+            Some(&body),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Rename a collection
+    pub async fn set_display_name(&self, href: &str, name: &str) -> Result<()> {
+        let body = format!(
+            "{}<d:propertyupdate {}><d:set><d:prop><d:displayname>{}</d:displayname>\
+             </d:prop></d:set></d:propertyupdate>",
+            XML_HEADER,
+            NAMESPACES,
+            escape_xml(name)
+        );
+        self.expect_success(
+            "PROPPATCH",
+            href,
+            &[("Content-Type", XML_CONTENT_TYPE)],
+            Some(&body),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Split a URL into its origin ("https://host:port") and path
+pub fn split_url(url: &str) -> Result<(&str, &str)> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .with_context(|| format!("'{}' is not an http(s) URL", url))?;
+    let origin_len = url.len() - rest.len() + rest.find('/').unwrap_or(rest.len());
+    let (origin, path) = url.split_at(origin_len);
+    Ok((origin, if path.is_empty() { "/" } else { path }))
+}
+
+/// Whether two hrefs (absolute URLs or paths) name the same resource
+fn same_path(a: &str, b: &str) -> bool {
+    let path = |href: &'_ str| -> String {
+        let path = split_url(href).map_or(href, |(_, path)| path);
+        path.trim_end_matches('/').to_string()
+    };
+    path(a) == path(b)
+}
+
+/// Quote a value of a curl config file
+fn escape_config(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Read the output of `curl --include`: status line, headers, blank line and
+/// body. Interim responses ("100 Continue") come first and are skipped
+fn parse_response(output: &str) -> Result<DavResponse> {
+    let mut rest = output;
+    loop {
+        let (head, body) = rest
+            .split_once("\r\n\r\n")
+            .or_else(|| rest.split_once("\n\n"))
+            .unwrap_or((rest, ""));
+        let mut lines = head.lines();
+        let status: u16 = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse().ok())
+            .with_context(|| "Missing status line")?;
+
+        if (100..200).contains(&status) && !body.is_empty() {
+            rest = body;
+            continue;
+        }
+
+        let etag = lines
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("etag"))
+            .map(|(_, value)| value.trim().to_string());
+        return Ok(DavResponse {
+            status,
+            etag,
+            body: body.to_string(),
+        });
+    }
+}
+
+/// Trimmed text of an element, if it isn't empty
+fn text(node: Node) -> Option<String> {
+    let text: String = node
+        .descendants()
+        .filter(Node::is_text)
+        .filter_map(|text| text.text())
+        .collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// First child element of a node with a name in a namespace
+fn child<'a, 'input>(
+    node: Node<'a, 'input>,
+    namespace: &str,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.has_tag_name((namespace, name)))
+}
+
+/// Resources of a multistatus response (RFC 4918). Properties are only read
+/// from the successful parts of each response
+pub fn parse_multistatus(xml: &str) -> Result<Vec<DavResource>> {
+    let document = Document::parse(xml).with_context(|| "Invalid multistatus response")?;
+    let mut resources = Vec::new();
+
+    let responses = document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name((DAV, "response")));
+    for response in responses {
+        let Some(href) = child(response, DAV, "href").and_then(text) else {
+            continue;
+        };
+        let mut resource = DavResource {
+            href,
+            ..DavResource::default()
+        };
+
+        let propstats = response
+            .children()
+            .filter(|node| node.has_tag_name((DAV, "propstat")));
+        for propstat in propstats {
+            let status_ok = child(propstat, DAV, "status")
+                .and_then(text)
+                .is_none_or(|status| {
+                    status
+                        .split_whitespace()
+                        .nth(1)
+                        .is_some_and(|code| code.starts_with('2'))
+                });
+            let Some(prop) = child(propstat, DAV, "prop").filter(|_| status_ok) else {
+                continue;
+            };
+
+            for property in prop.children().filter(Node::is_element) {
+                let name = property.tag_name();
+                match (name.namespace(), name.name()) {
+                    (Some(DAV), "getetag") => resource.etag = text(property).or(resource.etag),
+                    (Some(DAV), "displayname") => {
+                        resource.display_name = text(property).or(resource.display_name)
+                    }
+                    (Some(DAV), "resourcetype") => {
+                        resource.is_calendar |= child(property, CALDAV, "calendar").is_some()
+                    }
+                    (Some(CALDAV), "supported-calendar-component-set") => {
+                        resource.components.extend(
+                            property
+                                .children()
+                                .filter(|comp| comp.has_tag_name((CALDAV, "comp")))
+                                .filter_map(|comp| comp.attribute("name"))
+                                .map(str::to_string),
+                        )
+                    }
+                    _ => {}
+                }
+            }
+        }
+        resources.push(resource);
+    }
+    Ok(resources)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_url() -> Result<()> {
+        assert_eq!(
+            split_url("https://dav.example.com:8443/cal/me/")?,
+            ("https://dav.example.com:8443", "/cal/me/")
+        );
+        assert_eq!(split_url("http://localhost")?, ("http://localhost", "/"));
+        assert!(split_url("ftp://example.com/").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_response_skips_interim_responses() -> Result<()> {
+        let output = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 201 Created\r\n\
+                      ETag: \"42\"\r\nContent-Length: 0\r\n\r\n";
+        let response = parse_response(output)?;
+        assert_eq!(response.status, 201);
+        assert_eq!(response.etag.as_deref(), Some("\"42\""));
+        assert_eq!(response.body, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_multistatus() -> Result<()> {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+            <D:multistatus xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+              <D:response>
+                <D:href>/cal/me/</D:href>
+                <D:propstat>
+                  <D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop>
+                  <D:status>HTTP/1.1 200 OK</D:status>
+                </D:propstat>
+              </D:response>
+              <D:response>
+                <D:href>/cal/me/groceries/</D:href>
+                <D:propstat>
+                  <D:prop>
+                    <D:resourcetype><D:collection/><C:calendar/></D:resourcetype>
+                    <D:displayname>Fish &amp; <![CDATA[chips]]></D:displayname>
+                    <C:supported-calendar-component-set>
+                      <C:comp name="VEVENT"/><C:comp name="VTODO"/>
+                    </C:supported-calendar-component-set>
+                  </D:prop>
+                  <D:status>HTTP/1.1 200 OK</D:status>
+                </D:propstat>
+                <D:propstat>
+                  <D:prop><D:getetag/></D:prop>
+                  <D:status>HTTP/1.1 404 Not Found</D:status>
+                </D:propstat>
+              </D:response>
+              <D:response>
+                <D:href>/cal/me/groceries/eggs.ics</D:href>
+                <D:propstat>
+                  <D:prop><D:getetag>"e1"</D:getetag></D:prop>
+                  <D:status>HTTP/1.1 200 OK</D:status>
+                </D:propstat>
+              </D:response>
+            </D:multistatus>"#;
+
+        let resources = parse_multistatus(xml)?;
+        assert_eq!(resources.len(), 3);
+        assert!(!resources[0].holds_todos());
+
+        assert_eq!(resources[1].href, "/cal/me/groceries/");
+        assert_eq!(resources[1].display_name.as_deref(), Some("Fish & chips"));
+        assert_eq!(resources[1].components, vec!["VEVENT", "VTODO"]);
+        assert_eq!(resources[1].etag, None);
+        assert!(resources[1].holds_todos());
+
+        assert_eq!(resources[2].etag.as_deref(), Some("\"e1\""));
+        assert!(!resources[2].is_calendar);
+
+        // Elements of other namespaces are not DAV properties
+        let xml = r#"<D:multistatus xmlns:D="DAV:" xmlns:X="urn:example">
+              <D:response>
+                <D:href>/cal/me/work/</D:href>
+                <D:propstat>
+                  <D:prop><X:resourcetype><X:calendar/></X:resourcetype></D:prop>
+                </D:propstat>
+              </D:response>
+            </D:multistatus>"#;
+        assert!(!parse_multistatus(xml)?[0].is_calendar);
+        assert!(parse_multistatus("<D:multistatus xmlns:D=\"DAV:\">").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_config() {
+        assert_eq!(escape_config("a\"b\\c\r\n"), r#"a\"b\\c\r\n"#);
+    }
+}
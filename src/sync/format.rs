@@ -0,0 +1,286 @@
+use crate::db::models::Priority;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::str::FromStr;
+
+/// Extension of the files holding a list in the sync directory
+pub const LIST_FILE_EXTENSION: &str = "todo";
+
+/// Plain-text representation of a list and its items.
+///
+/// Lists are identified by their `uid`, which is stable across databases, and
+/// written one per file. The format is line based so that git can diff and merge
+/// changes to different items independently:
+///
+/// ```text
+/// list 3f2a9c...
+/// name Groceries
+/// ordering 1
+///
+/// [ ] 9ab37d... - - Milk
+/// [ ]:doing 12fe45... - - Bread
+/// [x] 77cd01... high 2025-10-01T09:00:00+00:00 Eggs
+/// ```
+///
+/// Each item line holds the completion status (followed by the board status,
+/// if any), the item uid, the priority and the due date (`-` when missing) and
+/// the name. Items are written in order. Lists with a board of their own have a
+/// `statuses todo,doing,done` line after the ordering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListSnapshot {
+    pub uid: String,
+    pub name: String,
+    pub ordering: i64,
+    pub statuses: Option<String>,
+    pub items: Vec<ItemSnapshot>,
+}
+
+/// Plain-text representation of an item (see [`ListSnapshot`])
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemSnapshot {
+    pub uid: String,
+    pub name: String,
+    pub is_done: bool,
+    pub priority: Option<Priority>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub status: Option<String>,
+}
+
+/// Escape backslashes and newlines so that a name fits on a single line
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+/// Escape a value so that it fits in a single space-separated field
+fn escape_field(text: &str) -> String {
+    escape(text).replace(' ', "\\s")
+}
+
+/// Revert [`escape`]
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('s') => result.push(' '),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+impl ListSnapshot {
+    /// Name of the file holding the list
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.uid, LIST_FILE_EXTENSION)
+    }
+
+    /// Serialize the list in its canonical text format
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "list {}\nname {}\nordering {}\n",
+            self.uid,
+            escape(&self.name),
+            self.ordering
+        );
+        if let Some(statuses) = &self.statuses {
+            text.push_str(&format!("statuses {}\n", escape(statuses)));
+        }
+        text.push('\n');
+
+        for item in &self.items {
+            let mut status = if item.is_done { "[x]" } else { "[ ]" }.to_string();
+            if let Some(board_status) = &item.status {
+                status.push_str(&format!(":{}", escape_field(board_status)));
+            }
+            let priority = item.priority.as_ref().map_or("-", |p| p.as_str());
+            let due_date = item
+                .due_date
+                .map_or("-".to_string(), |due_date| due_date.to_rfc3339());
+
+            text.push_str(&format!(
+                "{} {} {} {} {}\n",
+                status,
+                item.uid,
+                priority,
+                due_date,
+                escape(&item.name)
+            ));
+        }
+
+        text
+    }
+
+    /// Parse a list written in its canonical text format
+    pub fn from_text(text: &str) -> Result<ListSnapshot> {
+        let mut lines = text.lines().peekable();
+
+        let mut header_value = |key: &str| -> Result<String> {
+            let line = lines
+                .next()
+                .with_context(|| format!("Missing '{}' line", key))?;
+            let value = line
+                .strip_prefix(key)
+                .and_then(|rest| rest.strip_prefix(' '))
+                .with_context(|| format!("Expected '{}' line, found '{}'", key, line))?;
+            Ok(value.to_string())
+        };
+
+        let uid = header_value("list")?;
+        let name = unescape(&header_value("name")?);
+        let ordering = header_value("ordering")?
+            .parse()
+            .with_context(|| "Invalid list ordering")?;
+        let statuses = lines
+            .next_if(|line| line.starts_with("statuses "))
+            .map(|line| unescape(&line["statuses ".len()..]));
+
+        let items = lines
+            .filter(|line| !line.trim().is_empty())
+            .map(ItemSnapshot::from_line)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ListSnapshot {
+            uid,
+            name,
+            ordering,
+            statuses,
+            items,
+        })
+    }
+}
+
+impl ItemSnapshot {
+    /// Parse an item line (see [`ListSnapshot`] for the format)
+    fn from_line(line: &str) -> Result<ItemSnapshot> {
+        let is_done = if line.starts_with("[x]") {
+            true
+        } else if line.starts_with("[ ]") {
+            false
+        } else {
+            anyhow::bail!("Invalid item line '{}'", line);
+        };
+
+        let (status, rest) = match line[3..].strip_prefix(':') {
+            Some(rest) => {
+                let (status, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+                (Some(unescape(status)), rest)
+            }
+            None => (
+                None,
+                line[3..]
+                    .strip_prefix(' ')
+                    .with_context(|| format!("Invalid item line '{}'", line))?,
+            ),
+        };
+
+        let mut fields = rest.splitn(4, ' ');
+        let mut next_field = |field: &str| {
+            fields
+                .next()
+                .with_context(|| format!("Missing item {} in '{}'", field, line))
+        };
+
+        let uid = next_field("uid")?.to_string();
+        let priority = match next_field("priority")? {
+            "-" => None,
+            priority => Some(Priority::from_str(priority)?),
+        };
+        let due_date = match next_field("due date")? {
+            "-" => None,
+            due_date => Some(
+                DateTime::parse_from_rfc3339(due_date)
+                    .with_context(|| format!("Invalid due date '{}'", due_date))?
+                    .with_timezone(&Utc),
+            ),
+        };
+        let name = unescape(next_field("name")?);
+
+        Ok(ItemSnapshot {
+            uid,
+            name,
+            is_done,
+            priority,
+            due_date,
+            status,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_list_text_round_trip() -> Result<()> {
+        let list = ListSnapshot {
+            uid: "aaaa".to_string(),
+            name: "Groceries \\ multi\nline".to_string(),
+            ordering: 3,
+            statuses: None,
+            items: vec![
+                ItemSnapshot {
+                    uid: "bbbb".to_string(),
+                    name: "Milk and  spaces ".to_string(),
+                    is_done: false,
+                    priority: None,
+                    due_date: None,
+                    status: None,
+                },
+                ItemSnapshot {
+                    uid: "cccc".to_string(),
+                    name: "Eggs".to_string(),
+                    is_done: true,
+                    priority: Some(Priority::High),
+                    due_date: Some(Utc.with_ymd_and_hms(2025, 10, 1, 9, 0, 0).unwrap()),
+                    status: None,
+                },
+            ],
+        };
+
+        let text = list.to_text();
+        assert_eq!(text.lines().count(), 6);
+        assert_eq!(ListSnapshot::from_text(&text)?, list);
+        Ok(())
+    }
+
+    #[test]
+    fn test_board_statuses_round_trip() -> Result<()> {
+        let list = ListSnapshot {
+            uid: "aaaa".to_string(),
+            name: "Release".to_string(),
+            ordering: 1,
+            statuses: Some("todo,in review,done".to_string()),
+            items: vec![ItemSnapshot {
+                uid: "bbbb".to_string(),
+                name: "Changelog".to_string(),
+                is_done: false,
+                priority: None,
+                due_date: None,
+                status: Some("in review".to_string()),
+            }],
+        };
+
+        let text = list.to_text();
+        assert!(text.contains("statuses todo,in review,done\n"));
+        assert!(text.contains("[ ]:in\\sreview bbbb - - Changelog\n"));
+        assert_eq!(ListSnapshot::from_text(&text)?, list);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_text_is_rejected() {
+        assert!(ListSnapshot::from_text("name Groceries\n").is_err());
+        assert!(ListSnapshot::from_text("list a\nname b\nordering 1\n\n[?] c - - d\n").is_err());
+        assert!(
+            ListSnapshot::from_text("list a\nname b\nordering 1\n\n[ ] c urgent - d\n").is_err()
+        );
+    }
+}
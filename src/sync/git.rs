@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Thin wrapper around the git command line, run from a directory inside a
+/// git working tree. Paths are relative to that directory. Commands run
+/// without blocking the runtime
+pub struct GitRepo {
+    dir: PathBuf,
+}
+
+impl GitRepo {
+    /// Open the git working tree containing `dir`, creating the directory and
+    /// initializing a repository if needed
+    pub async fn open(dir: &Path) -> Result<GitRepo> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create sync directory {}", dir.display()))?;
+
+        let repo = GitRepo {
+            dir: dir.to_path_buf(),
+        };
+
+        if repo
+            .run(&["rev-parse", "--is-inside-work-tree"])
+            .await
+            .is_err()
+        {
+            repo.run(&["init"]).await?;
+        }
+
+        Ok(repo)
+    }
+
+    /// Directory the repository was opened from
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Run a git command and return its standard output
+    pub async fn run(&self, args: &[&str]) -> Result<String> {
+        // Git removes the directory when its last file is deleted
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create sync directory {}", self.dir.display()))?;
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .output()
+            .await
+            .with_context(|| "Failed to run git")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Resolve a revision to a commit hash, if it exists
+    pub async fn rev_parse(&self, rev: &str) -> Option<String> {
+        self.run(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ])
+        .await
+        .ok()
+        .map(|hash| hash.trim().to_string())
+    }
+
+    /// Name of the checked out branch
+    pub async fn current_branch(&self) -> Result<String> {
+        Ok(self
+            .run(&["symbolic-ref", "--short", "HEAD"])
+            .await?
+            .trim()
+            .to_string())
+    }
+
+    /// Whether the given remote is configured
+    pub async fn has_remote(&self, remote: &str) -> bool {
+        self.run(&["remote"])
+            .await
+            .map(|remotes| remotes.lines().any(|r| r == remote))
+            .unwrap_or(false)
+    }
+
+    /// Stage everything in the directory and commit it. Returns whether a commit was made
+    pub async fn commit_all(&self, message: &str) -> Result<bool> {
+        self.run(&["add", "--all", "--", "."]).await?;
+
+        // Nothing staged: nothing to commit
+        if self
+            .run(&["diff", "--cached", "--quiet", "--", "."])
+            .await
+            .is_ok()
+        {
+            return Ok(false);
+        }
+
+        self.run(&["commit", "--quiet", "-m", message, "--", "."])
+            .await?;
+        Ok(true)
+    }
+
+    /// Files of the directory (relative paths) at the given revision
+    pub async fn list_files(&self, rev: &str) -> Result<Vec<String>> {
+        Ok(self
+            .run(&["ls-tree", "--name-only", rev, "--", "."])
+            .await?
+            .lines()
+            .map(|line| line.to_string())
+            .collect())
+    }
+
+    /// Content of a file of the directory at the given revision
+    pub async fn show(&self, rev: &str, file: &str) -> Result<String> {
+        self.run(&["show", &format!("{}:./{}", rev, file)]).await
+    }
+}
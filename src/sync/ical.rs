@@ -0,0 +1,570 @@
+use crate::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use crate::db::store::TodoStore;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// Product identifier of the calendars written by Judo
+const PRODID: &str = "-//judo//judo//EN";
+
+/// Maximum length of a content line, in octets, before it is folded
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Name of the list receiving imported tasks that say nothing about their list
+pub const DEFAULT_IMPORT_LIST: &str = "Imported";
+
+/// iCalendar (RFC 5545) file holding to-dos.
+///
+/// A list is exported as a calendar named after it (`X-WR-CALNAME`). A whole
+/// database is exported as a single calendar, each to-do naming its list in
+/// `CATEGORIES`:
+///
+/// ```text
+/// BEGIN:VCALENDAR
+/// VERSION:2.0
+/// PRODID:-//judo//judo//EN
+/// X-WR-CALNAME:Groceries
+/// BEGIN:VTODO
+/// UID:77cd01...
+/// DTSTAMP:20251001T081500Z
+/// SUMMARY:Eggs
+/// STATUS:COMPLETED
+/// COMPLETED:20251001T081500Z
+/// PRIORITY:1
+/// DUE:20251001T090000Z
+/// CREATED:20250930T180000Z
+/// LAST-MODIFIED:20251001T081500Z
+/// END:VTODO
+/// END:VCALENDAR
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VCalendar {
+    /// Name of the calendar, if any
+    pub name: Option<String>,
+    pub todos: Vec<VTodo>,
+}
+
+/// To-do of an iCalendar file (see [`VCalendar`])
+#[derive(Debug, Clone, PartialEq)]
+pub struct VTodo {
+    pub uid: String,
+    pub summary: String,
+    pub is_done: bool,
+    /// When the to-do was completed (the last modification of done items)
+    pub completed: Option<DateTime<Utc>>,
+    pub priority: Option<Priority>,
+    pub due: Option<DateTime<Utc>>,
+    pub created: Option<DateTime<Utc>>,
+    pub last_modified: Option<DateTime<Utc>>,
+    /// Name of the list of the to-do, when it isn't the name of the calendar
+    pub list: Option<String>,
+}
+
+impl VTodo {
+    /// To-do of an item, naming its list if given
+    pub fn from_item(item: &TodoItem, list: Option<&str>) -> VTodo {
+        VTodo {
+            uid: item.uid.clone(),
+            summary: item.name.clone(),
+            is_done: item.is_done,
+            completed: item.is_done.then_some(item.updated_at),
+            priority: item.priority.clone(),
+            due: item.due_date,
+            created: Some(item.created_at),
+            last_modified: Some(item.updated_at),
+            list: list.map(str::to_string),
+        }
+    }
+}
+
+/// RFC 5545 priority of a priority (1 is the highest, 9 the lowest)
+fn priority_number(priority: &Priority) -> u8 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+/// Priority of an RFC 5545 priority (0 means undefined)
+fn priority_from_number(number: u8) -> Option<Priority> {
+    match number {
+        1..=4 => Some(Priority::High),
+        5 => Some(Priority::Medium),
+        6..=9 => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// Escape the characters with a meaning in text values
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Revert [`escape_text`]
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// First value of a comma-separated list of text values
+fn first_text_value(text: &str) -> String {
+    let mut end = text.len();
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    unescape_text(&text[..end])
+}
+
+fn format_date_time(date_time: &DateTime<Utc>) -> String {
+    date_time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Parse a DATE-TIME (in UTC, or local time when floating or with a TZID) or
+/// a DATE (midnight, local time)
+fn parse_date_time(value: &str) -> Result<DateTime<Utc>> {
+    let local = |naive: NaiveDateTime| {
+        Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|date_time| date_time.with_timezone(&Utc))
+            .with_context(|| format!("Invalid local time '{}'", value))
+    };
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .with_context(|| format!("Invalid date-time '{}'", value))?;
+        Ok(naive.and_utc())
+    } else if value.contains('T') {
+        local(
+            NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+                .with_context(|| format!("Invalid date-time '{}'", value))?,
+        )
+    } else {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d")
+            .with_context(|| format!("Invalid date '{}'", value))?;
+        local(date.and_hms_opt(0, 0, 0).unwrap())
+    }
+}
+
+/// Write a content line, folded so that no line is longer than 75 octets
+fn push_line(ics: &mut String, name: &str, value: &str) {
+    let line = format!("{}:{}", name, value);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+impl VCalendar {
+    /// Calendar of a list and its items
+    pub fn from_list(list: &TodoList, items: &[TodoItem]) -> VCalendar {
+        VCalendar {
+            name: Some(list.name.clone()),
+            todos: items
+                .iter()
+                .map(|item| VTodo::from_item(item, None))
+                .collect(),
+        }
+    }
+
+    /// Calendar of every list of a database
+    pub async fn from_store(store: &dyn TodoStore) -> Result<VCalendar> {
+        let mut todos = Vec::new();
+        for list in TodoList::get_all(store).await? {
+            for item in TodoItem::get_by_list_id(store, list.id).await? {
+                todos.push(VTodo::from_item(&item, Some(&list.name)));
+            }
+        }
+        Ok(VCalendar { name: None, todos })
+    }
+
+    /// Write the calendar in the iCalendar format
+    pub fn to_ics(&self) -> String {
+        let mut ics = String::new();
+        push_line(&mut ics, "BEGIN", "VCALENDAR");
+        push_line(&mut ics, "VERSION", "2.0");
+        push_line(&mut ics, "PRODID", PRODID);
+        if let Some(name) = &self.name {
+            push_line(&mut ics, "X-WR-CALNAME", &escape_text(name));
+        }
+
+        for todo in &self.todos {
+            push_line(&mut ics, "BEGIN", "VTODO");
+            push_line(&mut ics, "UID", &escape_text(&todo.uid));
+            let stamp = todo.last_modified.or(todo.created).unwrap_or_else(Utc::now);
+            push_line(&mut ics, "DTSTAMP", &format_date_time(&stamp));
+            push_line(&mut ics, "SUMMARY", &escape_text(&todo.summary));
+            let status = if todo.is_done {
+                "COMPLETED"
+            } else {
+                "NEEDS-ACTION"
+            };
+            push_line(&mut ics, "STATUS", status);
+            if let Some(completed) = &todo.completed {
+                push_line(&mut ics, "COMPLETED", &format_date_time(completed));
+            }
+            if let Some(priority) = &todo.priority {
+                push_line(&mut ics, "PRIORITY", &priority_number(priority).to_string());
+            }
+            if let Some(due) = &todo.due {
+                push_line(&mut ics, "DUE", &format_date_time(due));
+            }
+            if let Some(created) = &todo.created {
+                push_line(&mut ics, "CREATED", &format_date_time(created));
+            }
+            if let Some(last_modified) = &todo.last_modified {
+                push_line(&mut ics, "LAST-MODIFIED", &format_date_time(last_modified));
+            }
+            if let Some(list) = &todo.list {
+                push_line(&mut ics, "CATEGORIES", &escape_text(list));
+            }
+            push_line(&mut ics, "END", "VTODO");
+        }
+
+        push_line(&mut ics, "END", "VCALENDAR");
+        ics
+    }
+
+    /// Parse the to-dos of an iCalendar file. Other components (events,
+    /// alarms, time zones...) are skipped, and so are unknown properties
+    pub fn parse(ics: &str) -> Result<VCalendar> {
+        let mut calendar = VCalendar::default();
+        // Components being read, innermost last
+        let mut components: Vec<String> = Vec::new();
+        let mut todo: Option<VTodo> = None;
+
+        for line in unfold(ics) {
+            let (name, value) = split_content_line(&line)
+                .with_context(|| format!("Invalid content line '{}'", line))?;
+
+            match name.as_str() {
+                "BEGIN" => {
+                    let component = value.to_ascii_uppercase();
+                    if component == "VTODO"
+                        && components.last().map(String::as_str) == Some("VCALENDAR")
+                    {
+                        todo = Some(VTodo {
+                            uid: String::new(),
+                            summary: String::new(),
+                            is_done: false,
+                            completed: None,
+                            priority: None,
+                            due: None,
+                            created: None,
+                            last_modified: None,
+                            list: None,
+                        });
+                    }
+                    components.push(component);
+                }
+                "END" => {
+                    let component = components
+                        .pop()
+                        .with_context(|| format!("END:{} without a matching BEGIN", value))?;
+                    if !component.eq_ignore_ascii_case(value) {
+                        anyhow::bail!("END:{} closes BEGIN:{}", value, component);
+                    }
+                    if component == "VTODO"
+                        && let Some(todo) = todo.take()
+                    {
+                        calendar.todos.push(todo);
+                    }
+                }
+                _ => match (components.last().map(String::as_str), todo.as_mut()) {
+                    (Some("VCALENDAR"), _) if name == "X-WR-CALNAME" => {
+                        calendar.name = Some(unescape_text(value));
+                    }
+                    (Some("VTODO"), Some(todo)) => {
+                        read_todo_property(todo, &name, value)
+                            .with_context(|| format!("Invalid {} '{}'", name, value))?;
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        if let Some(component) = components.pop() {
+            anyhow::bail!("BEGIN:{} is never closed", component);
+        }
+        Ok(calendar)
+    }
+}
+
+/// Set the field of a to-do read from one of its properties
+fn read_todo_property(todo: &mut VTodo, name: &str, value: &str) -> Result<()> {
+    match name {
+        "UID" => todo.uid = unescape_text(value),
+        "SUMMARY" => todo.summary = unescape_text(value),
+        "STATUS" => todo.is_done = todo.is_done || value.eq_ignore_ascii_case("COMPLETED"),
+        "COMPLETED" => {
+            todo.completed = Some(parse_date_time(value)?);
+            todo.is_done = true;
+        }
+        "PRIORITY" => todo.priority = priority_from_number(value.trim().parse()?),
+        "DUE" => todo.due = Some(parse_date_time(value)?),
+        "CREATED" => todo.created = Some(parse_date_time(value)?),
+        "LAST-MODIFIED" => todo.last_modified = Some(parse_date_time(value)?),
+        "CATEGORIES" => todo.list = Some(first_text_value(value)).filter(|l| !l.is_empty()),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Content lines of a file, with folded lines joined back
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Split a content line into its uppercase name and its value. Parameters
+/// (e.g. `;TZID=...` or `;VALUE=DATE`) are dropped, since values are parsed
+/// from their own shape
+fn split_content_line(line: &str) -> Option<(String, &str)> {
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => {
+                let name = line[..i].split(';').next()?.trim().to_ascii_uppercase();
+                return Some((name, &line[i + 1..]));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Items created and updated by an import
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub updated: usize,
+}
+
+/// Import the to-dos of a calendar into a store. Each to-do goes to the list
+/// named `list_name` if given, or else to its own list (`CATEGORIES`), the list
+/// named after the calendar or [`DEFAULT_IMPORT_LIST`]. Lists are created when
+/// missing. To-dos already in their list (with the same uid, or else the same
+/// name) update the item, so that importing a file twice doesn't duplicate it
+pub async fn import_calendar(
+    store: &dyn TodoStore,
+    calendar: &VCalendar,
+    list_name: Option<&str>,
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    let mut lists = TodoList::get_all(store).await?;
+
+    for todo in &calendar.todos {
+        if todo.summary.trim().is_empty() {
+            continue;
+        }
+
+        let name = list_name
+            .or(todo.list.as_deref())
+            .or(calendar.name.as_deref())
+            .unwrap_or(DEFAULT_IMPORT_LIST);
+        let list = match lists.iter().find(|list| list.name == name) {
+            Some(list) => list.clone(),
+            None => {
+                let new_list = NewTodoList {
+                    name: name.to_string(),
+                };
+                let list = TodoList::create(store, new_list).await?;
+                lists.push(list.clone());
+                list
+            }
+        };
+
+        let items = TodoItem::get_by_list_id(store, list.id).await?;
+        let existing = items
+            .iter()
+            .find(|item| !todo.uid.is_empty() && item.uid == todo.uid)
+            .or_else(|| items.iter().find(|item| item.name == todo.summary));
+
+        match existing {
+            Some(item) => {
+                let unchanged = item.name == todo.summary
+                    && item.is_done == todo.is_done
+                    && item.priority == todo.priority
+                    && item.due_date == todo.due;
+                if !unchanged {
+                    let mut item = item.clone();
+                    item.update_details(
+                        store,
+                        todo.summary.clone(),
+                        todo.is_done,
+                        todo.priority.clone(),
+                        todo.due,
+                    )
+                    .await?;
+                    summary.updated += 1;
+                }
+            }
+            None => {
+                let new_item = NewTodoItem {
+                    list_id: list.id,
+                    name: todo.summary.clone(),
+                    priority: todo.priority.clone(),
+                    due_date: todo.due,
+                };
+                let mut item = TodoItem::create(store, new_item).await?;
+                if todo.is_done {
+                    item.toggle_done(store).await?;
+                }
+                summary.created += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 10, day, hour, 0, 0).unwrap()
+    }
+
+    fn groceries() -> VCalendar {
+        VCalendar {
+            name: Some("Groceries, etc.".to_string()),
+            todos: vec![
+                VTodo {
+                    uid: "77cd01".to_string(),
+                    summary: "Eggs; free-range, large\nfrom the market \\o/".to_string(),
+                    is_done: true,
+                    completed: Some(at(1, 8)),
+                    priority: Some(Priority::High),
+                    due: Some(at(1, 9)),
+                    created: Some(at(1, 7)),
+                    last_modified: Some(at(1, 8)),
+                    list: None,
+                },
+                VTodo {
+                    uid: "9ab37d".to_string(),
+                    summary: "Milk ".repeat(40).trim_end().to_string(),
+                    is_done: false,
+                    completed: None,
+                    priority: None,
+                    due: None,
+                    created: None,
+                    last_modified: None,
+                    list: Some("Errands".to_string()),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_calendar_round_trip() -> Result<()> {
+        let calendar = groceries();
+        let ics = calendar.to_ics();
+
+        // Lines are folded and end with CRLF
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(ics.contains("SUMMARY:Eggs\\; free-range\\, large\\nfrom the market \\\\o/\r\n"));
+        assert!(ics.contains("X-WR-CALNAME:Groceries\\, etc.\r\n"));
+
+        assert_eq!(VCalendar::parse(&ics)?, calendar);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_calendar_of_another_client() -> Result<()> {
+        let ics = "BEGIN:VCALENDAR\n\
+                   VERSION:2.0\n\
+                   PRODID:-//Other//Client//EN\n\
+                   BEGIN:VEVENT\n\
+                   UID:event\n\
+                   SUMMARY:Not a to-do\n\
+                   END:VEVENT\n\
+                   BEGIN:VTODO\n\
+                   UID:a1\n\
+                   SUMMARY;LANGUAGE=en:Call the \n \u{20}plumber\n\
+                   PRIORITY:3\n\
+                   STATUS:NEEDS-ACTION\n\
+                   DUE;VALUE=DATE:20251002\n\
+                   CATEGORIES:Home,Chores\n\
+                   BEGIN:VALARM\n\
+                   SUMMARY:Alarm\n\
+                   END:VALARM\n\
+                   END:VTODO\n\
+                   BEGIN:VTODO\n\
+                   UID:a2\n\
+                   SUMMARY:Pay the bill\n\
+                   PRIORITY:7\n\
+                   COMPLETED:20251001T100000Z\n\
+                   DUE;TZID=\"Europe/Paris\":20251003T120000\n\
+                   END:VTODO\n\
+                   END:VCALENDAR\n";
+
+        let calendar = VCalendar::parse(ics)?;
+        assert_eq!(calendar.name, None);
+        assert_eq!(calendar.todos.len(), 2);
+
+        let plumber = &calendar.todos[0];
+        assert_eq!(plumber.summary, "Call the  plumber");
+        assert_eq!(plumber.priority, Some(Priority::High));
+        assert!(!plumber.is_done);
+        assert_eq!(plumber.list.as_deref(), Some("Home"));
+        let midnight = NaiveDate::from_ymd_opt(2025, 10, 2)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let due = plumber.due.unwrap().with_timezone(&Local).naive_local();
+        assert_eq!(due, midnight);
+
+        let bill = &calendar.todos[1];
+        assert!(bill.is_done);
+        assert_eq!(bill.priority, Some(Priority::Low));
+        assert!(bill.due.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rejects_unbalanced_components() {
+        assert!(VCalendar::parse("BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nEND:VCALENDAR\r\n").is_err());
+        assert!(VCalendar::parse("BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:a\r\n").is_err());
+        assert!(VCalendar::parse("BEGIN:VCALENDAR\r\nno colon\r\nEND:VCALENDAR\r\n").is_err());
+    }
+}
@@ -0,0 +1,363 @@
+use crate::sync::format::{ItemSnapshot, ListSnapshot};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Change that could not be merged automatically. Local changes win, and the
+/// conflict is reported to the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncConflict {
+    /// Name of the list where the conflict happened
+    pub list_name: String,
+    /// Human-readable description of the conflict
+    pub description: String,
+}
+
+/// Three-way merge of a single value. Returns the merged value and whether both
+/// sides changed it in different ways (in which case ours is kept)
+pub(crate) fn merge_value<T: PartialEq + Clone>(
+    base: Option<&T>,
+    ours: &T,
+    theirs: &T,
+) -> (T, bool) {
+    if ours == theirs || Some(theirs) == base {
+        (ours.clone(), false)
+    } else if Some(ours) == base {
+        (theirs.clone(), false)
+    } else {
+        (ours.clone(), true)
+    }
+}
+
+/// Three-way merge of a list. `base` is the state of the last sync, `ours` the
+/// local state and `theirs` the remote state (`None` when the list doesn't exist)
+pub fn merge_list(
+    base: Option<&ListSnapshot>,
+    ours: Option<&ListSnapshot>,
+    theirs: Option<&ListSnapshot>,
+    conflicts: &mut Vec<SyncConflict>,
+) -> Option<ListSnapshot> {
+    match (base, ours, theirs) {
+        (_, Some(ours), Some(theirs)) => Some(merge_existing_list(base, ours, theirs, conflicts)),
+        (None, Some(list), None) | (None, None, Some(list)) => Some(list.clone()),
+        (Some(base), Some(list), None) | (Some(base), None, Some(list)) => {
+            // Deleted on one side: only honour the deletion if the other side didn't change it
+            if list == base {
+                None
+            } else {
+                conflicts.push(SyncConflict {
+                    list_name: list.name.clone(),
+                    description: "List was deleted on one side and modified on the other; kept it"
+                        .to_string(),
+                });
+                Some(list.clone())
+            }
+        }
+        (_, None, None) => None,
+    }
+}
+
+/// Merge a list that exists on both sides
+fn merge_existing_list(
+    base: Option<&ListSnapshot>,
+    ours: &ListSnapshot,
+    theirs: &ListSnapshot,
+    conflicts: &mut Vec<SyncConflict>,
+) -> ListSnapshot {
+    let (name, name_conflict) = merge_value(base.map(|b| &b.name), &ours.name, &theirs.name);
+    if name_conflict {
+        conflicts.push(SyncConflict {
+            list_name: name.clone(),
+            description: format!("List renamed on both sides; kept '{}'", ours.name),
+        });
+    }
+
+    // Ordering conflicts are harmless: keep ours silently
+    let (ordering, _) = merge_value(base.map(|b| &b.ordering), &ours.ordering, &theirs.ordering);
+
+    let (statuses, statuses_conflict) =
+        merge_value(base.map(|b| &b.statuses), &ours.statuses, &theirs.statuses);
+    if statuses_conflict {
+        conflicts.push(SyncConflict {
+            list_name: name.clone(),
+            description: "Board statuses changed on both sides; kept the local ones".to_string(),
+        });
+    }
+
+    let index = |list: Option<&ListSnapshot>| -> HashMap<String, ItemSnapshot> {
+        list.map(|l| l.items.iter().map(|i| (i.uid.clone(), i.clone())).collect())
+            .unwrap_or_default()
+    };
+    let base_items = index(base);
+    let our_items = index(Some(ours));
+    let their_items = index(Some(theirs));
+
+    let uids: BTreeSet<&String> = base_items
+        .keys()
+        .chain(our_items.keys())
+        .chain(their_items.keys())
+        .collect();
+
+    let mut merged_items = HashMap::new();
+    for uid in uids {
+        if let Some(item) = merge_item(
+            base_items.get(uid),
+            our_items.get(uid),
+            their_items.get(uid),
+            &name,
+            conflicts,
+        ) {
+            merged_items.insert(uid.clone(), item);
+        }
+    }
+
+    let order = merge_order(
+        &base.map(uids_of).unwrap_or_default(),
+        &uids_of(ours),
+        &uids_of(theirs),
+        &merged_items.keys().cloned().collect(),
+    );
+
+    ListSnapshot {
+        uid: ours.uid.clone(),
+        name,
+        ordering,
+        statuses,
+        items: order
+            .into_iter()
+            .filter_map(|uid| merged_items.remove(&uid))
+            .collect(),
+    }
+}
+
+/// Three-way merge of an item, field by field
+fn merge_item(
+    base: Option<&ItemSnapshot>,
+    ours: Option<&ItemSnapshot>,
+    theirs: Option<&ItemSnapshot>,
+    list_name: &str,
+    conflicts: &mut Vec<SyncConflict>,
+) -> Option<ItemSnapshot> {
+    match (base, ours, theirs) {
+        (_, Some(ours), Some(theirs)) => {
+            let (name, name_conflict) =
+                merge_value(base.map(|b| &b.name), &ours.name, &theirs.name);
+            let (is_done, done_conflict) =
+                merge_value(base.map(|b| &b.is_done), &ours.is_done, &theirs.is_done);
+            let (priority, priority_conflict) =
+                merge_value(base.map(|b| &b.priority), &ours.priority, &theirs.priority);
+            let (due_date, due_date_conflict) =
+                merge_value(base.map(|b| &b.due_date), &ours.due_date, &theirs.due_date);
+            let (status, status_conflict) =
+                merge_value(base.map(|b| &b.status), &ours.status, &theirs.status);
+
+            if name_conflict
+                || done_conflict
+                || priority_conflict
+                || due_date_conflict
+                || status_conflict
+            {
+                conflicts.push(SyncConflict {
+                    list_name: list_name.to_string(),
+                    description: format!(
+                        "Item '{}' changed on both sides; kept the local version",
+                        ours.name
+                    ),
+                });
+            }
+
+            Some(ItemSnapshot {
+                uid: ours.uid.clone(),
+                name,
+                is_done,
+                priority,
+                due_date,
+                status,
+            })
+        }
+        (None, Some(item), None) | (None, None, Some(item)) => Some(item.clone()),
+        (Some(base), Some(item), None) | (Some(base), None, Some(item)) => {
+            if item == base {
+                None
+            } else {
+                conflicts.push(SyncConflict {
+                    list_name: list_name.to_string(),
+                    description: format!(
+                        "Item '{}' was deleted on one side and modified on the other; kept it",
+                        item.name
+                    ),
+                });
+                Some(item.clone())
+            }
+        }
+        (_, None, None) => None,
+    }
+}
+
+fn uids_of(list: &ListSnapshot) -> Vec<String> {
+    list.items.iter().map(|i| i.uid.clone()).collect()
+}
+
+/// Merge the order of the items. The order of the side that reordered items wins
+/// (ours if both did); items only known to the other side are inserted after the
+/// item preceding them on that side
+fn merge_order(
+    base: &[String],
+    ours: &[String],
+    theirs: &[String],
+    keep: &HashSet<String>,
+) -> Vec<String> {
+    let relative_order = |order: &[String], other: &[String]| -> Vec<String> {
+        let other: HashSet<&String> = other.iter().collect();
+        order
+            .iter()
+            .filter(|u| other.contains(u))
+            .cloned()
+            .collect()
+    };
+    let ours_reordered = relative_order(ours, base) != relative_order(base, ours);
+
+    let (primary, secondary) = if ours_reordered {
+        (ours, theirs)
+    } else {
+        (theirs, ours)
+    };
+
+    let mut order: Vec<String> = primary
+        .iter()
+        .filter(|u| keep.contains(*u))
+        .cloned()
+        .collect();
+
+    for (i, uid) in secondary.iter().enumerate() {
+        if !keep.contains(uid) || order.contains(uid) {
+            continue;
+        }
+        let position = secondary[..i]
+            .iter()
+            .rev()
+            .find_map(|previous| order.iter().position(|u| u == previous))
+            .map_or(0, |p| p + 1);
+        order.insert(position, uid.clone());
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn item(uid: &str, name: &str, is_done: bool) -> ItemSnapshot {
+        ItemSnapshot {
+            uid: uid.to_string(),
+            name: name.to_string(),
+            is_done,
+            priority: None,
+            due_date: None,
+            status: None,
+        }
+    }
+
+    fn list(name: &str, items: Vec<ItemSnapshot>) -> ListSnapshot {
+        ListSnapshot {
+            uid: "list".to_string(),
+            name: name.to_string(),
+            ordering: 1,
+            statuses: None,
+            items,
+        }
+    }
+
+    #[test]
+    fn test_independent_changes_are_merged() {
+        let base = list(
+            "Groceries",
+            vec![item("a", "Milk", false), item("b", "Eggs", false)],
+        );
+        let ours = list(
+            "Groceries",
+            vec![item("a", "Milk", true), item("b", "Eggs", false)],
+        );
+        let theirs = list(
+            "Food",
+            vec![
+                item("a", "Milk", false),
+                item("b", "Free range eggs", false),
+                item("c", "Bread", false),
+            ],
+        );
+
+        let mut conflicts = Vec::new();
+        let merged = merge_list(Some(&base), Some(&ours), Some(&theirs), &mut conflicts).unwrap();
+
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.name, "Food");
+        assert_eq!(
+            merged.items,
+            vec![
+                item("a", "Milk", true),
+                item("b", "Free range eggs", false),
+                item("c", "Bread", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_concurrent_changes_keep_ours_and_report() {
+        let base = list("Groceries", vec![item("a", "Milk", false)]);
+        let ours = list("Groceries", vec![item("a", "Oat milk", false)]);
+        let theirs = list("Groceries", vec![item("a", "Soy milk", false)]);
+
+        let mut conflicts = Vec::new();
+        let merged = merge_list(Some(&base), Some(&ours), Some(&theirs), &mut conflicts).unwrap();
+
+        assert_eq!(merged.items, vec![item("a", "Oat milk", false)]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].list_name, "Groceries");
+    }
+
+    #[test]
+    fn test_deletions() {
+        let base = list(
+            "Groceries",
+            vec![item("a", "Milk", false), item("b", "Eggs", false)],
+        );
+        let ours = list("Groceries", vec![item("b", "Eggs", false)]);
+        let theirs = list(
+            "Groceries",
+            vec![item("a", "Milk", false), item("b", "Eggs", true)],
+        );
+
+        let mut conflicts = Vec::new();
+        let merged = merge_list(Some(&base), Some(&ours), Some(&theirs), &mut conflicts).unwrap();
+        assert_eq!(merged.items, vec![item("b", "Eggs", true)]);
+        assert!(conflicts.is_empty());
+
+        // Whole list deleted remotely and untouched locally
+        assert_eq!(
+            merge_list(Some(&base), Some(&base), None, &mut conflicts),
+            None
+        );
+
+        // Deleted remotely but modified locally: kept and reported
+        assert_eq!(
+            merge_list(Some(&base), Some(&ours), None, &mut conflicts),
+            Some(ours.clone())
+        );
+        assert_eq!(conflicts.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_order() {
+        let keep: HashSet<String> = ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect();
+        let to_vec = |uids: &[&str]| uids.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // Theirs reordered, ours appended an item after "b"
+        let order = merge_order(
+            &to_vec(&["a", "b", "c"]),
+            &to_vec(&["a", "b", "d", "c"]),
+            &to_vec(&["c", "b", "a"]),
+            &keep,
+        );
+        assert_eq!(order, to_vec(&["c", "b", "d", "a"]));
+    }
+}
@@ -0,0 +1,550 @@
+//! Sync of databases between machines.
+//!
+//! Two mechanisms are available. With git sync, a database is mirrored to a directory inside a git working tree, one plain-text
+//! file per list (see [`format::ListSnapshot`]). Local changes are committed as they
+//! happen; syncing fetches the remote, three-way merges lists and items by their
+//! stable uids, imports the result back into SQLite and pushes.
+//!
+//! With the change log (see [`change_log`]), every edit of a field is recorded with
+//! a hybrid logical clock. Logs are exchanged as files through any shared folder
+//! and merged deterministically: the latest change of each field wins and
+//! deletions win over edits.
+//!
+//! Lists and items can also be exchanged with calendar clients as iCalendar
+//! to-dos (see [`ical`]), or kept in sync with the task collections of a
+//! CalDAV server (see [`caldav`]). Tasks are also exchanged with Taskwarrior in
+//! the JSON format of `task export` (see [`taskwarrior`]).
+
+pub mod caldav;
+pub mod change_log;
+pub mod changes;
+pub mod dav;
+pub mod format;
+pub mod git;
+pub mod hlc;
+pub mod ical;
+pub mod merge;
+pub mod taskwarrior;
+
+use crate::db::config::GitSyncConfig;
+use crate::db::crypto::{self, seal_text};
+use crate::db::models::{TodoItem, TodoList};
+use crate::db::sqlite_store::park_orderings;
+use crate::db::store::{ORDERING_GAP, deduplicate_orderings};
+use crate::sync::format::{ItemSnapshot, LIST_FILE_EXTENSION, ListSnapshot};
+use crate::sync::git::GitRepo;
+use crate::sync::merge::{SyncConflict, merge_list};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use tokio::task::JoinHandle;
+
+/// Remote used when none is configured
+const DEFAULT_REMOTE: &str = "origin";
+
+/// Lists indexed by uid
+type Snapshots = BTreeMap<String, ListSnapshot>;
+
+/// Git sync or commit running in the background, see [`GitSync::spawn_sync`]
+/// and [`GitSync::spawn_commit`]
+pub struct RunningGitSync(JoinHandle<(GitSync, Result<Vec<SyncConflict>>)>);
+
+impl RunningGitSync {
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+
+    /// Wait for the end of the sync, and get the sync back (unless it panicked)
+    /// with its result
+    pub async fn join(self) -> (Option<GitSync>, Result<Vec<SyncConflict>>) {
+        match self.0.await {
+            Ok((sync, result)) => (Some(sync), result),
+            Err(e) => (None, Err(e.into())),
+        }
+    }
+}
+
+/// Git sync of a single database
+pub struct GitSync {
+    repo: GitRepo,
+    remote: String,
+    db_name: String,
+    /// Lists as last written to the sync directory, to skip exports when nothing changed
+    last_export: Option<Snapshots>,
+}
+
+impl GitSync {
+    /// Open (or initialize) the git working tree configured for a database
+    pub async fn new(config: &GitSyncConfig, db_name: &str) -> Result<GitSync> {
+        let path = match config.path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()
+                .with_context(|| "Could not find home directory")?
+                .join(rest),
+            None => PathBuf::from(&config.path),
+        };
+
+        Ok(GitSync {
+            repo: GitRepo::open(&path).await?,
+            remote: config
+                .remote
+                .clone()
+                .unwrap_or_else(|| DEFAULT_REMOTE.to_string()),
+            db_name: db_name.to_string(),
+            last_export: None,
+        })
+    }
+
+    /// Write the lists of the database to the sync directory and commit them if
+    /// anything changed. Returns whether a commit was made
+    pub async fn commit_changes(&mut self, pool: &SqlitePool) -> Result<bool> {
+        // The sync directory holds the names in plain text
+        if crypto::is_encrypted(pool).await? {
+            anyhow::bail!(
+                "Git sync is not available for encrypted database {}",
+                self.db_name
+            );
+        }
+        let lists = snapshots_from_db(pool).await?;
+        if self.last_export.as_ref() == Some(&lists) {
+            return Ok(false);
+        }
+
+        self.write_snapshots(&lists)?;
+        let committed = self
+            .repo
+            .commit_all(&format!("judo: update {}", self.db_name))
+            .await?;
+        self.last_export = Some(lists);
+
+        Ok(committed)
+    }
+
+    /// Commit local changes, merge the changes from the remote into the database
+    /// and push the result. Returns the conflicts that were resolved by keeping the
+    /// local version
+    pub async fn sync(&mut self, pool: &SqlitePool) -> Result<Vec<SyncConflict>> {
+        self.commit_changes(pool).await?;
+
+        if !self.repo.has_remote(&self.remote).await {
+            return Ok(Vec::new());
+        }
+
+        let branch = self.repo.current_branch().await?;
+        self.repo.run(&["fetch", "--quiet", &self.remote]).await?;
+
+        let remote_ref = format!("{}/{}", self.remote, branch);
+        let Some(theirs) = self.repo.rev_parse(&remote_ref).await else {
+            // Nothing on the remote yet
+            self.push(&branch).await?;
+            return Ok(Vec::new());
+        };
+
+        let Some(ours) = self.repo.rev_parse("HEAD").await else {
+            // Nothing local yet: take the remote as is
+            self.repo
+                .run(&["merge", "--quiet", "--ff-only", &theirs])
+                .await?;
+            return self.import_head(pool).await.map(|_| Vec::new());
+        };
+
+        let base = self
+            .repo
+            .run(&["merge-base", &ours, &theirs])
+            .await
+            .ok()
+            .map(|hash| hash.trim().to_string());
+
+        if base.as_deref() == Some(theirs.as_str()) {
+            // Remote has nothing new
+            self.push(&branch).await?;
+            return Ok(Vec::new());
+        }
+
+        if base.as_deref() == Some(ours.as_str()) {
+            // Nothing new locally: fast-forward
+            self.repo
+                .run(&["merge", "--quiet", "--ff-only", &theirs])
+                .await?;
+            return self.import_head(pool).await.map(|_| Vec::new());
+        }
+
+        // Both sides changed: merge lists and items
+        let base_lists = match &base {
+            Some(base) => self.snapshots_at(base).await?,
+            None => Snapshots::new(),
+        };
+        let our_lists = self.snapshots_at(&ours).await?;
+        let their_lists = self.snapshots_at(&theirs).await?;
+
+        let mut conflicts = Vec::new();
+        let uids: BTreeSet<&String> = base_lists
+            .keys()
+            .chain(our_lists.keys())
+            .chain(their_lists.keys())
+            .collect();
+        let merged: Snapshots = uids
+            .into_iter()
+            .filter_map(|uid| {
+                merge_list(
+                    base_lists.get(uid),
+                    our_lists.get(uid),
+                    their_lists.get(uid),
+                    &mut conflicts,
+                )
+                .map(|list| (uid.clone(), list))
+            })
+            .collect();
+
+        self.commit_merge(&theirs, base.is_none(), &merged).await?;
+        import_snapshots(pool, &merged).await?;
+        self.last_export = Some(merged);
+        self.push(&branch).await?;
+
+        Ok(conflicts)
+    }
+
+    /// Sync in a task of its own, so that the TUI keeps handling keys while git
+    /// talks to the remote
+    pub fn spawn_sync(mut self, pool: SqlitePool) -> RunningGitSync {
+        RunningGitSync(tokio::spawn(async move {
+            let result = self.sync(&pool).await;
+            (self, result)
+        }))
+    }
+
+    /// Commit the local changes in a task of its own
+    pub fn spawn_commit(mut self, pool: SqlitePool) -> RunningGitSync {
+        RunningGitSync(tokio::spawn(async move {
+            let result = self.commit_changes(&pool).await.map(|_| Vec::new());
+            (self, result)
+        }))
+    }
+
+    /// Let git merge the remote (so that files outside the sync directory are
+    /// merged as usual), replace the lists with the merged ones and commit
+    async fn commit_merge(&self, theirs: &str, unrelated: bool, merged: &Snapshots) -> Result<()> {
+        let mut args = vec!["merge", "--quiet", "--no-ff", "--no-commit"];
+        if unrelated {
+            args.push("--allow-unrelated-histories");
+        }
+        args.push(theirs);
+
+        // Conflicts are expected here: they are resolved right below
+        let _ = self.repo.run(&args).await;
+        if self.repo.rev_parse("MERGE_HEAD").await.is_none() {
+            anyhow::bail!("Failed to merge {}", theirs);
+        }
+
+        self.write_snapshots(merged)?;
+        self.repo.run(&["add", "--all", "--", "."]).await?;
+
+        let unresolved = self
+            .repo
+            .run(&["diff", "--name-only", "--diff-filter=U"])
+            .await?;
+        if !unresolved.trim().is_empty() {
+            let _ = self.repo.run(&["merge", "--abort"]).await;
+            anyhow::bail!(
+                "Merge conflicts outside of the sync directory: {}",
+                unresolved.trim()
+            );
+        }
+
+        self.repo
+            .run(&[
+                "commit",
+                "--quiet",
+                "-m",
+                &format!("judo: merge {}", self.db_name),
+            ])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn push(&self, branch: &str) -> Result<()> {
+        if self.repo.rev_parse("HEAD").await.is_some() {
+            self.repo
+                .run(&["push", "--quiet", &self.remote, &format!("HEAD:{}", branch)])
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Import the lists of the current commit into the database
+    async fn import_head(&mut self, pool: &SqlitePool) -> Result<()> {
+        let lists = self.snapshots_at("HEAD").await?;
+        import_snapshots(pool, &lists).await?;
+        self.last_export = Some(lists);
+        Ok(())
+    }
+
+    /// Read the lists stored at the given revision
+    async fn snapshots_at(&self, rev: &str) -> Result<Snapshots> {
+        let mut lists = Snapshots::new();
+        for file in self.repo.list_files(rev).await? {
+            if !file.ends_with(&format!(".{}", LIST_FILE_EXTENSION)) {
+                continue;
+            }
+            let text = self.repo.show(rev, &file).await?;
+            let list = ListSnapshot::from_text(&text)
+                .with_context(|| format!("Failed to parse {} at {}", file, rev))?;
+            lists.insert(list.uid.clone(), list);
+        }
+        Ok(lists)
+    }
+
+    /// Write the lists to the sync directory, removing files of deleted lists
+    fn write_snapshots(&self, lists: &Snapshots) -> Result<()> {
+        let dir = self.repo.dir();
+        let file_names: HashSet<String> = lists.values().map(|l| l.file_name()).collect();
+
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_list_file = path
+                .extension()
+                .is_some_and(|extension| extension == LIST_FILE_EXTENSION);
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if is_list_file && !file_names.contains(&name) {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+
+        for list in lists.values() {
+            let path = dir.join(list.file_name());
+            std::fs::write(&path, list.to_text())
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read all lists and items of the database
+pub async fn snapshots_from_db(pool: &SqlitePool) -> Result<Snapshots> {
+    let mut lists = Snapshots::new();
+
+    for list in TodoList::get_all(pool).await? {
+        let items = TodoItem::get_by_list_id(pool, list.id)
+            .await?
+            .into_iter()
+            .map(|item| ItemSnapshot {
+                uid: item.uid,
+                name: item.name,
+                is_done: item.is_done,
+                priority: item.priority,
+                due_date: item.due_date,
+                status: item.status,
+            })
+            .collect();
+
+        lists.insert(
+            list.uid.clone(),
+            ListSnapshot {
+                uid: list.uid,
+                name: list.name,
+                ordering: list.ordering,
+                statuses: list.statuses,
+                items,
+            },
+        );
+    }
+
+    Ok(lists)
+}
+
+/// Make the database match the given lists: lists and items are matched by uid,
+/// so unchanged rows keep their ids and timestamps
+pub async fn import_snapshots(pool: &SqlitePool, lists: &Snapshots) -> Result<()> {
+    let now = Utc::now();
+    let mut tx = pool
+        .begin()
+        .await
+        .with_context(|| "Failed to start transaction")?;
+
+    // Remove lists and items that no longer exist
+    let list_uids: HashSet<&str> = lists.keys().map(|uid| uid.as_str()).collect();
+    let item_uids: HashSet<&str> = lists
+        .values()
+        .flat_map(|l| l.items.iter().map(|i| i.uid.as_str()))
+        .collect();
+
+    let existing_lists: Vec<(i64, String, i64)> =
+        sqlx::query_as("SELECT id, uid, ordering FROM todo_lists")
+            .fetch_all(&mut *tx)
+            .await
+            .with_context(|| "Failed to fetch lists")?;
+    for (id, uid, _) in &existing_lists {
+        if !list_uids.contains(uid.as_str()) {
+            sqlx::query("DELETE FROM todo_lists WHERE id = ?1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| "Failed to delete list")?;
+        }
+    }
+
+    let existing_items: Vec<(i64, String, String, i64)> = sqlx::query_as(
+        r#"
+        SELECT i.id, i.uid, l.uid, i.ordering
+        FROM todo_items i JOIN todo_lists l ON l.id = i.list_id
+        "#,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .with_context(|| "Failed to fetch items")?;
+    for (id, uid, _, _) in &existing_items {
+        if !item_uids.contains(uid.as_str()) {
+            sqlx::query("DELETE FROM todo_items WHERE id = ?1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| "Failed to delete item")?;
+        }
+    }
+
+    // Orderings are unique among siblings: rows that change their ordering are
+    // moved out of the way first
+    let list_rows: Vec<(&str, i64)> = lists
+        .values()
+        .map(|list| (list.uid.as_str(), list.ordering))
+        .collect();
+    let list_orderings: HashMap<&str, i64> =
+        deduplicate_orderings(&list_rows).into_iter().collect();
+
+    let current_items: HashMap<&str, (&str, i64)> = existing_items
+        .iter()
+        .map(|(_, uid, list_uid, ordering)| (uid.as_str(), (list_uid.as_str(), *ordering)))
+        .collect();
+    let item_orderings: HashMap<&str, i64> = lists
+        .values()
+        .flat_map(|list| item_orderings(list, &current_items))
+        .collect();
+
+    let moved_lists: Vec<&str> = existing_lists
+        .iter()
+        .filter(|(_, uid, ordering)| {
+            list_orderings
+                .get(uid.as_str())
+                .is_some_and(|new_ordering| new_ordering != ordering)
+        })
+        .map(|(_, uid, _)| uid.as_str())
+        .collect();
+    park_orderings(&mut tx, "todo_lists", &moved_lists).await?;
+
+    let item_lists: HashMap<&str, &str> = lists
+        .values()
+        .flat_map(|l| l.items.iter().map(|i| (i.uid.as_str(), l.uid.as_str())))
+        .collect();
+    let moved_items: Vec<&str> = existing_items
+        .iter()
+        .filter(|(_, uid, list_uid, ordering)| {
+            let uid = uid.as_str();
+            item_lists
+                .get(uid)
+                .is_some_and(|new_list| new_list != list_uid)
+                || item_orderings.get(uid).is_some_and(|new| new != ordering)
+        })
+        .map(|(_, uid, _, _)| uid.as_str())
+        .collect();
+    park_orderings(&mut tx, "todo_items", &moved_items).await?;
+
+    // Insert or update the rest
+    for list in lists.values() {
+        let list_id: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO todo_lists (uid, name, ordering, statuses, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+            ON CONFLICT(uid) DO UPDATE SET
+                updated_at = CASE
+                    WHEN name != excluded.name OR ordering != excluded.ordering
+                        OR statuses IS NOT excluded.statuses
+                    THEN excluded.updated_at ELSE updated_at END,
+                name = excluded.name,
+                ordering = excluded.ordering,
+                statuses = excluded.statuses
+            RETURNING id
+            "#,
+        )
+        .bind(&list.uid)
+        .bind(seal_text(pool, &list.name)?)
+        .bind(list_orderings[list.uid.as_str()])
+        .bind(&list.statuses)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await
+        .with_context(|| format!("Failed to import list {}", list.name))?;
+
+        for item in &list.items {
+            sqlx::query(
+                r#"
+                INSERT INTO todo_items (uid, list_id, name, is_done, priority, due_date, ordering, status, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
+                ON CONFLICT(uid) DO UPDATE SET
+                    updated_at = CASE
+                        WHEN list_id != excluded.list_id
+                            OR name != excluded.name
+                            OR is_done != excluded.is_done
+                            OR priority IS NOT excluded.priority
+                            OR due_date IS NOT excluded.due_date
+                            OR status IS NOT excluded.status
+                        THEN excluded.updated_at ELSE updated_at END,
+                    list_id = excluded.list_id,
+                    name = excluded.name,
+                    is_done = excluded.is_done,
+                    priority = excluded.priority,
+                    due_date = excluded.due_date,
+                    ordering = excluded.ordering,
+                    status = excluded.status
+                "#,
+            )
+            .bind(&item.uid)
+            .bind(list_id)
+            .bind(seal_text(pool, &item.name)?)
+            .bind(item.is_done)
+            .bind(&item.priority)
+            .bind(item.due_date)
+            .bind(item_orderings[item.uid.as_str()])
+            .bind(&item.status)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to import item {}", item.name))?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .with_context(|| "Failed to commit transaction")?;
+
+    Ok(())
+}
+
+/// Orderings of the items of a list, in the order of the file. Items of the
+/// list keep their ordering as long as it comes after the previous one, so that
+/// moving an item in the file only changes that item
+fn item_orderings<'a>(
+    list: &'a ListSnapshot,
+    current: &HashMap<&str, (&str, i64)>,
+) -> Vec<(&'a str, i64)> {
+    let mut previous = 0;
+    list.items
+        .iter()
+        .map(|item| {
+            let ordering = match current.get(item.uid.as_str()) {
+                Some(&(list_uid, ordering)) if list_uid == list.uid && ordering > previous => {
+                    ordering
+                }
+                _ => previous + ORDERING_GAP,
+            };
+            previous = ordering;
+            (item.uid.as_str(), ordering)
+        })
+        .collect()
+}
@@ -0,0 +1,412 @@
+use crate::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use crate::db::store::TodoStore;
+use crate::sync::ical::DEFAULT_IMPORT_LIST;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// Fields computed by Taskwarrior itself, which say nothing about a task
+const COMPUTED_FIELDS: [&str; 2] = ["id", "urgency"];
+
+/// Status of a Taskwarrior task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Pending,
+    Completed,
+    Deleted,
+    Waiting,
+    /// Template of a recurring task, whose instances are pending tasks
+    Recurring,
+}
+
+/// Annotation of a task: a timestamped note
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    #[serde(default, with = "task_date")]
+    pub entry: Option<DateTime<Utc>>,
+    pub description: String,
+}
+
+/// Task in the JSON format of `task export` and `task import`:
+///
+/// ```json
+/// [{"uuid":"77cd01a2-...","description":"Eggs","status":"completed",
+///   "project":"Groceries","priority":"H","due":"20251001T090000Z",
+///   "entry":"20250930T180000Z","modified":"20251001T081500Z",
+///   "end":"20251001T081500Z","tags":["shop"]}]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    #[serde(default)]
+    pub uuid: String,
+    pub description: String,
+    pub status: TaskStatus,
+    /// Name of the list of the task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// "H", "M" or "L"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(default, with = "task_date", skip_serializing_if = "Option::is_none")]
+    pub due: Option<DateTime<Utc>>,
+    #[serde(default, with = "task_date", skip_serializing_if = "Option::is_none")]
+    pub entry: Option<DateTime<Utc>>,
+    #[serde(default, with = "task_date", skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
+    /// When the task was completed or deleted
+    #[serde(default, with = "task_date", skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    /// Other fields (wait, scheduled, recur, depends, user defined
+    /// attributes...), which Judo doesn't support
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+impl Task {
+    /// Task of an item of a list
+    pub fn from_item(item: &TodoItem, list: &TodoList) -> Task {
+        Task {
+            uuid: uuid_of_uid(&item.uid),
+            description: item.name.clone(),
+            status: if item.is_done {
+                TaskStatus::Completed
+            } else {
+                TaskStatus::Pending
+            },
+            project: Some(list.name.clone()),
+            priority: item
+                .priority
+                .as_ref()
+                .map(|p| priority_letter(p).to_string()),
+            due: item.due_date,
+            entry: Some(item.created_at),
+            modified: Some(item.updated_at),
+            end: item.is_done.then_some(item.updated_at),
+            tags: Vec::new(),
+            annotations: Vec::new(),
+            other: BTreeMap::new(),
+        }
+    }
+
+    /// Fields of the task that can't be imported
+    fn unsupported_fields(&self) -> Vec<String> {
+        let mut fields = Vec::new();
+        if !self.tags.is_empty() {
+            fields.push("tags".to_string());
+        }
+        if !self.annotations.is_empty() {
+            fields.push("annotations".to_string());
+        }
+        if self.priority.is_some() && self.priority().is_none() {
+            fields.push("priority".to_string());
+        }
+        fields.extend(
+            self.other
+                .keys()
+                .filter(|key| !COMPUTED_FIELDS.contains(&key.as_str()))
+                .cloned(),
+        );
+        fields
+    }
+
+    fn priority(&self) -> Option<Priority> {
+        match self.priority.as_deref()? {
+            "H" => Some(Priority::High),
+            "M" => Some(Priority::Medium),
+            "L" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+}
+
+fn priority_letter(priority: &Priority) -> &'static str {
+    match priority {
+        Priority::High => "H",
+        Priority::Medium => "M",
+        Priority::Low => "L",
+    }
+}
+
+/// Whether a string is a uid as generated by Judo: 32 lowercase hex digits
+fn is_uid(uid: &str) -> bool {
+    uid.len() == 32 && uid.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+}
+
+/// UUID written with dashes (as Taskwarrior does) of a uid of 32 hex digits
+fn uuid_of_uid(uid: &str) -> String {
+    if uid.len() != 32 || !uid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return uid.to_string();
+    }
+    format!(
+        "{}-{}-{}-{}-{}",
+        &uid[..8],
+        &uid[8..12],
+        &uid[12..16],
+        &uid[16..20],
+        &uid[20..]
+    )
+}
+
+/// Dates of Taskwarrior: "20251001T090000Z", or RFC 3339 as written by some
+/// tools
+mod task_date {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    const FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+    pub fn serialize<S: Serializer>(
+        date: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => serializer.serialize_str(&date.format(FORMAT).to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        let Some(text) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        NaiveDateTime::parse_from_str(&text, FORMAT)
+            .map(|date| date.and_utc())
+            .or_else(|_| DateTime::parse_from_rfc3339(&text).map(|date| date.to_utc()))
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom(format!("invalid date '{}'", text)))
+    }
+}
+
+/// Parse the output of `task export`
+pub fn parse_tasks(json: &str) -> Result<Vec<Task>> {
+    serde_json::from_str(json).with_context(|| "Invalid Taskwarrior export")
+}
+
+/// Tasks of the items of a store, or of one of its lists, in the order of the
+/// lists
+pub async fn export_tasks(store: &dyn TodoStore, list_name: Option<&str>) -> Result<Vec<Task>> {
+    let lists = TodoList::get_all(store).await?;
+    if let Some(name) = list_name
+        && !lists.iter().any(|list| list.name == name)
+    {
+        anyhow::bail!("No list named '{}'", name);
+    }
+
+    let mut tasks = Vec::new();
+    for list in lists
+        .iter()
+        .filter(|list| list_name.is_none_or(|name| list.name == name))
+    {
+        for item in TodoItem::get_by_list_id(store, list.id).await? {
+            tasks.push(Task::from_item(&item, list));
+        }
+    }
+    Ok(tasks)
+}
+
+/// What an import did, and what it couldn't import
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    /// Deleted tasks and templates of recurring tasks, which aren't imported
+    pub skipped: usize,
+    /// Number of imported tasks with each field Judo doesn't support
+    pub unsupported: BTreeMap<String, usize>,
+}
+
+impl ImportReport {
+    /// Lines describing the import
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Imported {} new items and updated {}",
+            self.created, self.updated
+        )];
+        if self.skipped > 0 {
+            lines.push(format!(
+                "Skipped {} deleted or recurring template tasks",
+                self.skipped
+            ));
+        }
+        if !self.unsupported.is_empty() {
+            lines.push("Fields that were not imported (not supported yet):".to_string());
+            for (field, count) in &self.unsupported {
+                let tasks = if *count == 1 { "task" } else { "tasks" };
+                lines.push(format!("  {}: {} {}", field, count, tasks));
+            }
+        }
+        lines
+    }
+}
+
+/// Import tasks into a store. Each task goes to the list named `list_name` if
+/// given, or else to the list of its project ([`DEFAULT_IMPORT_LIST`] without
+/// one). Lists are created when missing. Tasks already in their list (with the
+/// same uuid, or else the same description) update the item, so that importing
+/// an export twice doesn't duplicate it. New items keep the uuid of their task
+/// as uid (unless another item has it) and its entry time as creation time.
+/// Waiting tasks are imported as pending
+pub async fn import_tasks(
+    store: &dyn TodoStore,
+    tasks: &[Task],
+    list_name: Option<&str>,
+) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut lists = TodoList::get_all(store).await?;
+    let mut uids = HashSet::new();
+    for list in &lists {
+        let items = TodoItem::get_by_list_id(store, list.id).await?;
+        uids.extend(items.into_iter().map(|item| item.uid));
+    }
+
+    for task in tasks {
+        if matches!(task.status, TaskStatus::Deleted | TaskStatus::Recurring)
+            || task.description.trim().is_empty()
+        {
+            report.skipped += 1;
+            continue;
+        }
+        for field in task.unsupported_fields() {
+            *report.unsupported.entry(field).or_default() += 1;
+        }
+
+        let name = list_name
+            .or(task.project.as_deref())
+            .unwrap_or(DEFAULT_IMPORT_LIST);
+        let list = match lists.iter().find(|list| list.name == name) {
+            Some(list) => list.clone(),
+            None => {
+                let new_list = NewTodoList {
+                    name: name.to_string(),
+                };
+                let list = TodoList::create(store, new_list).await?;
+                lists.push(list.clone());
+                list
+            }
+        };
+
+        let is_done = task.status == TaskStatus::Completed;
+        let priority = task.priority();
+        let uid = task.uuid.replace('-', "").to_ascii_lowercase();
+        let items = TodoItem::get_by_list_id(store, list.id).await?;
+        let existing = items
+            .iter()
+            .find(|item| !uid.is_empty() && item.uid == uid)
+            .or_else(|| items.iter().find(|item| item.name == task.description));
+
+        let mut item = match existing {
+            Some(item) => {
+                let unchanged = item.name == task.description
+                    && item.is_done == is_done
+                    && item.priority == priority
+                    && item.due_date == task.due;
+                if unchanged {
+                    continue;
+                }
+                let mut item = item.clone();
+                item.update_details(store, task.description.clone(), is_done, priority, task.due)
+                    .await?;
+                report.updated += 1;
+                item
+            }
+            None => {
+                let new_item = NewTodoItem {
+                    list_id: list.id,
+                    name: task.description.clone(),
+                    priority,
+                    due_date: task.due,
+                };
+                let mut item = TodoItem::create(store, new_item).await?;
+                if is_uid(&uid) && uids.insert(uid.clone()) {
+                    item.uid = uid;
+                }
+                item.created_at = task.entry.unwrap_or(item.created_at);
+                store
+                    .set_item_origin(item.id, &item.uid, item.created_at)
+                    .await?;
+                if is_done {
+                    item.toggle_done(store).await?;
+                }
+                report.created += 1;
+                item
+            }
+        };
+
+        // Keep the time of the last change made in Taskwarrior
+        if let Some(modified) = task.end.filter(|_| is_done).or(task.modified) {
+            item.updated_at = modified;
+            store.update_item(&item).await?;
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    const EXPORT: &str = r#"[
+        {"id":0,"description":"Eggs","end":"20251001T081500Z",
+         "entry":"20250930T180000Z","modified":"20251001T081500Z",
+         "project":"Groceries","status":"completed",
+         "uuid":"77cd01a2-5c1e-4b8e-9a7f-0123456789ab","urgency":0},
+        {"id":1,"description":"Report","due":"20251003T170000Z",
+         "entry":"20250930T180000Z","priority":"H","status":"pending",
+         "tags":["work","q4"],"annotations":[{"entry":"20250930T180500Z",
+         "description":"ask Sam"}],"estimate":"2h",
+         "uuid":"0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0","urgency":8.2}
+    ]"#;
+
+    #[test]
+    fn test_parse_tasks() -> Result<()> {
+        let tasks = parse_tasks(EXPORT)?;
+        assert_eq!(tasks.len(), 2);
+
+        assert_eq!(tasks[0].status, TaskStatus::Completed);
+        assert_eq!(tasks[0].project.as_deref(), Some("Groceries"));
+        assert_eq!(
+            tasks[0].end,
+            Some(Utc.with_ymd_and_hms(2025, 10, 1, 8, 15, 0).unwrap())
+        );
+        assert!(tasks[0].unsupported_fields().is_empty());
+
+        assert_eq!(tasks[1].priority(), Some(Priority::High));
+        assert_eq!(
+            tasks[1].due,
+            Some(Utc.with_ymd_and_hms(2025, 10, 3, 17, 0, 0).unwrap())
+        );
+        assert_eq!(tasks[1].annotations[0].description, "ask Sam");
+        assert_eq!(
+            tasks[1].unsupported_fields(),
+            vec!["tags", "annotations", "estimate"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_task_round_trip() -> Result<()> {
+        let tasks = parse_tasks(EXPORT)?;
+        let json = serde_json::to_string(&tasks)?;
+        assert_eq!(parse_tasks(&json)?, tasks);
+        assert!(json.contains(r#""due":"20251003T170000Z""#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_uuid_of_uid() {
+        assert_eq!(
+            uuid_of_uid("77cd01a25c1e4b8e9a7f0123456789ab"),
+            "77cd01a2-5c1e-4b8e-9a7f-0123456789ab"
+        );
+        assert_eq!(uuid_of_uid("not-hex"), "not-hex");
+    }
+}
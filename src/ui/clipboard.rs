@@ -0,0 +1,143 @@
+//! Copy and paste of lists and items. Yanked lists and items are kept as
+//! Markdown checklists, both in the register of the app and in the system
+//! clipboard (set with an OSC 52 escape sequence, which the terminal forwards
+//! to the clipboard even over SSH or inside tmux)
+
+use crate::db::models::TodoItem;
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::io::{IsTerminal, Write};
+
+/// List or items read from the clipboard
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ClipboardList {
+    /// Name of the list (from a `# heading` line), if any
+    pub name: Option<String>,
+    /// Name and status of the items, in order
+    pub items: Vec<(String, bool)>,
+}
+
+/// Markdown checklist of items, under a heading with the name of the list if given
+pub fn to_markdown(list_name: Option<&str>, items: &[TodoItem]) -> String {
+    let mut lines = Vec::new();
+    if let Some(name) = list_name {
+        lines.push(format!("# {}", name));
+    }
+    for item in items {
+        let checkbox = if item.is_done { "[x]" } else { "[ ]" };
+        lines.push(format!("- {} {}", checkbox, item.name));
+    }
+    lines.join("\n")
+}
+
+/// Read pasted text, one item per non-empty line. Markdown list markers and
+/// checkboxes are understood (`- [x] Milk` is a done item named "Milk"), and a
+/// leading `# heading` gives the name of the list
+pub fn parse_markdown(text: &str) -> ClipboardList {
+    let mut list = ClipboardList::default();
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(heading) = line.strip_prefix('#') {
+            if list.name.is_none() && list.items.is_empty() {
+                list.name = Some(heading.trim_start_matches('#').trim().to_string());
+            }
+            continue;
+        }
+
+        let line = strip_list_marker(line);
+        let (name, is_done) = match line.get(..3) {
+            Some("[ ]") => (&line[3..], false),
+            Some("[x]") | Some("[X]") => (&line[3..], true),
+            _ => (line, false),
+        };
+        let name = name.trim();
+        if !name.is_empty() {
+            list.items.push((name.to_string(), is_done));
+        }
+    }
+
+    list
+}
+
+/// Remove a bullet (`-`, `*`, `+`) or number (`1.`, `2)`) in front of a line
+fn strip_list_marker(line: &str) -> &str {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return rest.trim_start();
+        }
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0
+        && let Some(rest) = line[digits..]
+            .strip_prefix(". ")
+            .or_else(|| line[digits..].strip_prefix(") "))
+    {
+        return rest.trim_start();
+    }
+
+    line
+}
+
+/// OSC 52 escape sequence setting the system clipboard to `text`. Inside tmux
+/// the sequence is wrapped so that tmux passes it through to the terminal
+pub fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if in_tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+/// Copy text to the system clipboard through the terminal (nothing is done
+/// when the output isn't a terminal)
+pub fn copy_to_system_clipboard(text: &str) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        return Ok(());
+    }
+
+    let in_tmux = std::env::var_os("TMUX").is_some();
+    stdout.write_all(osc52_sequence(text, in_tmux).as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_markdown() {
+        let text = "# Groceries\n\n- [ ] Milk\n* [x] Bread\n3. Eggs\n  Butter  \n[X] Jam\n- [ ]\n";
+        let list = parse_markdown(text);
+
+        assert_eq!(list.name.as_deref(), Some("Groceries"));
+        assert_eq!(
+            list.items,
+            vec![
+                ("Milk".to_string(), false),
+                ("Bread".to_string(), true),
+                ("Eggs".to_string(), false),
+                ("Butter".to_string(), false),
+                ("Jam".to_string(), true),
+            ]
+        );
+
+        // Plain lines, without a heading
+        let list = parse_markdown("Milk\nBread");
+        assert_eq!(list.name, None);
+        assert_eq!(list.items.len(), 2);
+    }
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("Milk", false), "\x1b]52;c;TWlsaw==\x07");
+        assert_eq!(
+            osc52_sequence("Milk", true),
+            "\x1bPtmux;\x1b\x1b]52;c;TWlsaw==\x07\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use crate::db::models::{TodoItem, UIList};
+use crate::db::store::TodoStore;
+use crate::ui::components::ListsComponent;
+use crate::ui::theme::Theme;
+use anyhow::Result;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, Widget, Wrap};
+
+/// Board of the selected list: one column per status, with the items as cards
+#[derive(Default)]
+pub struct BoardComponent {
+    /// Index of the selected column
+    pub column: usize,
+    /// Index of the selected card in its column
+    pub card: usize,
+}
+
+impl BoardComponent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Statuses of the board of a list, with the items in each of them (in the
+    /// order of the list)
+    pub fn columns(ui_list: &UIList) -> (Vec<String>, Vec<Vec<&TodoItem>>) {
+        let statuses = ui_list.list.statuses();
+        let mut columns = vec![Vec::new(); statuses.len()];
+        for ui_item in &ui_list.items {
+            let status = ui_item.item.status_in(&statuses);
+            if let Some(i) = statuses.iter().position(|s| s == status) {
+                columns[i].push(&ui_item.item);
+            }
+        }
+        (statuses, columns)
+    }
+
+    /// Item of the selected card, if any
+    pub fn selected_item<'a>(&self, ui_list: &'a UIList) -> Option<&'a TodoItem> {
+        let (_, columns) = BoardComponent::columns(ui_list);
+        columns.get(self.column)?.get(self.card).copied()
+    }
+
+    /// Select the column `offset` columns away, keeping the card in range
+    pub fn select_column(&mut self, ui_list: &UIList, offset: isize) {
+        let (statuses, columns) = BoardComponent::columns(ui_list);
+        self.column = self
+            .column
+            .saturating_add_signed(offset)
+            .min(statuses.len() - 1);
+        self.card = self.card.min(columns[self.column].len().saturating_sub(1));
+    }
+
+    /// Select the card `offset` cards away in the selected column
+    pub fn select_card(&mut self, ui_list: &UIList, offset: isize) {
+        let (_, columns) = BoardComponent::columns(ui_list);
+        let count = columns.get(self.column).map_or(0, Vec::len);
+        self.card = self
+            .card
+            .saturating_add_signed(offset)
+            .min(count.saturating_sub(1));
+    }
+
+    /// Select the card of an item
+    pub fn select_item(&mut self, ui_list: &UIList, item_id: i64) {
+        let (_, columns) = BoardComponent::columns(ui_list);
+        for (i, column) in columns.iter().enumerate() {
+            if let Some(j) = column.iter().position(|item| item.id == item_id) {
+                self.column = i;
+                self.card = j;
+            }
+        }
+    }
+
+    /// Move the selected card to the column `offset` columns away, changing
+    /// the status of its item
+    pub async fn move_card_to_column(
+        board: &mut BoardComponent,
+        lists_component: &mut ListsComponent,
+        offset: isize,
+        store: &dyn TodoStore,
+    ) -> Result<()> {
+        let Some(ui_list) = lists_component.get_selected_list() else {
+            return Ok(());
+        };
+        let statuses = ui_list.list.statuses();
+        let Some(mut item) = board.selected_item(ui_list).cloned() else {
+            return Ok(());
+        };
+        let Some(status) = board
+            .column
+            .checked_add_signed(offset)
+            .and_then(|column| statuses.get(column))
+        else {
+            return Ok(());
+        };
+
+        item.update_status(store, &statuses, status).await?;
+        lists_component.refresh_lists(store).await?;
+
+        if let Some(ui_list) = lists_component.get_selected_list() {
+            board.select_item(ui_list, item.id);
+        }
+        Ok(())
+    }
+
+    /// Move the selected card up (negative `offset`) or down in its column,
+    /// right before or after the card it passes. Only the ordering of the item
+    /// in its list changes
+    pub async fn move_card_in_column(
+        board: &mut BoardComponent,
+        lists_component: &mut ListsComponent,
+        offset: isize,
+        store: &dyn TodoStore,
+    ) -> Result<()> {
+        let Some(ui_list) = lists_component.get_selected_list() else {
+            return Ok(());
+        };
+        let (_, columns) = BoardComponent::columns(ui_list);
+        let Some(column) = columns.get(board.column) else {
+            return Ok(());
+        };
+        let (Some(card), Some(other)) = (
+            column.get(board.card),
+            board
+                .card
+                .checked_add_signed(offset)
+                .and_then(|j| column.get(j)),
+        ) else {
+            return Ok(());
+        };
+
+        // Positions of both cards among all the items of the list
+        let position = |id: i64| ui_list.items.iter().position(|i| i.item.id == id);
+        let (Some(from), Some(to)) = (position(card.id), position(other.id)) else {
+            return Ok(());
+        };
+
+        let mut item = (*card).clone();
+        if to < from {
+            item.move_up_by(store, from - to).await?;
+        } else {
+            item.move_down_by(store, to - from).await?;
+        }
+        lists_component.refresh_lists(store).await?;
+
+        if let Some(ui_list) = lists_component.get_selected_list() {
+            board.select_item(ui_list, item.id);
+        }
+        Ok(())
+    }
+
+    /// Render the board of a list over the area
+    pub fn render(&self, ui_list: Option<&UIList>, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        Clear.render(area, buf);
+        Block::default()
+            .style(Theme::bg(&theme.background))
+            .render(area, buf);
+
+        let list_name = ui_list.map_or("", |ui_list| ui_list.list.name.as_str());
+        let title_line = Line::from(vec![
+            Span::raw("  B O A R D "),
+            Span::styled(format!("{}  ", list_name), Theme::fg(&theme.accent)),
+        ]);
+
+        let command_hints = Line::from(vec![
+            Span::raw(" "),
+            Span::styled(" hjkl", Theme::fg(&theme.accent)),
+            Span::styled(" select ", Theme::fg(&theme.foreground)),
+            Span::styled("H/L", Theme::fg(&theme.accent)),
+            Span::styled(" change status ", Theme::fg(&theme.foreground)),
+            Span::styled("J/K", Theme::fg(&theme.accent)),
+            Span::styled(" reorder ", Theme::fg(&theme.foreground)),
+            Span::styled("[e]", Theme::fg(&theme.accent)),
+            Span::styled("dit statuses ", Theme::fg(&theme.foreground)),
+        ]);
+
+        let block = Block::default()
+            .padding(Padding::new(1, 1, 1, 0))
+            .title_top(title_line)
+            .title_bottom(command_hints)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Theme::fg(&theme.border_accent));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let Some(ui_list) = ui_list else {
+            return;
+        };
+
+        let (statuses, columns) = BoardComponent::columns(ui_list);
+        let column_areas = Layout::horizontal(vec![Constraint::Fill(1); statuses.len()])
+            .spacing(1)
+            .split(inner);
+
+        for (i, (status, cards)) in statuses.iter().zip(&columns).enumerate() {
+            let selected_card = (i == self.column).then_some(self.card);
+            render_column(status, cards, selected_card, column_areas[i], buf, theme);
+        }
+    }
+}
+
+/// Render a column of cards, scrolled so that the selected card is visible
+fn render_column(
+    status: &str,
+    cards: &[&TodoItem],
+    selected_card: Option<usize>,
+    area: Rect,
+    buf: &mut Buffer,
+    theme: &Theme,
+) {
+    let title = Line::from(vec![
+        Span::styled(format!(" {} ", status), Theme::fg(&theme.highlight_fg)),
+        Span::styled(format!("{} ", cards.len()), Theme::fg(&theme.border)),
+    ]);
+    let block = Block::default()
+        .title_top(title)
+        .borders(Borders::TOP)
+        .border_style(if selected_card.is_some() {
+            Theme::fg(&theme.border_accent)
+        } else {
+            Theme::fg(&theme.border)
+        });
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    // Each card is a box of three rows
+    const CARD_HEIGHT: u16 = 3;
+    let visible = (inner.height / CARD_HEIGHT).max(1) as usize;
+    let first = selected_card.map_or(0, |j| (j + 1).saturating_sub(visible));
+
+    for (row, (j, item)) in cards
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(visible)
+        .enumerate()
+    {
+        let card_area = Rect {
+            x: inner.x,
+            y: inner.y + row as u16 * CARD_HEIGHT,
+            width: inner.width,
+            height: CARD_HEIGHT.min(inner.height),
+        };
+
+        let is_selected = selected_card == Some(j);
+        let name_style = if item.is_done {
+            Theme::fg(&theme.border)
+        } else {
+            Theme::fg(&theme.foreground)
+        };
+        let card = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(if is_selected {
+                Theme::fg(&theme.accent)
+            } else {
+                Theme::fg(&theme.border)
+            });
+
+        Paragraph::new(Line::styled(item.name.clone(), name_style))
+            .wrap(Wrap { trim: true })
+            .style(if is_selected {
+                theme.highlight(true)
+            } else {
+                Theme::bg(&theme.background)
+            })
+            .block(card)
+            .render(card_area, buf);
+    }
+}
@@ -0,0 +1,488 @@
+use crate::db::models::{Priority, TodoItem, UIList};
+use crate::db::store::TodoStore;
+use crate::ui::components::ListsComponent;
+use crate::ui::theme::Theme;
+use anyhow::{Context, Result};
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, BorderType, Borders, Clear, HighlightSpacing, List, ListItem, ListState, Padding,
+    Paragraph, StatefulWidget, Widget,
+};
+use std::collections::HashMap;
+
+/// Part of the calendar receiving the keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarFocus {
+    /// Month grid, where a day is selected
+    Days,
+    /// Items due on the selected day
+    Items,
+}
+
+/// Item being rescheduled: it is dropped on the day selected when confirmed
+#[derive(Debug, Clone)]
+pub struct MovingItem {
+    pub item: TodoItem,
+    /// Day the item was due on when picked up
+    pub from: NaiveDate,
+}
+
+/// Items due on a day, as shown in the month grid
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DaySummary {
+    /// Number of items due that day
+    pub count: usize,
+    /// Highest priority among the items not done yet
+    pub priority: Option<Priority>,
+    /// Whether some items aren't done yet
+    pub has_open: bool,
+}
+
+/// Month view of the items with a due date
+pub struct CalendarComponent {
+    /// Day selected in the month grid
+    pub selected_date: NaiveDate,
+    /// Day highlighted as today
+    pub today: NaiveDate,
+    /// Part of the calendar receiving the keys
+    pub focus: CalendarFocus,
+    /// Selection among the items of the selected day
+    pub item_state: ListState,
+    /// Item being rescheduled, if any
+    pub moving: Option<MovingItem>,
+}
+
+impl Default for CalendarComponent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CalendarComponent {
+    pub fn new() -> Self {
+        let today = Local::now().date_naive();
+        Self {
+            selected_date: today,
+            today,
+            focus: CalendarFocus::Days,
+            item_state: ListState::default(),
+            moving: None,
+        }
+    }
+
+    /// Day an item is due on, in local time
+    pub fn due_day(item: &TodoItem) -> Option<NaiveDate> {
+        item.due_date
+            .map(|due_date| due_date.with_timezone(&Local).date_naive())
+    }
+
+    /// Items (with the name of their list) due on a day, from all lists
+    pub fn items_on(lists: &[UIList], date: NaiveDate) -> Vec<(&str, &TodoItem)> {
+        lists
+            .iter()
+            .flat_map(|ui_list| {
+                ui_list
+                    .items
+                    .iter()
+                    .map(move |ui_item| (ui_list.list.name.as_str(), &ui_item.item))
+            })
+            .filter(|(_, item)| CalendarComponent::due_day(item) == Some(date))
+            .collect()
+    }
+
+    /// Summary of the items due on each day
+    pub fn day_summaries(lists: &[UIList]) -> HashMap<NaiveDate, DaySummary> {
+        let mut summaries: HashMap<NaiveDate, DaySummary> = HashMap::new();
+        for ui_item in lists.iter().flat_map(|ui_list| &ui_list.items) {
+            let item = &ui_item.item;
+            let Some(day) = CalendarComponent::due_day(item) else {
+                continue;
+            };
+
+            let summary = summaries.entry(day).or_default();
+            summary.count += 1;
+            if !item.is_done {
+                summary.has_open = true;
+                if priority_rank(&item.priority) > priority_rank(&summary.priority) {
+                    summary.priority = item.priority.clone();
+                }
+            }
+        }
+        summaries
+    }
+
+    /// Select another day, `days` later (or earlier if negative)
+    pub fn move_days(&mut self, days: i64) {
+        let date = if days >= 0 {
+            self.selected_date.checked_add_days(Days::new(days as u64))
+        } else {
+            self.selected_date
+                .checked_sub_days(Days::new(days.unsigned_abs()))
+        };
+        self.select_date(date.unwrap_or(self.selected_date));
+    }
+
+    /// Select the same day in another month, `months` later (or earlier if
+    /// negative). The day is clamped to the end of shorter months
+    pub fn move_months(&mut self, months: i32) {
+        let date = if months >= 0 {
+            self.selected_date
+                .checked_add_months(Months::new(months as u32))
+        } else {
+            self.selected_date
+                .checked_sub_months(Months::new(months.unsigned_abs()))
+        };
+        self.select_date(date.unwrap_or(self.selected_date));
+    }
+
+    /// Select a day and forget the selected item
+    pub fn select_date(&mut self, date: NaiveDate) {
+        self.selected_date = date;
+        self.item_state.select(None);
+    }
+
+    /// Focus the items of the selected day, if there are any
+    pub fn focus_items(&mut self, lists: &[UIList]) {
+        if !CalendarComponent::items_on(lists, self.selected_date).is_empty() {
+            self.focus = CalendarFocus::Items;
+            if self.item_state.selected().is_none() {
+                self.item_state.select(Some(0));
+            }
+        }
+    }
+
+    /// Focus the month grid
+    pub fn focus_days(&mut self) {
+        self.focus = CalendarFocus::Days;
+        self.item_state.select(None);
+    }
+
+    /// Select the next item of the selected day
+    pub fn select_next_item(&mut self, lists: &[UIList]) {
+        let count = CalendarComponent::items_on(lists, self.selected_date).len();
+        if let Some(i) = self.item_state.selected()
+            && i + 1 < count
+        {
+            self.item_state.select(Some(i + 1));
+        }
+    }
+
+    /// Select the previous item of the selected day
+    pub fn select_previous_item(&mut self) {
+        if let Some(i) = self.item_state.selected() {
+            self.item_state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    /// Pick up the selected item to reschedule it: the days are focused again
+    /// and the item is dropped with `drop_moving_item`
+    pub fn pick_selected_item(&mut self, lists: &[UIList]) {
+        let items = CalendarComponent::items_on(lists, self.selected_date);
+        if let Some(i) = self.item_state.selected()
+            && let Some((_, item)) = items.get(i)
+        {
+            self.moving = Some(MovingItem {
+                item: (*item).clone(),
+                from: self.selected_date,
+            });
+            self.focus_days();
+        }
+    }
+
+    /// Reschedule the item being moved to the selected day, keeping its time
+    /// of day, then select it among the items of that day
+    pub async fn drop_moving_item(
+        calendar: &mut CalendarComponent,
+        lists_component: &mut ListsComponent,
+        store: &dyn TodoStore,
+    ) -> Result<()> {
+        let Some(MovingItem { mut item, from }) = calendar.moving.take() else {
+            return Ok(());
+        };
+
+        let due_date = item.due_date.context("Item has no due date")?;
+        let shift = calendar.selected_date.signed_duration_since(from);
+        item.update_due_date(store, due_date + shift).await?;
+        lists_component.refresh_lists(store).await?;
+
+        let items = CalendarComponent::items_on(&lists_component.lists, calendar.selected_date);
+        let index = items.iter().position(|(_, i)| i.id == item.id);
+        calendar.focus = CalendarFocus::Items;
+        calendar.item_state.select(index);
+        Ok(())
+    }
+
+    /// Render the month grid and the items of the selected day over the area
+    pub fn render(&mut self, lists: &[UIList], area: Rect, buf: &mut Buffer, theme: &Theme) {
+        Clear.render(area, buf);
+        Block::default()
+            .style(Theme::bg(&theme.background))
+            .render(area, buf);
+
+        let [month_area, day_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(area);
+
+        self.render_month(lists, month_area, buf, theme);
+        self.render_day(lists, day_area, buf, theme);
+    }
+
+    fn render_month(&self, lists: &[UIList], area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let focused = self.focus == CalendarFocus::Days;
+        let summaries = CalendarComponent::day_summaries(lists);
+
+        let title_line = Line::from(vec![
+            Span::raw("  C A L E N D A R "),
+            Span::styled(
+                self.selected_date.format("%B %Y  ").to_string(),
+                Theme::fg(&theme.accent),
+            ),
+        ]);
+
+        let command_hints = match &self.moving {
+            Some(moving) => Line::from(vec![
+                Span::raw(" "),
+                Span::styled(
+                    format!(" Moving '{}' ", moving.item.name),
+                    theme.highlight(true),
+                ),
+                Span::styled(" [Enter]", Theme::fg(&theme.accent)),
+                Span::styled(" drop ", Theme::fg(&theme.foreground)),
+                Span::styled("[Esc]", Theme::fg(&theme.accent)),
+                Span::styled(" cancel ", Theme::fg(&theme.foreground)),
+            ]),
+            None => Line::from(vec![
+                Span::raw(" "),
+                Span::styled(" hjkl", Theme::fg(&theme.accent)),
+                Span::styled(" day/week ", Theme::fg(&theme.foreground)),
+                Span::styled("H/L", Theme::fg(&theme.accent)),
+                Span::styled(" month ", Theme::fg(&theme.foreground)),
+                Span::styled("[t]", Theme::fg(&theme.accent)),
+                Span::styled("oday ", Theme::fg(&theme.foreground)),
+                Span::styled("[Enter]", Theme::fg(&theme.accent)),
+                Span::styled(" items ", Theme::fg(&theme.foreground)),
+            ]),
+        };
+
+        let block = Block::default()
+            .padding(Padding::new(2, 2, 1, 1))
+            .title_top(title_line)
+            .title_bottom(command_hints)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(if focused {
+                Theme::fg(&theme.border_accent)
+            } else {
+                Theme::fg(&theme.border)
+            });
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // Seven columns, one per day of the week, starting on Monday
+        let cell_width = (inner.width / 7).max(4) as usize;
+        let mut lines = vec![Line::from(
+            ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"]
+                .iter()
+                .map(|day| {
+                    Span::styled(
+                        format!("{:^width$}", day, width = cell_width),
+                        Theme::fg(&theme.highlight_fg),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )];
+
+        // Each week takes a row of day numbers and a row of item counts
+        for week in month_weeks(self.selected_date) {
+            lines.push(Line::from(""));
+
+            let mut days = Vec::new();
+            let mut counts = Vec::new();
+            for day in week {
+                let Some(day) = day else {
+                    days.push(Span::raw(" ".repeat(cell_width)));
+                    counts.push(Span::raw(" ".repeat(cell_width)));
+                    continue;
+                };
+
+                let day_style = if day == self.selected_date {
+                    if self.moving.is_some() {
+                        Theme::fg_bg(&theme.background, &theme.accent)
+                    } else {
+                        theme.highlight(focused)
+                    }
+                } else if day == self.today {
+                    Theme::fg(&theme.accent)
+                } else {
+                    Theme::fg(&theme.foreground)
+                };
+                days.push(Span::styled(
+                    format!("{:^width$}", day.day(), width = cell_width),
+                    day_style,
+                ));
+
+                counts.push(match summaries.get(&day) {
+                    Some(summary) => {
+                        let style = if summary.has_open {
+                            priority_style(&summary.priority, theme)
+                        } else {
+                            Theme::fg(&theme.border)
+                        };
+                        Span::styled(
+                            format!(
+                                "{:^width$}",
+                                format!("•{}", summary.count),
+                                width = cell_width
+                            ),
+                            style,
+                        )
+                    }
+                    None => Span::raw(" ".repeat(cell_width)),
+                });
+            }
+            lines.push(Line::from(days));
+            lines.push(Line::from(counts));
+        }
+
+        Paragraph::new(lines).render(inner, buf);
+    }
+
+    fn render_day(&mut self, lists: &[UIList], area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let focused = self.focus == CalendarFocus::Items;
+
+        let title_line = Line::from(vec![
+            Span::raw("  "),
+            Span::styled(
+                self.selected_date.format("%A %-d %B  ").to_string(),
+                Theme::fg(&theme.foreground),
+            ),
+        ]);
+
+        let command_hints = Line::from(vec![
+            Span::raw(" "),
+            Span::styled(" ↓↑ ", Theme::fg(&theme.accent)),
+            Span::styled("[m]", Theme::fg(&theme.accent)),
+            Span::styled("ove to another day ", Theme::fg(&theme.foreground)),
+        ]);
+
+        let block = Block::default()
+            .padding(Padding::new(2, 2, 1, 1))
+            .title_top(title_line)
+            .title_bottom(command_hints)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(if focused {
+                Theme::fg(&theme.border_accent)
+            } else {
+                Theme::fg(&theme.border)
+            });
+
+        let items: Vec<ListItem> = CalendarComponent::items_on(lists, self.selected_date)
+            .into_iter()
+            .map(|(list_name, item)| {
+                let checkbox = if item.is_done { "[x] " } else { "[ ] " };
+                ListItem::new(Line::from(vec![
+                    Span::styled(checkbox, priority_style(&item.priority, theme)),
+                    Span::raw(item.name.clone()),
+                    Span::styled(format!("  {}", list_name), Theme::fg(&theme.border)),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .style(Theme::fg(&theme.foreground))
+            .highlight_symbol(" ▸ ")
+            .highlight_style(theme.highlight(focused))
+            .highlight_spacing(HighlightSpacing::Always);
+
+        StatefulWidget::render(list, area, buf, &mut self.item_state);
+    }
+}
+
+/// Days of the month of `date`, by week starting on Monday. Days of the
+/// neighbouring months are `None`
+fn month_weeks(date: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let mut weeks = Vec::new();
+    let mut week = [None; 7];
+    let mut day = date.with_day(1).unwrap_or(date);
+
+    while day.month() == date.month() {
+        let weekday = day.weekday().num_days_from_monday() as usize;
+        week[weekday] = Some(day);
+        if weekday == 6 {
+            weeks.push(week);
+            week = [None; 7];
+        }
+        match day.succ_opt() {
+            Some(next) => day = next,
+            None => break,
+        }
+    }
+
+    if week.iter().any(Option::is_some) {
+        weeks.push(week);
+    }
+    weeks
+}
+
+/// Rank of a priority, higher for more urgent items
+fn priority_rank(priority: &Option<Priority>) -> u8 {
+    match priority {
+        Some(Priority::High) => 3,
+        Some(Priority::Medium) => 2,
+        Some(Priority::Low) => 1,
+        None => 0,
+    }
+}
+
+/// Color of items and counts of a given priority
+fn priority_style(priority: &Option<Priority>, theme: &Theme) -> Style {
+    match priority {
+        Some(Priority::High) => Style::default().fg(Color::Red),
+        Some(Priority::Medium) => Style::default().fg(Color::Yellow),
+        Some(Priority::Low) => Style::default().fg(Color::Green),
+        None => Theme::fg(&theme.foreground),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_month_weeks() {
+        // May 2026 starts on a Friday and ends on a Sunday
+        let date = NaiveDate::from_ymd_opt(2026, 5, 14).unwrap();
+        let weeks = month_weeks(date);
+
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0][..4], [None; 4]);
+        assert_eq!(weeks[0][4], NaiveDate::from_ymd_opt(2026, 5, 1));
+        assert_eq!(weeks[4][6], NaiveDate::from_ymd_opt(2026, 5, 31));
+    }
+
+    #[test]
+    fn test_move_months_clamps_day() {
+        let mut calendar = CalendarComponent::new();
+        calendar.select_date(NaiveDate::from_ymd_opt(2026, 1, 31).unwrap());
+
+        calendar.move_months(1);
+        assert_eq!(
+            calendar.selected_date,
+            NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+        );
+
+        calendar.move_days(-27);
+        calendar.move_months(-1);
+        assert_eq!(
+            calendar.selected_date,
+            NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+        );
+    }
+}
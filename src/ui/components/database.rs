@@ -14,6 +14,12 @@ pub struct DatabaseComponent {
     pub list_state: ListState,
 }
 
+impl Default for DatabaseComponent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DatabaseComponent {
     pub fn new() -> Self {
         Self {
@@ -47,6 +53,15 @@ impl DatabaseComponent {
         }
     }
 
+    /// Number of rows taken by each database when rendered with the given width
+    pub fn row_heights(config: &Config, width: usize) -> Vec<usize> {
+        config
+            .dbs
+            .iter()
+            .map(|db| wrap(&db.name, width).len())
+            .collect()
+    }
+
     /// Render the database selector
     pub fn render(
         &mut self,
@@ -118,7 +133,7 @@ impl DatabaseComponent {
         let list = List::new(items)
             .block(block)
             .highlight_symbol(" ▸ ")
-            .highlight_style(Theme::highlight(&theme, selected))
+            .highlight_style(Theme::highlight(theme, selected))
             .highlight_spacing(HighlightSpacing::Always);
 
         StatefulWidget::render(list, area, buf, &mut self.list_state);
@@ -9,6 +9,8 @@ pub struct InputState {
     pub cursor_pos: usize,
     /// Flag where true indicates item is being modified and not created from scratched
     pub is_modifying: bool,
+    /// Position where a new list or item is inserted (at the end if `None`)
+    pub insert_position: Option<usize>,
 }
 
 impl Default for InputState {
@@ -23,6 +25,7 @@ impl InputState {
             current_input: String::new(),
             cursor_pos: 0,
             is_modifying: false,
+            insert_position: None,
         }
     }
 }
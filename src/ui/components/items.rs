@@ -1,4 +1,6 @@
 use crate::db::models::{NewTodoItem, TodoItem, UIItem, UIList};
+use crate::db::store::TodoStore;
+use crate::ui::clipboard;
 use crate::ui::theme::Theme;
 use anyhow::Result;
 use ratatui::buffer::Buffer;
@@ -8,18 +10,21 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{
     Block, BorderType, Borders, HighlightSpacing, List, ListItem, Padding, StatefulWidget, Widget,
 };
-use sqlx::SqlitePool;
 use textwrap::wrap;
 
 pub struct ItemsComponent;
 
 impl ItemsComponent {
     /// Apply styling to a todo item based on its completion status
-    fn style_item(ui_item: &UIItem, selected_index: i32, theme: Theme, selected: bool) -> Line<'_> {
+    fn style_item(
+        ui_item: &UIItem,
+        item_index: i32,
+        selected_index: i32,
+        theme: Theme,
+        selected: bool,
+    ) -> Line<'_> {
         let name = ui_item.item.name.clone();
 
-        let item_index = ui_item.item.ordering as i32 - 1;
-
         fn get_rel_index(current_index: i32, selected_index: i32) -> String {
             if current_index == selected_index {
                 format!("{}  ", current_index + 1)
@@ -96,15 +101,66 @@ impl ItemsComponent {
     }
 
     /// Toggle the "is done" status of the currently selected item
-    pub async fn toggle_item_done(ui_list: &mut UIList, pool: &SqlitePool) -> Result<()> {
+    pub async fn toggle_item_done(ui_list: &mut UIList, store: &dyn TodoStore) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
-            ui_list.items[j].item.toggle_done(pool).await?;
+            ui_list.items[j].item.toggle_done(store).await?;
         }
         Ok(())
     }
 
+    /// Markdown checklist of `count` items starting at the selected one
+    pub fn yank_selected_items(ui_list: &UIList, count: usize) -> Option<String> {
+        let j = ui_list.item_state.selected()?;
+        let items: Vec<TodoItem> = ui_list
+            .items
+            .iter()
+            .skip(j)
+            .take(count.max(1))
+            .map(|ui_item| ui_item.item.clone())
+            .collect();
+
+        (!items.is_empty()).then(|| clipboard::to_markdown(None, &items))
+    }
+
+    /// Create items from pasted lines at `position` in the list, and select the
+    /// first of them
+    pub async fn paste_items(
+        ui_list: &mut UIList,
+        text: &str,
+        position: usize,
+        store: &dyn TodoStore,
+    ) -> Result<()> {
+        let pasted = clipboard::parse_markdown(text);
+        if pasted.items.is_empty() {
+            return Ok(());
+        }
+
+        let position = position.min(ui_list.items.len());
+        for (i, (name, is_done)) in pasted.items.into_iter().enumerate() {
+            let new_item = NewTodoItem {
+                name,
+                list_id: ui_list.list.id,
+                priority: None,
+                due_date: None,
+            };
+            let mut item = TodoItem::create_at(store, new_item, position + i).await?;
+            if is_done {
+                item.toggle_done(store).await?;
+            }
+        }
+
+        ui_list.update_items(store).await?;
+        ui_list.item_state.select(Some(position));
+        Ok(())
+    }
+
     /// Create a new item in the given list
-    pub async fn create_item(ui_list: &mut UIList, name: String, pool: &SqlitePool) -> Result<()> {
+    pub async fn create_item(
+        ui_list: &mut UIList,
+        name: String,
+        position: Option<usize>,
+        store: &dyn TodoStore,
+    ) -> Result<()> {
         let new_item = NewTodoItem {
             name,
             list_id: ui_list.list.id,
@@ -112,31 +168,47 @@ impl ItemsComponent {
             due_date: None,
         };
 
-        TodoItem::create(pool, new_item).await?;
-        ui_list.update_items(pool).await?;
+        match position {
+            Some(position) => {
+                let item = TodoItem::create_at(store, new_item, position).await?;
+                ui_list.update_items(store).await?;
+
+                // Select the inserted item
+                let index = ui_list.items.iter().position(|i| i.item.id == item.id);
+                ui_list.item_state.select(index);
+            }
+            None => {
+                TodoItem::create(store, new_item).await?;
+                ui_list.update_items(store).await?;
+            }
+        }
         Ok(())
     }
 
     /// Update an existing item
-    pub async fn update_item(ui_list: &mut UIList, name: String, pool: &SqlitePool) -> Result<()> {
+    pub async fn update_item(
+        ui_list: &mut UIList,
+        name: String,
+        store: &dyn TodoStore,
+    ) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
             let mut item = ui_list.items[j].item.clone();
-            item.update_name(pool, name).await?;
+            item.update_name(store, name).await?;
 
             // Update list elements
-            ui_list.update_items(pool).await?;
+            ui_list.update_items(store).await?;
         }
         Ok(())
     }
 
     /// Delete the currently selected item
-    pub async fn delete_selected_item(ui_list: &mut UIList, pool: &SqlitePool) -> Result<()> {
+    pub async fn delete_selected_item(ui_list: &mut UIList, store: &dyn TodoStore) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
             let item = ui_list.items[j].item.clone();
-            item.delete(pool).await?;
+            item.delete(store).await?;
 
             // Update list elements
-            ui_list.update_items(pool).await?;
+            ui_list.update_items(store).await?;
 
             // Adjust selection after deletion - check bounds first
             if ui_list.items.is_empty() {
@@ -149,13 +221,13 @@ impl ItemsComponent {
     }
 
     /// Move the currently selected item up
-    pub async fn move_selected_item_up(ui_list: &mut UIList, pool: &SqlitePool) -> Result<()> {
+    pub async fn move_selected_item_up(ui_list: &mut UIList, store: &dyn TodoStore) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
             let mut item = ui_list.items[j].item.clone();
-            item.move_up(pool).await?;
+            item.move_up(store).await?;
 
             // Update list elements to reflect the new order
-            ui_list.update_items(pool).await?;
+            ui_list.update_items(store).await?;
 
             // Adjust selection to follow the moved item
             if j > 0 {
@@ -167,17 +239,17 @@ impl ItemsComponent {
 
     pub async fn move_selected_item_up_by(
         ui_list: &mut UIList,
-        pool: &SqlitePool,
+        store: &dyn TodoStore,
         amount: usize,
     ) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
             let real_amount = if amount > j { j } else { amount };
 
             let mut item = ui_list.items[j].item.clone();
-            item.move_up_by(pool, real_amount).await?;
+            item.move_up_by(store, real_amount).await?;
 
             // Update list elements to reflect the new order
-            ui_list.update_items(pool).await?;
+            ui_list.update_items(store).await?;
 
             // Adjust selection to follow the moved item
             if j > 0 {
@@ -188,13 +260,16 @@ impl ItemsComponent {
     }
 
     /// Move the currently selected item down
-    pub async fn move_selected_item_down(ui_list: &mut UIList, pool: &SqlitePool) -> Result<()> {
+    pub async fn move_selected_item_down(
+        ui_list: &mut UIList,
+        store: &dyn TodoStore,
+    ) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
             let mut item = ui_list.items[j].item.clone();
-            item.move_down(pool).await?;
+            item.move_down(store).await?;
 
             // Update list elements to reflect the new order
-            ui_list.update_items(pool).await?;
+            ui_list.update_items(store).await?;
 
             // Adjust selection to follow the moved item
             if j + 1 < ui_list.items.len() {
@@ -206,7 +281,7 @@ impl ItemsComponent {
 
     pub async fn move_selected_item_down_by(
         ui_list: &mut UIList,
-        pool: &SqlitePool,
+        store: &dyn TodoStore,
         amount: usize,
     ) -> Result<()> {
         if let Some(j) = ui_list.item_state.selected() {
@@ -217,10 +292,10 @@ impl ItemsComponent {
             };
 
             let mut item = ui_list.items[j].item.clone();
-            item.move_down_by(pool, real_amount).await?;
+            item.move_down_by(store, real_amount).await?;
 
             // Update list elements to reflect the new order
-            ui_list.update_items(pool).await?;
+            ui_list.update_items(store).await?;
 
             // Adjust selection to follow the moved item
             if j + real_amount < ui_list.items.len() {
@@ -230,6 +305,28 @@ impl ItemsComponent {
         Ok(())
     }
 
+    /// Width of the gutter showing the (relative) item numbers. Clicking inside it
+    /// toggles the item, much like a checkbox
+    pub fn gutter_width(ui_list: &UIList) -> usize {
+        ui_list.items.len().to_string().len() + 3
+    }
+
+    /// Number of rows taken by each item when rendered with the given width
+    pub fn row_heights(ui_list: &UIList, width: usize) -> Vec<usize> {
+        let max_index_digits = ui_list.items.len().to_string().len();
+        ui_list
+            .items
+            .iter()
+            .map(|ui_item| {
+                wrap(
+                    &ui_item.item.name,
+                    width.saturating_sub(max_index_digits + 5),
+                )
+                .len()
+            })
+            .collect()
+    }
+
     /// Render the list of todo items for the selected list
     pub fn render(
         selected_list: Option<&mut UIList>,
@@ -318,9 +415,15 @@ impl ItemsComponent {
             let items: Vec<ListItem> = ui_list
                 .items
                 .iter()
-                .map(|ui_item| {
-                    let styled_line =
-                        Self::style_item(ui_item, selected_index, theme.to_owned(), selected);
+                .enumerate()
+                .map(|(item_index, ui_item)| {
+                    let styled_line = Self::style_item(
+                        ui_item,
+                        item_index as i32,
+                        selected_index,
+                        theme.to_owned(),
+                        selected,
+                    );
 
                     // Assume styled_line.spans[0] and styled_line.spans[1] exist
                     let padding = "   "; // 3 spaces, adjust as needed
@@ -350,7 +453,7 @@ impl ItemsComponent {
                             let line_number_padding =
                                 " ".repeat(max_index_digits.saturating_sub(w.len()));
                             line_spans.push(Span::styled(
-                                line_number_padding.to_string() + w,
+                                line_number_padding.to_string() + w.as_ref(),
                                 styled_line.spans[0].style,
                             ));
                         }
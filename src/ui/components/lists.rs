@@ -1,4 +1,6 @@
-use crate::db::models::{NewTodoList, TodoList, UIList};
+use crate::db::models::{DEFAULT_STATUSES, NewTodoItem, NewTodoList, TodoItem, TodoList, UIList};
+use crate::db::store::TodoStore;
+use crate::ui::clipboard;
 use crate::ui::theme::Theme;
 use anyhow::Result;
 use ratatui::buffer::Buffer;
@@ -8,7 +10,6 @@ use ratatui::widgets::{
     Block, BorderType, Borders, HighlightSpacing, List, ListItem, ListState, Padding,
     StatefulWidget,
 };
-use sqlx::SqlitePool;
 use textwrap::wrap;
 
 pub struct ListsComponent {
@@ -31,8 +32,8 @@ impl ListsComponent {
     }
 
     /// Initialize lists from database
-    pub async fn load_lists(&mut self, pool: &SqlitePool) -> Result<()> {
-        self.lists = UIList::get_all(pool).await?;
+    pub async fn load_lists(&mut self, store: &dyn TodoStore) -> Result<()> {
+        self.lists = UIList::get_all(store).await?;
         Ok(())
     }
 
@@ -70,9 +71,9 @@ impl ListsComponent {
     }
 
     /// Refresh lists from database (used after reordering)
-    pub async fn refresh_lists(&mut self, pool: &SqlitePool) -> Result<()> {
+    pub async fn refresh_lists(&mut self, store: &dyn TodoStore) -> Result<()> {
         let selected_index = self.list_state.selected();
-        self.load_lists(pool).await?;
+        self.load_lists(store).await?;
 
         // Restore selection if it was set and still valid
         if let Some(index) = selected_index {
@@ -89,14 +90,14 @@ impl ListsComponent {
     /// Move the currently selected list up (static method like ItemsComponent)
     pub async fn move_selected_list_up(
         lists_component: &mut ListsComponent,
-        pool: &SqlitePool,
+        store: &dyn TodoStore,
     ) -> Result<()> {
         if let Some(i) = lists_component.list_state.selected() {
             let mut list = lists_component.lists[i].list.clone();
-            list.move_up(pool).await?;
+            list.move_up(store).await?;
 
             // Refresh lists to reflect the new order
-            lists_component.refresh_lists(pool).await?;
+            lists_component.refresh_lists(store).await?;
 
             // Adjust selection to follow the moved list
             if i > 0 {
@@ -109,14 +110,14 @@ impl ListsComponent {
     /// Move the currently selected list down (static method like ItemsComponent)
     pub async fn move_selected_list_down(
         lists_component: &mut ListsComponent,
-        pool: &SqlitePool,
+        store: &dyn TodoStore,
     ) -> Result<()> {
         if let Some(i) = lists_component.list_state.selected() {
             let mut list = lists_component.lists[i].list.clone();
-            list.move_down(pool).await?;
+            list.move_down(store).await?;
 
             // Refresh lists to reflect the new order
-            lists_component.refresh_lists(pool).await?;
+            lists_component.refresh_lists(store).await?;
 
             // Adjust selection to follow the moved list
             if i + 1 < lists_component.lists.len() {
@@ -129,14 +130,14 @@ impl ListsComponent {
     /// Delete the currently selected list (static method like ItemsComponent)
     pub async fn delete_selected_list_static(
         lists_component: &mut ListsComponent,
-        pool: &SqlitePool,
+        store: &dyn TodoStore,
     ) -> Result<()> {
         if let Some(i) = lists_component.list_state.selected() {
             let list = lists_component.lists[i].list.clone();
-            list.delete(pool).await?;
+            list.delete(store).await?;
 
             // Refresh the lists from database
-            lists_component.load_lists(pool).await?;
+            lists_component.load_lists(store).await?;
 
             // Adjust selection after deletion
             if lists_component.lists.is_empty() {
@@ -154,11 +155,68 @@ impl ListsComponent {
     pub async fn create_list(
         lists_component: &mut ListsComponent,
         name: String,
-        pool: &SqlitePool,
+        position: Option<usize>,
+        store: &dyn TodoStore,
     ) -> Result<()> {
         let new_list = NewTodoList { name };
-        TodoList::create(pool, new_list).await?;
-        lists_component.load_lists(pool).await?;
+        match position {
+            Some(position) => {
+                let list = TodoList::create_at(store, new_list, position).await?;
+                lists_component.load_lists(store).await?;
+
+                // Select the inserted list
+                let lists = &lists_component.lists;
+                let index = lists.iter().position(|l| l.list.id == list.id);
+                lists_component.list_state.select(index);
+            }
+            None => {
+                TodoList::create(store, new_list).await?;
+                lists_component.load_lists(store).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Markdown checklist of the selected list, with its name as heading
+    pub fn yank_selected_list(&self) -> Option<String> {
+        let ui_list = self.get_selected_list()?;
+        let items: Vec<TodoItem> = ui_list.items.iter().map(|i| i.item.clone()).collect();
+        Some(clipboard::to_markdown(Some(&ui_list.list.name), &items))
+    }
+
+    /// Create a list with its items from pasted text at `position` among the
+    /// lists, and select it. Pasting a yanked list duplicates it
+    pub async fn paste_list(
+        lists_component: &mut ListsComponent,
+        text: &str,
+        position: usize,
+        store: &dyn TodoStore,
+    ) -> Result<()> {
+        let pasted = clipboard::parse_markdown(text);
+        let new_list = NewTodoList {
+            name: pasted.name.unwrap_or_else(|| "Pasted list".to_string()),
+        };
+        let list = TodoList::create_at(store, new_list, position).await?;
+
+        for (name, is_done) in pasted.items {
+            let new_item = NewTodoItem {
+                name,
+                list_id: list.id,
+                priority: None,
+                due_date: None,
+            };
+            let mut item = TodoItem::create(store, new_item).await?;
+            if is_done {
+                item.toggle_done(store).await?;
+            }
+        }
+
+        lists_component.load_lists(store).await?;
+        let index = lists_component
+            .lists
+            .iter()
+            .position(|l| l.list.id == list.id);
+        lists_component.list_state.select(index);
         Ok(())
     }
 
@@ -166,16 +224,40 @@ impl ListsComponent {
     pub async fn update_list(
         lists_component: &mut ListsComponent,
         name: String,
-        pool: &SqlitePool,
+        store: &dyn TodoStore,
+    ) -> Result<()> {
+        if let Some(i) = lists_component.list_state.selected() {
+            let mut list = lists_component.lists[i].list.clone();
+            list.update_name(store, name).await?;
+            lists_component.load_lists(store).await?;
+        }
+        Ok(())
+    }
+
+    /// Change the statuses of the board of the selected list. The default
+    /// statuses aren't stored, so that lists keep following them
+    pub async fn update_selected_list_statuses(
+        lists_component: &mut ListsComponent,
+        statuses: Vec<String>,
+        store: &dyn TodoStore,
     ) -> Result<()> {
         if let Some(i) = lists_component.list_state.selected() {
+            let statuses = (statuses != DEFAULT_STATUSES).then(|| statuses.join(","));
             let mut list = lists_component.lists[i].list.clone();
-            list.update_name(pool, name).await?;
-            lists_component.load_lists(pool).await?;
+            list.update_statuses(store, statuses).await?;
+            lists_component.refresh_lists(store).await?;
         }
         Ok(())
     }
 
+    /// Number of rows taken by each list when rendered with the given width
+    pub fn row_heights(&self, width: usize) -> Vec<usize> {
+        self.lists
+            .iter()
+            .map(|ui_list| wrap(&ui_list.list.name, width).len())
+            .collect()
+    }
+
     /// Render the list of todo lists
     pub fn render(&mut self, area: Rect, buf: &mut Buffer, theme: &Theme, selected: bool) {
         // Command hints for lists
@@ -234,7 +316,7 @@ impl ListsComponent {
         let list: List = List::new(items)
             .block(block)
             .highlight_symbol(" ▸ ") // Selection indicator
-            .highlight_style(Theme::highlight(&theme, selected))
+            .highlight_style(Theme::highlight(theme, selected))
             .highlight_spacing(HighlightSpacing::Always);
 
         StatefulWidget::render(list, area, buf, &mut self.list_state);
@@ -1,3 +1,5 @@
+pub mod board;
+pub mod calendar;
 pub mod database;
 pub mod db_selector;
 pub mod input_states;
@@ -6,6 +8,8 @@ pub mod lists;
 pub mod logo;
 pub mod popups;
 
+pub use board::BoardComponent;
+pub use calendar::CalendarComponent;
 pub use database::DatabaseComponent;
 pub use db_selector::DBSelector;
 pub use input_states::InputState;
@@ -13,7 +17,9 @@ pub use items::ItemsComponent;
 pub use lists::ListsComponent;
 pub use logo::Logo;
 pub use popups::{
-    AddDBPopUp, AddItemPopUp, AddListPopUp, DeleteDatabaseConfirmationPopUp,
-    DeleteListConfirmationPopUp, HelpPopUp, LeaderHelpPopUp, ModifyDBPopUp, ModifyItemPopUp,
-    ModifyListPopUp,
+    AddDBPopUp, AddItemPopUp, AddListPopUp, CommandPalettePopUp, DeleteDatabaseConfirmationPopUp,
+    DeleteListConfirmationPopUp, EditStatusesPopUp, ExLinePopUp, HelpPopUp, HookLogPopUp,
+    LeaderHelpPopUp, ModifyDBPopUp, ModifyItemPopUp, ModifyListPopUp, PassphrasePopUp,
+    ScriptOutputPopUp, ScriptPromptPopUp, ScriptSelectPopUp, SyncReportPopUp, TemplateReportPopUp,
+    TemplateSelectPopUp, TemplateVariablePopUp, ThemeSelectionPopUp, ToastPopUp,
 };
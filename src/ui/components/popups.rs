@@ -1,5 +1,10 @@
+use crate::app::ex::ExLine;
+use crate::app::palette::PaletteEntry;
+use crate::hooks::HookFailure;
+use crate::remind::Reminder;
 use crate::ui::cursor::CursorState;
-use crate::ui::theme::Theme;
+use crate::ui::theme::{ColorSupport, THEME_PRESETS, Theme};
+use chrono::Local;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::text::{Line, Span};
@@ -58,7 +63,7 @@ fn render_list_popup_kernel<T: CursorState>(
         .padding(Padding::horizontal(1));
 
     // Define the text to render
-    let text_spans = state.create_cursor_text_spans(&theme);
+    let text_spans = state.create_cursor_text_spans(theme);
     let text_line = Line::from(text_spans);
 
     // Render the input field
@@ -82,6 +87,15 @@ impl ModifyListPopUp {
     }
 }
 
+pub struct EditStatusesPopUp;
+
+impl EditStatusesPopUp {
+    /// Render popup for editing the comma-separated statuses of a board
+    pub fn render<T: CursorState>(state: &T, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        render_list_popup_kernel(state, area, buf, "Board Statuses", theme);
+    }
+}
+
 pub struct AddItemPopUp;
 pub struct ModifyItemPopUp;
 
@@ -136,7 +150,7 @@ pub fn render_item_popup_kernel<T: CursorState>(
         .padding(Padding::horizontal(1));
 
     // Define the text to render
-    let text_spans = state.create_cursor_text_spans(&theme);
+    let text_spans = state.create_cursor_text_spans(theme);
     let text_line = Line::from(text_spans);
 
     // Render the input field
@@ -190,7 +204,7 @@ impl AddDBPopUp {
             .padding(Padding::horizontal(1));
 
         // Define the text to render
-        let text_spans = state.create_cursor_text_spans(&theme);
+        let text_spans = state.create_cursor_text_spans(theme);
         let text_line = Line::from(text_spans);
 
         // Render the input field using the full area
@@ -231,7 +245,7 @@ impl ModifyDBPopUp {
             .padding(Padding::horizontal(1));
 
         // Define the text to render
-        let text_spans = state.create_cursor_text_spans(&theme);
+        let text_spans = state.create_cursor_text_spans(theme);
         let text_line = Line::from(text_spans);
 
         // Render the input field using the full area
@@ -270,6 +284,30 @@ impl HelpPopUp {
                 Span::styled("    * SHIFT + ↓/j", Theme::fg(&theme.accent)),
                 Span::raw(" - Move the selected list/item down"),
             ]),
+            Line::from(vec![
+                Span::styled("    * SPACE + t", Theme::fg(&theme.accent)),
+                Span::raw(" - Preview and choose a theme"),
+            ]),
+            Line::from(vec![
+                Span::styled("    * SPACE + c", Theme::fg(&theme.accent)),
+                Span::raw(" - Show the items with a due date on a calendar"),
+            ]),
+            Line::from(vec![
+                Span::styled("    * b", Theme::fg(&theme.accent)),
+                Span::raw(" - Show the selected list as a kanban board"),
+            ]),
+            Line::from(vec![
+                Span::styled("    * SPACE + s", Theme::fg(&theme.accent)),
+                Span::raw(" - Sync with git or CalDAV (if configured)"),
+            ]),
+            Line::from(vec![
+                Span::styled("    * CTRL + p", Theme::fg(&theme.accent)),
+                Span::raw(" - Find and run an action in the command palette"),
+            ]),
+            Line::from(vec![
+                Span::styled("    * :", Theme::fg(&theme.accent)),
+                Span::raw(" - Run an ex command, e.g. :3,7d or :sort due"),
+            ]),
             Line::from(vec![
                 Span::styled("    * q", Theme::fg(&theme.accent)),
                 Span::raw(" - Quit"),
@@ -286,6 +324,18 @@ impl HelpPopUp {
                 Span::styled("    * →/l", Theme::fg(&theme.accent)),
                 Span::raw(" - Go to Item Selection"),
             ]),
+            Line::from(vec![
+                Span::styled("    * o/O", Theme::fg(&theme.accent)),
+                Span::raw(" - Add a list below/above the current one"),
+            ]),
+            Line::from(vec![
+                Span::styled("    * y p/P", Theme::fg(&theme.accent)),
+                Span::raw(" - Yank the list, paste below/above (duplicate)"),
+            ]),
+            Line::from(vec![
+                Span::styled("    * t/T", Theme::fg(&theme.accent)),
+                Span::raw(" - New list from a template/save the list as one"),
+            ]),
         ];
 
         let item_selection_help_lines = vec![
@@ -310,6 +360,18 @@ impl HelpPopUp {
                 Span::styled("    * G", Theme::fg(&theme.accent)),
                 Span::raw(" - Go to the last item"),
             ]),
+            Line::from(vec![
+                Span::styled("    * o/O", Theme::fg(&theme.accent)),
+                Span::raw(" - Add an item below/above the current one"),
+            ]),
+            Line::from(vec![
+                Span::styled("    * <count> y/Y", Theme::fg(&theme.accent)),
+                Span::raw(" - Yank <count> items/the whole list"),
+            ]),
+            Line::from(vec![
+                Span::styled("    * p/P", Theme::fg(&theme.accent)),
+                Span::raw(" - Paste below/above the current item"),
+            ]),
         ];
 
         let db_selection_help_lines = vec![
@@ -322,6 +384,10 @@ impl HelpPopUp {
                 Span::styled("    * Enter", Theme::fg(&theme.accent)),
                 Span::raw(" - Open the selected database"),
             ]),
+            Line::from(vec![
+                Span::styled("    * c/M/r", Theme::fg(&theme.accent)),
+                Span::raw(" - Duplicate/merge the database/move its file"),
+            ]),
         ];
 
         help_lines.push(Line::from(Span::raw("")));
@@ -340,7 +406,8 @@ impl HelpPopUp {
 
         // Calculate popup dimensions
         let popup_width = (area.width * 2) / 3; // 66% of the area width
-        let popup_height = help_lines.len() as u16 + 4; // Enough for all help lines + padding
+        // Enough for all help lines + padding, but no taller than the terminal
+        let popup_height = (help_lines.len() as u16 + 4).min(area.height);
 
         // Center horizontally within the area
         let popup_x = area.x + (area.width.saturating_sub(popup_width)) / 2;
@@ -402,6 +469,32 @@ impl LeaderHelpPopUp {
                 Span::raw(" → Go to "),
                 Span::styled("Database Selection", Theme::fg(&theme.highlight_fg)),
             ]),
+            Line::from(vec![
+                Span::styled("t", Theme::fg(&theme.accent)),
+                Span::raw(" → Choose "),
+                Span::styled("Theme", Theme::fg(&theme.highlight_fg)),
+            ]),
+            Line::from(vec![
+                Span::styled("c", Theme::fg(&theme.accent)),
+                Span::raw(" → Open the "),
+                Span::styled("Calendar", Theme::fg(&theme.highlight_fg)),
+            ]),
+            Line::from(vec![
+                Span::styled("s", Theme::fg(&theme.accent)),
+                Span::raw(" → "),
+                Span::styled("Sync", Theme::fg(&theme.highlight_fg)),
+                Span::raw(" with git"),
+            ]),
+            Line::from(vec![
+                Span::styled("h", Theme::fg(&theme.accent)),
+                Span::raw(" → Show the "),
+                Span::styled("Hook Log", Theme::fg(&theme.highlight_fg)),
+            ]),
+            Line::from(vec![
+                Span::styled("x", Theme::fg(&theme.accent)),
+                Span::raw(" → Run a "),
+                Span::styled("Script", Theme::fg(&theme.highlight_fg)),
+            ]),
         ];
 
         // Calculate popup dimensions
@@ -451,6 +544,614 @@ impl LeaderHelpPopUp {
     }
 }
 
+pub struct ThemeSelectionPopUp;
+
+impl ThemeSelectionPopUp {
+    /// Render popup listing the built-in themes, each with a preview of its colors
+    pub fn render(
+        area: Rect,
+        buf: &mut Buffer,
+        theme: &Theme,
+        selected_index: usize,
+        color_support: ColorSupport,
+    ) {
+        let name_width = THEME_PRESETS
+            .iter()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0);
+
+        let theme_lines: Vec<Line> = THEME_PRESETS
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let (marker, name_style) = if i == selected_index {
+                    (" ▸ ", theme.highlight(true))
+                } else {
+                    ("   ", Theme::fg(&theme.foreground))
+                };
+
+                let mut spans = vec![
+                    Span::styled(marker, Theme::fg(&theme.accent)),
+                    Span::styled(format!("{:<width$}", name, width = name_width), name_style),
+                    Span::raw("  "),
+                ];
+
+                // One swatch per color of the preset
+                if let Some(preset) = Theme::preset(name) {
+                    let preset = preset.adapted_to(color_support);
+                    for (_, color) in preset.colors() {
+                        spans.push(Span::styled("██", Theme::fg(color)));
+                    }
+                }
+
+                Line::from(spans)
+            })
+            .collect();
+
+        let command_hints = Line::from(vec![
+            Span::raw(" "),
+            Span::styled("↑↓", Theme::fg(&theme.accent)),
+            Span::raw(" Preview "),
+            Span::styled("[Enter]", Theme::fg(&theme.accent)),
+            Span::raw(" Save "),
+            Span::styled("[Esc]", Theme::fg(&theme.accent)),
+            Span::raw(" Cancel "),
+        ]);
+
+        // Calculate popup dimensions
+        let popup_width = (name_width as u16 + 30).min(area.width);
+        let popup_height = theme_lines.len() as u16 + 4;
+
+        let popup_x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let popup_y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+
+        let popup_area = Rect {
+            x: popup_x,
+            y: popup_y,
+            width: popup_width,
+            height: popup_height,
+        };
+
+        // Clear the background of the popup area first
+        Clear.render(popup_area, buf);
+        Block::default()
+            .style(Theme::bg(&theme.background))
+            .render(popup_area, buf);
+
+        let popup_block = Block::new()
+            .padding(Padding::new(1, 1, 1, 1))
+            .title(" Themes ")
+            .title_style(Theme::fg(&theme.foreground))
+            .title_bottom(command_hints)
+            .borders(Borders::ALL)
+            .border_style(Theme::fg(&theme.border_accent))
+            .border_type(BorderType::Rounded);
+
+        Paragraph::new(theme_lines)
+            .block(popup_block)
+            .render(popup_area, buf);
+    }
+}
+
+/// Render popup listing messages, one bullet each
+fn render_report_popup_kernel(
+    area: Rect,
+    buf: &mut Buffer,
+    theme: &Theme,
+    popup_title: &str,
+    messages: &[String],
+) {
+    let report_lines: Vec<Line> = messages
+        .iter()
+        .map(|message| {
+            Line::from(vec![
+                Span::styled("• ", Theme::fg(&theme.accent)),
+                Span::raw(message.clone()),
+            ])
+        })
+        .collect();
+
+    let command_hints = Line::from(vec![
+        Span::raw(" "),
+        Span::styled("[Esc]", Theme::fg(&theme.accent)),
+        Span::raw(" - Close this popup "),
+    ]);
+
+    // Calculate popup dimensions, leaving room for wrapped lines
+    let popup_width = (area.width * 2) / 3;
+    let popup_height = (report_lines.len() as u16 * 2 + 4).min(area.height);
+
+    let popup_x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect {
+        x: popup_x,
+        y: popup_y,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    // Clear the background of the popup area first
+    Clear.render(popup_area, buf);
+    Block::default()
+        .style(Theme::bg(&theme.background))
+        .render(popup_area, buf);
+
+    let popup_block = Block::new()
+        .padding(Padding::new(2, 2, 1, 1))
+        .title(format!(" {} ", popup_title))
+        .title_style(Theme::fg(&theme.foreground))
+        .title_bottom(command_hints)
+        .borders(Borders::ALL)
+        .border_style(Theme::fg(&theme.border_accent))
+        .border_type(BorderType::Rounded);
+
+    Paragraph::new(report_lines)
+        .wrap(Wrap { trim: false })
+        .block(popup_block)
+        .render(popup_area, buf);
+}
+
+pub struct SyncReportPopUp;
+
+impl SyncReportPopUp {
+    /// Render popup listing the conflicts and errors of the last git sync
+    pub fn render(area: Rect, buf: &mut Buffer, theme: &Theme, messages: &[String]) {
+        render_report_popup_kernel(area, buf, theme, "Sync Report", messages);
+    }
+}
+
+pub struct ScriptOutputPopUp;
+
+impl ScriptOutputPopUp {
+    /// Render popup listing the notifications and error of the last script
+    pub fn render(area: Rect, buf: &mut Buffer, theme: &Theme, messages: &[String]) {
+        render_report_popup_kernel(area, buf, theme, "Script Output", messages);
+    }
+}
+
+pub struct TemplateReportPopUp;
+
+impl TemplateReportPopUp {
+    /// Render popup telling where a template was saved, or why a template
+    /// couldn't be used or saved
+    pub fn render(area: Rect, buf: &mut Buffer, theme: &Theme, messages: &[String]) {
+        render_report_popup_kernel(area, buf, theme, "Templates", messages);
+    }
+}
+
+pub struct ScriptPromptPopUp;
+
+impl ScriptPromptPopUp {
+    /// Render popup for answering the question of a script
+    pub fn render<T: CursorState>(
+        state: &T,
+        area: Rect,
+        buf: &mut Buffer,
+        theme: &Theme,
+        question: &str,
+    ) {
+        render_list_popup_kernel(state, area, buf, question, theme);
+    }
+}
+
+/// Render a popup listing options to choose from, with a message if there are none
+fn render_select_popup_kernel(
+    area: Rect,
+    buf: &mut Buffer,
+    theme: &Theme,
+    title: &str,
+    options: &[String],
+    selected_index: usize,
+    empty_message: &str,
+) {
+    let option_lines: Vec<Line> = if options.is_empty() {
+        vec![Line::from(empty_message.to_string())]
+    } else {
+        options
+            .iter()
+            .enumerate()
+            .map(|(i, option)| {
+                let (marker, style) = if i == selected_index {
+                    (" ▸ ", theme.highlight(true))
+                } else {
+                    ("   ", Theme::fg(&theme.foreground))
+                };
+                Line::from(vec![
+                    Span::styled(marker, Theme::fg(&theme.accent)),
+                    Span::styled(option.clone(), style),
+                ])
+            })
+            .collect()
+    };
+
+    let command_hints = Line::from(vec![
+        Span::raw(" "),
+        Span::styled("[Enter]", Theme::fg(&theme.accent)),
+        Span::raw(" Choose "),
+        Span::styled("[Esc]", Theme::fg(&theme.accent)),
+        Span::raw(" Cancel "),
+    ]);
+
+    // Calculate popup dimensions
+    let popup_width = (area.width * 2) / 3;
+    let popup_height = (option_lines.len() as u16 + 4).min(area.height);
+
+    let popup_x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect {
+        x: popup_x,
+        y: popup_y,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    // Clear the background of the popup area first
+    Clear.render(popup_area, buf);
+    Block::default()
+        .style(Theme::bg(&theme.background))
+        .render(popup_area, buf);
+
+    let popup_block = Block::new()
+        .padding(Padding::new(1, 1, 1, 1))
+        .title(format!(" {} ", title))
+        .title_style(Theme::fg(&theme.foreground))
+        .title_bottom(command_hints)
+        .borders(Borders::ALL)
+        .border_style(Theme::fg(&theme.border_accent))
+        .border_type(BorderType::Rounded);
+
+    Paragraph::new(option_lines)
+        .block(popup_block)
+        .render(popup_area, buf);
+}
+
+pub struct ScriptSelectPopUp;
+
+impl ScriptSelectPopUp {
+    /// Render popup listing the options of a script (or the scripts to run)
+    pub fn render(
+        area: Rect,
+        buf: &mut Buffer,
+        theme: &Theme,
+        title: &str,
+        options: &[String],
+        selected_index: usize,
+    ) {
+        render_select_popup_kernel(
+            area,
+            buf,
+            theme,
+            title,
+            options,
+            selected_index,
+            "No scripts: add .rhai files to the scripts directory",
+        );
+    }
+}
+
+pub struct TemplateSelectPopUp;
+
+impl TemplateSelectPopUp {
+    /// Render popup listing the templates to create a list from
+    pub fn render(
+        area: Rect,
+        buf: &mut Buffer,
+        theme: &Theme,
+        templates: &[String],
+        selected_index: usize,
+    ) {
+        render_select_popup_kernel(
+            area,
+            buf,
+            theme,
+            "New List from Template",
+            templates,
+            selected_index,
+            "No templates: add .md files to the templates directory",
+        );
+    }
+}
+
+pub struct TemplateVariablePopUp;
+
+impl TemplateVariablePopUp {
+    /// Render popup asking for the value of a variable of a template
+    pub fn render<T: CursorState>(
+        state: &T,
+        area: Rect,
+        buf: &mut Buffer,
+        theme: &Theme,
+        template: &str,
+        variable: &str,
+    ) {
+        let title = format!("{} of {}", variable, template);
+        render_list_popup_kernel(state, area, buf, &title, theme);
+    }
+}
+
+pub struct HookLogPopUp;
+
+impl HookLogPopUp {
+    /// Render popup listing the hooks that failed, the most recent first
+    pub fn render(area: Rect, buf: &mut Buffer, theme: &Theme, failures: &[HookFailure]) {
+        let log_lines: Vec<Line> = if failures.is_empty() {
+            vec![Line::from("No hook failed")]
+        } else {
+            failures
+                .iter()
+                .flat_map(|failure| {
+                    let time = failure.time.with_timezone(&Local).format("%H:%M:%S");
+                    [
+                        Line::from(vec![
+                            Span::styled("• ", Theme::fg(&theme.accent)),
+                            Span::raw(format!("{} ", time)),
+                            Span::styled(failure.event.as_str(), Theme::fg(&theme.highlight_fg)),
+                            Span::raw(format!(" {}", failure.command)),
+                        ]),
+                        Line::from(format!("  {}", failure.error)),
+                    ]
+                })
+                .collect()
+        };
+
+        let command_hints = Line::from(vec![
+            Span::raw(" "),
+            Span::styled("[c]", Theme::fg(&theme.accent)),
+            Span::raw(" - Clear "),
+            Span::styled("[Esc]", Theme::fg(&theme.accent)),
+            Span::raw(" - Close "),
+        ]);
+
+        let popup_width = (area.width * 2) / 3;
+        let popup_height = (log_lines.len() as u16 + 4).min(area.height);
+
+        let popup_x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let popup_y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+
+        let popup_area = Rect {
+            x: popup_x,
+            y: popup_y,
+            width: popup_width,
+            height: popup_height,
+        };
+
+        // Clear the background of the popup area first
+        Clear.render(popup_area, buf);
+        Block::default()
+            .style(Theme::bg(&theme.background))
+            .render(popup_area, buf);
+
+        let popup_block = Block::new()
+            .padding(Padding::new(2, 2, 1, 1))
+            .title(" Hook Log ")
+            .title_style(Theme::fg(&theme.foreground))
+            .title_bottom(command_hints)
+            .borders(Borders::ALL)
+            .border_style(Theme::fg(&theme.border_accent))
+            .border_type(BorderType::Rounded);
+
+        Paragraph::new(log_lines)
+            .block(popup_block)
+            .render(popup_area, buf);
+    }
+}
+
+pub struct CommandPalettePopUp;
+
+impl CommandPalettePopUp {
+    /// Most entries shown at once
+    const MAX_ENTRIES: usize = 12;
+
+    /// Render popup with the input of the command palette and the commands
+    /// matching it, each with its description and keys
+    pub fn render<T: CursorState>(
+        area: Rect,
+        buf: &mut Buffer,
+        theme: &Theme,
+        state: &T,
+        entries: &[PaletteEntry],
+        selected_index: usize,
+    ) {
+        let popup_width = ((area.width * 3) / 4).min(90);
+        // Borders and padding
+        let inner_width = popup_width.saturating_sub(4) as usize;
+        let shown = entries.len().clamp(1, Self::MAX_ENTRIES);
+        let popup_height = (shown as u16 + 5).min(area.height);
+
+        // Scroll to keep the selected entry in view
+        let offset = selected_index.saturating_sub(Self::MAX_ENTRIES - 1);
+        let labels: Vec<String> = entries.iter().map(|entry| entry.label()).collect();
+        let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        let mut lines = vec![
+            Line::from(
+                [
+                    vec![Span::styled(": ", Theme::fg(&theme.accent))],
+                    state.create_cursor_text_spans(theme),
+                ]
+                .concat(),
+            ),
+            Line::from(""),
+        ];
+        if entries.is_empty() {
+            lines.push(Line::from("   No matching command"));
+        }
+        for (i, (entry, label)) in entries
+            .iter()
+            .zip(&labels)
+            .enumerate()
+            .skip(offset)
+            .take(shown)
+        {
+            let (marker, style) = if i == selected_index {
+                (" ▸ ", theme.highlight(true))
+            } else {
+                ("   ", Theme::fg(&theme.foreground))
+            };
+            let keys = entry.keys();
+            let description = entry.command.description;
+            let used = 3 + label_width + 2 + description.chars().count() + keys.chars().count();
+            lines.push(Line::from(vec![
+                Span::styled(marker, Theme::fg(&theme.accent)),
+                Span::styled(format!("{:<width$}", label, width = label_width), style),
+                Span::raw("  "),
+                Span::raw(description),
+                Span::raw(" ".repeat(inner_width.saturating_sub(used))),
+                Span::styled(keys, Theme::fg(&theme.accent)),
+            ]));
+        }
+
+        let command_hints = Line::from(vec![
+            Span::raw(" "),
+            Span::styled("[Enter]", Theme::fg(&theme.accent)),
+            Span::raw(" Run "),
+            Span::styled("[Tab]", Theme::fg(&theme.accent)),
+            Span::raw(" Complete "),
+            Span::styled("[Esc]", Theme::fg(&theme.accent)),
+            Span::raw(" Close "),
+        ]);
+
+        let popup_x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let popup_y = area.y + (area.height.saturating_sub(popup_height)) / 3;
+
+        let popup_area = Rect {
+            x: popup_x,
+            y: popup_y,
+            width: popup_width,
+            height: popup_height,
+        };
+
+        // Clear the background of the popup area first
+        Clear.render(popup_area, buf);
+        Block::default()
+            .style(Theme::bg(&theme.background))
+            .render(popup_area, buf);
+
+        let popup_block = Block::new()
+            .padding(Padding::new(1, 1, 1, 0))
+            .title(" Commands ")
+            .title_style(Theme::fg(&theme.foreground))
+            .title_bottom(command_hints)
+            .borders(Borders::ALL)
+            .border_style(Theme::fg(&theme.border_accent))
+            .border_type(BorderType::Rounded);
+
+        Paragraph::new(lines)
+            .block(popup_block)
+            .render(popup_area, buf);
+    }
+}
+
+pub struct ExLinePopUp;
+
+impl ExLinePopUp {
+    /// Render the ex command line at the bottom of the screen, with the error
+    /// of the last command or the candidates of the completion below it
+    pub fn render(area: Rect, buf: &mut Buffer, theme: &Theme, ex_line: &ExLine) {
+        let mut lines = vec![Line::from(
+            [
+                vec![Span::styled(":", Theme::fg(&theme.accent))],
+                ex_line.input.create_cursor_text_spans(theme),
+            ]
+            .concat(),
+        )];
+        if let Some(error) = &ex_line.error {
+            lines.push(Line::from(Span::styled(
+                error.as_str(),
+                Theme::fg(&theme.accent),
+            )));
+        } else if let Some((candidates, selected)) = ex_line.completions() {
+            let mut spans = Vec::new();
+            for (i, candidate) in candidates.iter().enumerate() {
+                let style = if i == selected {
+                    theme.highlight(true)
+                } else {
+                    Theme::fg(&theme.foreground)
+                };
+                spans.push(Span::styled(candidate.as_str(), style));
+                spans.push(Span::raw("  "));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        let command_hints = Line::from(vec![
+            Span::raw(" "),
+            Span::styled("[Enter]", Theme::fg(&theme.accent)),
+            Span::raw(" Run "),
+            Span::styled("[Tab]", Theme::fg(&theme.accent)),
+            Span::raw(" Complete "),
+            Span::styled("[↑↓]", Theme::fg(&theme.accent)),
+            Span::raw(" History "),
+            Span::styled("[Esc]", Theme::fg(&theme.accent)),
+            Span::raw(" Close "),
+        ]);
+
+        let popup_height = (lines.len() as u16 + 2).min(area.height);
+        let popup_area = Rect {
+            x: area.x,
+            y: area.bottom().saturating_sub(popup_height),
+            width: area.width,
+            height: popup_height,
+        };
+
+        Clear.render(popup_area, buf);
+        Block::default()
+            .style(Theme::bg(&theme.background))
+            .render(popup_area, buf);
+
+        let popup_block = Block::new()
+            .padding(Padding::horizontal(1))
+            .title_bottom(command_hints)
+            .borders(Borders::ALL)
+            .border_style(Theme::fg(&theme.border_accent))
+            .border_type(BorderType::Rounded);
+
+        Paragraph::new(lines)
+            .block(popup_block)
+            .render(popup_area, buf);
+    }
+}
+
+pub struct ToastPopUp;
+
+impl ToastPopUp {
+    /// Render a reminder of an item due soon in the bottom right corner
+    pub fn render(area: Rect, buf: &mut Buffer, theme: &Theme, reminder: &Reminder) {
+        let popup_width = area.width.min(50);
+        let popup_height = area.height.min(4);
+        let popup_area = Rect {
+            x: area.right().saturating_sub(popup_width + 1),
+            y: area.bottom().saturating_sub(popup_height + 1),
+            width: popup_width,
+            height: popup_height,
+        };
+
+        Clear.render(popup_area, buf);
+        Block::default()
+            .style(Theme::bg(&theme.background))
+            .render(popup_area, buf);
+
+        let popup_block = Block::new()
+            .padding(Padding::horizontal(1))
+            .title(" Reminder ")
+            .title_style(Theme::fg(&theme.accent))
+            .borders(Borders::ALL)
+            .border_style(Theme::fg(&theme.border_accent))
+            .border_type(BorderType::Rounded);
+
+        let lines = vec![
+            Line::styled(reminder.summary(), Theme::fg(&theme.highlight_fg)),
+            Line::styled(reminder.body(), Theme::fg(&theme.foreground)),
+        ];
+        Paragraph::new(lines)
+            .block(popup_block)
+            .render(popup_area, buf);
+    }
+}
+
 pub struct DeleteListConfirmationPopUp;
 
 impl DeleteListConfirmationPopUp {
@@ -520,7 +1221,7 @@ impl DeleteDatabaseConfirmationPopUp {
 
         let command_hints = Line::from(vec![
             Span::raw(" "),
-            Span::styled("[Y] Yes   [N] No", Theme::fg(&theme.accent)),
+            Span::styled("[Y] Yes [F] With file [N] No", Theme::fg(&theme.accent)),
             Span::raw(" "),
         ]);
 
@@ -556,3 +1257,80 @@ impl DeleteDatabaseConfirmationPopUp {
             .render(popup_area, buf);
     }
 }
+
+pub struct PassphrasePopUp;
+
+impl PassphrasePopUp {
+    /// Render popup asking for the passphrase of an encrypted database. The
+    /// passphrase is masked; `is_new` is set when the database is encrypted for
+    /// the first time
+    pub fn render(
+        area: Rect,
+        buf: &mut Buffer,
+        theme: &Theme,
+        db_name: &str,
+        passphrase_len: usize,
+        is_new: bool,
+        error: Option<&str>,
+    ) {
+        let prompt = if is_new {
+            "Choose a passphrase to encrypt "
+        } else {
+            "Enter the passphrase of "
+        };
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::raw(prompt),
+                Span::styled(db_name, Theme::fg(&theme.accent)),
+            ]),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("*".repeat(passphrase_len)),
+                Span::styled("█", Theme::fg(&theme.accent)),
+            ]),
+        ];
+        if let Some(error) = error {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(error, Theme::fg(&theme.accent))));
+        }
+
+        let command_hints = Line::from(vec![
+            Span::raw(" "),
+            Span::styled("[Enter]", Theme::fg(&theme.accent)),
+            Span::raw(" - Unlock "),
+            Span::styled("[Esc]", Theme::fg(&theme.accent)),
+            Span::raw(" - Cancel "),
+        ]);
+
+        let popup_width = (area.width * 2) / 3;
+        let popup_height = (lines.len() as u16 + 4).min(area.height);
+        let popup_x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let popup_y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup_area = Rect {
+            x: popup_x,
+            y: popup_y,
+            width: popup_width,
+            height: popup_height,
+        };
+
+        Clear.render(popup_area, buf);
+        Block::default()
+            .style(Theme::bg(&theme.background))
+            .render(popup_area, buf);
+
+        let popup_block = Block::new()
+            .padding(Padding::new(2, 2, 1, 1))
+            .title(" Encrypted Database ")
+            .title_style(Theme::fg(&theme.foreground))
+            .title_bottom(command_hints)
+            .borders(Borders::ALL)
+            .border_style(Theme::fg(&theme.border_accent))
+            .border_type(BorderType::Rounded);
+
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(popup_block)
+            .render(popup_area, buf);
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use std::str::FromStr;
+
+/// Key of a binding in judo.toml, in vim notation: a character as it is typed
+/// (`A`, `!`), or a named key such as `<C-a>` (Ctrl + a), `<A-a>` (Alt + a),
+/// `<F5>`, `<Tab>` or `<Enter>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Whether a key press is this binding. Shift is part of the character
+    /// typed, so it is only compared for the other keys
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let ignored = match key.code {
+            KeyCode::Char(_) => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        key.code == self.code && key.modifiers.difference(ignored) == self.modifiers
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(KeyBinding {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE,
+            });
+        }
+
+        let name = s
+            .strip_prefix('<')
+            .and_then(|s| s.strip_suffix('>'))
+            .with_context(|| format!("Invalid key '{}'", s))?;
+        let (modifiers, name) = match name.split_once('-') {
+            Some(("C", key)) if !key.is_empty() => (KeyModifiers::CONTROL, key),
+            Some(("A" | "M", key)) if !key.is_empty() => (KeyModifiers::ALT, key),
+            _ => (KeyModifiers::NONE, name),
+        };
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers != KeyModifiers::NONE => KeyCode::Char(c),
+            _ => match name {
+                "Enter" | "CR" => KeyCode::Enter,
+                "Esc" => KeyCode::Esc,
+                "Space" => KeyCode::Char(' '),
+                "Tab" => KeyCode::Tab,
+                "BS" => KeyCode::Backspace,
+                "Del" => KeyCode::Delete,
+                "Up" => KeyCode::Up,
+                "Down" => KeyCode::Down,
+                "Left" => KeyCode::Left,
+                "Right" => KeyCode::Right,
+                "lt" => KeyCode::Char('<'),
+                _ => name
+                    .strip_prefix('F')
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| (1..=12).contains(n))
+                    .map(KeyCode::F)
+                    .with_context(|| format!("Unknown key '{}'", s))?,
+            },
+        };
+        Ok(KeyBinding { code, modifiers })
+    }
+}
+
+impl From<KeyBinding> for KeyEvent {
+    /// Key press of a binding, with Shift for upper case characters
+    fn from(binding: KeyBinding) -> KeyEvent {
+        let shift = match binding.code {
+            KeyCode::Char(c) if c.is_uppercase() => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        KeyEvent::new(binding.code, binding.modifiers | shift)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let prefix = if self.modifiers.contains(KeyModifiers::CONTROL) {
+            "C-"
+        } else if self.modifiers.contains(KeyModifiers::ALT) {
+            "A-"
+        } else {
+            ""
+        };
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "<{}Space>", prefix),
+            KeyCode::Char(c) if prefix.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "<{}{}>", prefix, c),
+            KeyCode::F(n) => write!(f, "<{}F{}>", prefix, n),
+            code => write!(f, "<{}{:?}>", prefix, code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_key_binding() -> Result<()> {
+        let ctrl_a: KeyBinding = "<C-a>".parse()?;
+        assert!(ctrl_a.matches(&KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL)));
+        assert!(!ctrl_a.matches(&KeyEvent::from(KeyCode::Char('a'))));
+
+        let upper_a: KeyBinding = "A".parse()?;
+        assert!(upper_a.matches(&KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT)));
+        assert!(upper_a.matches(&KeyEvent::from(KeyCode::Char('A'))));
+
+        let f5: KeyBinding = "<F5>".parse()?;
+        assert!(f5.matches(&KeyEvent::from(KeyCode::F(5))));
+        assert_eq!(f5.to_string(), "<F5>");
+        assert_eq!(ctrl_a.to_string(), "<C-a>");
+
+        assert!("<F13>".parse::<KeyBinding>().is_err());
+        assert!("ab".parse::<KeyBinding>().is_err());
+        Ok(())
+    }
+}
@@ -52,9 +52,60 @@ impl AppLayout {
         (lists_area, items_area, logo_area, db_selector_area)
     }
 
+    /// Area available to the content of a bordered panel (lists, items, databases).
+    /// Mirrors the border and padding used by the panel blocks when rendering
+    pub fn panel_inner_area(area: Rect) -> Rect {
+        area.inner(Margin {
+            horizontal: 3, // 1 column of border + 2 columns of padding
+            vertical: 2,   // 1 row of border + 1 row of padding
+        })
+    }
+
+    /// Map a row inside a panel to the index of the entry displayed there.
+    ///
+    /// `heights` holds the number of rows taken by each entry (entries can wrap over
+    /// multiple lines) and `offset` is the index of the first visible entry, as stored
+    /// in the corresponding `ListState`.
+    pub fn index_at_row(heights: &[usize], offset: usize, row: u16) -> Option<usize> {
+        let mut top = 0;
+        for (i, height) in heights.iter().enumerate().skip(offset) {
+            let bottom = top + height.max(&1);
+            if (row as usize) < bottom {
+                return Some(i);
+            }
+            top = bottom;
+        }
+        None
+    }
+
     /// Render a background that fills the entire area
     pub fn render_background(area: Rect, buf: &mut Buffer, theme: &Theme) {
         let background = Block::default().style(Theme::fg_bg(&theme.foreground, &theme.background));
         background.render(area, buf);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_index_at_row_single_line_entries() {
+        let heights = vec![1, 1, 1];
+        assert_eq!(AppLayout::index_at_row(&heights, 0, 0), Some(0));
+        assert_eq!(AppLayout::index_at_row(&heights, 0, 2), Some(2));
+        assert_eq!(AppLayout::index_at_row(&heights, 0, 3), None);
+    }
+
+    #[test]
+    fn test_index_at_row_wrapped_entries_and_offset() {
+        let heights = vec![2, 3, 1, 1];
+        assert_eq!(AppLayout::index_at_row(&heights, 0, 1), Some(0));
+        assert_eq!(AppLayout::index_at_row(&heights, 0, 2), Some(1));
+        assert_eq!(AppLayout::index_at_row(&heights, 0, 4), Some(1));
+        assert_eq!(AppLayout::index_at_row(&heights, 0, 5), Some(2));
+        assert_eq!(AppLayout::index_at_row(&heights, 1, 0), Some(1));
+        assert_eq!(AppLayout::index_at_row(&heights, 1, 3), Some(2));
+        assert_eq!(AppLayout::index_at_row(&heights, 2, 2), None);
+    }
+}
@@ -1,6 +1,9 @@
+pub mod clipboard;
 pub mod components;
 pub mod cursor;
+pub mod keys;
 pub mod layout;
+pub mod templates;
 pub mod theme;
 
 pub use cursor::CursorState;
@@ -0,0 +1,258 @@
+//! List templates: Markdown checklists in the `templates` directory next to
+//! judo.toml, e.g. `release.md`:
+//!
+//! ```markdown
+//! # Release {{version}}
+//! - [ ] Bump the version to {{version}}
+//! - [ ] Tag the release on {{date}}
+//! ```
+//!
+//! Creating a list from a template (`t` in the lists) asks for the value of
+//! each `{{variable}}`, `{{date}}` defaulting to today. The list is named
+//! after the heading, or after the template without one.
+
+use crate::db::models::UIList;
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Extension of template files
+const EXTENSION: &str = "md";
+
+/// Template found in the templates directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    /// Name of the file, without extension
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Directory of the templates, next to judo.toml
+pub fn templates_dir(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .map(|dir| dir.join("templates"))
+        .unwrap_or_else(|| PathBuf::from("templates"))
+}
+
+/// Templates of a directory, sorted by name. A missing directory has none
+pub fn list_templates(dir: &Path) -> Vec<Template> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut templates: Vec<Template> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some(Template { name, path })
+        })
+        .collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// Names of the `{{variables}}` of a template, in order of first appearance
+pub fn variables(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim();
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    names
+}
+
+/// Value suggested for a variable before it is typed
+pub fn default_value(variable: &str) -> String {
+    match variable {
+        "date" => Local::now().format("%Y-%m-%d").to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Text of a template with its variables replaced by their values (variables
+/// without a value are left as they are)
+pub fn fill(text: &str, values: &[(String, String)]) -> String {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim();
+        filled.push_str(&rest[..start]);
+        match values.iter().find(|(variable, _)| variable == name) {
+            Some((_, value)) => filled.push_str(value),
+            None => filled.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Template being filled in, one variable at a time
+#[derive(Debug, Clone)]
+pub struct TemplateForm {
+    pub template: Template,
+    text: String,
+    /// Variables and the values typed so far
+    pub values: Vec<(String, String)>,
+    /// Variables left to ask for
+    pending: Vec<String>,
+}
+
+impl TemplateForm {
+    /// Read a template and its variables
+    pub fn load(template: Template) -> Result<Self> {
+        let text = std::fs::read_to_string(&template.path)
+            .with_context(|| format!("Failed to read template {}", template.path.display()))?;
+        let mut pending = variables(&text);
+        pending.reverse();
+        Ok(TemplateForm {
+            template,
+            text,
+            values: Vec::new(),
+            pending,
+        })
+    }
+
+    /// Variable asked for next, if any is left
+    pub fn next_variable(&self) -> Option<&str> {
+        self.pending.last().map(String::as_str)
+    }
+
+    /// Give the value of the variable asked for
+    pub fn answer(&mut self, value: String) {
+        if let Some(variable) = self.pending.pop() {
+            self.values.push((variable, value));
+        }
+    }
+
+    /// Markdown of the list to create, with a heading named after the template
+    /// if it has none
+    pub fn markdown(&self) -> String {
+        let text = fill(&self.text, &self.values);
+        if text.trim_start().starts_with('#') {
+            text
+        } else {
+            format!("# {}\n{}", self.template.name, text)
+        }
+    }
+}
+
+/// Save a list as a template named after it, its items unchecked. Returns the
+/// path of the template. An existing template is never overwritten: names that
+/// only differ by the characters replaced in file names (`a/b` and `a_b`) give
+/// the same file, and a template may have been edited since it was saved
+pub fn save_list(dir: &Path, ui_list: &UIList) -> Result<PathBuf> {
+    let file_name: String = ui_list
+        .list
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let path = dir.join(format!("{}.{}", file_name, EXTENSION));
+
+    let mut lines = vec![format!("# {}", ui_list.list.name)];
+    lines.extend(
+        ui_list
+            .items
+            .iter()
+            .map(|ui_item| format!("- [ ] {}", ui_item.item.name)),
+    );
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let mut file = match std::fs::File::create_new(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            anyhow::bail!(
+                "{} already exists, rename or delete it first",
+                path.display()
+            );
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to create {}", path.display())),
+    };
+    file.write_all((lines.join("\n") + "\n").as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::models::TodoList;
+    use chrono::Utc;
+    use ratatui::widgets::ListState;
+
+    const RELEASE: &str = "# Release {{version}}\n- [ ] Bump to {{ version }}\n- [ ] Tag on {{date}}\n- [ ] Unclosed {{";
+
+    #[test]
+    fn test_variables_and_fill() {
+        assert_eq!(variables(RELEASE), vec!["version", "date"]);
+
+        let values = vec![("version".to_string(), "1.2".to_string())];
+        assert_eq!(
+            fill(RELEASE, &values),
+            "# Release 1.2\n- [ ] Bump to 1.2\n- [ ] Tag on {{date}}\n- [ ] Unclosed {{"
+        );
+        assert_eq!(default_value("date").len(), 10);
+        assert_eq!(default_value("version"), "");
+    }
+
+    #[test]
+    fn test_template_form() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let dir = dir.path();
+        std::fs::write(dir.join("packing.md"), "- [ ] Passport for {{trip}}\n")?;
+        std::fs::write(dir.join("notes.txt"), "not a template")?;
+
+        let templates = list_templates(dir);
+        assert_eq!(templates.len(), 1);
+        let mut form = TemplateForm::load(templates[0].clone())?;
+        assert_eq!(form.next_variable(), Some("trip"));
+        form.answer("Rome".to_string());
+        assert_eq!(form.next_variable(), None);
+        assert_eq!(form.markdown(), "# packing\n- [ ] Passport for Rome\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_list_never_overwrites() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let dir = dir.path();
+        let ui_list = |name: &str| UIList {
+            list: TodoList {
+                id: 1,
+                uid: String::new(),
+                name: name.to_string(),
+                ordering: 1,
+                statuses: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            item_state: ListState::default(),
+            items: Vec::new(),
+        };
+
+        let path = save_list(dir, &ui_list("a/b"))?;
+        assert_eq!(path, dir.join("a_b.md"));
+        let error = save_list(dir, &ui_list("a_b")).unwrap_err().to_string();
+        assert!(error.contains("already exists"), "{}", error);
+        assert_eq!(std::fs::read_to_string(path)?, "# a/b\n");
+        Ok(())
+    }
+}
@@ -1,7 +1,267 @@
+use anyhow::{Result, anyhow};
 use ratatui::style::{Color, Style};
 use serde::{Deserialize, Serialize};
+use std::env;
 use std::str::FromStr;
 
+/// Names of the built-in themes, in the order shown by the theme switcher.
+/// "auto" picks "judo" or "judo-light" depending on the terminal background
+pub const THEME_PRESETS: [&str; 8] = [
+    "auto",
+    "judo",
+    "judo-light",
+    "nord",
+    "gruvbox",
+    "dracula",
+    "solarized-light",
+    "terminal",
+];
+
+/// Name of the theme used when none is configured
+pub const DEFAULT_THEME_PRESET: &str = "judo";
+
+/// Theme as written in judo.toml: either the name of a built-in theme
+/// (`theme = "nord"`) or a table of colors, optionally based on a preset
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ThemeConfig {
+    Preset(String),
+    Custom(Box<ThemeOverrides>),
+}
+
+/// Colors overriding those of a base theme. Missing colors fall back to the base
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ThemeOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_bg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_accent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_not_focused_fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_not_focused_bg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_line_number_fg: Option<String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig::Preset(DEFAULT_THEME_PRESET.to_string())
+    }
+}
+
+impl ThemeConfig {
+    /// Name of the preset the theme is based on
+    pub fn preset_name(&self) -> &str {
+        match self {
+            ThemeConfig::Preset(name) => name,
+            ThemeConfig::Custom(overrides) => {
+                overrides.preset.as_deref().unwrap_or(DEFAULT_THEME_PRESET)
+            }
+        }
+    }
+
+    /// Same theme config, but based on another preset (overrides are kept)
+    pub fn with_preset(&self, name: &str) -> ThemeConfig {
+        match self {
+            ThemeConfig::Preset(_) => ThemeConfig::Preset(name.to_string()),
+            ThemeConfig::Custom(overrides) => ThemeConfig::Custom(Box::new(ThemeOverrides {
+                preset: Some(name.to_string()),
+                ..(**overrides).clone()
+            })),
+        }
+    }
+
+    /// Build the theme described by the config, checking that every color is valid
+    pub fn resolve(&self) -> Result<Theme> {
+        let name = self.preset_name();
+        let mut theme = Theme::preset(name).ok_or_else(|| {
+            anyhow!(
+                "Unknown theme '{}' (available themes: {})",
+                name,
+                THEME_PRESETS.join(", ")
+            )
+        })?;
+
+        if let ThemeConfig::Custom(overrides) = self {
+            overrides.apply(&mut theme);
+        }
+
+        theme.validate()?;
+
+        Ok(theme)
+    }
+}
+
+impl ThemeOverrides {
+    /// Replace the colors of the theme with the ones set in the overrides
+    fn apply(&self, theme: &mut Theme) {
+        let overrides = [
+            &self.background,
+            &self.foreground,
+            &self.accent,
+            &self.border,
+            &self.highlight_bg,
+            &self.highlight_fg,
+            &self.border_accent,
+            &self.highlight_not_focused_fg,
+            &self.highlight_not_focused_bg,
+            &self.highlight_line_number_fg,
+        ];
+
+        for ((_, color), new_color) in theme.colors_mut().into_iter().zip(overrides) {
+            if let Some(new_color) = new_color {
+                *color = new_color.clone();
+            }
+        }
+    }
+}
+
+/// Colors the terminal is able to display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    /// 24-bit colors
+    TrueColor,
+    /// 256 indexed colors
+    Ansi256,
+    /// The 16 basic ANSI colors
+    Ansi16,
+}
+
+/// RGB values of the 16 basic ANSI colors (xterm defaults)
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Levels of each channel in the 6x6x6 color cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorSupport {
+    /// Detect the color support of the terminal from the environment
+    pub fn detect() -> Self {
+        // Windows Terminal and recent consoles support true colors
+        if cfg!(windows) || env::var("WT_SESSION").is_ok() {
+            return ColorSupport::TrueColor;
+        }
+
+        Self::from_env(
+            env::var("COLORTERM").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    /// Infer the color support from the values of COLORTERM and TERM
+    pub fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if matches!(colorterm, Some("truecolor") | Some("24bit")) {
+            ColorSupport::TrueColor
+        } else if term.is_some_and(|term| term.contains("256color")) {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+
+    /// Convert a color to the closest one the terminal can display
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::TrueColor, _) => color,
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_ansi256(r, g, b)),
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => rgb_to_ansi16(r, g, b),
+            (ColorSupport::Ansi16, Color::Indexed(i)) => {
+                let (r, g, b) = ansi256_to_rgb(i);
+                rgb_to_ansi16(r, g, b)
+            }
+            _ => color,
+        }
+    }
+}
+
+/// Squared distance between two RGB colors
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let dr = r1.abs_diff(r2) as u32;
+    let dg = g1.abs_diff(g2) as u32;
+    let db = b1.abs_diff(b2) as u32;
+    dr * dr + dg * dg + db * db
+}
+
+/// Closest color of the 256 color palette (color cube or grayscale ramp)
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let closest_level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(c))
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (closest_level(r), closest_level(g), closest_level(b));
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+    let cube_rgb = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = ((average.saturating_sub(8)) / 10).min(23);
+    let gray_level = (8 + 10 * gray_step) as u8;
+    let gray_index = 232 + gray_step as usize;
+
+    if distance((gray_level, gray_level, gray_level), (r, g, b)) < distance(cube_rgb, (r, g, b)) {
+        gray_index as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+/// RGB value of a color of the 256 color palette
+fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16[index as usize].1,
+        16..=231 => {
+            let i = (index - 16) as usize;
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[(i / 6) % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// Closest of the 16 basic ANSI colors
+fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb, (r, g, b)))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Theme {
     pub background: String,
@@ -16,12 +276,8 @@ pub struct Theme {
     pub highlight_line_number_fg: String,
 }
 
-impl Theme {
-    pub fn color_from_hex(hex: &str) -> Color {
-        Color::from_str(hex).unwrap_or(Color::Reset)
-    }
-
-    pub fn default() -> Theme {
+impl Default for Theme {
+    fn default() -> Theme {
         Theme {
             background: "#002626".to_string(),
             foreground: "#FCF1D5".to_string(),
@@ -35,6 +291,139 @@ impl Theme {
             highlight_line_number_fg: "#002626".to_string(),
         }
     }
+}
+
+impl Theme {
+    /// Build a theme from its colors, in the order of the struct fields
+    fn from_colors(colors: [&str; 10]) -> Theme {
+        Theme {
+            background: colors[0].to_string(),
+            foreground: colors[1].to_string(),
+            accent: colors[2].to_string(),
+            border: colors[3].to_string(),
+            highlight_bg: colors[4].to_string(),
+            highlight_fg: colors[5].to_string(),
+            border_accent: colors[6].to_string(),
+            highlight_not_focused_fg: colors[7].to_string(),
+            highlight_not_focused_bg: colors[8].to_string(),
+            highlight_line_number_fg: colors[9].to_string(),
+        }
+    }
+
+    /// Get one of the built-in themes by name
+    pub fn preset(name: &str) -> Option<Theme> {
+        let theme = match name {
+            "auto" => {
+                let name = if Self::terminal_has_light_background() {
+                    "judo-light"
+                } else {
+                    "judo"
+                };
+                return Self::preset(name);
+            }
+            "judo" => Theme::default(),
+            "judo-light" => Self::from_colors([
+                "#FCF1D5", "#002626", "#D1495B", "#002626", "#002626", "#FCF1D5", "#D1495B",
+                "#FCF1D5", "#4F6D6D", "#FCF1D5",
+            ]),
+            "nord" => Self::from_colors([
+                "#2E3440", "#D8DEE9", "#88C0D0", "#4C566A", "#88C0D0", "#2E3440", "#88C0D0",
+                "#D8DEE9", "#4C566A", "#2E3440",
+            ]),
+            "gruvbox" => Self::from_colors([
+                "#282828", "#EBDBB2", "#FE8019", "#A89984", "#EBDBB2", "#282828", "#FE8019",
+                "#EBDBB2", "#504945", "#282828",
+            ]),
+            "dracula" => Self::from_colors([
+                "#282A36", "#F8F8F2", "#FF79C6", "#6272A4", "#BD93F9", "#282A36", "#FF79C6",
+                "#F8F8F2", "#44475A", "#282A36",
+            ]),
+            "solarized-light" => Self::from_colors([
+                "#FDF6E3", "#657B83", "#CB4B16", "#93A1A1", "#268BD2", "#FDF6E3", "#CB4B16",
+                "#586E75", "#EEE8D5", "#FDF6E3",
+            ]),
+            // Only uses the palette of the terminal
+            "terminal" => Self::from_colors([
+                "reset", "reset", "yellow", "darkgray", "blue", "white", "yellow", "black", "gray",
+                "white",
+            ]),
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// Guess whether the terminal has a light background from COLORFGBG
+    /// (e.g. "15;0" is light text on a dark background)
+    pub fn terminal_has_light_background() -> bool {
+        env::var("COLORFGBG")
+            .ok()
+            .and_then(|value| value.rsplit(';').next()?.parse::<u8>().ok())
+            .is_some_and(|bg| matches!(bg, 7 | 9..=15))
+    }
+
+    /// Name and value of every color of the theme
+    pub fn colors(&self) -> [(&'static str, &String); 10] {
+        [
+            ("background", &self.background),
+            ("foreground", &self.foreground),
+            ("accent", &self.accent),
+            ("border", &self.border),
+            ("highlight_bg", &self.highlight_bg),
+            ("highlight_fg", &self.highlight_fg),
+            ("border_accent", &self.border_accent),
+            ("highlight_not_focused_fg", &self.highlight_not_focused_fg),
+            ("highlight_not_focused_bg", &self.highlight_not_focused_bg),
+            ("highlight_line_number_fg", &self.highlight_line_number_fg),
+        ]
+    }
+
+    /// Name and mutable value of every color of the theme
+    fn colors_mut(&mut self) -> [(&'static str, &mut String); 10] {
+        [
+            ("background", &mut self.background),
+            ("foreground", &mut self.foreground),
+            ("accent", &mut self.accent),
+            ("border", &mut self.border),
+            ("highlight_bg", &mut self.highlight_bg),
+            ("highlight_fg", &mut self.highlight_fg),
+            ("border_accent", &mut self.border_accent),
+            (
+                "highlight_not_focused_fg",
+                &mut self.highlight_not_focused_fg,
+            ),
+            (
+                "highlight_not_focused_bg",
+                &mut self.highlight_not_focused_bg,
+            ),
+            (
+                "highlight_line_number_fg",
+                &mut self.highlight_line_number_fg,
+            ),
+        ]
+    }
+
+    /// Check that every color is either a hex color, a 256 color index or an ANSI name
+    pub fn validate(&self) -> Result<()> {
+        for (key, value) in self.colors() {
+            Color::from_str(value)
+                .map_err(|_| anyhow!("Invalid color '{}' for theme key '{}'", value, key))?;
+        }
+        Ok(())
+    }
+
+    /// Convert every color to the closest one the terminal can display
+    pub fn adapted_to(mut self, support: ColorSupport) -> Theme {
+        for (_, value) in self.colors_mut() {
+            if let Ok(color) = Color::from_str(value) {
+                *value = support.adapt(color).to_string();
+            }
+        }
+        self
+    }
+
+    pub fn color_from_hex(hex: &str) -> Color {
+        Color::from_str(hex).unwrap_or(Color::Reset)
+    }
 
     pub fn fg(hex_color: &str) -> Style {
         Style::default().fg(Self::color_from_hex(hex_color))
@@ -65,3 +454,90 @@ impl Theme {
         Self::fg(&self.highlight_line_number_fg)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_all_presets_are_valid() -> Result<()> {
+        for name in THEME_PRESETS {
+            let theme = Theme::preset(name).expect("Preset should exist");
+            theme.validate()?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_overrides_fall_back_to_preset() -> Result<()> {
+        let config: ThemeConfig = toml::from_str(
+            r#"
+            preset = "nord"
+            accent = "red"
+            "#,
+        )?;
+
+        let theme = config.resolve()?;
+        let nord = Theme::preset("nord").unwrap();
+
+        assert_eq!(theme.accent, "red");
+        assert_eq!(theme.background, nord.background);
+        assert_eq!(theme.foreground, nord.foreground);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_color_reports_key() {
+        let config = ThemeConfig::Custom(Box::new(ThemeOverrides {
+            border: Some("#12345".to_string()),
+            ..Default::default()
+        }));
+
+        let error = config.resolve().unwrap_err().to_string();
+        assert!(error.contains("'border'"));
+        assert!(error.contains("#12345"));
+    }
+
+    #[test]
+    fn test_unknown_preset_is_an_error() {
+        let config = ThemeConfig::Preset("not-a-theme".to_string());
+        assert!(config.resolve().is_err());
+    }
+
+    #[test]
+    fn test_color_support_detection() {
+        assert_eq!(
+            ColorSupport::from_env(Some("truecolor"), Some("xterm-256color")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            ColorSupport::from_env(None, Some("xterm-256color")),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(
+            ColorSupport::from_env(None, Some("linux")),
+            ColorSupport::Ansi16
+        );
+    }
+
+    #[test]
+    fn test_colors_degrade_gracefully() {
+        let white = Color::Rgb(255, 255, 255);
+        assert_eq!(ColorSupport::TrueColor.adapt(white), white);
+        assert_eq!(ColorSupport::Ansi256.adapt(white), Color::Indexed(231));
+        assert_eq!(ColorSupport::Ansi16.adapt(white), Color::White);
+        assert_eq!(
+            ColorSupport::Ansi256.adapt(Color::Rgb(128, 128, 128)),
+            Color::Indexed(244)
+        );
+        assert_eq!(
+            ColorSupport::Ansi16.adapt(Color::Indexed(196)),
+            Color::LightRed
+        );
+        assert_eq!(ColorSupport::Ansi16.adapt(Color::Yellow), Color::Yellow);
+
+        let theme = Theme::default().adapted_to(ColorSupport::Ansi256);
+        theme.validate().unwrap();
+        assert!(theme.colors().iter().all(|(_, c)| !c.starts_with('#')));
+    }
+}
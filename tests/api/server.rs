@@ -0,0 +1,197 @@
+use crate::helpers::db::setup_test_db;
+use crate::helpers::http::{authorized, request, start_api};
+use anyhow::Result;
+use judo::db::models::{TodoItem, TodoList};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_requests_need_the_token() -> Result<()> {
+    let addr = start_api(Arc::new(setup_test_db().await?)).await?;
+
+    let response = request(addr, "GET", "/lists", &[], None).await?;
+    assert_eq!(response.status, 401);
+    assert_eq!(response.json()?["error"], "Invalid token");
+
+    let wrong = [("Authorization", "Bearer nope")];
+    let response = request(addr, "GET", "/lists", &wrong, None).await?;
+    assert_eq!(response.status, 401);
+
+    let response = authorized(addr, "GET", "/lists", None).await?;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()?, serde_json::json!([]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_internal_errors_are_not_detailed() -> Result<()> {
+    let pool = setup_test_db().await?;
+    let addr = start_api(Arc::new(pool.clone())).await?;
+    pool.close().await;
+
+    let response = authorized(addr, "GET", "/lists", None).await?;
+    assert_eq!(response.status, 500);
+    assert_eq!(response.json()?["error"], "Internal server error");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lists_and_items() -> Result<()> {
+    let pool = setup_test_db().await?;
+    let addr = start_api(Arc::new(pool.clone())).await?;
+
+    // Lists
+    for name in ["Groceries", "Chores"] {
+        let body = format!(r#"{{"name": "{}"}}"#, name);
+        let response = authorized(addr, "POST", "/lists", Some(&body)).await?;
+        assert_eq!(response.status, 201);
+        assert!(response.header("etag").is_some());
+    }
+    let response = authorized(addr, "POST", "/lists", Some(r#"{"name": " "}"#)).await?;
+    assert_eq!(response.status, 400);
+    let response = authorized(addr, "POST", "/lists", Some(r#"{"title": "A"}"#)).await?;
+    assert_eq!(response.status, 400);
+
+    let lists = authorized(addr, "GET", "/lists", None).await?.json()?;
+    assert_eq!(lists[0]["name"], "Groceries");
+    let groceries = lists[0]["id"].as_i64().unwrap();
+    let chores = lists[1]["id"].as_i64().unwrap();
+
+    let path = format!("/lists/{}/move", chores);
+    let response = authorized(addr, "POST", &path, Some(r#"{"by": -1}"#)).await?;
+    assert_eq!(response.status, 200);
+    let names: Vec<String> = TodoList::get_all(&pool)
+        .await?
+        .into_iter()
+        .map(|list| list.name)
+        .collect();
+    assert_eq!(names, vec!["Chores", "Groceries"]);
+
+    // Items
+    let path = format!("/lists/{}/items", groceries);
+    let body = r#"{"name": "Milk", "priority": "high", "due_date": "2025-10-03T17:00:00Z"}"#;
+    let milk = authorized(addr, "POST", &path, Some(body)).await?.json()?;
+    assert_eq!(milk["priority"], "high");
+    let body = r#"{"name": "Bread", "position": 0}"#;
+    let bread = authorized(addr, "POST", &path, Some(body)).await?.json()?;
+    let items = authorized(addr, "GET", &path, None).await?.json()?;
+    assert_eq!(items[0]["name"], "Bread");
+    assert_eq!(items[1]["name"], "Milk");
+
+    let milk_path = format!("/items/{}", milk["id"]);
+    let body = r#"{"name": "Oat milk", "priority": null}"#;
+    let response = authorized(addr, "PATCH", &milk_path, Some(body)).await?;
+    assert_eq!(response.status, 200);
+    let patched = response.json()?;
+    assert_eq!(patched["name"], "Oat milk");
+    assert_eq!(patched["priority"], serde_json::Value::Null);
+    assert_eq!(patched["due_date"], milk["due_date"]);
+
+    let toggle_path = format!("{}/toggle", milk_path);
+    let toggled = authorized(addr, "POST", &toggle_path, None).await?.json()?;
+    assert_eq!(toggled["is_done"], true);
+
+    let move_path = format!("{}/move", milk_path);
+    authorized(addr, "POST", &move_path, Some(r#"{"by": -1}"#)).await?;
+    let items = TodoItem::get_by_list_id(&pool, groceries).await?;
+    assert_eq!(items[0].name, "Oat milk");
+
+    let bread_path = format!("/items/{}", bread["id"]);
+    let response = authorized(addr, "DELETE", &bread_path, None).await?;
+    assert_eq!(response.status, 204);
+    let response = authorized(addr, "GET", &bread_path, None).await?;
+    assert_eq!(response.status, 404);
+    assert_eq!(
+        response.json()?["error"],
+        format!("No item with id {}", bread["id"])
+    );
+
+    let response = authorized(addr, "DELETE", &format!("/lists/{}", groceries), None).await?;
+    assert_eq!(response.status, 204);
+    assert!(TodoItem::get_by_list_id(&pool, groceries).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_if_match_prevents_lost_updates() -> Result<()> {
+    let addr = start_api(Arc::new(setup_test_db().await?)).await?;
+    let body = r#"{"name": "Groceries"}"#;
+    let list = authorized(addr, "POST", "/lists", Some(body))
+        .await?
+        .json()?;
+    let path = format!("/lists/{}/items", list["id"]);
+    let item = authorized(addr, "POST", &path, Some(r#"{"name": "Milk"}"#)).await?;
+    let etag = item.header("etag").unwrap().to_string();
+    let path = format!("/items/{}", item.json()?["id"]);
+    let auth = format!("Bearer {}", crate::helpers::http::TOKEN);
+
+    // A change with the current ETag goes through and changes the ETag
+    let headers = [("Authorization", auth.as_str()), ("If-Match", &etag)];
+    let body = r#"{"is_done": true}"#;
+    let response = request(addr, "PATCH", &path, &headers, Some(body)).await?;
+    assert_eq!(response.status, 200);
+    assert_ne!(response.header("etag"), Some(etag.as_str()));
+
+    // The same ETag is now stale
+    let body = r#"{"name": "Soy milk"}"#;
+    let response = request(addr, "PATCH", &path, &headers, Some(body)).await?;
+    assert_eq!(response.status, 412);
+    let response = request(addr, "DELETE", &path, &headers, None).await?;
+    assert_eq!(response.status, 412);
+
+    let item = authorized(addr, "GET", &path, None).await?.json()?;
+    assert_eq!(item["name"], "Milk");
+    assert_eq!(item["is_done"], true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_if_match_lets_one_concurrent_write_through() -> Result<()> {
+    let addr = start_api(Arc::new(setup_test_db().await?)).await?;
+    let body = r#"{"name": "Groceries"}"#;
+    let list = authorized(addr, "POST", "/lists", Some(body)).await?;
+    let etag = list.header("etag").unwrap().to_string();
+    let path = format!("/lists/{}", list.json()?["id"]);
+    let auth = format!("Bearer {}", crate::helpers::http::TOKEN);
+    let headers = [("Authorization", auth.as_str()), ("If-Match", &etag)];
+
+    // Both writers expect the same version: only one of them wins
+    let (first, second) = tokio::join!(
+        request(addr, "PATCH", &path, &headers, Some(r#"{"name": "Food"}"#)),
+        request(addr, "PATCH", &path, &headers, Some(r#"{"name": "Shop"}"#)),
+    );
+    let mut statuses = [first?.status, second?.status];
+    statuses.sort();
+    assert_eq!(statuses, [200, 412]);
+
+    // Moves check the ETag too
+    let move_path = format!("{}/move", path);
+    let body = r#"{"by": 1}"#;
+    let response = request(addr, "POST", &move_path, &headers, Some(body)).await?;
+    assert_eq!(response.status, 412);
+    let current = authorized(addr, "GET", &path, None).await?;
+    let etag = current.header("etag").unwrap().to_string();
+    let headers = [("Authorization", auth.as_str()), ("If-Match", &etag)];
+    let response = request(addr, "POST", &move_path, &headers, Some(body)).await?;
+    assert_eq!(response.status, 200);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schema() -> Result<()> {
+    let addr = start_api(Arc::new(setup_test_db().await?)).await?;
+    let schema = authorized(addr, "GET", "/schema", None).await?.json()?;
+    for name in [
+        "List",
+        "Item",
+        "NewList",
+        "ListPatch",
+        "NewItem",
+        "ItemPatch",
+        "Move",
+        "Error",
+    ] {
+        assert_eq!(schema[name]["type"], "object", "{}", name);
+    }
+    assert_eq!(schema["NewItem"]["required"], serde_json::json!(["name"]));
+    Ok(())
+}
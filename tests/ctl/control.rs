@@ -0,0 +1,154 @@
+use crate::helpers::tui::Tui;
+use anyhow::Result;
+use judo::app::App;
+use judo::ctl::{Client, ControlServer, Message, Request};
+use judo::db::models::{NewTodoList, TodoItem, TodoList};
+use std::time::Duration;
+
+/// Send a request while the app handles the requests of its socket, as its
+/// main loop does
+async fn send(app: &mut App, client: &mut Client, request: Request) -> Result<Message> {
+    let reply = client.send(&request);
+    tokio::pin!(reply);
+    loop {
+        tokio::select! {
+            reply = &mut reply => return reply,
+            _ = tokio::time::sleep(Duration::from_millis(5)) => app.handle_ctl_requests().await,
+        }
+    }
+}
+
+/// Events received by a client, until none comes for a while
+async fn events(client: &mut Client) -> Result<Vec<Message>> {
+    let mut events = Vec::new();
+    while let Ok(message) =
+        tokio::time::timeout(Duration::from_millis(100), client.next_message()).await
+    {
+        events.extend(message?);
+    }
+    Ok(events)
+}
+
+#[tokio::test]
+async fn test_drive_the_app_through_its_socket() -> Result<()> {
+    let mut tui = Tui::new().await?;
+    let dir = tempfile::tempdir()?;
+    let socket = dir.path().join("judo.sock");
+    tui.app.control = Some(ControlServer::bind(&socket)?);
+    let app = &mut tui.app;
+
+    let mut client = Client::connect(&socket).await?;
+    let mut watcher = Client::connect(&socket).await?;
+    let Message::State(state) = send(app, &mut watcher, Request::State).await? else {
+        panic!("Expected the state of the app");
+    };
+    assert_eq!((state.db.as_str(), state.list), ("test", None));
+
+    // A script writes to the database directly, then asks for a refresh
+    TodoList::create(
+        &*app.store,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+    assert_eq!(send(app, &mut client, Request::Refresh).await?, Message::Ok);
+    let request = Request::SelectList {
+        name: "Groceries".to_string(),
+    };
+    assert_eq!(send(app, &mut client, request).await?, Message::Ok);
+    let request = Request::AddItem {
+        name: "Milk".to_string(),
+        list: None,
+        priority: None,
+        due_date: None,
+    };
+    assert_eq!(send(app, &mut client, request).await?, Message::Ok);
+    let request = Request::Toggle {
+        item: Some("Milk".to_string()),
+        list: Some("Groceries".to_string()),
+    };
+    assert_eq!(send(app, &mut client, request).await?, Message::Ok);
+
+    let list_id = app.lists_component.lists[0].list.id;
+    let items = TodoItem::get_by_list_id(&*app.store, list_id).await?;
+    assert_eq!((items[0].name.as_str(), items[0].is_done), ("Milk", true));
+
+    // Mistakes are reported to the client only
+    let request = Request::SelectList {
+        name: "Chores".to_string(),
+    };
+    assert_eq!(
+        send(app, &mut client, request).await?,
+        Message::Error {
+            message: "No list named 'Chores'".to_string()
+        }
+    );
+
+    let request = Request::SwitchDb {
+        name: "work".to_string(),
+    };
+    assert_eq!(send(app, &mut client, request).await?, Message::Ok);
+    assert_eq!(app.current_db_config.name, "work");
+
+    assert_eq!(
+        events(&mut watcher).await?,
+        vec![
+            Message::Changed,
+            Message::ListSelected {
+                list: Some("Groceries".to_string())
+            },
+            Message::Changed,
+            Message::Changed,
+            Message::DbSwitched {
+                db: "work".to_string()
+            },
+            Message::ListSelected { list: None },
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_keyboard_changes_are_emitted() -> Result<()> {
+    let mut tui = Tui::new().await?;
+    let dir = tempfile::tempdir()?;
+    let socket = dir.path().join("judo.sock");
+    tui.app.control = Some(ControlServer::bind(&socket)?);
+
+    let mut watcher = Client::connect(&socket).await?;
+    send(&mut tui.app, &mut watcher, Request::State).await?;
+
+    tui.keys("aChores<Enter>").await?;
+    tui.app.handle_ctl_requests().await;
+    assert_eq!(events(&mut watcher).await?, vec![Message::Changed]);
+
+    // The socket is removed with the app
+    drop(tui);
+    assert!(!socket.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refuse_a_socket_directory_others_can_access() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::tempdir()?;
+    let shared = dir.path().join("judo-shared");
+    std::fs::create_dir(&shared)?;
+    std::fs::set_permissions(&shared, std::fs::Permissions::from_mode(0o777))?;
+    let error = ControlServer::bind_in(&shared).err().unwrap().to_string();
+    assert!(error.contains("accessible to other users"), "{}", error);
+    assert_eq!(std::fs::read_dir(&shared)?.count(), 0);
+
+    std::fs::set_permissions(&shared, std::fs::Permissions::from_mode(0o700))?;
+    let server = ControlServer::bind_in(&shared)?;
+    assert_eq!(server.path(), ControlServer::default_path(&shared));
+    assert!(server.path().exists());
+
+    // A directory created for the socket is private
+    let created = dir.path().join("judo-new");
+    ControlServer::bind_in(&created)?;
+    let mode = std::fs::metadata(&created)?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use judo::db::connections::init_db;
+use judo::db::crypto;
+use judo::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use judo::sync::change_log::record_local_changes;
+
+#[tokio::test]
+async fn test_encrypt_and_decrypt_database_in_place() -> Result<()> {
+    let pool = setup_test_db().await?;
+
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Customers".to_string(),
+        },
+    )
+    .await?;
+    TodoItem::create(
+        &pool,
+        NewTodoItem {
+            list_id: list.id,
+            name: "Call Alice about the renewal".to_string(),
+            priority: None,
+            due_date: None,
+        },
+    )
+    .await?;
+
+    assert!(!crypto::is_encrypted(&pool).await?);
+    crypto::encrypt_database(&pool, "correct horse").await?;
+    assert!(crypto::is_encrypted(&pool).await?);
+    assert!(crypto::is_unlocked(&pool));
+
+    // Names are encrypted on disk...
+    let stored: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM todo_lists UNION ALL SELECT name FROM todo_items")
+            .fetch_all(&pool)
+            .await?;
+    assert_eq!(stored.len(), 2);
+    assert!(stored.iter().all(|name| crypto::is_encrypted_text(name)));
+    assert!(stored.iter().all(|name| !name.contains("Alice")));
+
+    // ...but read and written in clear while unlocked
+    let mut list = TodoList::get_all(&pool).await?.remove(0);
+    assert_eq!(list.name, "Customers");
+    list.update_name(&pool, "Key customers".to_string()).await?;
+    let item = TodoItem::create(
+        &pool,
+        NewTodoItem {
+            list_id: list.id,
+            name: "Send Bob the quote".to_string(),
+            priority: None,
+            due_date: None,
+        },
+    )
+    .await?;
+    assert_eq!(item.name, "Send Bob the quote");
+    let names: Vec<String> = TodoItem::get_by_list_id(&pool, list.id)
+        .await?
+        .into_iter()
+        .map(|item| item.name)
+        .collect();
+    assert_eq!(
+        names,
+        vec!["Call Alice about the renewal", "Send Bob the quote"]
+    );
+
+    // Locked databases can't be read, and need the right passphrase
+    crypto::lock(&pool);
+    assert!(TodoList::get_all(&pool).await.is_err());
+    assert!(crypto::unlock(&pool, "wrong horse").await.is_err());
+    assert!(!crypto::is_unlocked(&pool));
+    crypto::unlock(&pool, "correct horse").await?;
+    assert_eq!(TodoList::get_all(&pool).await?[0].name, "Key customers");
+
+    // Decrypting restores plain names and removes the passphrase
+    crypto::decrypt_database(&pool, "correct horse").await?;
+    assert!(!crypto::is_encrypted(&pool).await?);
+    assert!(!crypto::is_unlocked(&pool));
+    let stored: Vec<String> = sqlx::query_scalar("SELECT name FROM todo_items ORDER BY ordering")
+        .fetch_all(&pool)
+        .await?;
+    assert_eq!(
+        stored,
+        vec!["Call Alice about the renewal", "Send Bob the quote"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encrypting_twice_or_with_empty_passphrase_fails() -> Result<()> {
+    let pool = setup_test_db().await?;
+
+    assert!(crypto::encrypt_database(&pool, "").await.is_err());
+    crypto::encrypt_database(&pool, "passphrase").await?;
+    assert!(crypto::encrypt_database(&pool, "passphrase").await.is_err());
+    assert!(crypto::decrypt_database(&pool, "other").await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_no_plain_names_left_in_the_database_file() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("customers.db");
+    let pool = init_db(&format!("sqlite:{}", path.display())).await?;
+
+    // History recorded before the database is encrypted...
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Customers".to_string(),
+        },
+    )
+    .await?;
+    TodoItem::create(
+        &pool,
+        NewTodoItem {
+            list_id: list.id,
+            name: "Call Alice about the renewal".to_string(),
+            priority: None,
+            due_date: None,
+        },
+    )
+    .await?;
+    record_local_changes(&pool).await?;
+    crypto::encrypt_database(&pool, "correct horse").await?;
+
+    // ...and after
+    TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Secret customer Bob".to_string(),
+        },
+    )
+    .await?;
+    record_local_changes(&pool).await?;
+
+    let logged: Vec<String> =
+        sqlx::query_scalar("SELECT value FROM sync_changes WHERE field = 'name'")
+            .fetch_all(&pool)
+            .await?;
+    assert_eq!(logged.len(), 3);
+    assert!(logged.iter().all(|name| crypto::is_encrypted_text(name)));
+    pool.close().await;
+
+    for entry in std::fs::read_dir(dir.path())? {
+        let bytes = std::fs::read(entry?.path())?;
+        let text = String::from_utf8_lossy(&bytes);
+        for name in ["Customers", "Alice", "Bob"] {
+            assert!(!text.contains(name), "{} found in the file", name);
+        }
+    }
+
+    // The log is still read in clear once unlocked
+    let pool = init_db(&format!("sqlite:{}", path.display())).await?;
+    crypto::unlock(&pool, "correct horse").await?;
+    assert_eq!(record_local_changes(&pool).await?, 0);
+    crypto::decrypt_database(&pool, "correct horse").await?;
+    let logged: Vec<String> =
+        sqlx::query_scalar("SELECT value FROM sync_changes WHERE field = 'name' ORDER BY id")
+            .fetch_all(&pool)
+            .await?;
+    assert_eq!(
+        logged,
+        [
+            "Customers",
+            "Call Alice about the renewal",
+            "Secret customer Bob"
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_writes_to_a_locked_database_fail() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let connection_str = format!("sqlite:{}", dir.path().join("vault.db").display());
+    let pool = init_db(&connection_str).await?;
+    let new_list = |name: &str| NewTodoList {
+        name: name.to_string(),
+    };
+    let mut list = TodoList::create(&pool, new_list("Customers")).await?;
+    crypto::encrypt_database(&pool, "correct horse").await?;
+    crypto::lock(&pool);
+
+    // Locked in this pool...
+    let error = TodoList::create(&pool, new_list("Bob")).await.unwrap_err();
+    assert!(format!("{:#}", error).contains("locked"), "{:#}", error);
+    assert!(list.update_name(&pool, "Alice".to_string()).await.is_err());
+    pool.close().await;
+
+    // ...and once opened again
+    let pool = init_db(&connection_str).await?;
+    assert!(TodoList::create(&pool, new_list("Bob")).await.is_err());
+    let stored: Vec<String> = sqlx::query_scalar("SELECT name FROM todo_lists")
+        .fetch_all(&pool)
+        .await?;
+    assert_eq!(stored.len(), 1);
+    assert!(crypto::is_encrypted_text(&stored[0]));
+
+    crypto::unlock(&pool, "correct horse").await?;
+    TodoList::create(&pool, new_list("Bob")).await?;
+    Ok(())
+}
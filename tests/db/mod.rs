@@ -1,2 +1,6 @@
+pub mod encryption;
+pub mod ordering;
+pub mod postgres;
+pub mod stores;
 pub mod todo_item_ops;
 pub mod todo_list_ops;
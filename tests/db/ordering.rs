@@ -0,0 +1,91 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use judo::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use judo::db::store::TodoStore;
+
+#[tokio::test]
+async fn test_orderings_are_unique() -> Result<()> {
+    let pool = setup_test_db().await?;
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+    let milk = TodoItem::create(
+        &pool,
+        NewTodoItem {
+            list_id: list.id,
+            name: "Milk".to_string(),
+            priority: None,
+            due_date: None,
+        },
+    )
+    .await?;
+    TodoItem::create(
+        &pool,
+        NewTodoItem {
+            list_id: list.id,
+            name: "Bread".to_string(),
+            priority: None,
+            due_date: None,
+        },
+    )
+    .await?;
+
+    let duplicate = sqlx::query("UPDATE todo_items SET ordering = ?1 WHERE name = 'Bread'")
+        .bind(milk.ordering)
+        .execute(&pool)
+        .await;
+    assert!(duplicate.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_repair_orderings() -> Result<()> {
+    let pool = setup_test_db().await?;
+
+    // Orderings as left by an interrupted reordering of an older version
+    sqlx::query("DROP INDEX idx_todo_items_list_ordering")
+        .execute(&pool)
+        .await?;
+    sqlx::query("DROP INDEX idx_todo_lists_ordering")
+        .execute(&pool)
+        .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO todo_lists (id, uid, name, ordering, created_at, updated_at)
+        VALUES (1, 'l1', 'Groceries', 1, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z'),
+               (2, 'l2', 'Chores', 1, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z');
+        INSERT INTO todo_items (uid, list_id, name, is_done, ordering, created_at, updated_at)
+        VALUES ('i1', 1, 'Milk', FALSE, 2, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z'),
+               ('i2', 1, 'Bread', FALSE, 2, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z'),
+               ('i3', 1, 'Eggs', FALSE, 0, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z'),
+               ('i4', 2, 'Laundry', FALSE, 7, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z');
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    assert_eq!(pool.repair_orderings().await?, 5);
+    assert_eq!(pool.repair_orderings().await?, 0);
+
+    let lists = TodoList::get_all(&pool).await?;
+    let orderings: Vec<(&str, i64)> = lists
+        .iter()
+        .map(|l| (l.name.as_str(), l.ordering))
+        .collect();
+    assert_eq!(orderings, vec![("Groceries", 1024), ("Chores", 2048)]);
+
+    let items = TodoItem::get_by_list_id(&pool, 1).await?;
+    let orderings: Vec<(&str, i64)> = items
+        .iter()
+        .map(|i| (i.name.as_str(), i.ordering))
+        .collect();
+    assert_eq!(
+        orderings,
+        vec![("Eggs", 1024), ("Milk", 2048), ("Bread", 3072)]
+    );
+    Ok(())
+}
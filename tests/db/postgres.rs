@@ -0,0 +1,102 @@
+use crate::db::stores::{check_orderings, check_store};
+use crate::helpers::db::setup_test_postgres;
+use anyhow::Result;
+use judo::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use judo::db::postgres_store::PostgresStore;
+use judo::db::store::TodoStore;
+use sqlx::postgres::PgConnectOptions;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server in JUDO_TEST_POSTGRES_URL"]
+async fn test_postgres_store() -> Result<()> {
+    let store = setup_test_postgres().await?;
+    check_store(&store).await
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server in JUDO_TEST_POSTGRES_URL"]
+async fn test_postgres_orderings() -> Result<()> {
+    let store = setup_test_postgres().await?;
+    check_orderings(&store).await
+}
+
+/// Orderings of the items of a list, in order
+async fn orderings(store: &dyn TodoStore, list_id: i64) -> Result<Vec<i64>> {
+    Ok(TodoItem::get_by_list_id(store, list_id)
+        .await?
+        .into_iter()
+        .map(|item| item.ordering)
+        .collect())
+}
+
+#[tokio::test]
+#[ignore = "needs a PostgreSQL server in JUDO_TEST_POSTGRES_URL"]
+async fn test_concurrent_users_keep_orderings_unique() -> Result<()> {
+    let store = setup_test_postgres().await?;
+
+    // A second user, with its own connections to the same database
+    let options: PgConnectOptions = store.pool().connect_options().as_ref().clone();
+    let other = PostgresStore::connect_with(options).await?;
+    let users: Vec<Arc<dyn TodoStore>> = vec![Arc::new(store), Arc::new(other)];
+
+    let list = TodoList::create(
+        &*users[0],
+        NewTodoList {
+            name: "Team".to_string(),
+        },
+    )
+    .await?;
+
+    // Both users add items at the same time
+    let mut tasks = Vec::new();
+    for i in 0..20 {
+        let store = users[i % 2].clone();
+        tasks.push(tokio::spawn(async move {
+            TodoItem::create(
+                &*store,
+                NewTodoItem {
+                    list_id: list.id,
+                    name: format!("Task {}", i),
+                    priority: None,
+                    due_date: None,
+                },
+            )
+            .await
+        }));
+    }
+    let mut items = Vec::new();
+    for task in tasks {
+        items.push(task.await??);
+    }
+    let created = orderings(&*users[0], list.id).await?;
+    assert_eq!(created.len(), 20);
+    assert!(
+        created.windows(2).all(|pair| pair[0] < pair[1]),
+        "duplicate orderings: {:?}",
+        created
+    );
+
+    // ...and reorder them at the same time
+    let mut tasks = Vec::new();
+    for (i, mut item) in items.into_iter().enumerate() {
+        let store = users[i % 2].clone();
+        tasks.push(tokio::spawn(async move {
+            if i % 3 == 0 {
+                item.move_up_by(&*store, i % 7 + 1).await
+            } else {
+                item.move_down_by(&*store, i % 5 + 1).await
+            }
+        }));
+    }
+    for task in tasks {
+        task.await??;
+    }
+
+    let orderings = orderings(&*users[1], list.id).await?;
+    let unique: HashSet<i64> = orderings.iter().copied().collect();
+    assert_eq!(orderings.len(), 20);
+    assert_eq!(unique.len(), 20, "duplicate orderings: {:?}", orderings);
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use judo::db::json_store::JsonStore;
+use judo::db::memory_store::MemoryStore;
+use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList, UIList};
+use judo::db::store::{TodoStore, open_store};
+
+/// Exercise the operations used by the UI on a store (shared by all backends)
+pub async fn check_store(store: &dyn TodoStore) -> Result<()> {
+    let groceries = TodoList::create(
+        store,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+    let mut chores = TodoList::create(
+        store,
+        NewTodoList {
+            name: "Chores".to_string(),
+        },
+    )
+    .await?;
+    assert!(chores.ordering > groceries.ordering);
+
+    let mut items = Vec::new();
+    for name in ["Milk", "Bread", "Eggs", "Butter"] {
+        let item = TodoItem::create(
+            store,
+            NewTodoItem {
+                list_id: groceries.id,
+                name: name.to_string(),
+                priority: None,
+                due_date: None,
+            },
+        )
+        .await?;
+        items.push(item);
+    }
+    let item_names =
+        |items: Vec<TodoItem>| -> Vec<String> { items.into_iter().map(|item| item.name).collect() };
+
+    // Updates
+    let mut eggs = items[2].clone();
+    eggs.update_name(store, "Free-range eggs".to_string())
+        .await?;
+    eggs.toggle_done(store).await?;
+    eggs.update_priority(store, Priority::High).await?;
+    let stored = TodoItem::get_by_id(store, eggs.id).await?.unwrap();
+    assert_eq!(stored.name, "Free-range eggs");
+    assert!(stored.is_done);
+    assert_eq!(stored.priority, Some(Priority::High));
+
+    // Ordering
+    eggs.move_up_by(store, 2).await?;
+    assert_eq!(
+        item_names(TodoItem::get_by_list_id(store, groceries.id).await?),
+        vec!["Free-range eggs", "Milk", "Bread", "Butter"]
+    );
+    eggs.move_down(store).await?;
+    let mut butter = items[3].clone();
+    butter.move_up_by(store, 10).await?;
+    assert_eq!(
+        item_names(TodoItem::get_by_list_id(store, groceries.id).await?),
+        vec!["Butter", "Milk", "Free-range eggs", "Bread"]
+    );
+    chores.move_up(store).await?;
+    let lists: Vec<String> = TodoList::get_all(store)
+        .await?
+        .into_iter()
+        .map(|l| l.name)
+        .collect();
+    assert_eq!(lists, vec!["Chores", "Groceries"]);
+
+    // Deletion
+    items[0].clone().delete(store).await?;
+    assert_eq!(
+        item_names(TodoItem::get_by_list_id(store, groceries.id).await?),
+        vec!["Butter", "Free-range eggs", "Bread"]
+    );
+
+    // Moving to another list keeps the item as it is
+    let sweep = NewTodoItem {
+        list_id: chores.id,
+        name: "Sweep".to_string(),
+        priority: None,
+        due_date: None,
+    };
+    TodoItem::create(store, sweep).await?;
+    let before = TodoItem::get_by_id(store, eggs.id).await?.unwrap();
+    let moved = store.move_item_to_list(eggs.id, chores.id, 0).await?;
+    let stored = TodoItem::get_by_id(store, eggs.id).await?.unwrap();
+    for item in [&moved, &stored] {
+        assert_eq!(item.list_id, chores.id);
+        assert_eq!(item.uid, before.uid);
+        assert_eq!(item.created_at, before.created_at);
+        assert_eq!(item.name, "Free-range eggs");
+        assert!(item.is_done);
+        assert_eq!(item.priority, Some(Priority::High));
+        assert_eq!(item.status, before.status);
+    }
+    assert_eq!(
+        item_names(TodoItem::get_by_list_id(store, chores.id).await?),
+        vec!["Free-range eggs", "Sweep"]
+    );
+    assert_eq!(
+        item_names(TodoItem::get_by_list_id(store, groceries.id).await?),
+        vec!["Butter", "Bread"]
+    );
+
+    groceries.clone().delete(store).await?;
+    assert!(TodoList::get_by_id(store, groceries.id).await?.is_none());
+    assert!(TodoItem::get_by_id(store, butter.id).await?.is_none());
+    assert_eq!(UIList::get_all(store).await?.len(), 1);
+
+    // Items need an existing list
+    let orphan = NewTodoItem {
+        list_id: groceries.id,
+        name: "Orphan".to_string(),
+        priority: None,
+        due_date: None,
+    };
+    assert!(TodoItem::create(store, orphan).await.is_err());
+
+    Ok(())
+}
+
+/// Check that a move only changes the moved item, until there's no room left
+/// and the list is spaced out again (shared by all backends)
+pub async fn check_orderings(store: &dyn TodoStore) -> Result<()> {
+    let list = TodoList::create(
+        store,
+        NewTodoList {
+            name: "Alphabet".to_string(),
+        },
+    )
+    .await?;
+    let mut names = Vec::new();
+    for name in ["a", "b", "c", "d", "e"] {
+        let new_item = NewTodoItem {
+            list_id: list.id,
+            name: name.to_string(),
+            priority: None,
+            due_date: None,
+        };
+        TodoItem::create(store, new_item).await?;
+        names.push(name.to_string());
+    }
+
+    // Moving the last item up only changes its ordering
+    let before = TodoItem::get_by_list_id(store, list.id).await?;
+    let mut last = before[4].clone();
+    last.move_up_by(store, 2).await?;
+    let after = TodoItem::get_by_list_id(store, list.id).await?;
+    let moved: Vec<&str> = after
+        .iter()
+        .filter(|item| {
+            !before
+                .iter()
+                .any(|b| b.id == item.id && b.ordering == item.ordering)
+        })
+        .map(|item| item.name.as_str())
+        .collect();
+    assert_eq!(moved, vec!["e"]);
+    let e = names.remove(4);
+    names.insert(2, e);
+
+    // Moving items to the same spot again and again runs out of room
+    for _ in 0..15 {
+        let items = TodoItem::get_by_list_id(store, list.id).await?;
+        let mut last = items[items.len() - 1].clone();
+        last.move_up_by(store, items.len() - 2).await?;
+        let name = names.pop().unwrap();
+        names.insert(1, name);
+    }
+
+    let items = TodoItem::get_by_list_id(store, list.id).await?;
+    let item_names: Vec<String> = items.iter().map(|item| item.name.clone()).collect();
+    assert_eq!(item_names, names);
+    assert!(
+        items
+            .windows(2)
+            .all(|pair| pair[0].ordering < pair[1].ordering)
+    );
+
+    // Items and lists inserted at a position
+    for (name, position) in [("first", 0), ("third", 2), ("last", 100)] {
+        let new_item = NewTodoItem {
+            list_id: list.id,
+            name: name.to_string(),
+            priority: None,
+            due_date: None,
+        };
+        TodoItem::create_at(store, new_item, position).await?;
+        names.insert(position.min(names.len()), name.to_string());
+    }
+    let items = TodoItem::get_by_list_id(store, list.id).await?;
+    let item_names: Vec<String> = items.iter().map(|item| item.name.clone()).collect();
+    assert_eq!(item_names, names);
+
+    let new_list = NewTodoList {
+        name: "Top".to_string(),
+    };
+    let top = TodoList::create_at(store, new_list, 0).await?;
+    assert_eq!(TodoList::get_all(store).await?[0].id, top.id);
+
+    // Nothing to repair
+    assert_eq!(store.repair_orderings().await?, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sqlite_store() -> Result<()> {
+    let pool = setup_test_db().await?;
+    check_store(&pool).await?;
+    check_orderings(&pool).await
+}
+
+#[tokio::test]
+async fn test_memory_store() -> Result<()> {
+    let store = MemoryStore::default();
+    check_store(&store).await?;
+    check_orderings(&store).await
+}
+
+#[tokio::test]
+async fn test_json_store_persists_changes() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("todos.json");
+    let path = path.to_str().unwrap();
+
+    check_store(&JsonStore::open(path)?).await?;
+
+    // Everything is read back from the file
+    let store = JsonStore::open(path)?;
+    let lists = UIList::get_all(&store).await?;
+    assert_eq!(lists.len(), 1);
+    assert_eq!(lists[0].list.name, "Chores");
+
+    let content = std::fs::read_to_string(path)?;
+    assert!(content.contains("\"Chores\""));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_store_by_scheme() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    let sqlite = open_store(&format!("sqlite:{}", dir.path().join("a.db").display())).await?;
+    assert!(sqlite.sqlite_pool().is_some());
+
+    let json = open_store(&format!("json:{}", dir.path().join("a.json").display())).await?;
+    assert!(json.sqlite_pool().is_none());
+    assert!(dir.path().join("a.json").exists());
+
+    // Memory stores live for the whole session
+    let memory = open_store("memory:scratch").await?;
+    memory
+        .create_list(NewTodoList {
+            name: "Scratch".to_string(),
+        })
+        .await?;
+    assert_eq!(
+        open_store("memory:scratch").await?.get_lists().await?.len(),
+        1
+    );
+    assert!(
+        open_store("memory:other")
+            .await?
+            .get_lists()
+            .await?
+            .is_empty()
+    );
+
+    assert!(open_store("postgres://localhost/judo").await.is_err());
+    assert!(open_store("judo.db").await.is_err());
+    Ok(())
+}
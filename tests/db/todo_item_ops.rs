@@ -37,7 +37,7 @@ async fn test_todo_item_crud_operations() -> Result<()> {
     // Verify the created list has correct properties
     assert!(created_item_with_all_fields.id > 0);
     assert_eq!(created_item_with_all_fields.list_id, created_list.id);
-    assert_eq!(created_item_with_all_fields.is_done, false);
+    assert!(!created_item_with_all_fields.is_done);
     assert_eq!(created_item_with_all_fields.name, "My item");
     assert_eq!(created_item_with_all_fields.priority, Some(Priority::High));
     assert!(created_item_with_all_fields.due_date.is_some());
@@ -53,7 +53,7 @@ async fn test_todo_item_crud_operations() -> Result<()> {
     assert!(created_item_without_due_date.id > 0);
     assert_eq!(created_item_without_due_date.list_id, created_list.id);
     assert_eq!(created_item_without_due_date.name, "My item without date");
-    assert_eq!(created_item_without_due_date.is_done, false);
+    assert!(!created_item_without_due_date.is_done);
     assert_eq!(created_item_without_due_date.priority, Some(Priority::Low));
     assert!(created_item_without_due_date.due_date.is_none());
     assert!(created_item_without_due_date.created_at <= Utc::now());
@@ -175,7 +175,7 @@ async fn test_todo_item_creation_edge_cases() -> Result<()> {
     assert_eq!(created_minimal.name, "Minimal item");
     assert_eq!(created_minimal.priority, None);
     assert_eq!(created_minimal.due_date, None);
-    assert_eq!(created_minimal.is_done, false);
+    assert!(!created_minimal.is_done);
 
     // Test creating item with empty name
     let empty_name_item = NewTodoItem {
@@ -379,7 +379,7 @@ async fn test_todo_item_toggle_done_edge_cases() -> Result<()> {
     .await?;
 
     // Initial state should be false
-    assert_eq!(test_item.is_done, false);
+    assert!(!test_item.is_done);
 
     // Toggle multiple times to test both directions
     for i in 0..10 {
@@ -674,3 +674,58 @@ async fn test_todo_item_ordering_by_creation_date() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_todo_item_status_follows_is_done() -> Result<()> {
+    let pool = setup_test_db().await?;
+    let mut list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Release".to_string(),
+        },
+    )
+    .await?;
+    let mut item = TodoItem::create(
+        &pool,
+        NewTodoItem {
+            list_id: list.id,
+            name: "Write changelog".to_string(),
+            priority: None,
+            due_date: None,
+        },
+    )
+    .await?;
+
+    // Lists without statuses use the default ones, and items start in the first
+    let statuses = list.statuses();
+    assert_eq!(statuses, ["todo", "doing", "blocked", "done"]);
+    assert_eq!(item.status_in(&statuses), "todo");
+
+    item.update_status(&pool, &statuses, "doing").await?;
+    let stored = TodoItem::get_by_id(&pool, item.id).await?.unwrap();
+    assert_eq!(stored.status.as_deref(), Some("doing"));
+    assert!(!stored.is_done);
+
+    // The last status is the done one, both ways
+    item.update_status(&pool, &statuses, "done").await?;
+    let stored = TodoItem::get_by_id(&pool, item.id).await?.unwrap();
+    assert!(stored.is_done);
+    assert_eq!(stored.status_in(&statuses), "done");
+
+    item.toggle_done(&pool).await?;
+    let stored = TodoItem::get_by_id(&pool, item.id).await?.unwrap();
+    assert!(!stored.is_done);
+    assert_eq!(stored.status_in(&statuses), "todo");
+
+    // Items in a status the list no longer has go back to the first one
+    item.update_status(&pool, &statuses, "blocked").await?;
+    list.update_statuses(&pool, Some("backlog,review,shipped".to_string()))
+        .await?;
+    let list = TodoList::get_by_id(&pool, list.id).await?.unwrap();
+    let statuses = list.statuses();
+    assert_eq!(statuses, ["backlog", "review", "shipped"]);
+    let stored = TodoItem::get_by_id(&pool, item.id).await?.unwrap();
+    assert_eq!(stored.status_in(&statuses), "backlog");
+
+    Ok(())
+}
@@ -250,7 +250,7 @@ async fn test_todo_list_cascade_deletion() -> Result<()> {
     let item3 = TodoItem::create(
         &pool,
         NewTodoItem {
-            list_id: test_list.id.clone(),
+            list_id: test_list.id,
             name: "Item 3".to_string(),
             priority: None,
             due_date: Some(Utc::now() - Duration::days(1)),
@@ -259,21 +259,21 @@ async fn test_todo_list_cascade_deletion() -> Result<()> {
     .await?;
 
     // Save the id
-    let test_list_id = test_list.id.clone();
+    let test_list_id = test_list.id;
 
     // Verify items exist
-    let items_before = TodoItem::get_by_list_id(&pool, test_list_id.clone()).await?;
+    let items_before = TodoItem::get_by_list_id(&pool, test_list_id).await?;
     assert_eq!(items_before.len(), 3);
 
     // Delete the list
     test_list.delete(&pool).await?;
 
     // Verify list is deleted
-    let deleted_list = TodoList::get_by_id(&pool, test_list_id.clone()).await?;
+    let deleted_list = TodoList::get_by_id(&pool, test_list_id).await?;
     assert!(deleted_list.is_none());
 
     // Verify all items are cascade deleted
-    let items_after = TodoItem::get_by_list_id(&pool, test_list_id.clone()).await?;
+    let items_after = TodoItem::get_by_list_id(&pool, test_list_id).await?;
     assert_eq!(items_after.len(), 0);
 
     // Verify each item individually
@@ -0,0 +1,358 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use judo::sync::ical::{VCalendar, VTodo};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// Path of the calendar home served
+const HOME: &str = "/calendars/me/";
+
+#[derive(Default)]
+struct Calendar {
+    name: String,
+    /// Resources by href: content and ETag
+    resources: BTreeMap<String, (String, String)>,
+}
+
+#[derive(Default)]
+struct ServerState {
+    /// Calendars by href
+    calendars: BTreeMap<String, Calendar>,
+    last_etag: u64,
+    /// Number of GET requests served
+    gets: usize,
+}
+
+impl ServerState {
+    fn new_etag(&mut self) -> String {
+        self.last_etag += 1;
+        format!("\"{}\"", self.last_etag)
+    }
+
+    fn calendar_href(&self, name: &str) -> String {
+        self.calendars
+            .iter()
+            .find(|(_, calendar)| calendar.name == name)
+            .map(|(href, _)| href.clone())
+            .unwrap_or_else(|| panic!("No calendar named {}", name))
+    }
+}
+
+/// Minimal CalDAV server running in a thread of the test, with a single user
+/// and basic authentication. Good enough for the requests of the CalDAV sync
+pub struct DavServer {
+    /// URL of the calendar home
+    pub url: String,
+    state: Arc<Mutex<ServerState>>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl DavServer {
+    pub fn start(username: &str, password: &str) -> DavServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}{}", listener.local_addr().unwrap(), HOME);
+        let state = Arc::new(Mutex::new(ServerState::default()));
+        let authorization = format!(
+            "Basic {}",
+            STANDARD.encode(format!("{}:{}", username, password))
+        );
+
+        let server_state = state.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = serve(stream, &server_state, &authorization);
+            }
+        });
+
+        DavServer { url, state }
+    }
+
+    /// Number of to-dos downloaded so far
+    pub fn gets(&self) -> usize {
+        self.state.lock().unwrap().gets
+    }
+
+    /// Names of the calendars, in the order of their hrefs
+    pub fn calendar_names(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.calendars.values().map(|c| c.name.clone()).collect()
+    }
+
+    /// To-dos of a calendar, sorted by summary
+    pub fn todos(&self, calendar: &str) -> Vec<VTodo> {
+        let state = self.state.lock().unwrap();
+        let href = state.calendar_href(calendar);
+        let mut todos: Vec<VTodo> = state.calendars[&href]
+            .resources
+            .values()
+            .flat_map(|(ics, _)| VCalendar::parse(ics).unwrap().todos)
+            .collect();
+        todos.sort_by(|a, b| a.summary.cmp(&b.summary));
+        todos
+    }
+
+    /// Create a calendar, as another client would
+    pub fn add_calendar(&self, name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let href = format!("{}{}/", HOME, name.to_lowercase());
+        state.calendars.insert(
+            href,
+            Calendar {
+                name: name.to_string(),
+                ..Calendar::default()
+            },
+        );
+    }
+
+    pub fn rename_calendar(&self, name: &str, new_name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let href = state.calendar_href(name);
+        state.calendars.get_mut(&href).unwrap().name = new_name.to_string();
+    }
+
+    pub fn delete_calendar(&self, name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let href = state.calendar_href(name);
+        state.calendars.remove(&href);
+    }
+
+    /// Add a to-do to a calendar, as another client would
+    pub fn add_todo(&self, calendar: &str, todo: VTodo) {
+        let mut state = self.state.lock().unwrap();
+        let href = state.calendar_href(calendar);
+        let resource = format!("{}{}.ics", href, todo.uid);
+        let etag = state.new_etag();
+        let ics = VCalendar {
+            name: None,
+            todos: vec![todo],
+        }
+        .to_ics();
+        let calendar = state.calendars.get_mut(&href).unwrap();
+        calendar.resources.insert(resource, (ics, etag));
+    }
+
+    /// Change the to-do with the given summary, as another client would
+    pub fn edit_todo(&self, calendar: &str, summary: &str, edit: impl FnOnce(&mut VTodo)) {
+        let mut state = self.state.lock().unwrap();
+        let href = state.calendar_href(calendar);
+        let etag = state.new_etag();
+        let calendar = state.calendars.get_mut(&href).unwrap();
+        let (ics, old_etag) = calendar
+            .resources
+            .values_mut()
+            .find(|(ics, _)| VCalendar::parse(ics).unwrap().todos[0].summary == summary)
+            .unwrap_or_else(|| panic!("No to-do named {}", summary));
+
+        let mut parsed = VCalendar::parse(ics).unwrap();
+        edit(&mut parsed.todos[0]);
+        *ics = parsed.to_ics();
+        *old_etag = etag;
+    }
+
+    pub fn delete_todo(&self, calendar: &str, summary: &str) {
+        let mut state = self.state.lock().unwrap();
+        let href = state.calendar_href(calendar);
+        let calendar = state.calendars.get_mut(&href).unwrap();
+        calendar
+            .resources
+            .retain(|_, (ics, _)| VCalendar::parse(ics).unwrap().todos[0].summary != summary);
+    }
+}
+
+fn read_request(stream: &TcpStream) -> std::io::Result<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let length: usize = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+fn serve(
+    mut stream: TcpStream,
+    state: &Mutex<ServerState>,
+    authorization: &str,
+) -> std::io::Result<()> {
+    let request = read_request(&stream)?;
+    let (status, etag, body) = if request.header("Authorization") != Some(authorization) {
+        (401, None, String::new())
+    } else {
+        handle(&request, &mut state.lock().unwrap())
+    };
+
+    let mut response = format!(
+        "HTTP/1.1 {} Status\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        body.len()
+    );
+    if let Some(etag) = etag {
+        response.push_str(&format!("ETag: {}\r\n", etag));
+    }
+    response.push_str("\r\n");
+    response.push_str(&body);
+    stream.write_all(response.as_bytes())
+}
+
+/// Status, ETag and body of the response to a request
+fn handle(request: &Request, state: &mut ServerState) -> (u16, Option<String>, String) {
+    let path = request.path.as_str();
+    let calendar_href = state
+        .calendars
+        .keys()
+        .find(|href| path.starts_with(href.as_str()))
+        .cloned();
+
+    match (request.method.as_str(), calendar_href) {
+        ("PROPFIND", _) if path == HOME => {
+            let mut responses = vec![response_xml(
+                HOME,
+                "<d:resourcetype><d:collection/></d:resourcetype>",
+            )];
+            for (href, calendar) in &state.calendars {
+                responses.push(response_xml(
+                    href,
+                    &format!(
+                        "<d:resourcetype><d:collection/><c:calendar/></d:resourcetype>\
+                         <d:displayname>{}</d:displayname>\
+                         <c:supported-calendar-component-set><c:comp name=\"VTODO\"/>\
+                         </c:supported-calendar-component-set>",
+                        calendar.name
+                    ),
+                ));
+            }
+            (207, None, multistatus(&responses))
+        }
+        ("MKCALENDAR", None) => {
+            let name = between(&request.body, "displayname>", "</").unwrap_or_default();
+            state.calendars.insert(
+                path.to_string(),
+                Calendar {
+                    name,
+                    ..Calendar::default()
+                },
+            );
+            (201, None, String::new())
+        }
+        ("PROPPATCH", Some(href)) if href == path => {
+            if let Some(name) = between(&request.body, "displayname>", "</") {
+                state.calendars.get_mut(&href).unwrap().name = name;
+            }
+            (207, None, multistatus(&[]))
+        }
+        ("REPORT", Some(href)) if href == path => {
+            let responses: Vec<String> = state.calendars[&href]
+                .resources
+                .iter()
+                .map(|(href, (_, etag))| {
+                    response_xml(href, &format!("<d:getetag>{}</d:getetag>", etag))
+                })
+                .collect();
+            (207, None, multistatus(&responses))
+        }
+        ("DELETE", Some(href)) if href == path => {
+            state.calendars.remove(&href);
+            (204, None, String::new())
+        }
+        (method, Some(href)) => {
+            let current = state.calendars[&href].resources.get(path).cloned();
+            let matches = match (request.header("If-Match"), request.header("If-None-Match")) {
+                (Some(etag), _) => current.as_ref().is_some_and(|(_, e)| e == etag),
+                (_, Some("*")) => current.is_none(),
+                _ => true,
+            };
+
+            match (method, current) {
+                _ if !matches => (412, None, String::new()),
+                ("GET", Some((ics, etag))) => {
+                    state.gets += 1;
+                    (200, Some(etag), ics)
+                }
+                ("PUT", current) => {
+                    let etag = state.new_etag();
+                    let calendar = state.calendars.get_mut(&href).unwrap();
+                    calendar
+                        .resources
+                        .insert(path.to_string(), (request.body.clone(), etag.clone()));
+                    (
+                        if current.is_some() { 204 } else { 201 },
+                        Some(etag),
+                        String::new(),
+                    )
+                }
+                ("DELETE", Some(_)) => {
+                    let calendar = state.calendars.get_mut(&href).unwrap();
+                    calendar.resources.remove(path);
+                    (204, None, String::new())
+                }
+                _ => (404, None, String::new()),
+            }
+        }
+        _ => (404, None, String::new()),
+    }
+}
+
+fn between(text: &str, start: &str, end: &str) -> Option<String> {
+    let rest = &text[text.find(start)? + start.len()..];
+    Some(rest[..rest.find(end)?].to_string())
+}
+
+fn response_xml(href: &str, props: &str) -> String {
+    format!(
+        "<d:response><d:href>{}</d:href><d:propstat><d:prop>{}</d:prop>\
+         <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+        href, props
+    )
+}
+
+fn multistatus(responses: &[String]) -> String {
+    format!(
+        "<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\" \
+         xmlns:c=\"urn:ietf:params:xml:ns:caldav\">{}</d:multistatus>",
+        responses.concat()
+    )
+}
@@ -1,5 +1,8 @@
 use anyhow::{Context, Result};
+use judo::db::postgres_store::PostgresStore;
+use sqlx::Connection;
 use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgConnectOptions, PgConnection};
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
 use std::str::FromStr;
 
@@ -21,3 +24,30 @@ pub async fn setup_test_db() -> Result<SqlitePool> {
 
     Ok(pool)
 }
+
+/// Create an empty PostgreSQL database for testing, on the server given by the
+/// `JUDO_TEST_POSTGRES_URL` environment variable (e.g.
+/// `postgres://postgres@localhost/postgres`). The tests using it are ignored
+/// by default, and fail when run without it
+pub async fn setup_test_postgres() -> Result<PostgresStore> {
+    let url = std::env::var("JUDO_TEST_POSTGRES_URL").with_context(
+        || "JUDO_TEST_POSTGRES_URL must point to a PostgreSQL server to run this test",
+    )?;
+
+    // Every test gets its own database
+    let opts =
+        PgConnectOptions::from_str(&url).with_context(|| "Invalid JUDO_TEST_POSTGRES_URL")?;
+    let mut suffix = [0u8; 8];
+    getrandom::fill(&mut suffix).map_err(|e| anyhow::anyhow!("No randomness: {}", e))?;
+    let name: String = suffix.iter().map(|b| format!("{:02x}", b)).collect();
+    let name = format!("judo_test_{}", name);
+
+    let mut conn = PgConnection::connect_with(&opts)
+        .await
+        .with_context(|| "Failed to connect to the PostgreSQL test server")?;
+    sqlx::query(&format!("CREATE DATABASE {}", name))
+        .execute(&mut conn)
+        .await?;
+
+    PostgresStore::connect_with(opts.database(&name)).await
+}
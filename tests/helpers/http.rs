@@ -0,0 +1,98 @@
+use anyhow::{Context, Result, bail};
+use judo::api;
+use judo::db::store::TodoStore;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Token of the servers started by the tests
+pub const TOKEN: &str = "test-token";
+
+/// Response to a request: status, headers (with lowercase names) and body
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn json(&self) -> Result<Value> {
+        serde_json::from_str(&self.body).with_context(|| format!("Not JSON: {}", self.body))
+    }
+}
+
+/// Serve the API over a store on a free local port, in the background
+pub async fn start_api(store: Arc<dyn TodoStore>) -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let router = api::router(store, TOKEN.to_string());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    Ok(addr)
+}
+
+/// Send a request over a new connection, with the given extra headers
+pub async fn request(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: Option<&str>,
+) -> Result<HttpResponse> {
+    let mut raw = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
+        method, path, addr
+    );
+    for (name, value) in headers {
+        raw.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if let Some(body) = body {
+        raw.push_str("Content-Type: application/json\r\n");
+        raw.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+    } else {
+        raw.push_str("Content-Length: 0\r\n\r\n");
+    }
+
+    let mut stream = TcpStream::connect(addr).await?;
+    stream.write_all(raw.as_bytes()).await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+
+    let Some((head, body)) = response.split_once("\r\n\r\n") else {
+        bail!("Incomplete response: {}", response);
+    };
+    let mut lines = head.lines();
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|code| code.parse().ok())
+        .with_context(|| format!("Invalid status line in {}", head))?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+        .collect();
+    Ok(HttpResponse {
+        status,
+        headers,
+        body: body.to_string(),
+    })
+}
+
+/// Send a request with the token of the test servers
+pub async fn authorized(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+) -> Result<HttpResponse> {
+    let auth = format!("Bearer {}", TOKEN);
+    request(addr, method, path, &[("Authorization", &auth)], body).await
+}
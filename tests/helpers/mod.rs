@@ -1 +1,4 @@
+pub mod dav;
 pub mod db;
+pub mod http;
+pub mod tui;
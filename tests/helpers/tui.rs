@@ -0,0 +1,191 @@
+use anyhow::{Context, Result};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use judo::app::App;
+use judo::db::config::{Config, DBConfig};
+use judo::db::store::open_store;
+use judo::script::ScriptsConfig;
+use judo::ui::theme::{ColorSupport, ThemeConfig};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// Size of the rendered terminal
+const WIDTH: u16 = 100;
+const HEIGHT: u16 = 30;
+
+/// Script of the scripts directory, bound to Ctrl + a
+const ARCHIVE_SCRIPT: &str = r#"// Move the items done to an archive list
+let name = prompt("Archive to?", "Archive");
+if name == () {
+    return;
+}
+let archive = list(name) ?? create_list(name);
+let count = 0;
+for item in items(SELECTED_LIST) {
+    if item.is_done {
+        move_to_list(item, archive);
+        count += 1;
+    }
+}
+notify(`${count} archived in ${name}`);
+"#;
+
+/// Template of the templates directory
+const RELEASE_TEMPLATE: &str = "# Release {{version}}
+- [ ] Bump the version to {{version}}
+- [ ] Tag the release on {{date}}
+";
+
+/// How long to wait for a script to ask something or end
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// App running against databases in a temporary directory, driven by scripted
+/// key presses and rendered to an in-memory terminal
+pub struct Tui {
+    pub app: App,
+    /// Directory of the config and databases, removed when dropped
+    _dir: TempDir,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Tui {
+    /// Start the app with a default "test" database, a "work" database and an
+    /// encrypted "vault" database, an "archive_done" script bound to Ctrl + a
+    /// and a "release" template
+    pub async fn new() -> Result<Tui> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir(dir.path().join("scripts"))?;
+        std::fs::write(dir.path().join("scripts/archive_done.rhai"), ARCHIVE_SCRIPT)?;
+        std::fs::create_dir(dir.path().join("templates"))?;
+        std::fs::write(dir.path().join("templates/release.md"), RELEASE_TEMPLATE)?;
+
+        let db = |name: &str, encrypted: bool| DBConfig {
+            name: name.to_string(),
+            connection_str: format!("sqlite:{}", dir.path().join(format!("{name}.db")).display()),
+            git_sync: None,
+            encrypted,
+            caldav: None,
+        };
+        let config = Config {
+            default: "test".to_string(),
+            dbs: vec![db("test", false), db("work", false), db("vault", true)],
+            theme: Some(ThemeConfig::default()),
+            remind: None,
+            hooks: None,
+            scripts: Some(ScriptsConfig {
+                keys: [("<C-a>".to_string(), "archive_done".to_string())].into(),
+            }),
+        };
+
+        let store = open_store(&config.get_default()?.connection_str).await?;
+        let mut app = App::with_config(
+            config,
+            store,
+            dir.path().join("judo.toml"),
+            dir.path().join("data"),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to start app: {}", e))?;
+        app.color_support = ColorSupport::TrueColor;
+
+        Ok(Tui {
+            app,
+            _dir: dir,
+            terminal: Terminal::new(TestBackend::new(WIDTH, HEIGHT))?,
+        })
+    }
+
+    /// Press the keys of a script, in vim notation: plain characters are typed
+    /// as they are, special keys are written as `<Enter>`, `<Esc>`, `<Space>`,
+    /// `<Tab>`, `<BS>`, `<Del>`, `<Up>`, `<Down>`, `<Left>`, `<Right>` and
+    /// `<C-x>` for Ctrl + x. Scripts started by the keys run until they ask
+    /// something or end before the next key is pressed
+    pub async fn keys(&mut self, script: &str) -> Result<&mut Tui> {
+        for key in parse_keys(script)? {
+            self.app.handle_event(Event::Key(key)).await;
+            self.wait_for_script().await?;
+        }
+        Ok(self)
+    }
+
+    /// Wait for the running script, if any, to ask something or end
+    async fn wait_for_script(&mut self) -> Result<()> {
+        let start = Instant::now();
+        loop {
+            self.app.handle_script_requests().await;
+            if self.app.running_script.is_none() || self.app.script_question.is_some() {
+                return Ok(());
+            }
+            anyhow::ensure!(start.elapsed() < SCRIPT_TIMEOUT, "The script didn't finish");
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    /// Render the app and return the screen as text, one line per row
+    pub fn render(&mut self) -> Result<String> {
+        // Recently pressed keys are shown for a while, which would make renders
+        // depend on timing
+        self.app.reset_key_buffer();
+
+        let app = &mut self.app;
+        self.terminal
+            .draw(|frame| frame.render_widget(&mut *app, frame.area()))?;
+
+        let buffer = self.terminal.backend().buffer();
+        let lines: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                let line: String = (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Parse a key script into key events
+pub fn parse_keys(script: &str) -> Result<Vec<KeyEvent>> {
+    let mut keys = Vec::new();
+    let mut chars = script.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            let modifiers = if c.is_ascii_uppercase() {
+                KeyModifiers::SHIFT
+            } else {
+                KeyModifiers::NONE
+            };
+            keys.push(KeyEvent::new(KeyCode::Char(c), modifiers));
+            continue;
+        }
+
+        let name: String = chars.by_ref().take_while(|&c| c != '>').collect();
+        let key = match name.as_str() {
+            "Enter" | "CR" => KeyEvent::from(KeyCode::Enter),
+            "Esc" => KeyEvent::from(KeyCode::Esc),
+            "Space" => KeyEvent::from(KeyCode::Char(' ')),
+            "Tab" => KeyEvent::from(KeyCode::Tab),
+            "S-Tab" => KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT),
+            "BS" => KeyEvent::from(KeyCode::Backspace),
+            "Del" => KeyEvent::from(KeyCode::Delete),
+            "Up" => KeyEvent::from(KeyCode::Up),
+            "Down" => KeyEvent::from(KeyCode::Down),
+            "Left" => KeyEvent::from(KeyCode::Left),
+            "Right" => KeyEvent::from(KeyCode::Right),
+            "lt" => KeyEvent::from(KeyCode::Char('<')),
+            _ => {
+                let c = name
+                    .strip_prefix("C-")
+                    .filter(|c| c.chars().count() == 1)
+                    .and_then(|c| c.chars().next())
+                    .with_context(|| format!("Unknown key <{}>", name))?;
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+            }
+        };
+        keys.push(key);
+    }
+
+    Ok(keys)
+}
@@ -0,0 +1,138 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use judo::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use judo::db::store::TodoStore;
+use judo::hooks::{HookEvent, HookKind, HookedStore, Hooks, HooksConfig};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Wait for a hook to write its file, and parse the event it was given
+async fn event_written(path: &Path) -> Result<HookEvent> {
+    for _ in 0..200 {
+        if let Ok(json) = std::fs::read_to_string(path)
+            && let Ok(event) = serde_json::from_str(&json)
+        {
+            return Ok(event);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    anyhow::bail!("{} was never written", path.display())
+}
+
+#[tokio::test]
+async fn test_hooks_get_the_events_of_the_store() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    // Hooks of the same kind run at the same time: each one writes its own
+    // file, then renames it, so that the file never holds a mix of events
+    let hook = |name: &str| {
+        let path = dir.path().join(name);
+        vec![format!(
+            "cat > '{0}.'$$ && mv '{0}.'$$ '{0}'",
+            path.display()
+        )]
+    };
+    let config = HooksConfig {
+        on_item_created: hook("item_created"),
+        on_item_done: hook("item_done"),
+        on_list_done: hook("list_done"),
+        on_item_deleted: hook("item_deleted"),
+        on_list_deleted: hook("list_deleted"),
+        ..HooksConfig::default()
+    };
+    let hooks = Arc::new(Hooks::new(config));
+    let store: Arc<dyn TodoStore> = Arc::new(HookedStore::new(
+        Arc::new(setup_test_db().await?),
+        hooks.clone(),
+        "dojo",
+    ));
+
+    let list = TodoList::create(
+        &*store,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+    for name in ["Milk", "Bread"] {
+        TodoItem::create(
+            &*store,
+            NewTodoItem {
+                list_id: list.id,
+                name: name.to_string(),
+                priority: None,
+                due_date: None,
+            },
+        )
+        .await?;
+    }
+    let event = event_written(&dir.path().join("item_created")).await?;
+    assert_eq!(
+        (event.event, event.db.as_str()),
+        (HookKind::ItemCreated, "dojo")
+    );
+    assert_eq!(event.list.unwrap().name, "Groceries");
+
+    // The list is done with its last item
+    let mut items = TodoItem::get_by_list_id(&*store, list.id).await?;
+    items[0].toggle_done(&*store).await?;
+    let event = event_written(&dir.path().join("item_done")).await?;
+    assert_eq!(event.item.unwrap().name, "Milk");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!dir.path().join("list_done").exists());
+    items[1].toggle_done(&*store).await?;
+    let event = event_written(&dir.path().join("list_done")).await?;
+    assert_eq!(
+        (event.event, event.list.unwrap().id),
+        (HookKind::ListDone, list.id)
+    );
+
+    items.remove(1).delete(&*store).await?;
+    let event = event_written(&dir.path().join("item_deleted")).await?;
+    assert_eq!(event.item.unwrap().name, "Bread");
+    list.delete(&*store).await?;
+    let event = event_written(&dir.path().join("list_deleted")).await?;
+    assert_eq!(event.list.unwrap().name, "Groceries");
+
+    assert!(hooks.failures().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_hooks_are_logged() -> Result<()> {
+    let config = HooksConfig {
+        timeout_secs: 1,
+        on_list_created: vec!["exit 2".to_string(), "sleep 10".to_string()],
+        ..HooksConfig::default()
+    };
+    let hooks = Arc::new(Hooks::new(config));
+    let store = HookedStore::new(Arc::new(setup_test_db().await?), hooks.clone(), "dojo");
+
+    // Creating the list doesn't wait for the hooks
+    let started = std::time::Instant::now();
+    TodoList::create(
+        &store,
+        NewTodoList {
+            name: "Chores".to_string(),
+        },
+    )
+    .await?;
+    assert!(started.elapsed() < Duration::from_millis(500));
+
+    for _ in 0..300 {
+        if hooks.failures().len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let failures = hooks.failures();
+    assert_eq!(failures.len(), 2);
+    assert_eq!(failures[0].command, "sleep 10");
+    assert_eq!(failures[0].error, "Timed out after 1s");
+    assert_eq!(failures[1].command, "exit 2");
+    assert_eq!(failures[1].event, HookKind::ListCreated);
+
+    hooks.clear_failures();
+    assert!(hooks.failures().is_empty());
+    Ok(())
+}
@@ -0,0 +1 @@
+pub mod hooked_store;
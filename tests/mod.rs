@@ -1,2 +1,7 @@
+mod api;
+mod ctl;
 mod db;
 mod helpers;
+mod hooks;
+mod sync;
+mod ui;
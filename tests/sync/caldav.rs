@@ -0,0 +1,383 @@
+use crate::helpers::dav::DavServer;
+use crate::helpers::db::setup_test_db;
+use crate::helpers::tui::Tui;
+use anyhow::Result;
+use judo::db::config::CaldavConfig;
+use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use judo::sync::caldav::CaldavSync;
+use judo::sync::ical::VTodo;
+use judo::sync::merge::SyncConflict;
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Lists with the names and status of their items, in order
+async fn contents(pool: &SqlitePool) -> Result<Vec<(String, Vec<(String, bool)>)>> {
+    let mut contents = Vec::new();
+    for list in TodoList::get_all(pool).await? {
+        let items = TodoItem::get_by_list_id(pool, list.id)
+            .await?
+            .into_iter()
+            .map(|item| (item.name, item.is_done))
+            .collect();
+        contents.push((list.name, items));
+    }
+    Ok(contents)
+}
+
+/// Contents with the items of each list sorted by name, since calendars don't
+/// keep the order of to-dos
+async fn sorted_contents(pool: &SqlitePool) -> Result<Vec<(String, Vec<(String, bool)>)>> {
+    let mut contents = contents(pool).await?;
+    for (_, items) in &mut contents {
+        items.sort();
+    }
+    Ok(contents)
+}
+
+/// Summaries and status of the to-dos of a calendar
+fn todos(server: &DavServer, calendar: &str) -> Vec<(String, bool)> {
+    server
+        .todos(calendar)
+        .into_iter()
+        .map(|todo| (todo.summary, todo.is_done))
+        .collect()
+}
+
+fn strings(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+async fn item_named(pool: &SqlitePool, list: &str, name: &str) -> Result<TodoItem> {
+    let list = TodoList::get_all(pool)
+        .await?
+        .into_iter()
+        .find(|l| l.name == list)
+        .unwrap();
+    Ok(TodoItem::get_by_list_id(pool, list.id)
+        .await?
+        .into_iter()
+        .find(|item| item.name == name)
+        .unwrap())
+}
+
+fn open_sync(server: &DavServer, state_path: &Path) -> Result<CaldavSync> {
+    let config = CaldavConfig {
+        url: server.url.clone(),
+        username: Some("me".to_string()),
+        password_env: None,
+    };
+    CaldavSync::open(
+        &config,
+        Some(("me".to_string(), "secret".to_string())),
+        state_path,
+    )
+}
+
+/// Groceries with milk (high priority) and bread
+async fn setup_groceries() -> Result<SqlitePool> {
+    let pool = setup_test_db().await?;
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+    for (name, priority) in [("Milk", Some(Priority::High)), ("Bread", None)] {
+        TodoItem::create(
+            &pool,
+            NewTodoItem {
+                list_id: list.id,
+                name: name.to_string(),
+                priority,
+                due_date: None,
+            },
+        )
+        .await?;
+    }
+    Ok(pool)
+}
+
+fn todo(uid: &str, summary: &str) -> VTodo {
+    VTodo {
+        uid: uid.to_string(),
+        summary: summary.to_string(),
+        is_done: false,
+        completed: None,
+        priority: None,
+        due: None,
+        created: None,
+        last_modified: None,
+        list: None,
+    }
+}
+
+#[tokio::test]
+async fn test_first_sync_uploads_lists() -> Result<()> {
+    let server = DavServer::start("me", "secret");
+    let dir = tempfile::tempdir()?;
+    let pool = setup_groceries().await?;
+    let mut sync = open_sync(&server, &dir.path().join("state.json"))?;
+
+    assert_eq!(sync.sync(&pool).await?, vec![]);
+    assert_eq!(server.calendar_names(), strings(&["Groceries"]));
+    assert_eq!(
+        todos(&server, "Groceries"),
+        vec![("Bread".to_string(), false), ("Milk".to_string(), false)]
+    );
+    assert_eq!(server.todos("Groceries")[1].priority, Some(Priority::High));
+
+    // Nothing changed: no to-do is downloaded
+    assert_eq!(sync.sync(&pool).await?, vec![]);
+    assert_eq!(server.gets(), 0);
+
+    // The state survives restarts
+    let mut sync = open_sync(&server, &dir.path().join("state.json"))?;
+    assert_eq!(sync.sync(&pool).await?, vec![]);
+    assert_eq!(server.gets(), 0);
+    assert_eq!(server.todos("Groceries").len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_remote_changes_are_pulled() -> Result<()> {
+    let server = DavServer::start("me", "secret");
+    let dir = tempfile::tempdir()?;
+    let pool = setup_groceries().await?;
+    let mut sync = open_sync(&server, &dir.path().join("state.json"))?;
+    sync.sync(&pool).await?;
+
+    server.rename_calendar("Groceries", "Shopping");
+    server.edit_todo("Shopping", "Milk", |todo| {
+        todo.summary = "Oat milk".to_string();
+        todo.is_done = true;
+    });
+    server.add_todo("Shopping", todo("eggs", "Eggs"));
+    server.add_calendar("Work");
+    server.add_todo("Work", todo("report", "Report"));
+
+    assert_eq!(sync.sync(&pool).await?, vec![]);
+    assert_eq!(
+        contents(&pool).await?,
+        vec![
+            (
+                "Shopping".to_string(),
+                vec![
+                    ("Oat milk".to_string(), true),
+                    ("Bread".to_string(), false),
+                    ("Eggs".to_string(), false),
+                ]
+            ),
+            ("Work".to_string(), vec![("Report".to_string(), false)]),
+        ]
+    );
+
+    // Only the changed and new to-dos were downloaded, once
+    assert_eq!(server.gets(), 3);
+    sync.sync(&pool).await?;
+    assert_eq!(server.gets(), 3);
+
+    // Local edits of pulled to-dos keep their UID
+    let mut eggs = item_named(&pool, "Shopping", "Eggs").await?;
+    eggs.toggle_done(&pool).await?;
+    sync.sync(&pool).await?;
+    let remote_eggs = server
+        .todos("Shopping")
+        .into_iter()
+        .find(|todo| todo.summary == "Eggs")
+        .unwrap();
+    assert_eq!(remote_eggs.uid, "eggs");
+    assert!(remote_eggs.is_done);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_between_two_databases() -> Result<()> {
+    let server = DavServer::start("me", "secret");
+    let dir = tempfile::tempdir()?;
+    let laptop = setup_groceries().await?;
+    let desktop = setup_test_db().await?;
+    let mut laptop_sync = open_sync(&server, &dir.path().join("laptop.json"))?;
+    let mut desktop_sync = open_sync(&server, &dir.path().join("desktop.json"))?;
+
+    laptop_sync.sync(&laptop).await?;
+    desktop_sync.sync(&desktop).await?;
+    assert_eq!(
+        sorted_contents(&desktop).await?,
+        sorted_contents(&laptop).await?
+    );
+
+    let mut bread = item_named(&desktop, "Groceries", "Bread").await?;
+    bread.update_name(&desktop, "Rye bread".to_string()).await?;
+    desktop_sync.sync(&desktop).await?;
+    laptop_sync.sync(&laptop).await?;
+
+    assert_eq!(
+        sorted_contents(&laptop).await?,
+        sorted_contents(&desktop).await?
+    );
+    assert_eq!(
+        item_named(&laptop, "Groceries", "Rye bread").await?.name,
+        "Rye bread"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deletions_propagate_both_ways() -> Result<()> {
+    let server = DavServer::start("me", "secret");
+    let dir = tempfile::tempdir()?;
+    let pool = setup_groceries().await?;
+    let chores = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Chores".to_string(),
+        },
+    )
+    .await?;
+    let mut sync = open_sync(&server, &dir.path().join("state.json"))?;
+    sync.sync(&pool).await?;
+
+    item_named(&pool, "Groceries", "Bread")
+        .await?
+        .delete(&pool)
+        .await?;
+    server.delete_todo("Groceries", "Milk");
+    chores.delete(&pool).await?;
+    assert_eq!(sync.sync(&pool).await?, vec![]);
+
+    assert_eq!(server.calendar_names(), strings(&["Groceries"]));
+    assert_eq!(todos(&server, "Groceries"), vec![]);
+    assert_eq!(
+        contents(&pool).await?,
+        vec![("Groceries".to_string(), vec![])]
+    );
+
+    server.delete_calendar("Groceries");
+    assert_eq!(sync.sync(&pool).await?, vec![]);
+    assert_eq!(contents(&pool).await?, vec![]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_conflicts_keep_the_local_version() -> Result<()> {
+    let server = DavServer::start("me", "secret");
+    let dir = tempfile::tempdir()?;
+    let pool = setup_groceries().await?;
+    let mut sync = open_sync(&server, &dir.path().join("state.json"))?;
+    sync.sync(&pool).await?;
+
+    // Both sides rename milk, and bread is deleted on the server after a
+    // local edit
+    let mut milk = item_named(&pool, "Groceries", "Milk").await?;
+    milk.update_name(&pool, "Whole milk".to_string()).await?;
+    server.edit_todo("Groceries", "Milk", |todo| {
+        todo.summary = "Skim milk".to_string()
+    });
+    let mut bread = item_named(&pool, "Groceries", "Bread").await?;
+    bread.toggle_done(&pool).await?;
+    server.delete_todo("Groceries", "Bread");
+
+    let mut conflicts = sync.sync(&pool).await?;
+    conflicts.sort_by(|a, b| a.description.cmp(&b.description));
+    assert_eq!(
+        conflicts,
+        vec![
+            SyncConflict {
+                list_name: "Groceries".to_string(),
+                description: "Item 'Bread' was deleted on the server and modified locally; \
+                              uploaded it again"
+                    .to_string(),
+            },
+            SyncConflict {
+                list_name: "Groceries".to_string(),
+                description: "Item 'Whole milk' changed on both sides; kept the local version"
+                    .to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        todos(&server, "Groceries"),
+        vec![
+            ("Bread".to_string(), true),
+            ("Whole milk".to_string(), false)
+        ]
+    );
+    assert_eq!(
+        contents(&pool).await?,
+        vec![(
+            "Groceries".to_string(),
+            vec![
+                ("Whole milk".to_string(), false),
+                ("Bread".to_string(), true)
+            ]
+        )]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrong_password_fails() -> Result<()> {
+    let server = DavServer::start("me", "secret");
+    let dir = tempfile::tempdir()?;
+    let pool = setup_groceries().await?;
+    let config = CaldavConfig {
+        url: server.url.clone(),
+        username: Some("me".to_string()),
+        password_env: None,
+    };
+    let mut sync = CaldavSync::open(
+        &config,
+        Some(("me".to_string(), "guess".to_string())),
+        &dir.path().join("state.json"),
+    )?;
+
+    let error = sync.sync(&pool).await.unwrap_err();
+    assert!(error.to_string().contains("access denied"), "{}", error);
+    assert_eq!(server.calendar_names(), Vec::<String>::new());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_the_app_syncs_in_the_background() -> Result<()> {
+    let mut tui = Tui::new().await?;
+    let dir = tempfile::tempdir()?;
+
+    // A server that never answers doesn't hold the app up
+    let silent = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let config = CaldavConfig {
+        url: format!("http://{}/cal/me/", silent.local_addr()?),
+        username: None,
+        password_env: None,
+    };
+    let state_path = dir.path().join("silent.json");
+    tui.app.caldav_sync = Some(CaldavSync::open(&config, None, &state_path)?);
+    let start = Instant::now();
+    tui.app.sync_now().await;
+    tui.keys("3").await?;
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(tui.app.running_caldav_sync.is_some());
+    tui.app.running_caldav_sync = None;
+
+    // Once the sync is over, the lists pulled are shown
+    let server = DavServer::start("me", "secret");
+    server.add_calendar("Chores");
+    server.add_todo("Chores", todo("sweep", "Sweep the floor"));
+    tui.app.caldav_sync = Some(open_sync(&server, &dir.path().join("state.json"))?);
+    tui.app.sync_now().await;
+    let start = Instant::now();
+    while tui.app.running_caldav_sync.is_some() {
+        anyhow::ensure!(
+            start.elapsed() < Duration::from_secs(10),
+            "The sync didn't end"
+        );
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        tui.app.handle_caldav_sync().await;
+    }
+    assert!(tui.app.caldav_sync.is_some());
+    assert_eq!(tui.app.sync_messages, Vec::<String>::new());
+    let lists = &tui.app.lists_component.lists;
+    assert!(lists.iter().any(|ui_list| ui_list.list.name == "Chores"));
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use judo::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use judo::sync::change_log::{apply_changes, export_changes, record_local_changes};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::time::Duration;
+
+/// Lists with their items (name and status), in order
+async fn contents(pool: &SqlitePool) -> Result<Vec<(String, Vec<(String, bool)>)>> {
+    let mut contents = Vec::new();
+    for list in TodoList::get_all(pool).await? {
+        let items = TodoItem::get_by_list_id(pool, list.id)
+            .await?
+            .into_iter()
+            .map(|item| (item.name, item.is_done))
+            .collect();
+        contents.push((list.name, items));
+    }
+    Ok(contents)
+}
+
+async fn item_named(pool: &SqlitePool, name: &str) -> Result<TodoItem> {
+    let lists = TodoList::get_all(pool).await?;
+    let items = TodoItem::get_by_list_id(pool, lists[0].id).await?;
+    Ok(items
+        .into_iter()
+        .find(|item| item.name == name)
+        .expect("Item should exist"))
+}
+
+/// Record the changes made so far and wait, so that the next changes get a later clock
+async fn record_and_wait(pool: &SqlitePool) -> Result<()> {
+    record_local_changes(pool).await?;
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    Ok(())
+}
+
+/// Exchange change logs through the shared folder
+async fn exchange(a: &SqlitePool, b: &SqlitePool, dir: &Path) -> Result<()> {
+    export_changes(a, dir).await?;
+    export_changes(b, dir).await?;
+    apply_changes(a, dir).await?;
+    apply_changes(b, dir).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_change_logs_merge_concurrent_edits() -> Result<()> {
+    let shared = tempfile::tempdir()?;
+    let pool_a = setup_test_db().await?;
+    let pool_b = setup_test_db().await?;
+
+    let list = TodoList::create(
+        &pool_a,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+    for name in ["Milk", "Eggs", "Bread"] {
+        TodoItem::create(
+            &pool_a,
+            NewTodoItem {
+                list_id: list.id,
+                name: name.to_string(),
+                priority: None,
+                due_date: None,
+            },
+        )
+        .await?;
+    }
+
+    // B gets everything, with the same uids
+    let path = export_changes(&pool_a, shared.path()).await?;
+    assert!(path.exists());
+    assert!(apply_changes(&pool_b, shared.path()).await? > 0);
+    assert_eq!(contents(&pool_b).await?, contents(&pool_a).await?);
+    assert_eq!(TodoList::get_all(&pool_b).await?[0].uid, list.uid);
+
+    // Applying the same logs again changes nothing
+    assert_eq!(apply_changes(&pool_b, shared.path()).await?, 0);
+
+    // Concurrent edits: different fields of the same item merge, the latest rename wins
+    item_named(&pool_a, "Milk")
+        .await?
+        .update_name(&pool_a, "Oat milk".to_string())
+        .await?;
+    record_and_wait(&pool_a).await?;
+    let mut milk = item_named(&pool_b, "Milk").await?;
+    milk.toggle_done(&pool_b).await?;
+    milk.update_name(&pool_b, "Soy milk".to_string()).await?;
+    record_and_wait(&pool_b).await?;
+
+    // Deletion wins over a later edit
+    item_named(&pool_a, "Eggs").await?.delete(&pool_a).await?;
+    record_and_wait(&pool_a).await?;
+    item_named(&pool_b, "Eggs")
+        .await?
+        .update_name(&pool_b, "Free range eggs".to_string())
+        .await?;
+    record_and_wait(&pool_b).await?;
+
+    exchange(&pool_a, &pool_b, shared.path()).await?;
+
+    let expected = vec![(
+        "Groceries".to_string(),
+        vec![("Soy milk".to_string(), true), ("Bread".to_string(), false)],
+    )];
+    assert_eq!(contents(&pool_a).await?, expected);
+    assert_eq!(contents(&pool_b).await?, expected);
+
+    // Deleting a list propagates to its items
+    TodoList::get_all(&pool_b)
+        .await?
+        .remove(0)
+        .delete(&pool_b)
+        .await?;
+    exchange(&pool_a, &pool_b, shared.path()).await?;
+    assert!(contents(&pool_a).await?.is_empty());
+    assert!(contents(&pool_b).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rows_carry_their_clock() -> Result<()> {
+    let pool = setup_test_db().await?;
+    TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Work".to_string(),
+        },
+    )
+    .await?;
+
+    // Name and ordering of the new list
+    assert_eq!(record_local_changes(&pool).await?, 2);
+    assert_eq!(record_local_changes(&pool).await?, 0);
+
+    let hlc: Option<String> = sqlx::query_scalar("SELECT hlc FROM todo_lists")
+        .fetch_one(&pool)
+        .await?;
+    assert!(hlc.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rows_added_on_both_sides_get_unique_orderings() -> Result<()> {
+    let shared = tempfile::tempdir()?;
+    let pool_a = setup_test_db().await?;
+    let pool_b = setup_test_db().await?;
+
+    // Both databases give their first list and items the same orderings
+    for (pool, names) in [
+        (&pool_a, ["Groceries", "Milk"]),
+        (&pool_b, ["Chores", "Laundry"]),
+    ] {
+        let list = TodoList::create(
+            pool,
+            NewTodoList {
+                name: names[0].to_string(),
+            },
+        )
+        .await?;
+        TodoItem::create(
+            pool,
+            NewTodoItem {
+                list_id: list.id,
+                name: names[1].to_string(),
+                priority: None,
+                due_date: None,
+            },
+        )
+        .await?;
+        record_and_wait(pool).await?;
+    }
+    exchange(&pool_a, &pool_b, shared.path()).await?;
+    assert_eq!(contents(&pool_a).await?, contents(&pool_b).await?);
+    assert_eq!(contents(&pool_a).await?.len(), 2);
+
+    // Different items moved to the same spot on both sides
+    let list = TodoList::get_all(&pool_a).await?.remove(0);
+    for name in ["Eggs", "Bread"] {
+        TodoItem::create(
+            &pool_a,
+            NewTodoItem {
+                list_id: list.id,
+                name: name.to_string(),
+                priority: None,
+                due_date: None,
+            },
+        )
+        .await?;
+    }
+    record_and_wait(&pool_a).await?;
+    exchange(&pool_a, &pool_b, shared.path()).await?;
+
+    item_named(&pool_a, "Eggs").await?.move_up(&pool_a).await?;
+    record_and_wait(&pool_a).await?;
+    let mut bread = item_named(&pool_b, "Bread").await?;
+    bread.move_up_by(&pool_b, 2).await?;
+    record_and_wait(&pool_b).await?;
+    assert_eq!(item_named(&pool_a, "Eggs").await?.ordering, bread.ordering);
+
+    exchange(&pool_a, &pool_b, shared.path()).await?;
+    assert_eq!(contents(&pool_a).await?, contents(&pool_b).await?);
+    let list = TodoList::get_all(&pool_b).await?.remove(0);
+    let items = TodoItem::get_by_list_id(&pool_b, list.id).await?;
+    assert_eq!(items.len(), 3);
+    assert!(
+        items
+            .windows(2)
+            .all(|pair| pair[0].ordering < pair[1].ordering)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_log_keeps_only_winning_changes_and_tombstones() -> Result<()> {
+    let pool = setup_test_db().await?;
+    let changes = async |pool: &SqlitePool| -> Result<Vec<(String, String)>> {
+        Ok(
+            sqlx::query_as("SELECT entity, field FROM sync_changes ORDER BY entity, field")
+                .fetch_all(pool)
+                .await?,
+        )
+    };
+
+    let mut list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Work".to_string(),
+        },
+    )
+    .await?;
+    record_and_wait(&pool).await?;
+    for name in ["Office", "Chores"] {
+        list.update_name(&pool, name.to_string()).await?;
+        record_and_wait(&pool).await?;
+    }
+    let fields = |entity: &str, names: &[&str]| -> Vec<(String, String)> {
+        names
+            .iter()
+            .map(|name| (entity.to_string(), name.to_string()))
+            .collect()
+    };
+    assert_eq!(changes(&pool).await?, fields("list", &["name", "ordering"]));
+    let name: String = sqlx::query_scalar("SELECT value FROM sync_changes WHERE field = 'name'")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(name, "Chores");
+
+    // Nothing written, nothing recorded
+    assert_eq!(record_local_changes(&pool).await?, 0);
+
+    list.delete(&pool).await?;
+    record_and_wait(&pool).await?;
+    assert_eq!(changes(&pool).await?, fields("list", &["deleted"]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encrypted_databases_apply_changes_but_dont_export_them() -> Result<()> {
+    let shared = tempfile::tempdir()?;
+    let vault = setup_test_db().await?;
+    judo::db::crypto::encrypt_database(&vault, "correct horse").await?;
+    let laptop = setup_test_db().await?;
+    for name in ["Groceries", "Chores"] {
+        let new_list = NewTodoList {
+            name: name.to_string(),
+        };
+        TodoList::create(&laptop, new_list).await?;
+    }
+
+    // The names would be written in clear
+    assert!(export_changes(&vault, shared.path()).await.is_err());
+
+    export_changes(&laptop, shared.path()).await?;
+    apply_changes(&vault, shared.path()).await?;
+    let stored = |pool: &SqlitePool| {
+        let pool = pool.clone();
+        async move {
+            let rows: Vec<(String, String)> =
+                sqlx::query_as("SELECT name, updated_at FROM todo_lists ORDER BY ordering")
+                    .fetch_all(&pool)
+                    .await?;
+            anyhow::Ok(rows)
+        }
+    };
+    let before = stored(&vault).await?;
+
+    // Only the renamed list is written again
+    record_and_wait(&laptop).await?;
+    let mut chores = TodoList::get_all(&laptop).await?.remove(1);
+    chores.update_name(&laptop, "Housework".to_string()).await?;
+    export_changes(&laptop, shared.path()).await?;
+    assert!(apply_changes(&vault, shared.path()).await? > 0);
+    let after = stored(&vault).await?;
+    assert_eq!(after[0], before[0]);
+    assert_ne!(after[1].0, before[1].0);
+    assert_eq!(contents(&vault).await?, contents(&laptop).await?);
+    Ok(())
+}
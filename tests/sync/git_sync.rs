@@ -0,0 +1,271 @@
+use crate::helpers::db::setup_test_db;
+use crate::helpers::tui::Tui;
+use anyhow::Result;
+use judo::db::config::GitSyncConfig;
+use judo::db::crypto;
+use judo::db::models::{NewTodoItem, NewTodoList, TodoItem, TodoList};
+use judo::sync::GitSync;
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Run git in the given directory, failing the test on errors
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .expect("Failed to run git");
+    assert!(status.status.success(), "git {:?} failed", args);
+}
+
+/// Create a working tree cloned from the bare remote and a git sync for its `judo` subdirectory
+async fn setup_clone(root: &Path, name: &str, remote: &Path) -> Result<GitSync> {
+    let dir = root.join(name);
+    std::fs::create_dir_all(&dir)?;
+    git(&dir, &["init", "--quiet"]);
+    git(&dir, &["config", "user.name", name]);
+    git(&dir, &["config", "user.email", "judo@example.com"]);
+    git(
+        &dir,
+        &["remote", "add", "origin", &remote.display().to_string()],
+    );
+
+    let config = GitSyncConfig {
+        path: dir.join("judo").display().to_string(),
+        remote: None,
+    };
+
+    GitSync::new(&config, "dojo").await
+}
+
+/// Names and status of the items of the only list in the database
+async fn items_of_single_list(pool: &SqlitePool) -> Result<Vec<(String, bool)>> {
+    let lists = TodoList::get_all(pool).await?;
+    assert_eq!(lists.len(), 1);
+    Ok(TodoItem::get_by_list_id(pool, lists[0].id)
+        .await?
+        .into_iter()
+        .map(|item| (item.name, item.is_done))
+        .collect())
+}
+
+async fn item_named(pool: &SqlitePool, name: &str) -> Result<TodoItem> {
+    let lists = TodoList::get_all(pool).await?;
+    let items = TodoItem::get_by_list_id(pool, lists[0].id).await?;
+    Ok(items
+        .into_iter()
+        .find(|item| item.name == name)
+        .expect("Item should exist"))
+}
+
+#[tokio::test]
+async fn test_git_sync_between_two_databases() -> Result<()> {
+    let root = tempfile::tempdir()?;
+    let remote = root.path().join("remote.git");
+    git(root.path(), &["init", "--quiet", "--bare", "remote.git"]);
+
+    let pool_a = setup_test_db().await?;
+    let pool_b = setup_test_db().await?;
+    let mut sync_a = setup_clone(root.path(), "a", &remote).await?;
+    let mut sync_b = setup_clone(root.path(), "b", &remote).await?;
+
+    // A creates a list and pushes it
+    let list = TodoList::create(
+        &pool_a,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+    for name in ["Milk", "Eggs"] {
+        TodoItem::create(
+            &pool_a,
+            NewTodoItem {
+                list_id: list.id,
+                name: name.to_string(),
+                priority: None,
+                due_date: None,
+            },
+        )
+        .await?;
+    }
+    assert!(sync_a.sync(&pool_a).await?.is_empty());
+    assert!(
+        root.path()
+            .join("a/judo")
+            .join(format!("{}.todo", list.uid))
+            .exists()
+    );
+
+    // B starts empty and gets everything, with the same uids
+    assert!(sync_b.sync(&pool_b).await?.is_empty());
+    assert_eq!(
+        items_of_single_list(&pool_b).await?,
+        vec![("Milk".to_string(), false), ("Eggs".to_string(), false)]
+    );
+    assert_eq!(TodoList::get_all(&pool_b).await?[0].uid, list.uid);
+
+    // Independent changes on both sides are merged
+    item_named(&pool_a, "Milk")
+        .await?
+        .toggle_done(&pool_a)
+        .await?;
+    item_named(&pool_b, "Eggs")
+        .await?
+        .update_name(&pool_b, "Free range eggs".to_string())
+        .await?;
+    let list_b = TodoList::get_all(&pool_b).await?.remove(0);
+    TodoItem::create(
+        &pool_b,
+        NewTodoItem {
+            list_id: list_b.id,
+            name: "Bread".to_string(),
+            priority: None,
+            due_date: None,
+        },
+    )
+    .await?;
+
+    assert!(sync_a.sync(&pool_a).await?.is_empty());
+    assert!(sync_b.sync(&pool_b).await?.is_empty());
+    assert!(sync_a.sync(&pool_a).await?.is_empty());
+
+    let expected = vec![
+        ("Milk".to_string(), true),
+        ("Free range eggs".to_string(), false),
+        ("Bread".to_string(), false),
+    ];
+    assert_eq!(items_of_single_list(&pool_a).await?, expected);
+    assert_eq!(items_of_single_list(&pool_b).await?, expected);
+
+    // Concurrent changes to the same item are reported, keeping the local version
+    item_named(&pool_a, "Bread")
+        .await?
+        .update_name(&pool_a, "Rye bread".to_string())
+        .await?;
+    item_named(&pool_b, "Bread")
+        .await?
+        .update_name(&pool_b, "Sourdough".to_string())
+        .await?;
+
+    assert!(sync_a.sync(&pool_a).await?.is_empty());
+    let conflicts = sync_b.sync(&pool_b).await?;
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].list_name, "Groceries");
+    assert!(sync_a.sync(&pool_a).await?.is_empty());
+
+    assert!(item_named(&pool_a, "Sourdough").await.is_ok());
+    assert!(item_named(&pool_b, "Sourdough").await.is_ok());
+
+    // Deleting a list propagates too
+    TodoList::get_all(&pool_b)
+        .await?
+        .remove(0)
+        .delete(&pool_b)
+        .await?;
+    assert!(sync_b.sync(&pool_b).await?.is_empty());
+    assert!(sync_a.sync(&pool_a).await?.is_empty());
+    assert!(TodoList::get_all(&pool_a).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_commit_changes_only_commits_when_something_changed() -> Result<()> {
+    let root = tempfile::tempdir()?;
+    let remote = root.path().join("remote.git");
+    git(root.path(), &["init", "--quiet", "--bare", "remote.git"]);
+
+    let pool = setup_test_db().await?;
+    let mut sync = setup_clone(root.path(), "a", &remote).await?;
+
+    TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Work".to_string(),
+        },
+    )
+    .await?;
+
+    assert!(sync.commit_changes(&pool).await?);
+    assert!(!sync.commit_changes(&pool).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_encrypted_databases_are_not_exported() -> Result<()> {
+    let root = tempfile::tempdir()?;
+    let remote = root.path().join("remote.git");
+    git(root.path(), &["init", "--quiet", "--bare", "remote.git"]);
+
+    let pool = setup_test_db().await?;
+    let mut sync = setup_clone(root.path(), "a", &remote).await?;
+    crypto::encrypt_database(&pool, "correct horse").await?;
+    TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Secret customer Alice".to_string(),
+        },
+    )
+    .await?;
+
+    assert!(sync.commit_changes(&pool).await.is_err());
+    assert!(sync.sync(&pool).await.is_err());
+    assert_eq!(std::fs::read_dir(root.path().join("a/judo"))?.count(), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_the_app_syncs_in_the_background() -> Result<()> {
+    let mut tui = Tui::new().await?;
+    let root = tempfile::tempdir()?;
+
+    // A remote that never answers doesn't hold the app up
+    let silent = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/remote.git", silent.local_addr()?);
+    tui.app.git_sync = Some(setup_clone(root.path(), "silent", Path::new(&url)).await?);
+    let start = Instant::now();
+    tui.app.sync_now().await;
+    tui.keys("3").await?;
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(tui.app.running_git_sync.is_some());
+    tui.app.running_git_sync = None;
+    tui.app.pending_git_job = None;
+
+    // Once the sync is over, the lists pulled are shown
+    let remote = root.path().join("remote.git");
+    git(root.path(), &["init", "--quiet", "--bare", "remote.git"]);
+    let pool = setup_test_db().await?;
+    TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Chores".to_string(),
+        },
+    )
+    .await?;
+    setup_clone(root.path(), "a", &remote)
+        .await?
+        .sync(&pool)
+        .await?;
+    tui.app.git_sync = Some(setup_clone(root.path(), "b", &remote).await?);
+    tui.app.sync_now().await;
+    let start = Instant::now();
+    while tui.app.running_git_sync.is_some() {
+        anyhow::ensure!(
+            start.elapsed() < Duration::from_secs(10),
+            "The sync didn't end"
+        );
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        tui.app.handle_git_sync().await;
+    }
+    assert!(tui.app.git_sync.is_some());
+    assert_eq!(tui.app.sync_messages, Vec::<String>::new());
+    let lists = &tui.app.lists_component.lists;
+    assert!(lists.iter().any(|ui_list| ui_list.list.name == "Chores"));
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use judo::sync::ical::{ImportSummary, VCalendar, import_calendar};
+use sqlx::SqlitePool;
+
+type Contents = Vec<(
+    String,
+    Vec<(String, bool, Option<Priority>, Option<DateTime<Utc>>)>,
+)>;
+
+/// Lists with their items (name, status, priority and due date), in order
+async fn contents(pool: &SqlitePool) -> Result<Contents> {
+    let mut contents = Vec::new();
+    for list in TodoList::get_all(pool).await? {
+        let items = TodoItem::get_by_list_id(pool, list.id)
+            .await?
+            .into_iter()
+            .map(|item| (item.name, item.is_done, item.priority, item.due_date))
+            .collect();
+        contents.push((list.name, items));
+    }
+    Ok(contents)
+}
+
+/// Groceries and chores, with priorities, due dates and a done item
+async fn setup_lists() -> Result<SqlitePool> {
+    let pool = setup_test_db().await?;
+    let due = Utc.with_ymd_and_hms(2025, 10, 1, 9, 0, 0).unwrap();
+
+    for (list_name, items) in [
+        (
+            "Groceries",
+            vec![
+                ("Milk", Some(Priority::High), Some(due)),
+                ("Bread, sliced", None, None),
+            ],
+        ),
+        ("Chores", vec![("Laundry", Some(Priority::Low), None)]),
+    ] {
+        let list = TodoList::create(
+            &pool,
+            NewTodoList {
+                name: list_name.to_string(),
+            },
+        )
+        .await?;
+        for (name, priority, due_date) in items {
+            TodoItem::create(
+                &pool,
+                NewTodoItem {
+                    list_id: list.id,
+                    name: name.to_string(),
+                    priority,
+                    due_date,
+                },
+            )
+            .await?;
+        }
+    }
+
+    let lists = TodoList::get_all(&pool).await?;
+    let mut milk = TodoItem::get_by_list_id(&pool, lists[0].id)
+        .await?
+        .remove(0);
+    milk.toggle_done(&pool).await?;
+    Ok(pool)
+}
+
+#[tokio::test]
+async fn test_database_round_trip() -> Result<()> {
+    let pool = setup_lists().await?;
+    let ics = VCalendar::from_store(&pool).await?.to_ics();
+
+    let other = setup_test_db().await?;
+    let calendar = VCalendar::parse(&ics)?;
+    let summary = import_calendar(&other, &calendar, None).await?;
+    assert_eq!(
+        summary,
+        ImportSummary {
+            created: 3,
+            updated: 0
+        }
+    );
+    assert_eq!(contents(&other).await?, contents(&pool).await?);
+
+    // Importing again changes nothing, and edits update the imported items
+    assert_eq!(
+        import_calendar(&other, &calendar, None).await?,
+        ImportSummary::default()
+    );
+
+    let lists = TodoList::get_all(&pool).await?;
+    let mut bread = TodoItem::get_by_list_id(&pool, lists[0].id)
+        .await?
+        .remove(1);
+    bread.toggle_done(&pool).await?;
+    let calendar = VCalendar::parse(&VCalendar::from_store(&pool).await?.to_ics())?;
+    let summary = import_calendar(&other, &calendar, None).await?;
+    assert_eq!(summary.updated, 1);
+    assert_eq!(contents(&other).await?, contents(&pool).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_list_into_named_list() -> Result<()> {
+    let pool = setup_lists().await?;
+    let lists = TodoList::get_all(&pool).await?;
+    let items = TodoItem::get_by_list_id(&pool, lists[1].id).await?;
+    let calendar = VCalendar::from_list(&lists[1], &items);
+    assert_eq!(calendar.name.as_deref(), Some("Chores"));
+
+    let other = setup_test_db().await?;
+    import_calendar(&other, &VCalendar::parse(&calendar.to_ics())?, Some("Home")).await?;
+    let imported = contents(&other).await?;
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].0, "Home");
+    assert_eq!(imported[0].1[0].0, "Laundry");
+    Ok(())
+}
@@ -0,0 +1,5 @@
+pub mod caldav;
+pub mod change_log;
+pub mod git_sync;
+pub mod ical;
+pub mod taskwarrior;
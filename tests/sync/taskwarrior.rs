@@ -0,0 +1,175 @@
+use crate::helpers::db::setup_test_db;
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use judo::db::models::{NewTodoItem, NewTodoList, Priority, TodoItem, TodoList};
+use judo::sync::taskwarrior::{ImportReport, export_tasks, import_tasks, parse_tasks};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+
+/// Lists with their items (name, status and priority), in order
+async fn contents(
+    pool: &SqlitePool,
+) -> Result<Vec<(String, Vec<(String, bool, Option<Priority>)>)>> {
+    let mut contents = Vec::new();
+    for list in TodoList::get_all(pool).await? {
+        let items = TodoItem::get_by_list_id(pool, list.id)
+            .await?
+            .into_iter()
+            .map(|item| (item.name, item.is_done, item.priority))
+            .collect();
+        contents.push((list.name, items));
+    }
+    Ok(contents)
+}
+
+/// Export of a Taskwarrior user, with a deleted task, a recurring template and
+/// fields Judo doesn't support
+const EXPORT: &str = r#"[
+{"id":1,"description":"Report","due":"20251003T170000Z","entry":"20250930T180000Z",
+ "modified":"20251001T100000Z","priority":"H","project":"Work","status":"pending",
+ "tags":["q4"],"uuid":"0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0","urgency":8.2},
+{"id":0,"description":"Eggs","end":"20251001T081500Z","entry":"20250930T180000Z",
+ "modified":"20251001T081500Z","project":"Groceries","status":"completed",
+ "annotations":[{"entry":"20250930T180500Z","description":"free range"}],
+ "uuid":"77cd01a2-5c1e-4b8e-9a7f-0123456789ab","urgency":0},
+{"id":2,"description":"Water plants","entry":"20250930T180000Z","status":"pending",
+ "wait":"20251010T000000Z","recur":"weekly","parent":"11111111-2222-3333-4444-555555555555",
+ "uuid":"aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee","urgency":1},
+{"description":"Water plants","entry":"20250930T180000Z","recur":"weekly",
+ "status":"recurring","uuid":"11111111-2222-3333-4444-555555555555"},
+{"description":"Old task","end":"20250901T000000Z","entry":"20250830T000000Z",
+ "status":"deleted","uuid":"99999999-2222-3333-4444-555555555555"}
+]"#;
+
+#[tokio::test]
+async fn test_import_taskwarrior_export() -> Result<()> {
+    let pool = setup_test_db().await?;
+    let report = import_tasks(&pool, &parse_tasks(EXPORT)?, None).await?;
+
+    assert_eq!(
+        report,
+        ImportReport {
+            created: 3,
+            updated: 0,
+            skipped: 2,
+            unsupported: BTreeMap::from([
+                ("annotations".to_string(), 1),
+                ("parent".to_string(), 1),
+                ("recur".to_string(), 1),
+                ("tags".to_string(), 1),
+                ("wait".to_string(), 1),
+            ]),
+        }
+    );
+    assert_eq!(
+        contents(&pool).await?,
+        vec![
+            (
+                "Work".to_string(),
+                vec![("Report".to_string(), false, Some(Priority::High))]
+            ),
+            (
+                "Groceries".to_string(),
+                vec![("Eggs".to_string(), true, None)]
+            ),
+            (
+                "Imported".to_string(),
+                vec![("Water plants".to_string(), false, None)]
+            ),
+        ]
+    );
+
+    // Due dates, uuids and the times of creation and last change are kept
+    let work = &TodoList::get_all(&pool).await?[0];
+    let report_item = &TodoItem::get_by_list_id(&pool, work.id).await?[0];
+    assert_eq!(
+        report_item.due_date,
+        Some(Utc.with_ymd_and_hms(2025, 10, 3, 17, 0, 0).unwrap())
+    );
+    assert_eq!(
+        report_item.updated_at,
+        Utc.with_ymd_and_hms(2025, 10, 1, 10, 0, 0).unwrap()
+    );
+    assert_eq!(report_item.uid, "0f1e2d3c4b5a69788796a5b4c3d2e1f0");
+    assert_eq!(
+        report_item.created_at,
+        Utc.with_ymd_and_hms(2025, 9, 30, 18, 0, 0).unwrap()
+    );
+
+    // Importing again doesn't duplicate anything
+    let report = import_tasks(&pool, &parse_tasks(EXPORT)?, None).await?;
+    assert_eq!((report.created, report.updated), (0, 0));
+    assert_eq!(contents(&pool).await?.len(), 3);
+
+    // A task renamed in Taskwarrior is found by its uuid
+    let mut tasks = parse_tasks(EXPORT)?;
+    tasks[0].description = "Q4 report".to_string();
+    let report = import_tasks(&pool, &tasks, None).await?;
+    assert_eq!((report.created, report.updated), (0, 1));
+    assert_eq!(contents(&pool).await?[0].1[0].0, "Q4 report");
+
+    // Copies in another list get uids of their own
+    let report = import_tasks(&pool, &tasks, Some("Inbox")).await?;
+    assert_eq!(report.created, 3);
+    let inbox = &TodoList::get_all(&pool).await?[3];
+    let copy = &TodoItem::get_by_list_id(&pool, inbox.id).await?[0];
+    assert_eq!(copy.name, "Q4 report");
+    assert_ne!(copy.uid, report_item.uid);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_round_trip() -> Result<()> {
+    let pool = setup_test_db().await?;
+    let list = TodoList::create(
+        &pool,
+        NewTodoList {
+            name: "Groceries".to_string(),
+        },
+    )
+    .await?;
+    for (name, priority) in [("Milk", Some(Priority::Low)), ("Bread", None)] {
+        TodoItem::create(
+            &pool,
+            NewTodoItem {
+                list_id: list.id,
+                name: name.to_string(),
+                priority,
+                due_date: None,
+            },
+        )
+        .await?;
+    }
+    let mut milk = TodoItem::get_by_list_id(&pool, list.id).await?.remove(0);
+    milk.toggle_done(&pool).await?;
+
+    let tasks = export_tasks(&pool, Some("Groceries")).await?;
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].priority.as_deref(), Some("L"));
+    assert!(tasks[0].end.is_some());
+    assert!(export_tasks(&pool, Some("Chores")).await.is_err());
+
+    let json = serde_json::to_string_pretty(&tasks)?;
+    let other = setup_test_db().await?;
+    let report = import_tasks(&other, &parse_tasks(&json)?, None).await?;
+    assert_eq!(report.created, 2);
+    assert!(report.unsupported.is_empty());
+    assert_eq!(contents(&other).await?, contents(&pool).await?);
+    let uids = |items: Vec<TodoItem>| items.into_iter().map(|item| item.uid).collect::<Vec<_>>();
+    let other_list = &TodoList::get_all(&other).await?[0];
+    assert_eq!(
+        uids(TodoItem::get_by_list_id(&other, other_list.id).await?),
+        uids(TodoItem::get_by_list_id(&pool, list.id).await?)
+    );
+
+    // Changes made in Taskwarrior update the items they came from
+    let mut tasks = parse_tasks(&json)?;
+    tasks[1].description = "Rye bread".to_string();
+    let report = import_tasks(&pool, &tasks, None).await?;
+    assert_eq!((report.created, report.updated), (0, 1));
+    assert_eq!(
+        contents(&pool).await?[0].1[1],
+        ("Rye bread".to_string(), false, None)
+    );
+    Ok(())
+}
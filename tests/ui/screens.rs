@@ -0,0 +1,670 @@
+use crate::helpers::tui::Tui;
+use anyhow::Result;
+use chrono::{NaiveDate, TimeZone, Utc};
+use crossterm::event::Event;
+use judo::app::CurrentScreen;
+use judo::db::manage::db_file;
+use judo::db::models::{Priority, TodoItem, TodoList};
+use judo::db::store::open_store;
+use judo::remind::{Reminder, ReminderKind};
+use judo::ui::cursor::CursorState;
+
+/// Keys leading to each screen, starting from the items of the groceries list.
+/// Every screen must be reachable (and therefore covered by a snapshot)
+fn script(screen: &CurrentScreen) -> &'static str {
+    match screen {
+        CurrentScreen::DBSelection => "<Space>3",
+        CurrentScreen::ListSelection => "h",
+        CurrentScreen::ItemSelection => "",
+        CurrentScreen::AddList => "haChores",
+        CurrentScreen::ModifyList => "hm<Del><Del><Del><Del><Del><Del><Del><Del><Del>Shopping",
+        CurrentScreen::AddItem => "aButter",
+        CurrentScreen::ModifyItem => "m<Del><Del><Del><Del>Free-range eggs",
+        CurrentScreen::AddDB => "<Space>3ahome",
+        CurrentScreen::ModifyDB => "<Space>3jm",
+        CurrentScreen::Help => "<C-h>",
+        CurrentScreen::LeaderHelp => "<Space>",
+        CurrentScreen::DeleteListConfirmation => "hd",
+        CurrentScreen::DeleteDatabaseConfirmation => "<Space>3jd",
+        CurrentScreen::ThemeSelection => "<Space>tj",
+        CurrentScreen::EnterPassphrase => "<Space>3jj<Enter>secret",
+        CurrentScreen::Calendar => "<Space>c",
+        CurrentScreen::Board => "b",
+        CurrentScreen::EditStatuses => "beidea,",
+        CurrentScreen::HookLog => "<Space>h",
+        CurrentScreen::ScriptPrompt => "<C-a>",
+        CurrentScreen::ScriptSelect => "<Space>x",
+        CurrentScreen::CommandPalette => "<C-p>mo",
+        CurrentScreen::ExLine => ":sort <Tab><Tab>",
+        CurrentScreen::TemplateSelect => "ht",
+        CurrentScreen::TemplateVariable => "ht<Enter>1.4",
+    }
+}
+
+/// App with a list of groceries, with the items panel focused on the last item
+async fn groceries() -> Result<Tui> {
+    let mut tui = Tui::new().await?;
+    tui.keys("aGroceries<Enter>jlaMilk<Enter>aBread<Enter>aEggs<Enter>")
+        .await?;
+    Ok(tui)
+}
+
+/// Groceries with due dates in May 2026, and the calendar opened on the 14th
+async fn calendar() -> Result<Tui> {
+    let mut tui = groceries().await?;
+    let store = tui.app.store.clone();
+    let list_id = tui.app.lists_component.lists[0].list.id;
+    let mut items = TodoItem::get_by_list_id(&*store, list_id).await?;
+
+    let due = |day| Utc.with_ymd_and_hms(2026, 5, day, 12, 0, 0).unwrap();
+    items[0].update_due_date(&*store, due(14)).await?;
+    items[0].update_priority(&*store, Priority::High).await?;
+    items[1].update_due_date(&*store, due(14)).await?;
+    items[2].update_due_date(&*store, due(20)).await?;
+    items[2].toggle_done(&*store).await?;
+    tui.app.lists_component.refresh_lists(&*store).await?;
+
+    tui.keys(script(&CurrentScreen::Calendar)).await?;
+    let may_14 = NaiveDate::from_ymd_opt(2026, 5, 14).unwrap();
+    tui.app.calendar_component.today = may_14;
+    tui.app.calendar_component.select_date(may_14);
+    Ok(tui)
+}
+
+/// Drive the app to a screen and compare its render with the snapshot
+async fn assert_screen(screen: CurrentScreen, name: &str) -> Result<()> {
+    let mut tui = groceries().await?;
+    tui.keys(script(&screen)).await?;
+    assert_eq!(tui.app.current_screen, screen);
+
+    let rendered = tui.render()?;
+    insta::with_settings!({ prepend_module_to_snapshot => false }, {
+        insta::assert_snapshot!(name, rendered);
+    });
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_db_selection_screen() -> Result<()> {
+    assert_screen(CurrentScreen::DBSelection, "db_selection").await
+}
+
+#[tokio::test]
+async fn test_list_selection_screen() -> Result<()> {
+    assert_screen(CurrentScreen::ListSelection, "list_selection").await
+}
+
+#[tokio::test]
+async fn test_item_selection_screen() -> Result<()> {
+    assert_screen(CurrentScreen::ItemSelection, "item_selection").await
+}
+
+#[tokio::test]
+async fn test_add_list_screen() -> Result<()> {
+    assert_screen(CurrentScreen::AddList, "add_list").await
+}
+
+#[tokio::test]
+async fn test_modify_list_screen() -> Result<()> {
+    assert_screen(CurrentScreen::ModifyList, "modify_list").await
+}
+
+#[tokio::test]
+async fn test_add_item_screen() -> Result<()> {
+    assert_screen(CurrentScreen::AddItem, "add_item").await
+}
+
+#[tokio::test]
+async fn test_modify_item_screen() -> Result<()> {
+    assert_screen(CurrentScreen::ModifyItem, "modify_item").await
+}
+
+#[tokio::test]
+async fn test_add_db_screen() -> Result<()> {
+    assert_screen(CurrentScreen::AddDB, "add_db").await
+}
+
+#[tokio::test]
+async fn test_modify_db_screen() -> Result<()> {
+    assert_screen(CurrentScreen::ModifyDB, "modify_db").await
+}
+
+#[tokio::test]
+async fn test_help_screen() -> Result<()> {
+    assert_screen(CurrentScreen::Help, "help").await
+}
+
+#[tokio::test]
+async fn test_leader_help_screen() -> Result<()> {
+    assert_screen(CurrentScreen::LeaderHelp, "leader_help").await
+}
+
+#[tokio::test]
+async fn test_delete_list_confirmation_screen() -> Result<()> {
+    assert_screen(
+        CurrentScreen::DeleteListConfirmation,
+        "delete_list_confirmation",
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_delete_database_confirmation_screen() -> Result<()> {
+    assert_screen(
+        CurrentScreen::DeleteDatabaseConfirmation,
+        "delete_database_confirmation",
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_theme_selection_screen() -> Result<()> {
+    assert_screen(CurrentScreen::ThemeSelection, "theme_selection").await
+}
+
+#[tokio::test]
+async fn test_enter_passphrase_screen() -> Result<()> {
+    assert_screen(CurrentScreen::EnterPassphrase, "enter_passphrase").await
+}
+
+#[tokio::test]
+async fn test_scripted_session_persists_changes() -> Result<()> {
+    let mut tui = groceries().await?;
+
+    // Complete the first item, rename the list and add a second one
+    tui.keys("gg<Enter>hm<Del><Del><Del><Del><Del><Del><Del><Del><Del>Shopping<Enter>")
+        .await?;
+    tui.keys("aChores<Enter>").await?;
+
+    let lists = &tui.app.lists_component.lists;
+    let names: Vec<&str> = lists.iter().map(|l| l.list.name.as_str()).collect();
+    assert_eq!(names, vec!["Shopping", "Chores"]);
+    let done: Vec<bool> = lists[0].items.iter().map(|i| i.item.is_done).collect();
+    assert_eq!(done, vec![true, false, false]);
+
+    // Switching databases and back reloads everything from the database
+    tui.keys("<Space>3j<Enter><Space>3k<Enter>").await?;
+    assert_eq!(tui.app.current_db_config.name, "test");
+    assert_eq!(tui.app.lists_component.lists.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_insert_below_and_above_selection() -> Result<()> {
+    let mut tui = groceries().await?;
+
+    // Items: below Milk, then above Eggs
+    tui.keys("ggoButter<Enter>").await?;
+    let item_names = |tui: &Tui| -> Vec<String> {
+        let list = &tui.app.lists_component.lists[0];
+        list.items.iter().map(|i| i.item.name.clone()).collect()
+    };
+    assert_eq!(item_names(&tui), vec!["Milk", "Butter", "Bread", "Eggs"]);
+    let selected = tui.app.lists_component.lists[0].item_state.selected();
+    assert_eq!(selected, Some(1));
+
+    tui.keys("jjOJam<Enter>").await?;
+    assert_eq!(
+        item_names(&tui),
+        vec!["Milk", "Butter", "Bread", "Jam", "Eggs"]
+    );
+    let selected = tui.app.lists_component.lists[0].item_state.selected();
+    assert_eq!(selected, Some(3));
+
+    // Lists: above the selected one
+    tui.keys("hOChores<Enter>").await?;
+    let lists = &tui.app.lists_component.lists;
+    let names: Vec<&str> = lists.iter().map(|l| l.list.name.as_str()).collect();
+    assert_eq!(names, vec!["Chores", "Groceries"]);
+    assert_eq!(tui.app.lists_component.selected(), Some(0));
+
+    // The positions are saved
+    let items = TodoItem::get_by_list_id(&*tui.app.store, lists[1].list.id).await?;
+    let names: Vec<&str> = items.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, vec!["Milk", "Butter", "Bread", "Jam", "Eggs"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_yank_and_paste() -> Result<()> {
+    let mut tui = groceries().await?;
+    let item_names = |tui: &Tui, list: usize| -> Vec<String> {
+        let list = &tui.app.lists_component.lists[list];
+        list.items.iter().map(|i| i.item.name.clone()).collect()
+    };
+
+    // Two items, pasted below the last one and above the first one
+    tui.keys("gg<Enter>2yGp").await?;
+    let register = tui.app.register.clone();
+    assert_eq!(register.as_deref(), Some("- [x] Milk\n- [ ] Bread"));
+    assert_eq!(
+        item_names(&tui, 0),
+        vec!["Milk", "Bread", "Eggs", "Milk", "Bread"]
+    );
+    assert!(tui.app.lists_component.lists[0].items[3].item.is_done);
+
+    // Duplicate the list
+    tui.keys("hyp").await?;
+    let lists = &tui.app.lists_component.lists;
+    let names: Vec<&str> = lists.iter().map(|l| l.list.name.as_str()).collect();
+    assert_eq!(names, vec!["Groceries", "Groceries"]);
+    assert_eq!(tui.app.lists_component.selected(), Some(1));
+    assert_eq!(item_names(&tui, 1), item_names(&tui, 0));
+
+    // Text pasted in the terminal becomes items
+    tui.keys("lgg").await?;
+    let pasted = Event::Paste("- [ ] Jam\n\n- [x] Butter\n".to_string());
+    tui.app.handle_event(pasted).await;
+    assert_eq!(
+        item_names(&tui, 1),
+        vec!["Milk", "Jam", "Butter", "Bread", "Eggs", "Milk", "Bread"]
+    );
+    let list_id = tui.app.lists_component.lists[1].list.id;
+    let items = TodoItem::get_by_list_id(&*tui.app.store, list_id).await?;
+    assert!(items[2].is_done);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_calendar_screen() -> Result<()> {
+    let mut tui = calendar().await?;
+    assert_eq!(tui.app.current_screen, CurrentScreen::Calendar);
+
+    let rendered = tui.render()?;
+    insta::with_settings!({ prepend_module_to_snapshot => false }, {
+        insta::assert_snapshot!("calendar", rendered);
+    });
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reschedule_from_calendar() -> Result<()> {
+    let mut tui = calendar().await?;
+
+    // Pick up Bread on the 14th and drop it a week and a day later
+    tui.keys("<Enter>jmjl<Enter>").await?;
+    let calendar = &tui.app.calendar_component;
+    assert_eq!(
+        calendar.selected_date,
+        NaiveDate::from_ymd_opt(2026, 5, 22).unwrap()
+    );
+    assert_eq!(calendar.item_state.selected(), Some(0));
+    assert!(calendar.moving.is_none());
+
+    let list_id = tui.app.lists_component.lists[0].list.id;
+    let items = TodoItem::get_by_list_id(&*tui.app.store, list_id).await?;
+    let bread = items.iter().find(|i| i.name == "Bread").unwrap();
+    assert_eq!(
+        bread.due_date,
+        Some(Utc.with_ymd_and_hms(2026, 5, 22, 12, 0, 0).unwrap())
+    );
+
+    // Cancelled moves leave the item where it was
+    tui.keys("mk<Esc>").await?;
+    assert!(tui.app.calendar_component.moving.is_none());
+    tui.keys("<Esc>").await?;
+    assert_eq!(tui.app.current_screen, CurrentScreen::ItemSelection);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_board_screen() -> Result<()> {
+    assert_screen(CurrentScreen::Board, "board").await
+}
+
+#[tokio::test]
+async fn test_edit_statuses_screen() -> Result<()> {
+    assert_screen(CurrentScreen::EditStatuses, "edit_statuses").await
+}
+
+#[tokio::test]
+async fn test_hook_log_screen() -> Result<()> {
+    assert_screen(CurrentScreen::HookLog, "hook_log").await
+}
+
+#[tokio::test]
+async fn test_script_prompt_screen() -> Result<()> {
+    assert_screen(CurrentScreen::ScriptPrompt, "script_prompt").await
+}
+
+#[tokio::test]
+async fn test_script_select_screen() -> Result<()> {
+    assert_screen(CurrentScreen::ScriptSelect, "script_select").await
+}
+
+#[tokio::test]
+async fn test_run_script() -> Result<()> {
+    let mut tui = groceries().await?;
+    let names = |tui: &Tui, i: usize| -> Vec<String> {
+        let items = &tui.app.lists_component.lists[i].items;
+        items.iter().map(|i| i.item.name.clone()).collect()
+    };
+
+    // Eggs is done: the script bound to Ctrl + a moves it to the archive
+    tui.keys("jj<Enter><C-a><Enter>").await?;
+    assert!(tui.app.running_script.is_none());
+    assert_eq!(tui.app.current_screen, CurrentScreen::ItemSelection);
+    assert_eq!(tui.app.script_output, vec!["1 archived in Archive"]);
+    assert_eq!(names(&tui, 0), vec!["Milk", "Bread"]);
+    assert_eq!(names(&tui, 1), vec!["Eggs"]);
+    assert!(tui.app.lists_component.lists[1].items[0].item.is_done);
+
+    // Run from the picker, cancelling the prompt
+    tui.keys("<Enter>k<Enter><Space>x<Enter><Esc>").await?;
+    assert!(tui.app.script_output.is_empty());
+    assert_eq!(names(&tui, 0), vec!["Milk", "Bread"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_palette_screen() -> Result<()> {
+    assert_screen(CurrentScreen::CommandPalette, "command_palette").await
+}
+
+#[tokio::test]
+async fn test_run_commands_from_palette() -> Result<()> {
+    let mut tui = groceries().await?;
+    let item = |tui: &Tui, i: usize, j: usize| -> (String, bool) {
+        let ui_item = &tui.app.lists_component.lists[i].items[j];
+        (ui_item.item.name.clone(), ui_item.item.is_done)
+    };
+
+    // Commands with an argument, and commands run with their keys
+    tui.keys("<C-p>rename Whole milk<Enter>").await?;
+    assert_eq!(item(&tui, 0, 0), ("Whole milk".to_string(), false));
+    tui.keys("<C-p>tgd<Enter>").await?;
+    assert_eq!(item(&tui, 0, 0), ("Whole milk".to_string(), true));
+    assert_eq!(tui.app.current_screen, CurrentScreen::ItemSelection);
+
+    // Enter on a command missing its argument completes it first
+    tui.keys("<C-p>back<Enter>aChores<Enter>kl").await?;
+    tui.keys("<C-p>mt<Enter>").await?;
+    assert_eq!(
+        tui.app.palette.as_ref().unwrap().input.current_input,
+        "move-to "
+    );
+    tui.keys("cho<Enter>").await?;
+    assert_eq!(item(&tui, 1, 0), ("Whole milk".to_string(), true));
+    assert_eq!(tui.app.lists_component.lists[0].items.len(), 2);
+
+    tui.keys("<C-p>db wo<Enter>").await?;
+    assert_eq!(tui.app.current_db_config.name, "work");
+    assert!(tui.app.palette.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ex_line_screen() -> Result<()> {
+    assert_screen(CurrentScreen::ExLine, "ex_line").await
+}
+
+#[tokio::test]
+async fn test_run_ex_commands() -> Result<()> {
+    let mut tui = groceries().await?;
+    let names = |tui: &Tui| -> Vec<String> {
+        tui.app.lists_component.lists[0]
+            .items
+            .iter()
+            .map(|ui_item| ui_item.item.name.clone())
+            .collect()
+    };
+    let selected = |tui: &Tui| tui.app.lists_component.lists[0].item_state.selected();
+
+    // Go to an item, move it to the bottom, and sort the items
+    tui.keys(":1<Enter>").await?;
+    assert_eq!(selected(&tui), Some(0));
+    tui.keys(":m $<Enter>").await?;
+    assert_eq!(names(&tui), ["Bread", "Eggs", "Milk"]);
+    assert_eq!(selected(&tui), Some(2));
+    tui.keys(":sort!<Enter>").await?;
+    assert_eq!(names(&tui), ["Milk", "Eggs", "Bread"]);
+    assert_eq!(tui.app.current_screen, CurrentScreen::ItemSelection);
+
+    // Export the list, and refuse to overwrite the file without !
+    let export = tui.app.data_dir.join("export.md");
+    tui.keys(&format!(":w {}<Enter>", export.display())).await?;
+    assert!(std::fs::read_to_string(&export)?.contains("Milk"));
+    tui.keys(&format!(":w {}<Enter>", export.display())).await?;
+    assert_eq!(tui.app.current_screen, CurrentScreen::ExLine);
+    assert!(tui.app.ex_line.as_ref().unwrap().error.is_some());
+    tui.keys("<Esc>").await?;
+
+    // A count starts a range from the selected item
+    tui.keys("k2:").await?;
+    assert_eq!(tui.app.ex_line.as_ref().unwrap().input.get_text(), ".,.+1");
+    tui.keys("d<Enter>").await?;
+    assert_eq!(names(&tui), ["Milk"]);
+
+    // The history is saved, and browsed from what was typed
+    let history = std::fs::read_to_string(tui.app.data_dir.join("ex_history"))?;
+    assert!(history.ends_with(".,.+1d\n"));
+    tui.keys(":s<Up>").await?;
+    assert_eq!(tui.app.ex_line.as_ref().unwrap().input.get_text(), "sort!");
+    tui.keys("<Esc>").await?;
+
+    tui.keys(":e wo<Tab><Enter>").await?;
+    assert_eq!(tui.app.current_db_config.name, "work");
+    assert!(tui.app.ex_line.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_template_select_screen() -> Result<()> {
+    assert_screen(CurrentScreen::TemplateSelect, "template_select").await
+}
+
+#[tokio::test]
+async fn test_template_variable_screen() -> Result<()> {
+    assert_screen(CurrentScreen::TemplateVariable, "template_variable").await
+}
+
+#[tokio::test]
+async fn test_lists_from_templates() -> Result<()> {
+    let mut tui = groceries().await?;
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+
+    // The variables are asked in order, the date defaulting to today
+    tui.keys("ht<Enter>1.4<Enter>").await?;
+    assert_eq!(tui.app.current_screen, CurrentScreen::TemplateVariable);
+    assert_eq!(tui.app.input_state.get_text(), today);
+    tui.keys("<Enter>").await?;
+    assert_eq!(tui.app.current_screen, CurrentScreen::ListSelection);
+
+    let ui_list = &tui.app.lists_component.lists[1];
+    assert_eq!(ui_list.list.name, "Release 1.4");
+    let items: Vec<&str> = ui_list.items.iter().map(|i| i.item.name.as_str()).collect();
+    assert_eq!(
+        items,
+        [
+            "Bump the version to 1.4".to_string(),
+            format!("Tag the release on {}", today)
+        ]
+    );
+    assert_eq!(tui.app.lists_component.selected(), Some(1));
+
+    // Cancelling creates nothing
+    tui.keys("t<Enter><Esc>").await?;
+    assert_eq!(tui.app.lists_component.lists.len(), 2);
+    assert!(tui.app.template_form.is_none());
+
+    // Save the groceries as a template, their items unchecked, and tell where
+    tui.keys("kl<Enter>hT").await?;
+    assert!(tui.app.lists_component.lists[0].items[0].item.is_done);
+    let path = tui.app.config_path.with_file_name("templates/Groceries.md");
+    assert_eq!(
+        std::fs::read_to_string(&path)?,
+        "# Groceries\n- [ ] Milk\n- [ ] Bread\n- [ ] Eggs\n"
+    );
+    assert_eq!(
+        tui.app.template_messages,
+        [format!("Saved template to {}", path.display())]
+    );
+    tui.keys("<Esc>").await?;
+    assert!(tui.app.template_messages.is_empty());
+
+    // Saving again keeps the template as edited, and says why
+    std::fs::write(&path, "# Groceries for {{day}}\n- [ ] Milk\n")?;
+    tui.keys("T").await?;
+    assert_eq!(
+        std::fs::read_to_string(&path)?,
+        "# Groceries for {{day}}\n- [ ] Milk\n"
+    );
+    assert_eq!(tui.app.template_messages.len(), 1);
+    assert!(
+        tui.app.template_messages[0].contains("already exists"),
+        "{:?}",
+        tui.app.template_messages
+    );
+    tui.keys("<Esc>").await?;
+    tui.keys("t").await?;
+    let names: Vec<&str> = tui.app.templates.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["Groceries", "release"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_manage_databases() -> Result<()> {
+    let mut tui = groceries().await?;
+    let db_path = |tui: &Tui, name: &str| -> Option<std::path::PathBuf> {
+        let db = tui.app.config.dbs.iter().find(|db| db.name == name)?;
+        db_file(&db.connection_str)
+    };
+    let list_names = async |tui: &Tui, name: &str| -> Result<Vec<String>> {
+        let db = tui
+            .app
+            .config
+            .dbs
+            .iter()
+            .find(|db| db.name == name)
+            .unwrap();
+        let store = open_store(&db.connection_str).await?;
+        Ok(TodoList::get_all(&*store)
+            .await?
+            .into_iter()
+            .map(|list| list.name)
+            .collect())
+    };
+
+    // Renaming the open database moves its file, and keeps it open
+    let old_path = db_path(&tui, "test").unwrap();
+    tui.keys("<Space>3m<Del><Del><Del><Del>home<Enter>").await?;
+    let path = db_path(&tui, "home").unwrap();
+    assert_eq!(path, old_path.with_file_name("home.db"));
+    assert!(path.exists() && !old_path.exists());
+    assert_eq!(tui.app.current_db_config.name, "home");
+    assert_eq!(TodoList::get_all(&*tui.app.store).await?.len(), 1);
+
+    // Names can't move the file to another directory
+    tui.keys("m<Del><Del><Del><Del>../x<Enter>ca/b<Enter>")
+        .await?;
+    assert_eq!(tui.app.config.dbs[0].name, "home");
+    assert_eq!(tui.app.config.dbs.len(), 3);
+    assert!(path.exists());
+
+    // Duplicate it next to the original, and select the copy
+    tui.keys("ccopy<Enter>").await?;
+    assert_eq!(tui.app.current_screen, CurrentScreen::DBSelection);
+    assert!(db_path(&tui, "copy").unwrap().exists());
+    assert_eq!(tui.app.selected_db_index, 3);
+    assert_eq!(list_names(&tui, "copy").await?, ["Groceries"]);
+
+    // Merging the copy back renames the lists clashing with the open ones
+    tui.keys("Mhome<Enter>").await?;
+    let names: Vec<&str> = tui
+        .app
+        .lists_component
+        .lists
+        .iter()
+        .map(|ui_list| ui_list.list.name.as_str())
+        .collect();
+    assert_eq!(names, ["Groceries", "Groceries (copy)"]);
+    assert_eq!(tui.app.lists_component.lists[1].items.len(), 3);
+    // A database can't be merged into itself
+    tui.keys("Mcopy<Enter>").await?;
+    assert_eq!(list_names(&tui, "copy").await?, ["Groceries"]);
+
+    // Move the open database to a directory in the data directory
+    tui.keys("kkkrmoved/home.db<Enter>").await?;
+    let path = db_path(&tui, "home").unwrap();
+    assert_eq!(path, tui.app.data_dir.join("moved/home.db"));
+    assert!(path.exists());
+    assert_eq!(TodoList::get_all(&*tui.app.store).await?.len(), 2);
+
+    // The file of the open database can't be deleted, other files can
+    tui.keys("df").await?;
+    assert_eq!(tui.app.config.dbs.len(), 4);
+    let copy_path = db_path(&tui, "copy").unwrap();
+    tui.keys("jjjdf").await?;
+    assert!(!copy_path.exists());
+    assert!(db_path(&tui, "copy").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_move_cards_on_board() -> Result<()> {
+    let mut tui = groceries().await?;
+    let list_id = tui.app.lists_component.lists[0].list.id;
+    let statuses = |items: &[TodoItem]| -> Vec<(String, Option<String>, bool)> {
+        items
+            .iter()
+            .map(|i| (i.name.clone(), i.status.clone(), i.is_done))
+            .collect()
+    };
+
+    // Move Eggs to "doing", then Milk, and Eggs to the top of the column
+    tui.keys("b").await?;
+    assert_eq!(tui.app.current_screen, CurrentScreen::Board);
+    tui.keys("jjj").await?;
+    assert_eq!(tui.app.board_component.card, 2);
+    tui.keys("LhLjK").await?;
+    let items = TodoItem::get_by_list_id(&*tui.app.store, list_id).await?;
+    assert_eq!(
+        statuses(&items),
+        [
+            ("Eggs".to_string(), Some("doing".to_string()), false),
+            ("Milk".to_string(), Some("doing".to_string()), false),
+            ("Bread".to_string(), None, false),
+        ]
+    );
+    let board = &tui.app.board_component;
+    assert_eq!((board.column, board.card), (1, 0));
+
+    // Moving a card to the last column marks it as done, and back undoes it
+    tui.keys("LL").await?;
+    let items = TodoItem::get_by_list_id(&*tui.app.store, list_id).await?;
+    assert!(items[0].is_done);
+    tui.keys("H").await?;
+    let items = TodoItem::get_by_list_id(&*tui.app.store, list_id).await?;
+    assert_eq!(items[0].status.as_deref(), Some("blocked"));
+    assert!(!items[0].is_done);
+
+    // Custom statuses, the last one being the done one
+    tui.keys("e").await?;
+    tui.app.input_state.clear();
+    tui.keys("new, shipped<Enter>").await?;
+    let list = &tui.app.lists_component.lists[0].list;
+    assert_eq!(list.statuses(), ["new", "shipped"]);
+    tui.keys("<Esc>").await?;
+    assert_eq!(tui.app.current_screen, CurrentScreen::ItemSelection);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reminder_toast() -> Result<()> {
+    let mut tui = groceries().await?;
+    tui.app.show_reminder(Reminder {
+        db_name: "test".to_string(),
+        list_name: "Groceries".to_string(),
+        item_name: "Milk".to_string(),
+        item_uid: "0123".to_string(),
+        due_date: Utc.with_ymd_and_hms(2026, 5, 14, 12, 0, 0).unwrap(),
+        kind: ReminderKind::Overdue,
+    });
+
+    let rendered = tui.render()?;
+    assert!(rendered.contains("Reminder"));
+    assert!(rendered.contains("Milk is overdue"));
+    assert!(rendered.contains("Groceries (test), due 2026-05-14"));
+    Ok(())
+}